@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+/// Two addresses answering for the same instance this close together are treated
+/// as a duplicate rather than a device that moved.
+pub const DUPLICATE_WINDOW: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct AddressRecord {
+    pub address: SocketAddr,
+    pub first_seen: Instant,
+    pub last_seen: Instant,
+    /// Number of I-Am messages received from this address
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DeviceAddresses {
+    /// Every address seen for the instance, oldest first
+    pub records: Vec<AddressRecord>,
+    /// Index into `records` of the address the device last answered from
+    current: usize,
+    /// Set once two stations were seen answering with this instance
+    pub duplicate: bool,
+    /// Set when the device moved to a new address
+    pub previous_address: Option<SocketAddr>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddressEvent {
+    /// Two or more stations answer with the same device instance
+    Duplicate { device_id: u32, addresses: Vec<SocketAddr> },
+    /// The device now answers from a different address than before
+    Changed { device_id: u32, from: SocketAddr, to: SocketAddr },
+}
+
+impl fmt::Display for AddressEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressEvent::Duplicate { device_id, addresses } => {
+                let list: Vec<String> = addresses.iter().map(|a| a.to_string()).collect();
+                write!(f, "Duplicate device instance {} at {}", device_id, list.join(", "))
+            }
+            AddressEvent::Changed { device_id, from, to } => {
+                write!(f, "Device {} changed address from {} to {}", device_id, from, to)
+            }
+        }
+    }
+}
+
+/// Address history per device instance, kept across rescans so duplicate
+/// instances and address changes can be spotted during commissioning.
+#[derive(Debug, Default)]
+pub struct AddressBook {
+    devices: HashMap<u32, DeviceAddresses>,
+}
+
+impl AddressBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records an I-Am from `address` and reports a duplicate or address change if it is one.
+    pub fn record(&mut self, device_id: u32, address: SocketAddr, now: Instant) -> Option<AddressEvent> {
+        let entry = self.devices.entry(device_id).or_default();
+
+        if entry.records.is_empty() {
+            entry.records.push(AddressRecord { address, first_seen: now, last_seen: now, count: 1 });
+            return None;
+        }
+
+        let current = entry.records[entry.current].clone();
+        let index = match entry.records.iter().position(|r| r.address == address) {
+            Some(i) => {
+                entry.records[i].last_seen = now;
+                entry.records[i].count += 1;
+                i
+            }
+            None => {
+                entry.records.push(AddressRecord { address, first_seen: now, last_seen: now, count: 1 });
+                entry.records.len() - 1
+            }
+        };
+        entry.current = index;
+
+        if current.address == address {
+            return None;
+        }
+
+        if entry.duplicate || now.saturating_duration_since(current.last_seen) < DUPLICATE_WINDOW {
+            entry.duplicate = true;
+            let addresses = entry.records.iter()
+                .filter(|r| now.saturating_duration_since(r.last_seen) < DUPLICATE_WINDOW)
+                .map(|r| r.address)
+                .collect();
+            Some(AddressEvent::Duplicate { device_id, addresses })
+        } else {
+            entry.previous_address = Some(current.address);
+            Some(AddressEvent::Changed { device_id, from: current.address, to: address })
+        }
+    }
+
+    pub fn get(&self, device_id: u32) -> Option<&DeviceAddresses> {
+        self.devices.get(&device_id)
+    }
+
+    pub fn is_duplicate(&self, device_id: u32) -> bool {
+        self.devices.get(&device_id).is_some_and(|d| d.duplicate)
+    }
+
+    pub fn previous_address(&self, device_id: u32) -> Option<SocketAddr> {
+        self.devices.get(&device_id).and_then(|d| d.previous_address)
+    }
+
+    /// Summarises every flagged device, sorted by device ID.
+    pub fn conflicts(&self) -> Vec<AddressEvent> {
+        let mut ids: Vec<_> = self.devices.keys().cloned().collect();
+        ids.sort();
+        ids.into_iter()
+            .filter_map(|device_id| {
+                let entry = &self.devices[&device_id];
+                if entry.duplicate {
+                    let addresses = entry.records.iter().map(|r| r.address).collect();
+                    Some(AddressEvent::Duplicate { device_id, addresses })
+                } else {
+                    entry.previous_address.map(|from| AddressEvent::Changed {
+                        device_id,
+                        from,
+                        to: entry.records[entry.current].address,
+                    })
+                }
+            })
+            .collect()
+    }
+}
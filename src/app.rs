@@ -1,24 +1,440 @@
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
+use ratatui::layout::{Position, Rect};
 use ratatui::widgets::{ListState, TableState};
-use crate::bacnet::DiscoveredDevice;
-use bacnet_rs::object::ObjectIdentifier;
-use std::time::Instant;
+use crate::address_book::{AddressBook, AddressEvent};
+use crate::bacnet::{DiscoveredDevice, PointReading};
+use crate::ede::EdeExport;
+use crate::export::ScanExport;
+use crate::health::{DeviceHealth, HealthStats};
+use crate::history::PointHistory;
+use crate::scan_diff::ScanDiff;
+use crate::traffic::{PacketFilter, TrafficLog};
+use crate::site_db::SiteDb;
+use crate::storm::{BroadcastMonitor, StormWarning};
+use crate::topology::{TreeNode, Topology};
+use crate::property::{self, BacnetValue, PresentValue, PropertyEntry, StateTexts, StatusFlags};
+use bacnet_rs::object::{ObjectIdentifier, ObjectType};
+use std::time::{Duration, Instant};
 use if_addrs::Interface;
 
 #[derive(Debug, Clone)]
 pub struct BacnetObject {
     pub id: ObjectIdentifier,
     pub name: String,
-    pub present_value: String,
+    pub present_value: PresentValue,
+    /// Name of the engineering units, empty until read
     pub units: String,
+    /// BACnetEngineeringUnits value behind `units`
+    pub units_code: Option<u32>,
+    pub status_flags: Option<StatusFlags>,
+    /// BACnetReliability; 0 is no-fault-detected
+    pub reliability: Option<u32>,
+    pub out_of_service: Option<bool>,
+    /// None until read with the first poll
+    pub description: Option<String>,
+    /// None until the texts have been read; binary and multistate objects only
+    pub state_texts: Option<StateTexts>,
     pub last_updated: Instant,
+    /// Numeric present values from recent polls, for the trend pane
+    pub history: PointHistory,
 }
 
+impl BacnetObject {
+    /// An object known only by its identifier and name; everything else is read by the polls
+    pub fn new(id: ObjectIdentifier, name: String) -> Self {
+        Self {
+            id,
+            name,
+            present_value: PresentValue::Unknown,
+            units: String::new(),
+            units_code: None,
+            status_flags: None,
+            reliability: None,
+            out_of_service: None,
+            description: None,
+            state_texts: None,
+            last_updated: Instant::now(),
+            history: PointHistory::default(),
+        }
+    }
+
+    pub fn apply_reading(&mut self, reading: PointReading) {
+        self.present_value = reading.present_value;
+        let now = Instant::now();
+        if let Some(value) = self.present_value.as_f64() {
+            self.history.push(now, value);
+        }
+        self.status_flags = reading.status_flags.or(self.status_flags);
+        self.reliability = reading.reliability.or(self.reliability);
+        self.out_of_service = reading.out_of_service.or(self.out_of_service);
+        if let Some(name) = reading.name.filter(|n| !n.is_empty()) {
+            self.name = name;
+        }
+        if reading.description.is_some() {
+            self.description = reading.description;
+        }
+        if let Some(code) = reading.units {
+            self.units_code = Some(code);
+            self.units = property::units_name(code).unwrap_or_else(|| code.to_string());
+        }
+        if reading.state_texts.is_some() {
+            self.state_texts = reading.state_texts;
+        }
+        self.last_updated = now;
+    }
+
+    /// True while the name, description or state texts are still to be read
+    pub fn needs_details(&self) -> bool {
+        self.description.is_none()
+            || (self.state_texts.is_none() && !property::state_text_properties(self.id.object_type).is_empty())
+    }
+
+    pub fn in_alarm(&self) -> bool {
+        self.status_flags.is_some_and(|f| f.in_alarm)
+    }
+
+    /// Fault flag set or a Reliability other than no-fault-detected
+    pub fn is_faulted(&self) -> bool {
+        self.status_flags.is_some_and(|f| f.fault) || self.reliability.is_some_and(|r| r != 0)
+    }
+
+    pub fn is_overridden(&self) -> bool {
+        self.status_flags.is_some_and(|f| f.overridden)
+    }
+
+    pub fn is_out_of_service(&self) -> bool {
+        self.out_of_service.unwrap_or(false) || self.status_flags.is_some_and(|f| f.out_of_service)
+    }
+}
+
+/// Object type groups the object table can be limited to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TypeFilter {
+    #[default]
+    All,
+    AnalogInput,
+    AnalogOutput,
+    AnalogValue,
+    BinaryInput,
+    BinaryOutput,
+    BinaryValue,
+    /// Multi-state inputs, outputs and values
+    MultiState,
+    /// Every other object type
+    Other,
+}
+
+impl TypeFilter {
+    const CYCLE: [TypeFilter; 9] = [
+        TypeFilter::All,
+        TypeFilter::AnalogInput,
+        TypeFilter::AnalogOutput,
+        TypeFilter::AnalogValue,
+        TypeFilter::BinaryInput,
+        TypeFilter::BinaryOutput,
+        TypeFilter::BinaryValue,
+        TypeFilter::MultiState,
+        TypeFilter::Other,
+    ];
+
+    /// The filter after this one, wrapping back to All
+    pub fn next(self) -> Self {
+        let i = Self::CYCLE.iter().position(|f| *f == self).unwrap_or(0);
+        Self::CYCLE[(i + 1) % Self::CYCLE.len()]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TypeFilter::All => "All",
+            TypeFilter::AnalogInput => "AI",
+            TypeFilter::AnalogOutput => "AO",
+            TypeFilter::AnalogValue => "AV",
+            TypeFilter::BinaryInput => "BI",
+            TypeFilter::BinaryOutput => "BO",
+            TypeFilter::BinaryValue => "BV",
+            TypeFilter::MultiState => "MSx",
+            TypeFilter::Other => "Other",
+        }
+    }
+
+    pub fn matches(self, object_type: ObjectType) -> bool {
+        let group = match object_type {
+            ObjectType::AnalogInput => TypeFilter::AnalogInput,
+            ObjectType::AnalogOutput => TypeFilter::AnalogOutput,
+            ObjectType::AnalogValue => TypeFilter::AnalogValue,
+            ObjectType::BinaryInput => TypeFilter::BinaryInput,
+            ObjectType::BinaryOutput => TypeFilter::BinaryOutput,
+            ObjectType::BinaryValue => TypeFilter::BinaryValue,
+            ObjectType::MultiStateInput | ObjectType::MultiStateOutput | ObjectType::MultiStateValue => TypeFilter::MultiState,
+            _ => TypeFilter::Other,
+        };
+        self == TypeFilter::All || self == group
+    }
+}
+
+/// Search text and filters applied to the object table
+#[derive(Debug, Clone, Default)]
+pub struct ObjectFilter {
+    /// Fuzzy search on name, description and object ID
+    pub query: String,
+    pub type_filter: TypeFilter,
+    /// Only show points that are in alarm or overridden
+    pub problems_only: bool,
+}
+
+impl ObjectFilter {
+    pub fn is_active(&self) -> bool {
+        !self.query.is_empty() || self.type_filter != TypeFilter::All || self.problems_only
+    }
+
+    pub fn matches(&self, obj: &BacnetObject) -> bool {
+        if !self.type_filter.matches(obj.id.object_type) {
+            return false;
+        }
+        if self.problems_only && !(obj.in_alarm() || obj.is_overridden()) {
+            return false;
+        }
+        if self.query.is_empty() {
+            return true;
+        }
+        let long_id = format!("{:?}:{}", obj.id.object_type, obj.id.instance);
+        let short_id = property::object_type_abbreviation(obj.id.object_type)
+            .map(|abbr| format!("{}{}", abbr, obj.id.instance))
+            .unwrap_or_default();
+        [obj.name.as_str(), obj.description.as_deref().unwrap_or_default(), &long_id, &short_id]
+            .iter()
+            .any(|candidate| fuzzy_match(&self.query, candidate))
+    }
+
+    /// The objects passing the filter, in their original order
+    pub fn apply<'a>(&self, objects: &'a [BacnetObject]) -> Vec<&'a BacnetObject> {
+        objects.iter().filter(|obj| self.matches(obj)).collect()
+    }
+
+    /// Short summary for the table title, empty when nothing is filtered
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if self.type_filter != TypeFilter::All {
+            parts.push(format!("type {}", self.type_filter.label()));
+        }
+        if self.problems_only {
+            parts.push("alarmed/overridden".to_string());
+        }
+        if !self.query.is_empty() {
+            parts.push(format!("/{}", self.query));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Columns the device list can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeviceSortKey {
+    #[default]
+    DeviceId,
+    Vendor,
+    Address,
+    LastSeen,
+    /// Object_Name of the Device object, once read; unnamed devices sort last in
+    /// either direction
+    Name,
+}
+
+impl DeviceSortKey {
+    pub fn next(self) -> Self {
+        match self {
+            DeviceSortKey::DeviceId => DeviceSortKey::Vendor,
+            DeviceSortKey::Vendor => DeviceSortKey::Address,
+            DeviceSortKey::Address => DeviceSortKey::LastSeen,
+            DeviceSortKey::LastSeen => DeviceSortKey::Name,
+            DeviceSortKey::Name => DeviceSortKey::DeviceId,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DeviceSortKey::DeviceId => "ID",
+            DeviceSortKey::Vendor => "Vendor",
+            DeviceSortKey::Address => "IP",
+            DeviceSortKey::LastSeen => "Last Seen",
+            DeviceSortKey::Name => "Name",
+        }
+    }
+}
+
+/// Order of the device list
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeviceSort {
+    pub key: DeviceSortKey,
+    pub descending: bool,
+}
+
+impl DeviceSort {
+    /// Device IDs in display order. Ties fall back to the device ID.
+    pub fn apply(&self, devices: &HashMap<u32, DiscoveredDevice>, properties: &HashMap<u32, Vec<PropertyEntry>>) -> Vec<u32> {
+        let name = |id: &u32| match properties.get(id).and_then(|p| property::find(p, property::OBJECT_NAME)) {
+            Some(BacnetValue::CharacterString(name)) if !name.trim().is_empty() => Some(name.to_lowercase()),
+            _ => None,
+        };
+        let mut ids: Vec<u32> = devices.keys().cloned().collect();
+        ids.sort_by(|a, b| {
+            let (da, db) = (&devices[a], &devices[b]);
+            let ordering = match self.key {
+                DeviceSortKey::DeviceId => Ordering::Equal,
+                DeviceSortKey::Vendor => da.vendor_name.to_lowercase().cmp(&db.vendor_name.to_lowercase()),
+                DeviceSortKey::Address => da.address.cmp(&db.address),
+                DeviceSortKey::LastSeen => da.last_seen.cmp(&db.last_seen),
+                DeviceSortKey::Name => match (name(a), name(b)) {
+                    (Some(na), Some(nb)) => na.cmp(&nb),
+                    // Not reversed by the direction, so unnamed devices stay at the end
+                    (Some(_), None) => return Ordering::Less,
+                    (None, Some(_)) => return Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                },
+            }
+            .then(a.cmp(b));
+            if self.descending { ordering.reverse() } else { ordering }
+        });
+        ids
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{} {}", self.key.label(), if self.descending { "desc" } else { "asc" })
+    }
+}
+
+/// Columns the object table can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ObjectSortKey {
+    /// Object type, then instance
+    #[default]
+    Type,
+    Instance,
+    Name,
+    /// Numeric values first, then text, then unknown
+    Value,
+    LastUpdate,
+}
+
+impl ObjectSortKey {
+    pub fn next(self) -> Self {
+        match self {
+            ObjectSortKey::Type => ObjectSortKey::Instance,
+            ObjectSortKey::Instance => ObjectSortKey::Name,
+            ObjectSortKey::Name => ObjectSortKey::Value,
+            ObjectSortKey::Value => ObjectSortKey::LastUpdate,
+            ObjectSortKey::LastUpdate => ObjectSortKey::Type,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ObjectSortKey::Type => "Type",
+            ObjectSortKey::Instance => "Instance",
+            ObjectSortKey::Name => "Name",
+            ObjectSortKey::Value => "Value",
+            ObjectSortKey::LastUpdate => "Last Update",
+        }
+    }
+}
+
+/// Order of the object table
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ObjectSort {
+    pub key: ObjectSortKey,
+    pub descending: bool,
+}
+
+impl ObjectSort {
+    /// Sorts in place. Ties fall back to type and instance.
+    pub fn apply(&self, objects: &mut [&BacnetObject]) {
+        let by_id = |a: &BacnetObject, b: &BacnetObject| {
+            (a.id.object_type as u16, a.id.instance).cmp(&(b.id.object_type as u16, b.id.instance))
+        };
+        objects.sort_by(|a, b| {
+            let ordering = match self.key {
+                ObjectSortKey::Type => Ordering::Equal,
+                ObjectSortKey::Instance => a.id.instance.cmp(&b.id.instance),
+                ObjectSortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+                ObjectSortKey::Value => compare_values(&a.present_value, &b.present_value),
+                ObjectSortKey::LastUpdate => a.last_updated.cmp(&b.last_updated),
+            }
+            .then_with(|| by_id(a, b));
+            if self.descending { ordering.reverse() } else { ordering }
+        });
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{} {}", self.key.label(), if self.descending { "desc" } else { "asc" })
+    }
+}
+
+fn compare_values(a: &PresentValue, b: &PresentValue) -> Ordering {
+    let text = |v: &PresentValue| match v {
+        PresentValue::Text(t) => Some(t.to_lowercase()),
+        _ => None,
+    };
+    match (a.as_f64(), b.as_f64()) {
+        (Some(x), Some(y)) => x.total_cmp(&y),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => match (text(a), text(b)) {
+            (Some(x), Some(y)) => x.cmp(&y),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        },
+    }
+}
+
+/// Case-insensitive subsequence match, so "ahusat" finds "AHU-1 Supply Air Temp".
+/// Whitespace in the query is ignored.
+pub fn fuzzy_match(query: &str, candidate: &str) -> bool {
+    let mut remaining = candidate.chars().flat_map(char::to_lowercase);
+    query.chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .all(|q| remaining.any(|c| c == q))
+}
+
+/// A selected row, independent of its position in the list
+enum RowKey {
+    Device(u32),
+    Object(ObjectIdentifier),
+}
+
+/// How a device compares with the last saved scan of the site
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SiteMarker {
+    /// Answered in this session but not in the site database
+    New,
+    /// In the site database but has not answered in this session
+    Missing,
+}
+
+/// Identifies an object on a specific device
+pub type ObjectKey = (u32, ObjectIdentifier);
+
 pub enum ViewState {
     InterfaceSelect,
     DeviceList,
     ObjectList(u32), // Selected Device ID
+    ObjectProperties(u32, ObjectIdentifier), // Property browser for one object
+    ScanDiff, // Changes since the baseline scan
+    Traffic, // Decoded packets received on the BACnet sockets
+    Health, // Request statistics per device, worst first
+    Topology, // Subnets, routers, networks and devices as a tree
+}
+
+impl ViewState {
+    /// Views opened on top of the current one, which Esc returns to
+    fn is_overlay(&self) -> bool {
+        matches!(self, ViewState::ScanDiff | ViewState::Traffic | ViewState::Health | ViewState::Topology)
+    }
 }
 
 pub struct App {
@@ -28,28 +444,98 @@ pub struct App {
     pub interface_list_state: ListState,
     /// Selected interface index
     pub selected_interface_index: Option<usize>,
+    /// Serial device offered as an extra MS/TP entry after the IP interfaces
+    pub mstp_port: Option<String>,
     
     /// Discovered devices indexed by their Device ID
     pub devices: Arc<Mutex<HashMap<u32, DiscoveredDevice>>>,
+    /// Address history per device ID; survives rescans
+    pub address_book: Arc<Mutex<AddressBook>>,
+    /// Device object properties read when a device is entered
+    pub device_properties: Arc<Mutex<HashMap<u32, Vec<PropertyEntry>>>>,
     /// Objects for each device
     pub device_objects: Arc<Mutex<HashMap<u32, Vec<BacnetObject>>>>,
+    /// Every property of an object, read when it is opened in the property browser
+    pub object_properties: Arc<Mutex<HashMap<ObjectKey, Vec<PropertyEntry>>>>,
     /// State for the UI list widget
     pub list_state: ListState,
     /// State for the object table widget
     pub object_table_state: TableState,
+    /// State for the property browser table
+    pub property_table_state: TableState,
+    /// Search and filters applied to the object table
+    pub object_filter: ObjectFilter,
+    /// True while the '/' search prompt is taking keystrokes
+    pub search_editing: bool,
+    /// Order of the device list
+    pub device_sort: DeviceSort,
+    /// Order of the object table
+    pub object_sort: ObjectSort,
+    /// Digits typed for a jump, while the prompt is open
+    pub jump_input: Option<String>,
+    /// Screen area of the rows of the list in the current view, set on every draw
+    pub list_rows_area: Option<Rect>,
+    /// Rows moved by PageUp/PageDown; the visible height of the current list
+    pub page_size: usize,
+    /// Site database loaded at startup; the baseline for new/missing markers
+    pub site_db: Option<SiteDb>,
+    /// Devices that sent an I-Am since the last scan started
+    pub seen_devices: HashSet<u32>,
+    /// Scan to compare against with 'c'; the site database is used when unset
+    pub baseline: Option<ScanExport>,
+    /// Result of the last comparison, shown in the scan diff view
+    pub scan_diff: Option<ScanDiff>,
+    /// State for the scan diff list
+    pub diff_list_state: ListState,
+    /// Datagrams received on the discovery and client sockets, for the traffic view
+    pub traffic: Arc<Mutex<TrafficLog>>,
+    /// Filter applied to the traffic view
+    pub traffic_filter: PacketFilter,
+    /// Filter expression being typed, while the prompt is open
+    pub traffic_filter_input: Option<String>,
+    /// State for the traffic list
+    pub traffic_list_state: ListState,
+    /// Keep the newest packet selected as traffic arrives
+    pub traffic_follow: bool,
+    /// Outcome and round-trip statistics of confirmed requests, per device address
+    pub health: Arc<Mutex<HealthStats>>,
+    /// State for the health table
+    pub health_table_state: TableState,
+    /// Broadcasts received on the BACnet sockets, for storm and chatty-device warnings
+    pub broadcasts: Arc<Mutex<BroadcastMonitor>>,
+    /// Broadcast thresholds exceeded at the last check
+    pub broadcast_warnings: Vec<StormWarning>,
+    /// Routers, networks, BBMDs and devices learned from the received traffic
+    pub topology: Arc<Mutex<Topology>>,
+    /// State for the topology tree
+    pub topology_list_state: ListState,
     /// Current status bar message
     pub status_message: String,
     /// Current view state
     pub view_state: ViewState,
+    /// View to return to when an overlay view is closed
+    previous_view: Option<ViewState>,
     /// Is a discovery scan currently active?
     pub is_scanning: bool,
     /// Monotonic counter for confirmed requests
     pub next_invoke_id: u8,
 }
 
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl App {
     pub fn new() -> Self {
         let interfaces = if_addrs::get_if_addrs().unwrap_or_default();
+        let mut topology = Topology::new();
+        for iface in &interfaces {
+            if let if_addrs::IfAddr::V4(v4) = &iface.addr {
+                topology.add_local_subnet(v4.ip, v4.netmask);
+            }
+        }
         let mut interface_list_state = ListState::default();
         if !interfaces.is_empty() {
             interface_list_state.select(Some(0));
@@ -59,17 +545,56 @@ impl App {
             interfaces,
             interface_list_state,
             selected_interface_index: None,
+            mstp_port: None,
             devices: Arc::new(Mutex::new(HashMap::new())),
+            address_book: Arc::new(Mutex::new(AddressBook::new())),
+            device_properties: Arc::new(Mutex::new(HashMap::new())),
             device_objects: Arc::new(Mutex::new(HashMap::new())),
+            object_properties: Arc::new(Mutex::new(HashMap::new())),
             list_state: ListState::default(),
             object_table_state: TableState::default(),
+            property_table_state: TableState::default(),
+            object_filter: ObjectFilter::default(),
+            search_editing: false,
+            device_sort: DeviceSort::default(),
+            object_sort: ObjectSort::default(),
+            jump_input: None,
+            list_rows_area: None,
+            page_size: 10,
+            site_db: None,
+            seen_devices: HashSet::new(),
+            baseline: None,
+            scan_diff: None,
+            diff_list_state: ListState::default(),
+            traffic: Arc::new(Mutex::new(TrafficLog::default())),
+            traffic_filter: PacketFilter::default(),
+            traffic_filter_input: None,
+            traffic_list_state: ListState::default(),
+            traffic_follow: true,
+            health: Arc::new(Mutex::new(HealthStats::new())),
+            health_table_state: TableState::default(),
+            broadcasts: Arc::new(Mutex::new(BroadcastMonitor::default())),
+            broadcast_warnings: Vec::new(),
+            topology: Arc::new(Mutex::new(topology)),
+            topology_list_state: ListState::default(),
             status_message: "Select an interface and press 'Enter'".to_string(),
             view_state: ViewState::InterfaceSelect,
+            previous_view: None,
             is_scanning: false,
             next_invoke_id: 1,
         }
     }
 
+    /// Number of entries in the interface list, including the MS/TP port if configured
+    pub fn interface_count(&self) -> usize {
+        self.interfaces.len() + usize::from(self.mstp_port.is_some())
+    }
+
+    /// True when the selected interface is the MS/TP serial port
+    pub fn is_mstp_selected(&self) -> bool {
+        self.mstp_port.is_some() && self.selected_interface_index == Some(self.interfaces.len())
+    }
+
     /// Gets the next invoke ID and increments the counter
     pub fn get_next_invoke_id(&mut self) -> u8 {
         let id = self.next_invoke_id;
@@ -78,77 +603,197 @@ impl App {
         id
     }
 
-    pub fn next(&mut self) {
+    /// Adds a device from an I-Am, checking its address against the history
+    pub fn record_device(&mut self, device: DiscoveredDevice) -> Option<AddressEvent> {
+        let event = self.address_book.lock().unwrap().record(device.device_id, device.address, Instant::now());
+        self.seen_devices.insert(device.device_id);
+        self.devices.lock().unwrap().insert(device.device_id, device);
+        event
+    }
+
+    /// Refreshes liveness for a device after any successful response
+    pub fn mark_device_seen(&self, device_id: u32) {
+        if let Some(device) = self.devices.lock().unwrap().get_mut(&device_id) {
+            device.mark_seen();
+        }
+    }
+
+    /// Counts a request timeout against a device. Returns true if it just went offline.
+    pub fn record_device_timeout(&self, device_id: u32) -> bool {
+        self.devices.lock().unwrap()
+            .get_mut(&device_id)
+            .is_some_and(|device| device.record_timeout())
+    }
+
+    /// Number of rows in the list of the current view
+    fn list_len(&self) -> usize {
         match self.view_state {
-            ViewState::InterfaceSelect => {
-                if self.interfaces.is_empty() { return; }
-                let i = match self.interface_list_state.selected() {
-                    Some(i) => if i >= self.interfaces.len() - 1 { 0 } else { i + 1 },
-                    None => 0,
-                };
-                self.interface_list_state.select(Some(i));
-            }
-            ViewState::DeviceList => {
-                let devices = self.devices.lock().unwrap();
-                if devices.is_empty() { return; }
-                let i = match self.list_state.selected() {
-                    Some(i) => if i >= devices.len() - 1 { 0 } else { i + 1 },
-                    None => 0,
-                };
-                self.list_state.select(Some(i));
-            }
+            ViewState::InterfaceSelect => self.interface_count(),
+            ViewState::DeviceList => self.devices.lock().unwrap().len(),
             ViewState::ObjectList(device_id) => {
                 let objects = self.device_objects.lock().unwrap();
-                if let Some(device_objs) = objects.get(&device_id) {
-                    if device_objs.is_empty() { return; }
-                    let i = match self.object_table_state.selected() {
-                        Some(i) => if i >= device_objs.len() - 1 { 0 } else { i + 1 },
-                        None => 0,
-                    };
-                    self.object_table_state.select(Some(i));
-                }
+                objects.get(&device_id).map_or(0, |objs| objs.iter().filter(|o| self.object_filter.matches(o)).count())
+            }
+            ViewState::ObjectProperties(device_id, object_id) => {
+                self.object_properties.lock().unwrap().get(&(device_id, object_id)).map_or(0, Vec::len)
             }
+            ViewState::ScanDiff => self.scan_diff.as_ref().map_or(0, |diff| diff.lines().len()),
+            ViewState::Traffic => self.traffic.lock().unwrap().matching(&self.traffic_filter).len(),
+            ViewState::Health => self.health.lock().unwrap().len(),
+            ViewState::Topology => self.topology.lock().unwrap().tree().len(),
         }
     }
 
-    pub fn previous(&mut self) {
+    fn selected_index(&self) -> Option<usize> {
         match self.view_state {
-            ViewState::InterfaceSelect => {
-                if self.interfaces.is_empty() { return; }
-                let i = match self.interface_list_state.selected() {
-                    Some(i) => if i == 0 { self.interfaces.len() - 1 } else { i - 1 },
-                    None => 0,
-                };
-                self.interface_list_state.select(Some(i));
-            }
-            ViewState::DeviceList => {
-                let devices = self.devices.lock().unwrap();
-                if devices.is_empty() { return; }
-                let i = match self.list_state.selected() {
-                    Some(i) => if i == 0 { devices.len() - 1 } else { i - 1 },
-                    None => 0,
-                };
-                self.list_state.select(Some(i));
+            ViewState::InterfaceSelect => self.interface_list_state.selected(),
+            ViewState::DeviceList => self.list_state.selected(),
+            ViewState::ObjectList(_) => self.object_table_state.selected(),
+            ViewState::ObjectProperties(..) => self.property_table_state.selected(),
+            ViewState::ScanDiff => self.diff_list_state.selected(),
+            ViewState::Traffic => self.traffic_list_state.selected(),
+            ViewState::Health => self.health_table_state.selected(),
+            ViewState::Topology => self.topology_list_state.selected(),
+        }
+    }
+
+    /// Index of the first row currently scrolled into view
+    fn list_offset(&self) -> usize {
+        match self.view_state {
+            ViewState::InterfaceSelect => self.interface_list_state.offset(),
+            ViewState::DeviceList => self.list_state.offset(),
+            ViewState::ObjectList(_) => self.object_table_state.offset(),
+            ViewState::ObjectProperties(..) => self.property_table_state.offset(),
+            ViewState::ScanDiff => self.diff_list_state.offset(),
+            ViewState::Traffic => self.traffic_list_state.offset(),
+            ViewState::Health => self.health_table_state.offset(),
+            ViewState::Topology => self.topology_list_state.offset(),
+        }
+    }
+
+    fn set_selected(&mut self, index: Option<usize>) {
+        match self.view_state {
+            ViewState::InterfaceSelect => self.interface_list_state.select(index),
+            ViewState::DeviceList => self.list_state.select(index),
+            ViewState::ObjectList(_) => self.object_table_state.select(index),
+            ViewState::ObjectProperties(..) => self.property_table_state.select(index),
+            ViewState::ScanDiff => self.diff_list_state.select(index),
+            ViewState::Traffic => {
+                // Moving to the newest packet resumes following; anywhere else holds the selection
+                self.traffic_follow = index.is_none_or(|i| i + 1 >= self.list_len());
+                self.traffic_list_state.select(index);
             }
+            ViewState::Health => self.health_table_state.select(index),
+            ViewState::Topology => self.topology_list_state.select(index),
+        }
+    }
+
+    /// Selects the next row, wrapping to the top
+    pub fn next(&mut self) {
+        let count = self.list_len();
+        if count == 0 { return; }
+        let i = match self.selected_index() {
+            Some(i) => if i >= count - 1 { 0 } else { i + 1 },
+            None => 0,
+        };
+        self.set_selected(Some(i));
+    }
+
+    /// Selects the previous row, wrapping to the bottom
+    pub fn previous(&mut self) {
+        let count = self.list_len();
+        if count == 0 { return; }
+        let i = match self.selected_index() {
+            Some(i) => if i == 0 { count - 1 } else { i - 1 },
+            None => 0,
+        };
+        self.set_selected(Some(i));
+    }
+
+    /// Moves the selection by `delta` rows, stopping at either end
+    pub fn move_selection(&mut self, delta: isize) {
+        let count = self.list_len();
+        if count == 0 { return; }
+        let i = self.selected_index().map_or(0, |i| i.saturating_add_signed(delta).min(count - 1));
+        self.set_selected(Some(i));
+    }
+
+    pub fn page_down(&mut self) {
+        self.move_selection(self.page_size.max(1) as isize);
+    }
+
+    pub fn page_up(&mut self) {
+        self.move_selection(-(self.page_size.max(1) as isize));
+    }
+
+    pub fn select_first(&mut self) {
+        if self.list_len() > 0 {
+            self.set_selected(Some(0));
+        }
+    }
+
+    pub fn select_last(&mut self) {
+        let count = self.list_len();
+        if count > 0 {
+            self.set_selected(Some(count - 1));
+        }
+    }
+
+    /// Selects the row under a mouse click. Returns false if the click missed the list.
+    pub fn click(&mut self, column: u16, row: u16) -> bool {
+        let Some(area) = self.list_rows_area.filter(|a| a.contains(Position::new(column, row))) else { return false };
+        let index = self.list_offset() + (row - area.y) as usize;
+        if index >= self.list_len() { return false; }
+        self.set_selected(Some(index));
+        true
+    }
+
+    /// Adds a digit to the jump prompt, opening it if needed
+    pub fn push_jump_digit(&mut self, digit: char) {
+        self.jump_input.get_or_insert_with(String::new).push(digit);
+    }
+
+    pub fn pop_jump_digit(&mut self) {
+        if let Some(input) = &mut self.jump_input {
+            input.pop();
+        }
+    }
+
+    /// Closes the jump prompt, jumping to the number typed unless cancelled
+    pub fn finish_jump(&mut self, jump: bool) {
+        let Some(input) = self.jump_input.take() else { return };
+        if let (true, Ok(number)) = (jump, input.parse::<u32>()) {
+            self.jump_to_number(number);
+        }
+    }
+
+    /// Jumps to the device with this ID or the object with this instance; failing
+    /// that, to the row with this 1-based number.
+    pub fn jump_to_number(&mut self, number: u32) {
+        let by_id = match self.view_state {
+            ViewState::DeviceList => self.visible_device_ids().iter().position(|id| *id == number),
             ViewState::ObjectList(device_id) => {
                 let objects = self.device_objects.lock().unwrap();
-                if let Some(device_objs) = objects.get(&device_id) {
-                    if device_objs.is_empty() { return; }
-                    let i = match self.object_table_state.selected() {
-                        Some(i) => if i == 0 { device_objs.len() - 1 } else { i - 1 },
-                        None => 0,
-                    };
-                    self.object_table_state.select(Some(i));
-                }
+                objects.get(&device_id).and_then(|objs| self.visible_objects(objs).iter().position(|o| o.id.instance == number))
             }
+            _ => None,
+        };
+        let count = self.list_len();
+        match by_id.or_else(|| (number as usize).checked_sub(1).filter(|row| *row < count)) {
+            Some(index) => self.set_selected(Some(index)),
+            None => self.status_message = format!("Nothing to jump to at {}.", number),
         }
     }
 
+    /// Starts a new scan. Devices from the site database stay listed, offline,
+    /// until they answer again.
     pub fn clear(&mut self) {
-        let mut devices = self.devices.lock().unwrap();
-        devices.clear();
-        let mut objects = self.device_objects.lock().unwrap();
-        objects.clear();
+        self.devices.lock().unwrap().clear();
+        self.device_properties.lock().unwrap().clear();
+        self.object_properties.lock().unwrap().clear();
+        self.device_objects.lock().unwrap().clear();
+        self.seen_devices.clear();
+        self.restore_site();
         self.list_state.select(None);
         self.object_table_state.select(None);
         self.status_message = "Scanning for devices...".to_string();
@@ -159,32 +804,415 @@ impl App {
         if let Some(i) = self.interface_list_state.selected() {
             self.selected_interface_index = Some(i);
             self.view_state = ViewState::DeviceList;
-            self.status_message = format!("Interface {} selected. Press 'd' to discover.", self.interfaces[i].name);
+            let name = match self.interfaces.get(i) {
+                Some(iface) => iface.name.clone(),
+                None => format!("MS/TP {}", self.mstp_port.as_deref().unwrap_or_default()),
+            };
+            self.status_message = format!("Interface {} selected. Press 'd' to discover.", name);
         }
     }
 
     pub fn enter_device(&mut self) {
         if let ViewState::DeviceList = self.view_state {
-            let devices = self.devices.lock().unwrap();
-            let mut device_ids: Vec<_> = devices.keys().cloned().collect();
-            device_ids.sort();
-            
-            if let Some(index) = self.list_state.selected() {
-                if let Some(id) = device_ids.get(index) {
-                    self.view_state = ViewState::ObjectList(*id);
-                    self.object_table_state.select(Some(0));
-                    self.status_message = format!("Viewing device {}. Press 'Esc' to go back, 'd' to discover points", id);
-                }
+            let device_ids = self.visible_device_ids();
+            if let Some(index) = self.list_state.selected()
+                && let Some(id) = device_ids.get(index)
+            {
+                self.view_state = ViewState::ObjectList(*id);
+                self.object_table_state.select(Some(0));
+                self.object_filter = ObjectFilter::default();
+                self.status_message = format!("Viewing device {}. Press 'Esc' to go back, 'd' to discover points", id);
+            }
+        }
+    }
+
+    /// Opens the property browser for the selected object
+    pub fn enter_object(&mut self) {
+        if let ViewState::ObjectList(device_id) = self.view_state {
+            let objects = self.device_objects.lock().unwrap();
+            if let Some(index) = self.object_table_state.selected()
+                && let Some(obj) = objects.get(&device_id).and_then(|objs| self.visible_objects(objs).get(index).copied())
+            {
+                self.view_state = ViewState::ObjectProperties(device_id, obj.id);
+                self.property_table_state.select(Some(0));
+                self.status_message = format!("Reading properties of {}. Press 'Esc' to go back, 'd' to re-read", obj.name);
             }
         }
     }
 
+    /// Takes a site database as the baseline and lists its devices until they answer
+    pub fn load_site(&mut self, db: SiteDb) {
+        self.site_db = Some(db);
+        self.restore_site();
+    }
+
+    fn restore_site(&mut self) {
+        let Some(db) = &self.site_db else { return };
+        let site = db.restore();
+        self.devices.lock().unwrap().extend(site.devices);
+        self.device_properties.lock().unwrap().extend(site.device_properties);
+        self.device_objects.lock().unwrap().extend(site.device_objects);
+    }
+
+    /// New/missing marker for a device; None without a site database or when nothing changed
+    pub fn site_marker(&self, device_id: u32) -> Option<SiteMarker> {
+        let db = self.site_db.as_ref()?;
+        let known = db.devices.iter().any(|d| d.device_id == device_id);
+        match (known, self.seen_devices.contains(&device_id)) {
+            (false, true) => Some(SiteMarker::New),
+            (true, false) => Some(SiteMarker::Missing),
+            _ => None,
+        }
+    }
+
+    /// Snapshot of everything discovered so far, for export
+    pub fn export(&self) -> ScanExport {
+        let devices = self.devices.lock().unwrap();
+        let properties = self.device_properties.lock().unwrap();
+        let objects = self.device_objects.lock().unwrap();
+        let mut scan = ScanExport::new(&devices, &properties, &objects);
+        scan.set_conflicts(&self.address_book.lock().unwrap().conflicts());
+        scan
+    }
+
+    /// Compares this session with the baseline scan (or the site database) and
+    /// opens the scan diff view. Devices only known from the site database are
+    /// left out, so the ones that did not answer show up as removed.
+    pub fn compare_with_baseline(&mut self) {
+        let Some(old) = self.baseline.clone().or_else(|| self.site_db.as_ref().map(SiteDb::to_scan)) else {
+            self.status_message = "No baseline to compare with: start with --baseline <scan.json> or a site database.".to_string();
+            return;
+        };
+        let mut current = self.export();
+        if self.site_db.is_some() {
+            current.devices.retain(|d| self.seen_devices.contains(&d.device_id));
+        }
+        let diff = ScanDiff::new(&old, &current);
+        self.status_message = diff.lines().last().cloned().unwrap_or_default();
+        self.scan_diff = Some(diff);
+        self.diff_list_state.select(Some(0));
+        self.open_overlay(ViewState::ScanDiff);
+    }
+
+    /// EDE files for everything discovered so far
+    pub fn export_ede(&self, project: &str) -> EdeExport {
+        let devices = self.devices.lock().unwrap();
+        let properties = self.device_properties.lock().unwrap();
+        let objects = self.device_objects.lock().unwrap();
+        EdeExport::new(project, &devices, &properties, &objects)
+    }
+
+    /// Device IDs in the order the device list shows them
+    pub fn visible_device_ids(&self) -> Vec<u32> {
+        let devices = self.devices.lock().unwrap();
+        let properties = self.device_properties.lock().unwrap();
+        self.device_sort.apply(&devices, &properties)
+    }
+
+    /// The rows of the object table: filtered, then sorted
+    pub fn visible_objects<'a>(&self, objects: &'a [BacnetObject]) -> Vec<&'a BacnetObject> {
+        let mut visible = self.object_filter.apply(objects);
+        self.object_sort.apply(&mut visible);
+        visible
+    }
+
+    /// Steps to the next sort column of the current list, keeping the selected row selected
+    pub fn cycle_sort_key(&mut self) {
+        let selected = self.selected_row();
+        match self.view_state {
+            ViewState::DeviceList => {
+                self.device_sort.key = self.device_sort.key.next();
+                self.status_message = format!("Devices sorted by {}.", self.device_sort.describe());
+            }
+            ViewState::ObjectList(_) => {
+                self.object_sort.key = self.object_sort.key.next();
+                self.status_message = format!("Objects sorted by {}.", self.object_sort.describe());
+            }
+            _ => return,
+        }
+        self.reselect(selected);
+    }
+
+    /// Flips between ascending and descending order of the current list
+    pub fn toggle_sort_direction(&mut self) {
+        let selected = self.selected_row();
+        match self.view_state {
+            ViewState::DeviceList => {
+                self.device_sort.descending = !self.device_sort.descending;
+                self.status_message = format!("Devices sorted by {}.", self.device_sort.describe());
+            }
+            ViewState::ObjectList(_) => {
+                self.object_sort.descending = !self.object_sort.descending;
+                self.status_message = format!("Objects sorted by {}.", self.object_sort.describe());
+            }
+            _ => return,
+        }
+        self.reselect(selected);
+    }
+
+    fn selected_row(&self) -> Option<RowKey> {
+        match self.view_state {
+            ViewState::DeviceList => {
+                let index = self.list_state.selected()?;
+                self.visible_device_ids().get(index).map(|id| RowKey::Device(*id))
+            }
+            ViewState::ObjectList(device_id) => {
+                let index = self.object_table_state.selected()?;
+                let objects = self.device_objects.lock().unwrap();
+                objects.get(&device_id).and_then(|objs| self.visible_objects(objs).get(index).map(|o| RowKey::Object(o.id)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Moves the selection to where a row ended up after the list was re-ordered
+    fn reselect(&mut self, row: Option<RowKey>) {
+        match (row, &self.view_state) {
+            (Some(RowKey::Device(id)), ViewState::DeviceList) => {
+                let index = self.visible_device_ids().iter().position(|d| *d == id);
+                self.list_state.select(index);
+            }
+            (Some(RowKey::Object(id)), ViewState::ObjectList(device_id)) => {
+                let objects = self.device_objects.lock().unwrap();
+                let index = objects.get(device_id).and_then(|objs| self.visible_objects(objs).iter().position(|o| o.id == id));
+                self.object_table_state.select(index);
+            }
+            _ => {}
+        }
+    }
+
+    /// Opens the '/' search prompt on the object table, or the filter prompt in the traffic view
+    pub fn start_search(&mut self) {
+        match self.view_state {
+            ViewState::ObjectList(_) => self.search_editing = true,
+            ViewState::Traffic => self.start_traffic_filter(),
+            _ => {}
+        }
+    }
+
+    pub fn push_search_char(&mut self, c: char) {
+        self.object_filter.query.push(c);
+        self.reset_object_selection();
+    }
+
+    pub fn pop_search_char(&mut self) {
+        self.object_filter.query.pop();
+        self.reset_object_selection();
+    }
+
+    /// Closes the search prompt, keeping the query or clearing it
+    pub fn finish_search(&mut self, keep: bool) {
+        self.search_editing = false;
+        if !keep {
+            self.object_filter.query.clear();
+            self.reset_object_selection();
+        }
+    }
+
+    /// Steps the object table through All, AI, AO, AV, BI, BO, BV, MSx and Other
+    pub fn cycle_type_filter(&mut self) {
+        if let ViewState::ObjectList(_) = self.view_state {
+            self.object_filter.type_filter = self.object_filter.type_filter.next();
+            self.status_message = format!("Showing {} objects.", self.object_filter.type_filter.label());
+            self.reset_object_selection();
+        }
+    }
+
+    pub fn toggle_problems_filter(&mut self) {
+        if let ViewState::ObjectList(_) = self.view_state {
+            self.object_filter.problems_only = !self.object_filter.problems_only;
+            self.status_message = if self.object_filter.problems_only {
+                "Showing only alarmed or overridden objects.".to_string()
+            } else {
+                "Showing all objects.".to_string()
+            };
+            self.reset_object_selection();
+        }
+    }
+
+    /// Selects the first row of the filtered object table, if there is one
+    fn reset_object_selection(&mut self) {
+        let ViewState::ObjectList(device_id) = self.view_state else { return };
+        let objects = self.device_objects.lock().unwrap();
+        let any = objects.get(&device_id).is_some_and(|objs| objs.iter().any(|o| self.object_filter.matches(o)));
+        self.object_table_state.select(any.then_some(0));
+    }
+
+    /// Opens the traffic view; traffic is only captured once an interface is selected
+    pub fn show_traffic(&mut self) {
+        if let ViewState::InterfaceSelect = self.view_state {
+            return;
+        }
+        self.open_overlay(ViewState::Traffic);
+        self.traffic_follow = true;
+        self.status_message = "Traffic: 'p' pause, '/' filter, 'c' clear, Esc to go back".to_string();
+    }
+
+    /// Pauses or resumes capturing into the traffic view
+    pub fn toggle_traffic_pause(&mut self) {
+        let mut log = self.traffic.lock().unwrap();
+        let paused = !log.is_paused();
+        log.set_paused(paused);
+        self.status_message = if paused {
+            "Traffic capture paused; press 'p' to resume.".to_string()
+        } else {
+            format!("Traffic capture resumed ({} packets missed while paused).", log.missed)
+        };
+    }
+
+    pub fn clear_traffic(&mut self) {
+        self.traffic.lock().unwrap().clear();
+        self.traffic_list_state.select(None);
+        self.traffic_follow = true;
+        self.status_message = "Traffic cleared.".to_string();
+    }
+
+    /// Opens the filter prompt with the current expression
+    pub fn start_traffic_filter(&mut self) {
+        self.traffic_filter_input = Some(self.traffic_filter.as_str().to_string());
+    }
+
+    /// Closes the filter prompt, applying the expression or discarding it. An expression
+    /// that does not parse leaves the prompt open with the error in the status bar.
+    pub fn finish_traffic_filter(&mut self, apply: bool) {
+        let Some(input) = self.traffic_filter_input.take() else { return };
+        if !apply {
+            return;
+        }
+        match PacketFilter::parse(&input) {
+            Ok(filter) => {
+                self.status_message = if filter.is_empty() {
+                    "Showing all traffic.".to_string()
+                } else {
+                    format!("Filter: {}", filter.as_str())
+                };
+                self.traffic_filter = filter;
+                self.traffic_follow = true;
+            }
+            Err(e) => {
+                self.status_message = format!("Invalid filter: {}", e);
+                self.traffic_filter_input = Some(input);
+            }
+        }
+    }
+
+    /// Keeps the newest matching packet selected while following
+    pub fn follow_traffic(&mut self) {
+        if self.traffic_follow {
+            let count = self.traffic.lock().unwrap().matching(&self.traffic_filter).len();
+            self.traffic_list_state.select(count.checked_sub(1));
+        }
+    }
+
+    /// Opens the health view; requests are only sent once an interface is selected
+    pub fn show_health(&mut self) {
+        if let ViewState::InterfaceSelect = self.view_state {
+            return;
+        }
+        self.open_overlay(ViewState::Health);
+        let count = self.health.lock().unwrap().len();
+        self.health_table_state.select((count > 0).then_some(0));
+        self.status_message = "Health: worst devices first; 'c' resets the counters, Esc to go back".to_string();
+    }
+
+    /// Resets the request counters and the broadcast monitor
+    pub fn clear_health(&mut self) {
+        self.health.lock().unwrap().clear();
+        self.broadcasts.lock().unwrap().clear();
+        self.broadcast_warnings.clear();
+        self.health_table_state.select(None);
+        self.status_message = "Health counters reset.".to_string();
+    }
+
+    /// Re-checks the broadcast rates at `now` (time since the Unix epoch). Warnings that
+    /// were not active at the last check are returned, and the first goes to the status bar.
+    pub fn check_broadcasts(&mut self, now: Duration) -> Vec<StormWarning> {
+        let warnings = self.broadcasts.lock().unwrap().warnings(now);
+        let raised: Vec<StormWarning> = warnings.iter()
+            .filter(|w| !self.broadcast_warnings.iter().any(|old| old.same_subject(w)))
+            .cloned()
+            .collect();
+        if let Some(first) = raised.first() {
+            self.status_message = format!("WARNING: {}", first);
+        }
+        self.broadcast_warnings = warnings;
+        raised
+    }
+
+    /// Request statistics for a device, if any request has been sent to it
+    pub fn device_health(&self, device: &DiscoveredDevice) -> Option<DeviceHealth> {
+        self.health.lock().unwrap().get(&device.address).cloned()
+    }
+
+    /// Health of every device requests were sent to, worst first, with the ID of the
+    /// device currently at each address
+    pub fn health_ranking(&self) -> Vec<(Option<u32>, SocketAddr, DeviceHealth)> {
+        let devices = self.devices.lock().unwrap();
+        let health = self.health.lock().unwrap();
+        health.ranked().into_iter().map(|(addr, h)| {
+            let device_id = devices.values().find(|d| d.address == addr).map(|d| d.device_id);
+            (device_id, addr, h.clone())
+        }).collect()
+    }
+
+    /// Opens the topology view; the tree fills in as traffic is received
+    pub fn show_topology(&mut self) {
+        if let ViewState::InterfaceSelect = self.view_state {
+            return;
+        }
+        self.open_overlay(ViewState::Topology);
+        let empty = self.topology.lock().unwrap().is_empty();
+        self.topology_list_state.select((!empty).then_some(0));
+        self.status_message = "Topology: 'd' asks routers and BBMDs again, 'x' exports DOT, 'c' clears, Esc to go back".to_string();
+    }
+
+    /// The topology as tree rows, subnet first
+    pub fn topology_tree(&self) -> Vec<TreeNode> {
+        self.topology.lock().unwrap().tree()
+    }
+
+    pub fn clear_topology(&mut self) {
+        self.topology.lock().unwrap().clear();
+        self.topology_list_state.select(None);
+        self.status_message = "Topology cleared.".to_string();
+    }
+
+    /// Writes the topology as Graphviz DOT to `<base>.dot`
+    pub fn export_topology(&mut self, base: &Path) {
+        let path = base.with_extension("dot");
+        let dot = self.topology.lock().unwrap().to_dot();
+        self.status_message = match std::fs::write(&path, dot) {
+            Ok(()) => format!("Topology written to {}.", path.display()),
+            Err(e) => format!("Topology export failed: {}", e),
+        };
+    }
+
+    /// Switches to an overlay view, remembering the view below it for Esc
+    fn open_overlay(&mut self, view: ViewState) {
+        let previous = std::mem::replace(&mut self.view_state, view);
+        if !previous.is_overlay() {
+            self.previous_view = Some(previous);
+        }
+    }
+
     pub fn exit_view(&mut self) {
         match self.view_state {
+            ViewState::ObjectProperties(device_id, _) => {
+                self.view_state = ViewState::ObjectList(device_id);
+                self.status_message = format!("Viewing device {}. Press 'Esc' to go back, 'd' to discover points", device_id);
+            }
             ViewState::ObjectList(_) => {
                 self.view_state = ViewState::DeviceList;
                 self.status_message = "Press 'd' to discover devices, 'Enter' to view points, 'q' to quit".to_string();
             }
+            ViewState::ScanDiff | ViewState::Traffic | ViewState::Health | ViewState::Topology => {
+                self.view_state = self.previous_view.take().unwrap_or(ViewState::DeviceList);
+                self.status_message = match self.view_state {
+                    ViewState::ObjectList(device_id) => format!("Viewing device {}. Press 'Esc' to go back, 'd' to discover points", device_id),
+                    ViewState::ObjectProperties(..) => "Press 'Esc' to go back, 'd' to re-read".to_string(),
+                    _ => "Press 'd' to discover devices, 'Enter' to view points, 'q' to quit".to_string(),
+                };
+            }
             ViewState::DeviceList => {
                 self.view_state = ViewState::InterfaceSelect;
                 self.status_message = "Select an interface and press 'Enter'".to_string();
@@ -8,11 +8,25 @@ use bacnet_rs::{
     },
     vendor::get_vendor_name,
 };
-use std::net::{SocketAddr, IpAddr, UdpSocket};
+use std::collections::{HashMap, HashSet};
+use std::net::{SocketAddr, IpAddr};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use anyhow::{Result, anyhow};
 use tracing::{debug, info, warn};
 use crate::app::BacnetObject;
+use crate::health::{HealthStats, RequestOutcome};
+use crate::network::DataLink;
+use crate::property::{self, parse_read_property_ack, parse_rpm_ack, BacnetValue, PresentValue, PropertyEntry, PropertyError, StateTexts, StatusFlags};
+
+/// Consecutive request timeouts before a device is considered offline
+pub const OFFLINE_AFTER_TIMEOUTS: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceStatus {
+    Online,
+    Offline,
+}
 
 #[derive(Debug, Clone)]
 pub struct DiscoveredDevice {
@@ -23,18 +37,188 @@ pub struct DiscoveredDevice {
     pub max_apdu: u32,
     pub segmentation: u32,
     pub last_seen: Instant,
+    pub status: DeviceStatus,
+    /// Timeouts since the last successful response
+    pub consecutive_timeouts: u32,
+}
+
+impl DiscoveredDevice {
+    /// A device that just answered, before its vendor, APDU size and segmentation are known
+    pub fn new(device_id: u32, address: SocketAddr) -> Self {
+        Self {
+            device_id,
+            address,
+            vendor_id: 0,
+            vendor_name: String::new(),
+            max_apdu: 1476,
+            segmentation: 3,
+            last_seen: Instant::now(),
+            status: DeviceStatus::Online,
+            consecutive_timeouts: 0,
+        }
+    }
+
+    /// Records a successful response from the device.
+    pub fn mark_seen(&mut self) {
+        self.last_seen = Instant::now();
+        self.consecutive_timeouts = 0;
+        self.status = DeviceStatus::Online;
+    }
+
+    /// Records a request timeout. Returns true if this timeout took the device offline.
+    pub fn record_timeout(&mut self) -> bool {
+        self.consecutive_timeouts += 1;
+        if self.status == DeviceStatus::Online && self.consecutive_timeouts >= OFFLINE_AFTER_TIMEOUTS {
+            self.status = DeviceStatus::Offline;
+            return true;
+        }
+        false
+    }
+
+    pub fn is_online(&self) -> bool {
+        self.status == DeviceStatus::Online
+    }
+}
+
+/// Returned by confirmed requests that get no answer in time.
+#[derive(Debug)]
+pub struct RequestTimeout {
+    pub addr: SocketAddr,
+    pub invoke_id: u8,
+}
+
+impl std::fmt::Display for RequestTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Timeout waiting for response from {} (Invoke {})", self.addr, self.invoke_id)
+    }
+}
+
+impl std::error::Error for RequestTimeout {}
+
+pub fn is_timeout(error: &anyhow::Error) -> bool {
+    error.is::<RequestTimeout>()
+}
+
+/// What a device sent back for a confirmed request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfirmedResponse {
+    /// Simple or complex ACK with its service data (empty for a simple ACK)
+    Ack(Vec<u8>),
+    /// Error PDU; class and code are `UNKNOWN_ERROR` when the body could not be decoded
+    Error { class: u32, code: u32 },
+    Reject(u8),
+    Abort(u8),
+}
+
+/// Error class and code reported for an Error PDU whose body could not be decoded.
+pub const UNKNOWN_ERROR: u32 = u32::MAX;
+
+const ERROR_CLASS_SERVICES: u32 = 5;
+const ERROR_SERVICE_REQUEST_DENIED: u32 = 29;
+const ERROR_UNRECOGNIZED_SERVICE: u32 = 53;
+
+/// Returned by confirmed requests that the device answers with Error, Reject or Abort.
+#[derive(Debug)]
+pub struct RequestFailed {
+    pub addr: SocketAddr,
+    pub response: ConfirmedResponse,
+}
+
+impl std::fmt::Display for RequestFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.response {
+            ConfirmedResponse::Error { class: UNKNOWN_ERROR, code: UNKNOWN_ERROR } => write!(f, "{} returned an undecodable error", self.addr),
+            ConfirmedResponse::Error { class, code } => write!(f, "{} returned error class {} code {}", self.addr, class, code),
+            ConfirmedResponse::Reject(reason) => write!(f, "{} rejected the request (reason {})", self.addr, reason),
+            ConfirmedResponse::Abort(reason) => write!(f, "{} aborted the request (reason {})", self.addr, reason),
+            ConfirmedResponse::Ack(_) => write!(f, "{} acknowledged the request", self.addr),
+        }
+    }
+}
+
+impl std::error::Error for RequestFailed {}
+
+/// Response channels of the requests in flight, by destination address and invoke ID
+pub type PendingRequests = HashMap<(SocketAddr, u8), tokio::sync::oneshot::Sender<ConfirmedResponse>>;
+
+/// Registers the response channel for a request, by destination address and invoke ID,
+/// with the receive loop and, when given a health table, records how each confirmed
+/// request ended.
+#[derive(Clone)]
+pub struct RequestRegistrar {
+    sender: tokio::sync::mpsc::Sender<(SocketAddr, u8, tokio::sync::oneshot::Sender<ConfirmedResponse>)>,
+    health: Option<Arc<Mutex<HealthStats>>>,
+    /// Devices that turned ReadPropertyMultiple down; polled with ReadProperty from then on
+    rpm_unsupported: Arc<Mutex<HashSet<SocketAddr>>>,
 }
 
-pub fn send_whois_to(socket: &UdpSocket, dest: SocketAddr) -> Result<()> {
+impl RequestRegistrar {
+    pub fn new(sender: tokio::sync::mpsc::Sender<(SocketAddr, u8, tokio::sync::oneshot::Sender<ConfirmedResponse>)>) -> Self {
+        Self { sender, health: None, rpm_unsupported: Arc::default() }
+    }
+
+    /// Records the outcome and round trip of every request into `health`
+    pub fn with_health(mut self, health: Arc<Mutex<HealthStats>>) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    async fn register(&self, addr: SocketAddr, invoke_id: u8, tx: tokio::sync::oneshot::Sender<ConfirmedResponse>) -> Result<()> {
+        self.sender.send((addr, invoke_id, tx)).await.map_err(|_| anyhow!("Failed to register request"))
+    }
+
+    fn record(&self, addr: SocketAddr, outcome: RequestOutcome, rtt: Option<Duration>) {
+        if let Some(health) = &self.health {
+            health.lock().unwrap().record(addr, outcome, rtt);
+        }
+    }
+
+    /// True once the device at `addr` has turned ReadPropertyMultiple down
+    pub fn rpm_unsupported(&self, addr: SocketAddr) -> bool {
+        self.rpm_unsupported.lock().unwrap().contains(&addr)
+    }
+
+    /// Whether an RPM to `addr` that failed with `e` should be repeated with
+    /// ReadProperty. A Reject or a services error marks the device as not
+    /// supporting RPM; an Abort, typically a reply too large for an unsegmented
+    /// response, only falls back for this request.
+    fn rpm_failed(&self, addr: SocketAddr, e: &anyhow::Error) -> bool {
+        match e.downcast_ref::<RequestFailed>().map(|f| &f.response) {
+            Some(ConfirmedResponse::Reject(_))
+            | Some(ConfirmedResponse::Error { class: ERROR_CLASS_SERVICES, code: ERROR_SERVICE_REQUEST_DENIED | ERROR_UNRECOGNIZED_SERVICE }) => {
+                self.rpm_unsupported.lock().unwrap().insert(addr);
+                true
+            }
+            Some(ConfirmedResponse::Abort(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+pub fn send_whois_to(socket: &(impl DataLink + ?Sized), dest: SocketAddr) -> Result<()> {
     debug!("Encoding Who-Is request for {}", dest);
-    let whois = WhoIsRequest::new();
+    send_whois(socket, dest, WhoIsRequest::new(), Npdu::global_broadcast())
+}
+
+/// Sends a Who-Is for device instances `low..=high`; broadcast or unicast depending on `dest`.
+pub fn send_ranged_whois(socket: &(impl DataLink + ?Sized), dest: SocketAddr, low: u32, high: u32) -> Result<()> {
+    debug!("Encoding Who-Is {}-{} for {}", low, high, dest);
+    send_whois(socket, dest, WhoIsRequest::for_range(low, high), Npdu::global_broadcast())
+}
+
+/// Sends a Who-Is limited to a single instance straight to a known device address.
+pub fn send_directed_whois(socket: &(impl DataLink + ?Sized), dest: SocketAddr, device_id: u32) -> Result<()> {
+    debug!("Encoding directed Who-Is for device {} at {}", device_id, dest);
+    send_whois(socket, dest, WhoIsRequest::for_device(device_id), Npdu::new())
+}
+
+fn send_whois(socket: &(impl DataLink + ?Sized), dest: SocketAddr, whois: WhoIsRequest, npdu: Npdu) -> Result<()> {
     let mut service_data = Vec::new();
     whois.encode(&mut service_data)?;
 
     let mut apdu = vec![0x10, UnconfirmedServiceChoice::WhoIs as u8];
     apdu.extend_from_slice(&service_data);
 
-    let npdu = Npdu::global_broadcast();
     let npdu_buffer = npdu.encode();
 
     let mut message = npdu_buffer;
@@ -70,6 +254,14 @@ pub fn process_response(data: &[u8], source: SocketAddr) -> Option<DiscoveredDev
         return None;
     }
 
+    // A BBMD relays broadcasts as Forwarded-NPDU carrying the originating B/IP address
+    let source = if bvlc_func == 0x04 {
+        let ip = std::net::Ipv4Addr::new(data[4], data[5], data[6], data[7]);
+        SocketAddr::new(IpAddr::V4(ip), u16::from_be_bytes([data[8], data[9]]))
+    } else {
+        source
+    };
+
     let (_npdu, npdu_len) = match Npdu::decode(&data[npdu_start..]) {
         Ok(res) => res,
         Err(_) => return None,
@@ -93,13 +285,11 @@ pub fn process_response(data: &[u8], source: SocketAddr) -> Option<DiscoveredDev
                 .to_string();
 
             Some(DiscoveredDevice {
-                device_id: iam.device_identifier.instance,
-                address: source,
                 vendor_id,
                 vendor_name,
                 max_apdu: iam.max_apdu_length_accepted,
                 segmentation: iam.segmentation_supported,
-                last_seen: Instant::now(),
+                ..DiscoveredDevice::new(iam.device_identifier.instance, source)
             })
         }
         Err(_) => None,
@@ -107,11 +297,11 @@ pub fn process_response(data: &[u8], source: SocketAddr) -> Option<DiscoveredDev
 }
 
 pub async fn read_device_objects(
-    socket: &UdpSocket, 
+    socket: &(impl DataLink + ?Sized), 
     addr: SocketAddr, 
     device_id: u32,
     invoke_id: u8,
-    tx_request: &tokio::sync::mpsc::Sender<(u8, tokio::sync::oneshot::Sender<Vec<u8>>)>
+    tx_request: &RequestRegistrar
 ) -> Result<Vec<BacnetObject>> {
     info!("Reading object list for device {} at {} (Invoke ID: {})", device_id, addr, invoke_id);
     
@@ -123,14 +313,37 @@ pub async fn read_device_objects(
     let mut service_data = Vec::new();
     encode_rpm_request_into(&rpm_request, &mut service_data)?;
 
-    let response = send_confirmed_request_async(
-        socket, 
-        addr, 
-        invoke_id, 
-        ConfirmedServiceChoice::ReadPropertyMultiple, 
-        &service_data,
-        tx_request
-    ).await?;
+    let rpm_response = if tx_request.rpm_unsupported(addr) {
+        None
+    } else {
+        match send_confirmed_request_async(
+            socket, 
+            addr, 
+            invoke_id, 
+            ConfirmedServiceChoice::ReadPropertyMultiple, 
+            &service_data,
+            tx_request
+        ).await {
+            Ok(response) => Some(response),
+            Err(e) if tx_request.rpm_failed(addr, &e) => {
+                debug!("{}; reading the object list with ReadProperty", e);
+                None
+            }
+            Err(e) => return Err(e),
+        }
+    };
+    // The object identifiers are picked out of either ack the same way
+    let response = match rpm_response {
+        Some(response) => response,
+        None => send_confirmed_request_async(
+            socket,
+            addr,
+            invoke_id,
+            ConfirmedServiceChoice::ReadProperty,
+            &read_property_request(device_obj, property::OBJECT_LIST),
+            tx_request
+        ).await?,
+    };
 
     debug!("Received RPM response: {} bytes", response.len());
 
@@ -144,16 +357,10 @@ pub async fn read_device_objects(
             let obj_type = ((encoded >> 22) & 0x3FF) as u16;
             let instance = encoded & 0x3FFFFF;
             
-            if let Ok(ot) = ObjectType::try_from(obj_type) {
-                if ot != ObjectType::Device {
-                    objects.push(BacnetObject {
-                        id: ObjectIdentifier::new(ot, instance),
-                        name: format!("{:?}:{}", ot, instance),
-                        present_value: "N/A".to_string(),
-                        units: "".to_string(),
-                        last_updated: Instant::now(),
-                    });
-                }
+            if let Ok(ot) = ObjectType::try_from(obj_type)
+                && ot != ObjectType::Device
+            {
+                objects.push(BacnetObject::new(ObjectIdentifier::new(ot, instance), format!("{:?}:{}", ot, instance)));
             }
             pos += 4;
         } else {
@@ -165,78 +372,280 @@ pub async fn read_device_objects(
     Ok(objects)
 }
 
-pub async fn read_present_value(
-    socket: &UdpSocket, 
-    addr: SocketAddr, 
+/// Reads a set of properties from one object with a single ReadPropertyMultiple.
+/// Properties the device rejects come back as entries with an error.
+pub async fn read_properties(
+    socket: &(impl DataLink + ?Sized),
+    addr: SocketAddr,
     obj: ObjectIdentifier,
+    properties: &[u32],
     invoke_id: u8,
-    tx_request: &tokio::sync::mpsc::Sender<(u8, tokio::sync::oneshot::Sender<Vec<u8>>)>
-) -> Result<String> {
-    debug!("Polling Present_Value for {:?}:{} at {} (Invoke ID: {})", obj.object_type, obj.instance, addr, invoke_id);
-    let service_data = vec![0x09, 0x55];
-    
-    let mut apdu_service_data = vec![0x0C];
-    let encoded_id = ((obj.object_type as u32) << 22) | (obj.instance & 0x3FFFFF);
-    apdu_service_data.extend_from_slice(&encoded_id.to_be_bytes());
-    apdu_service_data.extend_from_slice(&service_data);
+    tx_request: &RequestRegistrar
+) -> Result<Vec<PropertyEntry>> {
+    debug!("Reading {} properties of {:?}:{} at {} (Invoke ID: {})", properties.len(), obj.object_type, obj.instance, addr, invoke_id);
+    let refs = properties.iter().map(|id| PropertyReference::new(*id)).collect();
+    let rpm_request = ReadPropertyMultipleRequest::new(vec![ReadAccessSpecification::new(obj, refs)]);
+
+    let mut service_data = Vec::new();
+    encode_rpm_request_into(&rpm_request, &mut service_data)?;
 
     let response = send_confirmed_request_async(
-        socket, 
-        addr, 
-        invoke_id, 
-        ConfirmedServiceChoice::ReadProperty, 
-        &apdu_service_data,
+        socket,
+        addr,
+        invoke_id,
+        ConfirmedServiceChoice::ReadPropertyMultiple,
+        &service_data,
         tx_request
     ).await?;
 
-    // Scan for any opening tag (lower 4 bits 0xE) followed by a value tag
-    let mut pos = 0;
-    while pos < response.len() {
-        if (response[pos] & 0x0F) == 0x0E {
-            let val_data = &response[pos+1..];
-            if !val_data.is_empty() {
-                match val_data[0] {
-                    0x44 => {
-                        if val_data.len() >= 5 {
-                            let bytes = [val_data[1], val_data[2], val_data[3], val_data[4]];
-                            return Ok(format!("{:.2}", f32::from_be_bytes(bytes)));
-                        }
-                    }
-                    0x11 => {
-                        if val_data.len() >= 2 {
-                            return Ok(if val_data[1] != 0 { "Active".to_string() } else { "Inactive".to_string() });
-                        }
-                    }
-                    0x21 => {
-                        if val_data.len() >= 2 {
-                            return Ok(val_data[1].to_string());
-                        }
-                    }
-                    0x91 => {
-                        if val_data.len() >= 2 {
-                            return Ok(format!("State {}", val_data[1]));
-                        }
-                    }
-                    _ => debug!("Found tag 0x{:02X} inside opening tag 0x{:02X}", val_data[0], response[pos]),
-                }
+    let results = parse_rpm_ack(&response).ok_or_else(|| anyhow!("Malformed ReadPropertyMultiple response from {}", addr))?;
+    Ok(results.into_iter()
+        .filter(|r| r.object == obj)
+        .flat_map(|r| r.properties)
+        .collect())
+}
+
+/// Reads a set of properties with one RPM, or with a ReadProperty each when the
+/// device turns RPM down. After a Reject or a services error every later read
+/// from that device goes straight to ReadProperty. Properties the device has no
+/// value for come back as entries with an error either way.
+pub async fn read_property_set(
+    socket: &(impl DataLink + ?Sized),
+    addr: SocketAddr,
+    obj: ObjectIdentifier,
+    properties: &[u32],
+    invoke_id: u8,
+    tx_request: &RequestRegistrar
+) -> Result<Vec<PropertyEntry>> {
+    if !tx_request.rpm_unsupported(addr) {
+        match read_properties(socket, addr, obj, properties, invoke_id, tx_request).await {
+            Err(e) if tx_request.rpm_failed(addr, &e) => {
+                debug!("{}; reading {:?}:{} one property at a time", e, obj.object_type, obj.instance);
             }
+            result => return result,
+        }
+    }
+    // Each transaction completes before the next starts, so the invoke ID is reused
+    read_each_property(socket, addr, obj, properties, || invoke_id, tx_request).await
+}
+
+/// Reads the properties with a ReadProperty each. A property the device answers
+/// with an Error comes back as an entry with that error; any other failure ends
+/// the read.
+async fn read_each_property(
+    socket: &(impl DataLink + ?Sized),
+    addr: SocketAddr,
+    obj: ObjectIdentifier,
+    properties: &[u32],
+    mut next_invoke_id: impl FnMut() -> u8,
+    tx_request: &RequestRegistrar
+) -> Result<Vec<PropertyEntry>> {
+    let mut props = Vec::new();
+    for &id in properties {
+        let value = match read_property(socket, addr, obj, id, next_invoke_id(), tx_request).await {
+            Ok(value) => Ok(value),
+            Err(e) => match e.downcast_ref::<RequestFailed>() {
+                Some(RequestFailed { response: ConfirmedResponse::Error { class, code }, .. }) => {
+                    Err(PropertyError { class: *class, code: *code })
+                }
+                _ => return Err(e),
+            },
+        };
+        props.push(PropertyEntry { id, array_index: None, value });
+    }
+    Ok(props)
+}
+
+/// Reads a single property with ReadProperty.
+pub async fn read_property(
+    socket: &(impl DataLink + ?Sized),
+    addr: SocketAddr,
+    obj: ObjectIdentifier,
+    property: u32,
+    invoke_id: u8,
+    tx_request: &RequestRegistrar
+) -> Result<BacnetValue> {
+    let response = send_confirmed_request_async(
+        socket,
+        addr,
+        invoke_id,
+        ConfirmedServiceChoice::ReadProperty,
+        &read_property_request(obj, property),
+        tx_request
+    ).await?;
+
+    match parse_read_property_ack(&response) {
+        Some((_, PropertyEntry { value: Ok(value), .. })) => Ok(value),
+        _ => Err(anyhow!("Malformed ReadProperty response from {}", addr)),
+    }
+}
+
+/// Service data of a ReadProperty request
+fn read_property_request(obj: ObjectIdentifier, property: u32) -> Vec<u8> {
+    let mut service_data = vec![0x0C];
+    let encoded_id = ((obj.object_type as u32) << 22) | (obj.instance & 0x3FFFFF);
+    service_data.extend_from_slice(&encoded_id.to_be_bytes());
+    encode_context_unsigned(&mut service_data, 1, property);
+    service_data
+}
+
+/// Reads every property of an object. Tries RPM with ALL, then REQUIRED and
+/// OPTIONAL, then the properties named by Property_List or, failing that, the
+/// standard list for the object type. Each step only falls through when the
+/// device answers with an error, reject or abort; a timeout ends the attempt.
+pub async fn read_all_properties(
+    socket: &(impl DataLink + ?Sized),
+    addr: SocketAddr,
+    obj: ObjectIdentifier,
+    mut next_invoke_id: impl FnMut() -> u8,
+    tx_request: &RequestRegistrar
+) -> Result<Vec<PropertyEntry>> {
+    info!("Reading all properties of {:?}:{} at {}", obj.object_type, obj.instance, addr);
+
+    for special in [&[property::ALL][..], &[property::REQUIRED, property::OPTIONAL]] {
+        match read_properties(socket, addr, obj, special, next_invoke_id(), tx_request).await {
+            Ok(props) if props.iter().any(|p| p.value.is_ok()) => return Ok(props),
+            Ok(_) => debug!("{:?}:{} returned no values for {:?}", obj.object_type, obj.instance, special),
+            Err(e) if e.is::<RequestFailed>() => debug!("{}", e),
+            Err(e) => return Err(e),
+        }
+    }
+
+    let ids: Vec<u32> = match read_property(socket, addr, obj, property::PROPERTY_LIST, next_invoke_id(), tx_request).await {
+        Ok(value) => {
+            let listed: Vec<u32> = match value {
+                BacnetValue::List(values) => values.iter().filter_map(|v| match v {
+                    BacnetValue::Enumerated(id) => Some(*id),
+                    _ => None,
+                }).collect(),
+                BacnetValue::Enumerated(id) => vec![id],
+                _ => Vec::new(),
+            };
+            // Property_List leaves out the properties every object has
+            let common = [property::OBJECT_IDENTIFIER, property::OBJECT_NAME, property::OBJECT_TYPE];
+            common.into_iter().chain(listed.into_iter().filter(|id| !common.contains(id))).collect()
         }
-        pos += 1;
+        Err(e) if e.is::<RequestFailed>() => property::standard_properties(obj.object_type),
+        Err(e) => return Err(e),
+    };
+
+    match read_properties(socket, addr, obj, &ids, next_invoke_id(), tx_request).await {
+        Ok(props) => return Ok(props),
+        Err(e) if e.is::<RequestFailed>() => debug!("{}", e),
+        Err(e) => return Err(e),
     }
 
-    Ok("N/A".to_string())
+    // No usable RPM support; read the properties one at a time
+    read_each_property(socket, addr, obj, &ids, next_invoke_id, tx_request).await
+}
+
+/// Properties polled for every point
+pub const POLLED_PROPERTIES: &[u32] = &[
+    property::PRESENT_VALUE,
+    property::STATUS_FLAGS,
+    property::RELIABILITY,
+    property::OUT_OF_SERVICE,
+];
+
+/// One poll of a point. Status fields are None when the device did not return them.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PointReading {
+    pub present_value: PresentValue,
+    pub status_flags: Option<StatusFlags>,
+    pub reliability: Option<u32>,
+    pub out_of_service: Option<bool>,
+    /// Set when Object_Name was requested with this poll
+    pub name: Option<String>,
+    /// Set when Description was requested; empty if the object has none
+    pub description: Option<String>,
+    /// BACnetEngineeringUnits, when Units was requested and returned
+    pub units: Option<u32>,
+    /// Set when the state texts were requested with this poll
+    pub state_texts: Option<StateTexts>,
+}
+
+impl PointReading {
+    pub fn from_properties(object_type: ObjectType, props: &[PropertyEntry]) -> Self {
+        let text = |id| match property::find(props, id) {
+            Some(BacnetValue::CharacterString(text)) => Some(text.clone()),
+            _ => None,
+        };
+        let requested = |id| props.iter().any(|p| p.id == id);
+        let state_texts = (requested(property::STATE_TEXT) || requested(property::ACTIVE_TEXT)).then(|| StateTexts {
+            inactive: text(property::INACTIVE_TEXT),
+            active: text(property::ACTIVE_TEXT),
+            states: match property::find(props, property::STATE_TEXT) {
+                Some(BacnetValue::List(values)) => values.iter().map(|v| match v {
+                    BacnetValue::CharacterString(text) => text.clone(),
+                    _ => String::new(),
+                }).collect(),
+                Some(BacnetValue::CharacterString(text)) => vec![text.clone()],
+                _ => Vec::new(),
+            },
+        });
+
+        Self {
+            present_value: property::find(props, property::PRESENT_VALUE)
+                .map_or(PresentValue::Unknown, |v| PresentValue::from_value(object_type, v)),
+            status_flags: property::find(props, property::STATUS_FLAGS).and_then(StatusFlags::from_value),
+            reliability: match property::find(props, property::RELIABILITY) {
+                Some(BacnetValue::Enumerated(r)) => Some(*r),
+                _ => None,
+            },
+            out_of_service: match property::find(props, property::OUT_OF_SERVICE) {
+                Some(BacnetValue::Boolean(b)) => Some(*b),
+                _ => None,
+            },
+            name: text(property::OBJECT_NAME),
+            description: requested(property::DESCRIPTION).then(|| text(property::DESCRIPTION).unwrap_or_default()),
+            units: match property::find(props, property::UNITS) {
+                Some(BacnetValue::Enumerated(u)) => Some(*u),
+                _ => None,
+            },
+            state_texts,
+        }
+    }
+}
+
+/// The polled properties plus Object_Name, Description, Units where the type has
+/// them and the state texts of binary and multistate objects
+pub fn detail_properties(object_type: ObjectType) -> Vec<u32> {
+    let mut props = POLLED_PROPERTIES.to_vec();
+    props.extend_from_slice(&[property::OBJECT_NAME, property::DESCRIPTION]);
+    if property::has_units(object_type) {
+        props.push(property::UNITS);
+    }
+    props.extend_from_slice(property::state_text_properties(object_type));
+    props
+}
+
+/// Polls Present_Value together with the status properties in one RPM. With
+/// `read_details`, Object_Name, Description, Units and the state texts of binary
+/// and multistate objects are read as well. Devices that turn RPM down are read
+/// one property at a time, as by [`read_property_set`].
+pub async fn read_point(
+    socket: &(impl DataLink + ?Sized),
+    addr: SocketAddr,
+    obj: ObjectIdentifier,
+    read_details: bool,
+    invoke_id: u8,
+    tx_request: &RequestRegistrar
+) -> Result<PointReading> {
+    let props = if read_details { detail_properties(obj.object_type) } else { POLLED_PROPERTIES.to_vec() };
+    let props = read_property_set(socket, addr, obj, &props, invoke_id, tx_request).await?;
+    Ok(PointReading::from_properties(obj.object_type, &props))
 }
 
 async fn send_confirmed_request_async(
-    socket: &UdpSocket,
+    socket: &(impl DataLink + ?Sized),
     addr: SocketAddr,
     invoke_id: u8,
     service_choice: ConfirmedServiceChoice,
     service_data: &[u8],
-    tx_request: &tokio::sync::mpsc::Sender<(u8, tokio::sync::oneshot::Sender<Vec<u8>>)>
+    tx_request: &RequestRegistrar
 ) -> Result<Vec<u8>> {
     let (tx_response, rx_response) = tokio::sync::oneshot::channel();
-    tx_request.send((invoke_id, tx_response)).await.map_err(|_| anyhow!("Failed to register request"))?;
+    tx_request.register(addr, invoke_id, tx_response).await?;
 
     let apdu = Apdu::ConfirmedRequest {
         segmented: false,
@@ -263,27 +672,58 @@ async fn send_confirmed_request_async(
     bvlc[2] = (total_len >> 8) as u8;
     bvlc[3] = (total_len & 0xFF) as u8;
 
+    let sent = Instant::now();
     socket.send_to(&bvlc, addr)?;
 
     match tokio::time::timeout(Duration::from_secs(5), rx_response).await {
-        Ok(Ok(data)) => Ok(data),
+        Ok(Ok(response)) => {
+            let outcome = match response {
+                ConfirmedResponse::Ack(_) => RequestOutcome::Ack,
+                ConfirmedResponse::Error { .. } => RequestOutcome::Error,
+                ConfirmedResponse::Reject(_) => RequestOutcome::Reject,
+                ConfirmedResponse::Abort(_) => RequestOutcome::Abort,
+            };
+            tx_request.record(addr, outcome, Some(sent.elapsed()));
+            match response {
+                ConfirmedResponse::Ack(data) => Ok(data),
+                response => Err(RequestFailed { addr, response }.into()),
+            }
+        }
         Ok(Err(_)) => Err(anyhow!("Response channel closed")),
-        Err(_) => Err(anyhow!("Timeout waiting for response from {} (Invoke {})", addr, invoke_id)),
+        Err(_) => {
+            tx_request.record(addr, RequestOutcome::Timeout, None);
+            Err(RequestTimeout { addr, invoke_id }.into())
+        }
     }
 }
 
-pub fn parse_confirmed_response(data: &[u8]) -> Option<(u8, Vec<u8>)> {
+pub fn parse_confirmed_response(data: &[u8]) -> Option<(u8, ConfirmedResponse)> {
     if data.len() < 4 || data[0] != 0x81 { return None; }
     let npdu_start = match data[1] { 0x0A => 4, 0x04 => 10, _ => return None };
     if data.len() <= npdu_start { return None; }
     let (_npdu, npdu_len) = Npdu::decode(&data[npdu_start..]).ok()?;
-    let apdu = Apdu::decode(&data[npdu_start + npdu_len..]).ok()?;
+    let apdu_data = &data[npdu_start + npdu_len..];
+
+    // bacnet-rs reads the error class and code as raw octets, but they are tagged enumerations
+    if apdu_data.len() >= 3 && apdu_data[0] >> 4 == 5 {
+        let invoke_id = apdu_data[1];
+        // Still answer the request when the body is malformed, rather than letting it time out
+        let error = property::decode_error(&apdu_data[3..])
+            .unwrap_or(property::PropertyError { class: UNKNOWN_ERROR, code: UNKNOWN_ERROR });
+        warn!("BACnet Error for invoke {}: class={}, code={}", invoke_id, error.class, error.code);
+        return Some((invoke_id, ConfirmedResponse::Error { class: error.class, code: error.code }));
+    }
 
-    match apdu {
-        Apdu::ComplexAck { invoke_id, service_data, .. } => Some((invoke_id, service_data)),
-        Apdu::Error { invoke_id, error_class, error_code, .. } => {
-            warn!("BACnet Error for invoke {}: class={}, code={}", invoke_id, error_class, error_code);
-            None
+    match Apdu::decode(apdu_data).ok()? {
+        Apdu::ComplexAck { invoke_id, service_data, .. } => Some((invoke_id, ConfirmedResponse::Ack(service_data))),
+        Apdu::SimpleAck { invoke_id, .. } => Some((invoke_id, ConfirmedResponse::Ack(Vec::new()))),
+        Apdu::Reject { invoke_id, reject_reason } => {
+            warn!("BACnet Reject for invoke {}: reason={}", invoke_id, reject_reason);
+            Some((invoke_id, ConfirmedResponse::Reject(reject_reason)))
+        }
+        Apdu::Abort { invoke_id, abort_reason, .. } => {
+            warn!("BACnet Abort for invoke {}: reason={}", invoke_id, abort_reason);
+            Some((invoke_id, ConfirmedResponse::Abort(abort_reason)))
         }
         _ => None,
     }
@@ -296,14 +736,24 @@ fn encode_rpm_request_into(request: &ReadPropertyMultipleRequest, buffer: &mut V
         buffer.extend_from_slice(&obj_id.to_be_bytes());
         buffer.push(0x1E); // Context tag 1, opening tag
         for prop_ref in &spec.property_references {
-            buffer.push(0x09); // Context tag 0, length 1
-            buffer.push(prop_ref.property_identifier as u8);
+            encode_context_unsigned(buffer, 0, prop_ref.property_identifier);
+            if let Some(index) = prop_ref.property_array_index {
+                encode_context_unsigned(buffer, 1, index);
+            }
         }
         buffer.push(0x1F); // Context tag 1, closing tag
     }
     Ok(())
 }
 
+/// Encodes `value` as a context-tagged unsigned using the fewest octets.
+fn encode_context_unsigned(buffer: &mut Vec<u8>, tag: u8, value: u32) {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take(3).take_while(|b| **b == 0).count();
+    buffer.push((tag << 4) | 0x08 | (4 - skip) as u8);
+    buffer.extend_from_slice(&bytes[skip..]);
+}
+
 pub fn get_interface_broadcast(iface: &if_addrs::Interface) -> Option<SocketAddr> {
     if let if_addrs::IfAddr::V4(v4) = &iface.addr {
         v4.broadcast.map(|b| SocketAddr::new(IpAddr::V4(b), 47808))
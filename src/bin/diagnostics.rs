@@ -1,49 +1,188 @@
 //! BACnet Network Diagnostic Tool
 //!
-//! Checks for common BACnet network issues like port availability and broadcast capability.
-
-use std::net::{UdpSocket, SocketAddr};
-
-fn main() {
-    println!("BACnet Network Diagnostic");
-    println!("=========================");
-
-    let port = 47808;
-    let addr: SocketAddr = format!("0.0.0.0:{}", port).parse().unwrap();
-
-    // 1. Check if port is available
-    println!("[1] Checking if port {} is available...", port);
-    match UdpSocket::bind(addr) {
-        Ok(socket) => {
-            println!("  ✅ Port {} is available for binding.", port);
-            
-            // 2. Check broadcast capability
-            println!("[2] Checking broadcast capability...");
-            match socket.set_broadcast(true) {
-                Ok(_) => println!("  ✅ Broadcast capability enabled."),
-                Err(e) => println!("  ❌ Failed to enable broadcast: {}", e),
-            }
+//! Probes the network for common BACnet/IP problems: a port held by another
+//! process, interfaces nobody answers on, duplicate device instances, BBMDs and
+//! their tables, devices outside the local subnets and broadcast storms. Prints a
+//! pass/warn/fail report as text or JSON.
+
+use anyhow::{Result, anyhow};
+use bacnet_discovery::bacnet::send_whois_to;
+use bacnet_discovery::cli::parse_duration;
+use bacnet_discovery::network::create_shared_socket;
+use bacnet_discovery::probe::{self, LocalInterface, ProbeReport, Status};
+use bacnet_discovery::storm::{BroadcastMonitor, StormThresholds};
+use bacnet_discovery::traffic::{Datagram, received_destination};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::process::ExitCode;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const USAGE: &str = "\
+Usage: diagnostics [options]
+
+  --format <fmt>             text (default) or json
+  --port <port>              BACnet/IP port (default 47808)
+  --duration <secs>          How long to wait for I-Am replies (default 3, max 86400)
+  --bbmd <addr>              Also send a Read-BDT to this address; repeatable
+  --monitor <secs>           How long to watch the broadcasts (default 10, 0 skips,
+                             max 86400)
+  --total-limit <per-sec>    Broadcasts per second in total (default 50)
+  --source-limit <per-sec>   Broadcasts per second from one source (default 5)
+  --service-limit <per-sec>  Broadcasts per second of one service (default 30)
+
+Exit code: 0 when no check failed, 1 when one did, 2 on errors.";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Text,
+    Json,
+}
+
+struct Args {
+    format: Format,
+    port: u16,
+    duration: Duration,
+    bbmds: Vec<SocketAddr>,
+    /// How long to watch the broadcasts; zero skips the check
+    monitor: Duration,
+    thresholds: StormThresholds,
+}
 
-            // 3. Interface Check
-            println!("[3] Local interfaces information:");
-            if let Ok(addrs) = if_addrs::get_if_addrs() {
-                for iface in addrs {
-                    println!("  - Interface: {}", iface.name);
-                    println!("    Address:   {}", iface.ip());
-                }
-            } else {
-                println!("  ❌ Could not retrieve interface information.");
+/// Parses the options in [`USAGE`]. Returns None for `--help`.
+fn parse_args() -> Result<Option<Args>> {
+    let mut args = std::env::args().skip(1);
+    let mut parsed = Args {
+        format: Format::Text,
+        port: 47808,
+        duration: Duration::from_secs(3),
+        bbmds: Vec::new(),
+        monitor: Duration::from_secs(10),
+        thresholds: StormThresholds::default(),
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| anyhow!("Missing value for {}", arg));
+        let number = |text: String| -> Result<f64> {
+            text.parse::<f64>().ok().filter(|v| *v >= 0.0).ok_or_else(|| anyhow!("Invalid number: {}", text))
+        };
+        match arg.as_str() {
+            "--format" => parsed.format = match value()?.as_str() {
+                "text" => Format::Text,
+                "json" => Format::Json,
+                other => return Err(anyhow!("Unknown format {} (expected text or json)", other)),
+            },
+            "--port" => parsed.port = value()?.parse().map_err(|_| anyhow!("Invalid port"))?,
+            "--duration" => parsed.duration = parse_duration(&value()?)?,
+            "--bbmd" => {
+                let text = value()?;
+                let addr = text.parse::<SocketAddr>()
+                    .or_else(|_| text.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 47808)))
+                    .map_err(|_| anyhow!("Invalid address: {}", text))?;
+                parsed.bbmds.push(addr);
             }
-        },
+            "--monitor" => parsed.monitor = parse_duration(&value()?)?,
+            "--total-limit" => parsed.thresholds.total = number(value()?)?,
+            "--source-limit" => parsed.thresholds.per_source = number(value()?)?,
+            "--service-limit" => parsed.thresholds.per_service = number(value()?)?,
+            "--help" | "-h" => return Ok(None),
+            _ => return Err(anyhow!("Unknown argument: {}\n\n{}", arg, USAGE)),
+        }
+    }
+    Ok(Some(parsed))
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    match run(&args) {
+        Ok(report) if report.status() == Status::Fail => ExitCode::from(1),
+        Ok(_) => ExitCode::SUCCESS,
         Err(e) => {
-            println!("  ❌ Could not bind to port {}: {}", port, e);
-            println!("     This usually means another BACnet application or service is already running.");
+            eprintln!("Error: {}", e);
+            ExitCode::from(2)
         }
     }
+}
+
+fn run(args: &Args) -> Result<ProbeReport> {
+    let mut report = ProbeReport::new();
+    // Progress goes to stderr so a JSON report on stdout stays clean
+    eprintln!("[1] Checking port {}...", args.port);
+    probe::check_port(args.port).into_iter().for_each(|check| report.push(check));
+
+    eprintln!("[2] Sending Who-Is on every interface...");
+    let interfaces = LocalInterface::list();
+    // Without a shared socket on the BACnet port, broadcast I-Am replies are missed
+    let socket = match create_shared_socket(args.port) {
+        Ok(socket) => socket,
+        Err(_) => UdpSocket::bind("0.0.0.0:0")?,
+    };
+    socket.set_broadcast(true)?;
+    let mut destinations: Vec<SocketAddr> = interfaces.iter()
+        .filter_map(|iface| iface.broadcast)
+        .map(|b| SocketAddr::new(IpAddr::V4(b), args.port))
+        .collect();
+    if destinations.is_empty() {
+        destinations.push(SocketAddr::new(IpAddr::V4(Ipv4Addr::BROADCAST), args.port));
+    }
+    for destination in &destinations {
+        if let Err(e) = send_whois_to(&socket, *destination) {
+            eprintln!("    Could not send Who-Is to {}: {}", destination, e);
+        }
+    }
+    let responders = probe::collect_responders(&socket, args.duration);
+    probe::check_interfaces(&interfaces, &responders).into_iter().for_each(|check| report.push(check));
+
+    eprintln!("[3] Checking for duplicate device instances and subnet mismatches...");
+    report.push(probe::check_duplicates(&responders));
+    report.push(probe::check_subnets(&interfaces, &responders));
+
+    eprintln!("[4] Probing for BBMDs with Read-BDT...");
+    let mut targets = probe::probe_targets(&responders);
+    targets.extend(args.bbmds.iter().filter(|addr| !targets.contains(addr)).collect::<Vec<_>>());
+    let replies = probe::read_bdts(&socket, &targets, Duration::from_secs(2))?;
+    report.push(probe::check_bbmds(&replies));
+    drop(socket);
+
+    if !args.monitor.is_zero() {
+        eprintln!("[5] Watching broadcasts for {:.0} s...", args.monitor.as_secs_f64());
+        report.push(watch_broadcasts(args)?);
+    }
+
+    match args.format {
+        Format::Text => print!("{}", report.to_text()),
+        Format::Json => println!("{}", report.to_json()?),
+    }
+    Ok(report)
+}
+
+fn watch_broadcasts(args: &Args) -> Result<probe::CheckResult> {
+    let socket = create_shared_socket(args.port)
+        .map_err(|e| anyhow!("Could not listen on port {}: {}", args.port, e))?;
+    socket.set_read_timeout(Some(Duration::from_millis(100)))?;
+    let local = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), args.port);
+    let broadcast = SocketAddr::new(IpAddr::V4(Ipv4Addr::BROADCAST), args.port);
+    // Rates cover the whole run, so a burst early on is not forgotten by the end
+    let mut monitor = BroadcastMonitor::with_window(args.thresholds, args.monitor);
+    let mut buf = [0u8; 1500];
+    let start = Instant::now();
+    monitor.start(epoch_now());
+    while start.elapsed() < args.monitor {
+        if let Ok((len, source)) = socket.recv_from(&mut buf) {
+            let data = &buf[..len];
+            monitor.record(&Datagram::new(epoch_now(), source, received_destination(data, local, broadcast), data));
+        }
+    }
+    Ok(probe::check_broadcasts(&monitor, epoch_now()))
+}
 
-    println!("
-Recommendations:");
-    println!("- If the discovery tool finds nothing, try running the responder: 'cargo run --bin responder'");
-    println!("- Ensure your firewall allows UDP port 47808.");
-    println!("- If port 47808 is occupied, close the conflicting application.");
+fn epoch_now() -> Duration {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default()
 }
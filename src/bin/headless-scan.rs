@@ -1,49 +1,338 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use bacnet_discovery::address_book::AddressBook;
+use bacnet_discovery::bacnet::{self, ConfirmedResponse, DiscoveredDevice, PendingRequests, send_ranged_whois, send_whois_to, process_response, parse_confirmed_response, read_device_objects};
+use bacnet_discovery::cli::parse_duration;
+use bacnet_discovery::ede::EdeExport;
+use bacnet_discovery::export::ScanExport;
+use bacnet_discovery::inventory::{InvokeIds, inventory_device};
 use bacnet_discovery::network::create_shared_socket;
-use bacnet_discovery::bacnet::{send_whois_to, process_response};
-use std::net::{SocketAddr, UdpSocket};
+use bacnet_discovery::property;
+use futures_util::{stream, StreamExt};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tracing::{info, warn, Level};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{error, info, warn, Level};
 use tracing_subscriber::FmtSubscriber;
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::DEBUG)
-        .finish();
-    tracing::subscriber::set_global_default(subscriber)?;
+const USAGE: &str = "\
+Usage: headless-scan [options]
 
-    info!("Starting Headless BACnet Scan");
+  --interface <name>     Broadcast on this interface's subnet
+  --broadcast <addr>     Broadcast address (default 255.255.255.255:47808)
+  --target <addr>        Send a unicast Who-Is to this address; repeatable.
+                         Without --interface or --broadcast, no broadcast is sent
+  --range <low>-<high>   Only devices with instances in this range
+  --duration <secs>      How long to wait for I-Am replies (default 5, max 86400)
+  --points               Read the object list of every device found
+  --deep                 Inventory: read the Device object, the object list and
+                         a property set from every object of every device
+  --properties <list>    Properties read per object in a deep scan, by name or
+                         number (e.g. object-name,present-value,85). Default:
+                         name, description, units, value, status and state texts
+  --parallel <n>         Devices read at the same time in a deep scan (default 4)
+  --format <fmt>         table (default), json or csv
+  --output <path>        Write the report to a file instead of stdout
+  --export <path>        Also write <path>.csv and <path>.json
+  --ede <path>           Also write EDE files <path>_EDE.csv, ...
+  --verbose              Debug logging on stderr
+
+Exit code: 0 when devices were found, 1 when none were, 2 on errors.";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+struct Args {
+    /// None when only unicast targets are scanned
+    broadcast: Option<SocketAddr>,
+    targets: Vec<SocketAddr>,
+    range: Option<(u32, u32)>,
+    duration: Duration,
+    points: bool,
+    deep: bool,
+    /// Properties read per object in a deep scan; None for the point details
+    properties: Option<Vec<u32>>,
+    parallel: usize,
+    format: Format,
+    output: Option<PathBuf>,
+    export: Option<PathBuf>,
+    ede: Option<PathBuf>,
+    verbose: bool,
+}
+
+/// Parses the options in [`USAGE`]. Returns None for `--help`.
+fn parse_args() -> Result<Option<Args>> {
+    let mut args = std::env::args().skip(1);
+    let mut parsed = Args {
+        broadcast: None,
+        targets: Vec::new(),
+        range: None,
+        duration: Duration::from_secs(5),
+        points: false,
+        deep: false,
+        properties: None,
+        parallel: 4,
+        format: Format::Table,
+        output: None,
+        export: None,
+        ede: None,
+        verbose: false,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| anyhow!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--interface" => parsed.broadcast = Some(interface_broadcast(&value()?)?),
+            "--broadcast" => parsed.broadcast = Some(parse_addr(&value()?)?),
+            "--target" => parsed.targets.push(parse_addr(&value()?)?),
+            "--range" => parsed.range = Some(parse_range(&value()?)?),
+            "--duration" => parsed.duration = parse_duration(&value()?)?,
+            "--points" => parsed.points = true,
+            "--deep" => parsed.deep = true,
+            "--properties" => parsed.properties = Some(parse_properties(&value()?)?),
+            "--parallel" => parsed.parallel = value()?.parse::<usize>()?.clamp(1, 64),
+            "--format" => parsed.format = match value()?.as_str() {
+                "table" => Format::Table,
+                "json" => Format::Json,
+                "csv" => Format::Csv,
+                other => return Err(anyhow!("Unknown format {} (expected table, json or csv)", other)),
+            },
+            "--output" => parsed.output = Some(PathBuf::from(value()?)),
+            "--export" => parsed.export = Some(PathBuf::from(value()?)),
+            "--ede" => parsed.ede = Some(PathBuf::from(value()?)),
+            "--verbose" => parsed.verbose = true,
+            "--help" | "-h" => return Ok(None),
+            _ => return Err(anyhow!("Unknown argument: {}\n\n{}", arg, USAGE)),
+        }
+    }
+    if parsed.broadcast.is_none() && parsed.targets.is_empty() {
+        parsed.broadcast = Some(SocketAddr::from(([255, 255, 255, 255], 47808)));
+    }
+    Ok(Some(parsed))
+}
+
+/// `ip` or `ip:port`; the port defaults to 47808
+fn parse_addr(text: &str) -> Result<SocketAddr> {
+    text.parse::<SocketAddr>()
+        .or_else(|_| text.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 47808)))
+        .map_err(|_| anyhow!("Invalid address: {}", text))
+}
 
+/// Comma-separated property names or numbers
+fn parse_properties(text: &str) -> Result<Vec<u32>> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| property::property_id(name).ok_or_else(|| anyhow!("Unknown property: {}", name)))
+        .collect()
+}
+
+/// `low-high`, or a single instance
+fn parse_range(text: &str) -> Result<(u32, u32)> {
+    let (low, high) = text.split_once('-').unwrap_or((text, text));
+    let (low, high) = (low.trim().parse()?, high.trim().parse()?);
+    if low > high || high > 4_194_303 {
+        return Err(anyhow!("Invalid instance range: {}", text));
+    }
+    Ok((low, high))
+}
+
+fn interface_broadcast(name: &str) -> Result<SocketAddr> {
+    if_addrs::get_if_addrs()?
+        .iter()
+        .filter(|iface| iface.name == name)
+        .find_map(bacnet::get_interface_broadcast)
+        .ok_or_else(|| anyhow!("No IPv4 interface named {} with a broadcast address", name))
+}
+
+/// Hands I-Am replies to `tx_found` and confirmed responses to the request that registered
+/// their source address and invoke ID. Invoke IDs are shared by every device read in
+/// parallel, so the address keeps a late reply from completing another device's request.
+fn spawn_receiver(
+    socket: Arc<UdpSocket>,
+    tx_found: mpsc::UnboundedSender<DiscoveredDevice>,
+    mut rx_register: mpsc::Receiver<(SocketAddr, u8, oneshot::Sender<ConfirmedResponse>)>,
+) {
+    tokio::spawn(async move {
+        let mut pending = PendingRequests::new();
+        let mut drain = |pending: &mut PendingRequests| {
+            while let Ok((addr, id, tx)) = rx_register.try_recv() { pending.insert((addr, id), tx); }
+            // Requests that timed out dropped their receiver
+            pending.retain(|_, tx| !tx.is_closed());
+        };
+        let mut buf = [0u8; 1500];
+        loop {
+            match socket.recv_from(&mut buf) {
+                Ok((len, addr)) => {
+                    if let Some(device) = process_response(&buf[..len], addr) {
+                        let _ = tx_found.send(device);
+                    } else if let Some((id, response)) = parse_confirmed_response(&buf[..len]) {
+                        // The request is registered before it is sent, so pick up any registration still queued
+                        drain(&mut pending);
+                        if let Some(tx) = pending.remove(&(addr, id)) { let _ = tx.send(response); }
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock || e.kind() == std::io::ErrorKind::TimedOut => {
+                    drain(&mut pending);
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                }
+                Err(e) => warn!("Receive error: {}", e),
+            }
+        }
+    });
+}
+
+/// Runs the scan and writes the report; returns the number of devices found
+async fn run(args: Args) -> Result<usize> {
     let socket = create_shared_socket(47808).unwrap_or_else(|e| {
         warn!("Failed to bind to 47808 ({}). Trying random port.", e);
         UdpSocket::bind("0.0.0.0:0").expect("Failed to bind")
     });
+    socket.set_nonblocking(true)?;
+    let socket = Arc::new(socket);
+
+    let (tx_found, mut rx_found) = mpsc::unbounded_channel();
+    let (tx_register, rx_register) = mpsc::channel(16);
+    spawn_receiver(Arc::clone(&socket), tx_found, rx_register);
+    let tx_register = bacnet::RequestRegistrar::new(tx_register);
 
-    info!("Who-Is broadcast sent.");
-    let broadcast_addr: SocketAddr = "255.255.255.255:47808".parse()?;
-    send_whois_to(&socket, broadcast_addr)?;
-
-    let mut buf = [0u8; 1500];
-    let start = Instant::now();
-    let scan_duration = Duration::from_secs(5);
-    let mut discovered_count = 0;
-
-    while start.elapsed() < scan_duration {
-        match socket.recv_from(&mut buf) {
-            Ok((len, addr)) => {
-                if let Some(device) = process_response(&buf[..len], addr) {
-                    discovered_count += 1;
-                    info!("FOUND DEVICE: ID={} Vendor={} Address={}", device.device_id, device.vendor_name, device.address);
+    let (low, high) = args.range.unwrap_or((0, 4_194_303));
+    let destinations = args.broadcast.iter().chain(&args.targets);
+    for dest in destinations {
+        match args.range {
+            Some(_) => send_ranged_whois(&*socket, *dest, low, high)?,
+            None => send_whois_to(&*socket, *dest)?,
+        }
+        info!("Who-Is sent to {}", dest);
+    }
+
+    let deadline = tokio::time::Instant::now() + args.duration;
+    let mut address_book = AddressBook::new();
+    let mut devices = HashMap::new();
+    while let Ok(Some(device)) = tokio::time::timeout_at(deadline, rx_found.recv()).await {
+        // Devices are free to ignore the range, so it is checked here as well
+        if !(low..=high).contains(&device.device_id) {
+            continue;
+        }
+        if !devices.contains_key(&device.device_id) {
+            info!("Found device {} ({}) at {}", device.device_id, device.vendor_name, device.address);
+        }
+        if let Some(event) = address_book.record(device.device_id, device.address, Instant::now()) {
+            warn!("{}", event);
+        }
+        devices.insert(device.device_id, device);
+    }
+
+    info!("Scan complete. Total devices found: {}", devices.len());
+    for conflict in address_book.conflicts() {
+        warn!("CONFLICT: {}", conflict);
+    }
+
+    let mut objects = HashMap::new();
+    let mut properties = HashMap::new();
+    let mut point_properties = Vec::new();
+    if args.deep {
+        let invoke_ids = InvokeIds::default();
+        let mut ids: Vec<u32> = devices.keys().cloned().collect();
+        ids.sort();
+        info!("Reading {} devices, {} at a time", ids.len(), args.parallel);
+        let mut inventories = stream::iter(ids)
+            .map(|id| inventory_device(&*socket, &devices[&id], args.properties.as_deref(), &invoke_ids, &tx_register))
+            .buffer_unordered(args.parallel);
+        while let Some(inventory) = inventories.next().await {
+            let mut points = Vec::new();
+            for (point, props) in inventory.objects {
+                if args.properties.is_some() {
+                    point_properties.push((inventory.device_id, point.id, props));
                 }
+                points.push(point);
             }
-            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock || e.kind() == std::io::ErrorKind::TimedOut => {
-                tokio::task::yield_now().await;
+            properties.insert(inventory.device_id, inventory.properties);
+            objects.insert(inventory.device_id, points);
+        }
+    } else if args.points {
+        let mut ids: Vec<u32> = devices.keys().cloned().collect();
+        ids.sort();
+        for (n, device_id) in ids.into_iter().enumerate() {
+            // Invoke IDs 1..=255; a request is finished before the next one starts
+            let invoke_id = (n % 255) as u8 + 1;
+            let address = devices[&device_id].address;
+            match read_device_objects(&*socket, address, device_id, invoke_id, &tx_register).await {
+                Ok(points) => { objects.insert(device_id, points); }
+                Err(e) => warn!("Point discovery failed for device {}: {}", device_id, e),
             }
-            Err(e) => warn!("Receive error: {}", e),
         }
     }
 
-    info!("Scan complete. Total devices found: {}", discovered_count);
-    Ok(())
+    let mut scan = ScanExport::new(&devices, &properties, &objects);
+    scan.set_conflicts(&address_book.conflicts());
+    for (device_id, object, props) in &point_properties {
+        scan.set_properties(*device_id, *object, props);
+    }
+    let report = match args.format {
+        Format::Table => scan.to_table(),
+        Format::Json => scan.to_json()? + "\n",
+        Format::Csv => scan.to_csv(),
+    };
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, report)?;
+            info!("Report written to {}", path.display());
+        }
+        None => print!("{}", report),
+    }
+
+    if let Some(base) = args.export {
+        let (csv, json) = scan.write_files(&base)?;
+        info!("Exported {} devices to {} and {}", devices.len(), csv.display(), json.display());
+    }
+    if let Some(base) = args.ede {
+        let project = base.display().to_string();
+        let [main, ..] = EdeExport::new(&project, &devices, &properties, &objects).write_files(&base)?;
+        info!("Wrote EDE files for {} devices, starting with {}", devices.len(), main.display());
+    }
+    Ok(devices.len())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("headless-scan: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    // Logs go to stderr so the report on stdout can be piped
+    let subscriber = FmtSubscriber::builder()
+        .with_max_level(if args.verbose { Level::DEBUG } else { Level::INFO })
+        .with_writer(std::io::stderr)
+        .finish();
+    if tracing::subscriber::set_global_default(subscriber).is_err() {
+        eprintln!("headless-scan: failed to set up logging");
+    }
+
+    info!("Starting Headless BACnet Scan");
+    match run(args).await {
+        Ok(0) => {
+            error!("No devices found");
+            ExitCode::from(1)
+        }
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{}", e);
+            ExitCode::from(2)
+        }
+    }
 }
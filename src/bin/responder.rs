@@ -47,14 +47,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     12 => { // ReadProperty
                         if let Some(obj_id) = extract_object_id_from_rp(&service_data) {
                             println!("Received ReadProperty for {:?} from {}", obj_id, source);
-                            if let Some(response) = handle_read_property(invoke_id, obj_id) {
+                            if let Some(response) = handle_read_property(invoke_id, device_id, obj_id, &service_data) {
                                 let _ = socket.send_to(&response, source);
                             }
                         }
                     }
                     14 => { // ReadPropertyMultiple
                         println!("Received ReadPropertyMultiple from {}", source);
-                        if let Some(response) = handle_read_property_multiple(invoke_id, device_id) {
+                        if let Some(response) = handle_read_property_multiple(invoke_id, device_id, &service_data) {
                             let _ = socket.send_to(&response, source);
                         }
                     }
@@ -106,54 +106,160 @@ fn extract_object_id_from_rp(data: &[u8]) -> Option<ObjectIdentifier> {
     }
 }
 
-fn handle_read_property(invoke_id: u8, obj_id: ObjectIdentifier) -> Option<Vec<u8>> {
-    let val_bytes = match obj_id.object_type {
-        ObjectType::AnalogInput => vec![0x44, 0x41, 0xB4, 0x00, 0x00],
-        ObjectType::BinaryInput => vec![0x11, 0x01],
-        ObjectType::AnalogValue => vec![0x44, 0x42, 0x48, 0x00, 0x00],
-        _ => vec![0x21, 0x00],
+fn handle_read_property(invoke_id: u8, device_id: u32, obj_id: ObjectIdentifier, request: &[u8]) -> Option<Vec<u8>> {
+    // Context tag 1 after the object identifier carries the property
+    let prop = match request.get(5..)? {
+        [0x19, p, ..] => *p as u32,
+        [0x1A, hi, lo, ..] => u16::from_be_bytes([*hi, *lo]) as u32,
+        _ => return None,
     };
 
     let mut response_data = Vec::new();
     let encoded_id = ((obj_id.object_type as u32) << 22) | (obj_id.instance & 0x3FFFFF);
     response_data.push(0x0C);
     response_data.extend_from_slice(&encoded_id.to_be_bytes());
-    response_data.push(0x19);
-    response_data.push(85);
-    response_data.push(0x3E);
-    response_data.extend_from_slice(&val_bytes);
-    response_data.push(0x3F);
+    if prop > 255 {
+        response_data.push(0x1A);
+        response_data.extend_from_slice(&(prop as u16).to_be_bytes());
+    } else {
+        response_data.extend_from_slice(&[0x19, prop as u8]);
+    }
+    match object_property(device_id, obj_id, prop) {
+        Some(value) => {
+            response_data.push(0x3E);
+            response_data.extend_from_slice(&value);
+            response_data.push(0x3F);
+            create_complex_ack(invoke_id, ConfirmedServiceChoice::ReadProperty, response_data).ok()
+        }
+        None => create_error(invoke_id, ConfirmedServiceChoice::ReadProperty, 2, 32).ok(),
+    }
+}
 
-    create_complex_ack(invoke_id, ConfirmedServiceChoice::ReadProperty, response_data).ok()
+/// Property identifiers requested for the first object of an RPM request
+fn requested_properties(data: &[u8]) -> Vec<u32> {
+    let mut props = Vec::new();
+    let mut pos = 6; // Object identifier and opening tag 1
+    while pos < data.len() && data[pos] != 0x1F {
+        let len = (data[pos] & 0x07) as usize;
+        let Some(bytes) = data.get(pos + 1..pos + 1 + len) else { break };
+        if data[pos] >> 4 == 0 {
+            props.push(bytes.iter().fold(0u32, |acc, b| (acc << 8) | *b as u32));
+        }
+        pos += 1 + len;
+    }
+    props
 }
 
-fn handle_read_property_multiple(invoke_id: u8, device_id: u32) -> Option<Vec<u8>> {
-    let objects = vec![
-        (ObjectType::Device, device_id),
-        (ObjectType::AnalogInput, 1),
-        (ObjectType::BinaryInput, 1),
-        (ObjectType::AnalogValue, 1),
-    ];
-
-    let mut service_data = Vec::new();
-    let dev_id_encoded = ((ObjectType::Device as u32) << 22) | (device_id & 0x3FFFFF);
-    service_data.push(0x0C);
-    service_data.extend_from_slice(&dev_id_encoded.to_be_bytes());
+fn character_string(text: &str) -> Vec<u8> {
+    let mut bytes = vec![0x75, text.len() as u8 + 1, 0x00];
+    bytes.extend_from_slice(text.as_bytes());
+    bytes
+}
+
+fn object_id_value(ot: ObjectType, instance: u32) -> Vec<u8> {
+    let mut bytes = vec![0xC4];
+    bytes.extend_from_slice(&(((ot as u32) << 22) | (instance & 0x3FFFFF)).to_be_bytes());
+    bytes
+}
+
+/// Properties returned for the ALL property identifier
+fn all_properties(obj_id: ObjectIdentifier) -> Vec<u32> {
+    match obj_id.object_type {
+        ObjectType::Device => vec![75, 77, 79, 70, 44, 12, 58, 28, 98, 139, 112, 11, 119, 56, 57, 97, 96, 76],
+        ObjectType::AnalogInput | ObjectType::AnalogValue => vec![75, 77, 79, 85, 28, 111, 36, 103, 81, 117, 22],
+        ObjectType::MultiStateValue => vec![75, 77, 79, 85, 28, 111, 36, 103, 81, 74, 110],
+        _ => vec![75, 77, 79, 85, 28, 111, 36, 103, 81, 46, 4],
+    }
+}
+
+fn object_property(device_id: u32, obj_id: ObjectIdentifier, prop: u32) -> Option<Vec<u8>> {
+    let value = match (obj_id.object_type, prop) {
+        (ot, 75) => object_id_value(ot, obj_id.instance),
+        (ot, 79) => vec![0x91, ot as u8],
+        (ObjectType::Device, 77) => character_string(&format!("Test Device {}", device_id)),
+        (ObjectType::Device, 70) => character_string("Responder"),
+        (ObjectType::Device, 44) => character_string("1.0"),
+        (ObjectType::Device, 12) => character_string(env!("CARGO_PKG_VERSION")),
+        (ObjectType::Device, 58) => character_string("Lab"),
+        (ObjectType::Device, 28) => character_string("BACnet test responder"),
+        (ObjectType::Device, 98) => vec![0x21, 1],
+        (ObjectType::Device, 139) => vec![0x21, 14],
+        (ObjectType::Device, 112) => vec![0x91, 0], // Operational
+        (ObjectType::Device, 11) => vec![0x22, 0x0B, 0xB8], // 3000 ms
+        (ObjectType::Device, 119) => vec![0x31, 0xC4], // -60 minutes
+        (ObjectType::Device, 56) => vec![0xA4, 126, 10, 18, 7],
+        (ObjectType::Device, 57) => vec![0xB4, 12, 30, 0, 0],
+        // ReadProperty, ReadPropertyMultiple, I-Am, Who-Is
+        (ObjectType::Device, 97) => vec![0x85, 0x06, 0x00, 0x00, 0x0A, 0x00, 0x20, 0x20],
+        // Analog Input, Analog Value, Binary Input, Device
+        (ObjectType::Device, 96) => vec![0x83, 0x00, 0xB0, 0x80],
+        (ObjectType::Device, 76) => [
+            (ObjectType::Device, device_id),
+            (ObjectType::AnalogInput, 1),
+            (ObjectType::BinaryInput, 1),
+            (ObjectType::AnalogValue, 1),
+            (ObjectType::MultiStateValue, 1),
+        ].iter().flat_map(|(ot, inst)| object_id_value(*ot, *inst)).collect(),
+        (ObjectType::Device, _) => return None,
+        (ot, 77) => character_string(&format!("{:?} {}", ot, obj_id.instance)),
+        (ObjectType::AnalogInput, 85) => vec![0x44, 0x41, 0xB4, 0x00, 0x00],
+        (ObjectType::AnalogValue, 85) => vec![0x44, 0x42, 0x48, 0x00, 0x00],
+        (ObjectType::BinaryInput, 85) => vec![0x91, 0x01],
+        (ObjectType::MultiStateValue, 85) => vec![0x21, 0x02],
+        (_, 85) => vec![0x21, 0x00],
+        (_, 28) => character_string("Simulated point"),
+        (_, 111) => vec![0x82, 0x04, 0x00], // Status_Flags: all clear
+        (_, 36) => vec![0x91, 0x00], // Event_State: normal
+        (_, 103) => vec![0x91, 0x00], // Reliability: no-fault-detected
+        (_, 81) => vec![0x10], // Out_Of_Service: false
+        (ObjectType::AnalogInput | ObjectType::AnalogValue, 117) => vec![0x91, 62], // degrees-celsius
+        (ObjectType::AnalogInput | ObjectType::AnalogValue, 22) => vec![0x44, 0x3F, 0x00, 0x00, 0x00], // 0.5
+        (ObjectType::BinaryInput, 46) => character_string("Off"),
+        (ObjectType::BinaryInput, 4) => character_string("On"),
+        (ObjectType::MultiStateValue, 74) => vec![0x21, 3],
+        (ObjectType::MultiStateValue, 110) => ["Off", "Low", "High"].iter().flat_map(|s| character_string(s)).collect(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn handle_read_property_multiple(invoke_id: u8, device_id: u32, request: &[u8]) -> Option<Vec<u8>> {
+    let obj_id = extract_object_id_from_rp(request)?;
+    let mut props = requested_properties(request);
+    if props.contains(&8) {
+        props = all_properties(obj_id);
+    }
+
+    let mut service_data = request[..5].to_vec();
     service_data.push(0x1E);
-    service_data.push(0x29);
-    service_data.push(76);
-    service_data.push(0x4E);
-    for (ot, inst) in objects {
-        let encoded = ((ot as u32) << 22) | (inst & 0x3FFFFF);
-        service_data.push(0xC4);
-        service_data.extend_from_slice(&encoded.to_be_bytes());
+    for prop in props {
+        service_data.push(0x29);
+        service_data.push(prop as u8);
+        if let Some(value) = object_property(device_id, obj_id, prop) {
+            service_data.push(0x4E);
+            service_data.extend_from_slice(&value);
+            service_data.push(0x4F);
+        } else {
+            // Property error: unknown-property
+            service_data.extend_from_slice(&[0x5E, 0x91, 0x02, 0x91, 0x20, 0x5F]);
+        }
     }
-    service_data.push(0x4F);
     service_data.push(0x1F);
 
     create_complex_ack(invoke_id, ConfirmedServiceChoice::ReadPropertyMultiple, service_data).ok()
 }
 
+fn create_error(invoke_id: u8, service: ConfirmedServiceChoice, class: u8, code: u8) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut message = Npdu::new().encode();
+    message.extend_from_slice(&[0x50, invoke_id, service as u8, 0x91, class, 0x91, code]);
+    let mut bvlc = vec![0x81, 0x0A, 0x00, 0x00];
+    bvlc.extend_from_slice(&message);
+    let total_len = bvlc.len() as u16;
+    bvlc[2] = (total_len >> 8) as u8;
+    bvlc[3] = (total_len & 0xFF) as u8;
+    Ok(bvlc)
+}
+
 fn create_complex_ack(invoke_id: u8, service: ConfirmedServiceChoice, service_data: Vec<u8>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
     let apdu = Apdu::ComplexAck {
         segmented: false,
@@ -164,7 +270,7 @@ fn create_complex_ack(invoke_id: u8, service: ConfirmedServiceChoice, service_da
         service_choice: service as u8,
         service_data,
     };
-    let mut npdu = Npdu::new();
+    let npdu = Npdu::new();
     let mut message = npdu.encode();
     message.extend_from_slice(&apdu.encode());
     let mut bvlc = vec![0x81, 0x0A, 0x00, 0x00];
@@ -0,0 +1,60 @@
+use anyhow::{Result, anyhow};
+use bacnet_discovery::scan_diff::{self, ScanDiff};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+struct Args {
+    old: PathBuf,
+    new: PathBuf,
+    json: bool,
+    output: Option<PathBuf>,
+}
+
+/// Parses `[--json] [--output <path>] <old.json> <new.json>`. Either file may be
+/// an export (`x` or `headless-scan --export`) or a site database.
+fn parse_args() -> Result<Args> {
+    let mut args = std::env::args().skip(1);
+    let (mut json, mut output, mut files) = (false, None, Vec::new());
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--output" => output = Some(args.next().map(PathBuf::from).ok_or_else(|| anyhow!("Missing value for {}", arg))?),
+            _ if arg.starts_with("--") => return Err(anyhow!("Unknown argument: {}", arg)),
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+    match <[PathBuf; 2]>::try_from(files) {
+        Ok([old, new]) => Ok(Args { old, new, json, output }),
+        Err(_) => Err(anyhow!("Usage: scan-diff [--json] [--output <path>] <old.json> <new.json>")),
+    }
+}
+
+/// Writes the report; returns true if the scans differ
+fn run() -> Result<bool> {
+    let args = parse_args()?;
+    let load = |path: &PathBuf| scan_diff::load_scan(path).map_err(|e| anyhow!("{}: {}", path.display(), e));
+    let diff = ScanDiff::new(&load(&args.old)?, &load(&args.new)?);
+
+    let report = if args.json {
+        diff.to_json()? + "\n"
+    } else {
+        format!("--- {}\n+++ {}\n{}", args.old.display(), args.new.display(), diff.to_text())
+    };
+    match &args.output {
+        Some(path) => std::fs::write(path, report)?,
+        None => print!("{}", report),
+    }
+    Ok(!diff.is_empty())
+}
+
+/// Exits with 0 when the scans match, 1 when they differ and 2 on errors, like diff(1)
+fn main() -> ExitCode {
+    match run() {
+        Ok(false) => ExitCode::SUCCESS,
+        Ok(true) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("scan-diff: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
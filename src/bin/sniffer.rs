@@ -1,69 +1,188 @@
-use bacnet_rs::{
-    network::Npdu,
-    service::UnconfirmedServiceChoice,
-};
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use anyhow::{Result, anyhow};
 use bacnet_discovery::network::create_shared_socket;
+use bacnet_discovery::pcap::{PcapWriter, read_capture_file};
+use bacnet_discovery::traffic::{Datagram, DeviceAddresses, PacketFilter, TrafficStats, received_destination, time_of_day};
+
+const USAGE: &str = "\
+Usage: sniffer [options]
+
+  --port <port>                     BACnet/IP port (default 47808)
+  --write <capture.pcap>            Also save the datagrams that match --filter
+  --read <capture.pcap|.pcapng>     Decode a saved capture instead of listening
+  --filter <expression>             Only show, count and save matching datagrams
+  --stats                           Print traffic summaries instead of each packet
+  --interval <seconds>              Seconds between summaries (default 10)";
+
+struct Args {
+    port: u16,
+    write: Option<PathBuf>,
+    read: Option<PathBuf>,
+    filter: PacketFilter,
+    stats: bool,
+    interval: Duration,
+}
+
+fn parse_args() -> Result<Args> {
+    let mut args = std::env::args().skip(1);
+    let mut parsed = Args { port: 47808, write: None, read: None, filter: PacketFilter::default(), stats: false, interval: Duration::from_secs(10) };
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| anyhow!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--port" => parsed.port = value()?.parse().map_err(|_| anyhow!("Invalid port"))?,
+            "--write" | "-w" => parsed.write = Some(PathBuf::from(value()?)),
+            "--read" | "-r" => parsed.read = Some(PathBuf::from(value()?)),
+            "--filter" | "-f" => parsed.filter = PacketFilter::parse(&value()?)?,
+            "--stats" | "-s" => parsed.stats = true,
+            "--interval" => {
+                let secs: u64 = value()?.parse().map_err(|_| anyhow!("Invalid interval"))?;
+                parsed.interval = Duration::from_secs(secs.max(1));
+            }
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            _ => return Err(anyhow!("Unknown argument: {}\n{}", arg, USAGE)),
+        }
+    }
+    if parsed.read.is_some() && parsed.write.is_some() {
+        return Err(anyhow!("--read and --write cannot be combined"));
+    }
+    Ok(parsed)
+}
+
+fn print_datagram(datagram: &Datagram) {
+    println!(
+        "--- {} {} -> {} ({} bytes): {} ---",
+        time_of_day(datagram.at), datagram.source, datagram.destination, datagram.length, datagram.packet.summary()
+    );
+    for line in datagram.packet.lines() {
+        println!("{}", line);
+    }
+    println!();
+}
+
+fn print_stats(title: &str, stats: &TrafficStats, elapsed: Duration, filter: &PacketFilter) {
+    if filter.is_empty() {
+        println!("=== {} ===", title);
+    } else {
+        println!("=== {} (filter: {}) ===", title, filter.as_str());
+    }
+    for line in stats.lines(elapsed) {
+        println!("{}", line);
+    }
+    println!();
+}
+
+/// Decodes a saved capture; datagrams that are not BVLL or neither come from nor go
+/// to the BACnet/IP port are skipped
+fn read_offline(path: &Path, args: &Args) -> Result<()> {
+    let packets = read_capture_file(path)?;
+    let datagrams: Vec<Datagram> = packets.iter()
+        .filter(|p| p.source.port() == args.port || p.destination.port() == args.port)
+        .filter(|p| p.payload.first() == Some(&0x81))
+        .map(|p| Datagram::new(p.timestamp, p.source, p.destination, &p.payload))
+        .collect();
+    // The whole capture is known up front, so devices match from before their I-Am too
+    let mut devices = DeviceAddresses::new();
+    datagrams.iter().for_each(|d| devices.learn(d));
+    let matching: Vec<&Datagram> = datagrams.iter().filter(|d| args.filter.matches(d, &devices)).collect();
+    println!(
+        "{}: {} UDP datagrams, {} BACnet/IP, {} matching\n",
+        path.display(), packets.len(), datagrams.len(), matching.len()
+    );
+    if args.stats {
+        let mut stats = TrafficStats::new();
+        matching.iter().for_each(|d| stats.record(d));
+        print_stats("Capture summary", &stats, stats.span(), &args.filter);
+    } else {
+        matching.into_iter().for_each(print_datagram);
+    }
+    Ok(())
+}
+
+fn local_ipv4() -> Ipv4Addr {
+    if_addrs::get_if_addrs().unwrap_or_default().into_iter()
+        .find_map(|iface| match iface.ip() {
+            IpAddr::V4(ip) if !iface.is_loopback() => Some(ip),
+            _ => None,
+        })
+        .unwrap_or(Ipv4Addr::UNSPECIFIED)
+}
+
+fn main() -> Result<()> {
+    let args = parse_args()?;
+    if let Some(path) = &args.read {
+        return read_offline(path, &args);
+    }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("BACnet Network Sniffer (Shared Mode)");
     println!("====================================");
 
-    let socket = match create_shared_socket(47808) {
+    let socket = match create_shared_socket(args.port) {
         Ok(s) => s,
         Err(e) => {
-            eprintln!("Error: Could not bind to 47808: {}", e);
+            eprintln!("Error: Could not bind to {}: {}", args.port, e);
             return Err(e.into());
         }
     };
+    let mut capture = match &args.write {
+        Some(path) => {
+            println!("Writing capture to {}", path.display());
+            Some(PcapWriter::create(path)?)
+        }
+        None => None,
+    };
+    let local = SocketAddr::new(IpAddr::V4(local_ipv4()), args.port);
+    let broadcast = SocketAddr::new(IpAddr::V4(Ipv4Addr::BROADCAST), args.port);
+    if !args.filter.is_empty() {
+        println!("Filter: {}", args.filter.as_str());
+    }
+    if args.stats {
+        // Wake up regularly so summaries are printed on a quiet network too
+        socket.set_read_timeout(Some(Duration::from_millis(500)))?;
+        println!("Printing a traffic summary every {} s", args.interval.as_secs());
+    }
 
-    println!("Listening for BACnet traffic on port 47808 (Shared)...");
+    println!("Listening for BACnet traffic on port {} (Shared)...", args.port);
     println!("Press Ctrl+C to stop.\n");
 
+    let mut stats = TrafficStats::new();
+    let mut devices = DeviceAddresses::new();
+    let mut interval_start = Instant::now();
     let mut buffer = [0u8; 1500];
     loop {
         match socket.recv_from(&mut buffer) {
             Ok((len, source)) => {
-                println!("--- Packet from {} ({} bytes) ---", source, len);
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
                 let data = &buffer[..len];
-                
-                if len >= 4 && data[0] == 0x81 {
-                    let bvlc_func = data[1];
-                    let bvlc_type = match bvlc_func {
-                        0x00 => "Result",
-                        0x04 => "Forwarded-NPDU",
-                        0x0A => "Original-Unicast-NPDU",
-                        0x0B => "Original-Broadcast-NPDU",
-                        _ => "Unknown",
-                    };
-                    println!("BVLL: {} (0x{:02X})", bvlc_type, bvlc_func);
-
-                    let npdu_start = match bvlc_func {
-                        0x0A | 0x0B => 4,
-                        0x04 => 10,
-                        _ => 0,
-                    };
-
-                    if npdu_start > 0 && len > npdu_start {
-                        if let Ok((_npdu, npdu_len)) = Npdu::decode(&data[npdu_start..]) {
-                            let apdu_start = npdu_start + npdu_len;
-                            if len > apdu_start {
-                                let apdu = &data[apdu_start..];
-                                let pdu_type = (apdu[0] & 0xF0) >> 4;
-                                if pdu_type == 1 && apdu.len() >= 2 {
-                                    let service = apdu[1];
-                                    if service == UnconfirmedServiceChoice::IAm as u8 {
-                                        println!("SERVICE: I-Am");
-                                    } else if service == UnconfirmedServiceChoice::WhoIs as u8 {
-                                        println!("SERVICE: Who-Is");
-                                    }
-                                }
-                            }
-                        }
+                let datagram = Datagram::new(now, source, received_destination(data, local, broadcast), data);
+                devices.learn(&datagram);
+                if args.filter.matches(&datagram, &devices) {
+                    if args.stats {
+                        stats.record(&datagram);
+                    } else {
+                        print_datagram(&datagram);
+                    }
+                    // Flushed per packet so the file is complete whenever Ctrl+C arrives
+                    if let Some(writer) = capture.as_mut()
+                        && let Err(e) = writer.write_packet(now, source, datagram.destination, data).and_then(|_| writer.flush())
+                    {
+                        eprintln!("Error writing capture: {}", e);
                     }
                 }
-                println!();
             }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
             Err(e) => eprintln!("Error receiving: {}", e),
         }
+        if args.stats && interval_start.elapsed() >= args.interval {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+            print_stats(&format!("Traffic summary at {}", time_of_day(now)), &stats, interval_start.elapsed(), &args.filter);
+            stats = TrafficStats::new();
+            interval_start = Instant::now();
+        }
     }
 }
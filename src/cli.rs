@@ -0,0 +1,18 @@
+//! Option parsing shared by the command-line tools.
+
+use anyhow::{Result, anyhow};
+use std::time::Duration;
+
+/// Longest `--duration` or similar wait the tools accept
+pub const MAX_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Seconds, fractions allowed, from zero up to [`MAX_DURATION`]
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let duration = text.parse::<f64>().ok()
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        .ok_or_else(|| anyhow!("Invalid duration: {}", text))?;
+    if duration > MAX_DURATION {
+        return Err(anyhow!("Duration {} is too long (at most {} s)", text, MAX_DURATION.as_secs()));
+    }
+    Ok(duration)
+}
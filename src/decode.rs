@@ -0,0 +1,1072 @@
+//! Protocol decode of BACnet/IP packets for the sniffer.
+//!
+//! [`decode_packet`] takes one UDP payload apart layer by layer: the BVLL header,
+//! the NPDU with its control bits, routing addresses and network-layer message,
+//! and the APDU header and service parameters. Decoding never fails as a whole;
+//! it stops at the first malformed layer and records why in
+//! [`DecodedPacket::error`], keeping everything decoded before it.
+//!
+//! Service parameters are decoded from a table of the context tags each service
+//! uses. Tags a table does not list are still shown, as raw values, so vendor
+//! extensions and services without a table remain visible.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use bacnet_rs::vendor::get_vendor_name;
+use serde::Serialize;
+use crate::format;
+use crate::property::{self, BacnetValue, PropertyEntry};
+
+/// A decoded field, with nested fields for constructed parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub value: String,
+    pub children: Vec<Field>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BvllHeader {
+    pub function: u8,
+    pub length: u16,
+    /// Address of the original sender of a Forwarded-NPDU
+    pub origin: Option<SocketAddr>,
+    /// Fields of BVLL messages that carry no NPDU (BVLC-Result, Register-Foreign-Device, ...)
+    pub fields: Vec<Field>,
+    /// Entries of a Write-BDT or Read-BDT-Ack
+    pub bdt: Vec<BdtEntry>,
+}
+
+/// One entry of a Broadcast Distribution Table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BdtEntry {
+    pub address: SocketAddr,
+    pub mask: Ipv4Addr,
+}
+
+/// A remote network address from the NPDU; an empty MAC is a broadcast on that network
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkAddress {
+    pub net: u16,
+    pub mac: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NpduHeader {
+    pub version: u8,
+    pub control: u8,
+    pub destination: Option<NetworkAddress>,
+    pub source: Option<NetworkAddress>,
+    pub hop_count: Option<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkMessage {
+    pub message_type: u8,
+    /// Present for proprietary message types (0x80 and above)
+    pub vendor_id: Option<u16>,
+    pub fields: Vec<Field>,
+    /// Network numbers the message lists, e.g. the networks an I-Am-Router-To-Network reaches
+    pub networks: Vec<u16>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApduHeader {
+    ConfirmedRequest {
+        segmented: bool,
+        more_follows: bool,
+        segmented_response_accepted: bool,
+        max_segments: u8,
+        max_apdu: u16,
+        invoke_id: u8,
+        sequence_number: Option<u8>,
+        window_size: Option<u8>,
+        service: u8,
+    },
+    UnconfirmedRequest { service: u8 },
+    SimpleAck { invoke_id: u8, service: u8 },
+    ComplexAck {
+        segmented: bool,
+        more_follows: bool,
+        invoke_id: u8,
+        sequence_number: Option<u8>,
+        window_size: Option<u8>,
+        service: u8,
+    },
+    SegmentAck { negative: bool, server: bool, invoke_id: u8, sequence_number: u8, window_size: u8 },
+    Error { invoke_id: u8, service: u8 },
+    Reject { invoke_id: u8, reason: u8 },
+    Abort { server: bool, invoke_id: u8, reason: u8 },
+}
+
+/// Everything decoded from one packet
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DecodedPacket {
+    pub bvll: Option<BvllHeader>,
+    pub npdu: Option<NpduHeader>,
+    pub network_message: Option<NetworkMessage>,
+    pub apdu: Option<ApduHeader>,
+    /// Service parameters of the APDU
+    pub parameters: Vec<Field>,
+    /// Device instance announced by an I-Am
+    pub iam_device: Option<u32>,
+    /// Instance limits of a Who-Is; None for a Who-Is addressed to every device
+    pub who_is_range: Option<(u32, u32)>,
+    /// Why decoding stopped early, if it did
+    pub error: Option<String>,
+}
+
+impl Field {
+    fn new(name: &str, value: impl Into<String>) -> Self {
+        Self { name: name.to_string(), value: value.into(), children: Vec::new() }
+    }
+}
+
+impl NpduHeader {
+    pub fn is_network_message(&self) -> bool {
+        self.control & 0x80 != 0
+    }
+
+    pub fn expecting_reply(&self) -> bool {
+        self.control & 0x04 != 0
+    }
+
+    pub fn priority(&self) -> u8 {
+        self.control & 0x03
+    }
+}
+
+impl ApduHeader {
+    pub fn invoke_id(&self) -> Option<u8> {
+        match *self {
+            ApduHeader::UnconfirmedRequest { .. } => None,
+            ApduHeader::ConfirmedRequest { invoke_id, .. }
+            | ApduHeader::SimpleAck { invoke_id, .. }
+            | ApduHeader::ComplexAck { invoke_id, .. }
+            | ApduHeader::SegmentAck { invoke_id, .. }
+            | ApduHeader::Error { invoke_id, .. }
+            | ApduHeader::Reject { invoke_id, .. }
+            | ApduHeader::Abort { invoke_id, .. } => Some(invoke_id),
+        }
+    }
+
+    /// Name of the PDU type, e.g. `Confirmed-Request`
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ApduHeader::ConfirmedRequest { .. } => "Confirmed-Request",
+            ApduHeader::UnconfirmedRequest { .. } => "Unconfirmed-Request",
+            ApduHeader::SimpleAck { .. } => "Simple-ACK",
+            ApduHeader::ComplexAck { .. } => "Complex-ACK",
+            ApduHeader::SegmentAck { .. } => "Segment-ACK",
+            ApduHeader::Error { .. } => "Error",
+            ApduHeader::Reject { .. } => "Reject",
+            ApduHeader::Abort { .. } => "Abort",
+        }
+    }
+
+    /// Name of the service, for PDU types that carry one
+    pub fn service_name(&self) -> Option<String> {
+        match *self {
+            ApduHeader::UnconfirmedRequest { service } => Some(unconfirmed_service_name(service)),
+            ApduHeader::ConfirmedRequest { service, .. }
+            | ApduHeader::SimpleAck { service, .. }
+            | ApduHeader::ComplexAck { service, .. }
+            | ApduHeader::Error { service, .. } => Some(confirmed_service_name(service)),
+            _ => None,
+        }
+    }
+}
+
+impl DecodedPacket {
+    /// What the packet carries: `confirmed`, `unconfirmed`, `simple-ack`, `complex-ack`,
+    /// `segment-ack`, `error`, `reject`, `abort`, `network` (a network layer message),
+    /// `bvll` (a BVLL message without NPDU) or `malformed`
+    pub fn kind(&self) -> &'static str {
+        match (&self.apdu, &self.network_message, &self.npdu, &self.bvll) {
+            (Some(ApduHeader::ConfirmedRequest { .. }), ..) => "confirmed",
+            (Some(ApduHeader::UnconfirmedRequest { .. }), ..) => "unconfirmed",
+            (Some(ApduHeader::SimpleAck { .. }), ..) => "simple-ack",
+            (Some(ApduHeader::ComplexAck { .. }), ..) => "complex-ack",
+            (Some(ApduHeader::SegmentAck { .. }), ..) => "segment-ack",
+            (Some(ApduHeader::Error { .. }), ..) => "error",
+            (Some(ApduHeader::Reject { .. }), ..) => "reject",
+            (Some(ApduHeader::Abort { .. }), ..) => "abort",
+            (None, Some(_), ..) => "network",
+            (None, None, None, Some(_)) if self.error.is_none() => "bvll",
+            _ => "malformed",
+        }
+    }
+
+    /// The service, network message or BVLL function the packet is about, for grouping traffic
+    pub fn category(&self) -> String {
+        if let Some(apdu) = &self.apdu {
+            return apdu.service_name().unwrap_or_else(|| apdu.type_name().to_string());
+        }
+        if let Some(message) = &self.network_message {
+            return network_message_name(message.message_type);
+        }
+        match &self.bvll {
+            Some(bvll) if self.error.is_none() => bvll_function_name(bvll.function).to_string(),
+            _ => "Malformed".to_string(),
+        }
+    }
+
+    /// Sent as a broadcast: a broadcast BVLL function, or a remote or global broadcast in the NPDU
+    pub fn is_broadcast(&self) -> bool {
+        let bvll = self.bvll.as_ref().is_some_and(|b| matches!(b.function, 0x04 | 0x09 | 0x0B));
+        let npdu = self.npdu.as_ref().and_then(|n| n.destination.as_ref()).is_some_and(|d| d.mac.is_empty());
+        bvll || npdu
+    }
+
+    /// One line describing the packet, e.g. `ReadProperty request AnalogInput:1 Present_Value (invoke 5)`
+    pub fn summary(&self) -> String {
+        if let Some(error) = &self.error
+            && self.apdu.is_none()
+            && self.network_message.is_none()
+        {
+            return match &self.bvll {
+                Some(bvll) => format!("{} (malformed: {})", bvll_function_name(bvll.function), error),
+                None => format!("Not BACnet/IP ({})", error),
+            };
+        }
+        if let Some(message) = &self.network_message {
+            return network_message_name(message.message_type);
+        }
+        let Some(apdu) = &self.apdu else {
+            return self.bvll.as_ref().map_or_else(String::new, |b| bvll_function_name(b.function).to_string());
+        };
+
+        let mut summary = match apdu {
+            ApduHeader::UnconfirmedRequest { .. } => apdu.service_name().unwrap_or_default(),
+            ApduHeader::ConfirmedRequest { .. } => format!("{} request", apdu.service_name().unwrap_or_default()),
+            ApduHeader::SimpleAck { .. } | ApduHeader::ComplexAck { .. } => format!("{} ack", apdu.service_name().unwrap_or_default()),
+            ApduHeader::Error { .. } => format!("{} error", apdu.service_name().unwrap_or_default()),
+            ApduHeader::Reject { reason, .. } => format!("Reject: {}", reject_reason_name(*reason)),
+            ApduHeader::Abort { reason, .. } => format!("Abort: {}", abort_reason_name(*reason)),
+            ApduHeader::SegmentAck { negative, sequence_number, .. } => {
+                format!("Segment-{} {}", if *negative { "NAK" } else { "ACK" }, sequence_number)
+            }
+        };
+        // The first few top-level values say what the request is about
+        let highlights: Vec<&str> = self.parameters.iter()
+            .filter(|f| f.children.is_empty())
+            .take(2)
+            .map(|f| f.value.as_str())
+            .collect();
+        if !highlights.is_empty() {
+            summary.push(' ');
+            summary.push_str(&highlights.join(" "));
+        }
+        if let Some(id) = apdu.invoke_id() {
+            summary.push_str(&format!(" (invoke {})", id));
+        }
+        summary
+    }
+
+    /// The full decode, one layer after another, with nested fields indented
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(bvll) = &self.bvll {
+            let mut line = format!("BVLL: {} (0x{:02X}), length {}", bvll_function_name(bvll.function), bvll.function, bvll.length);
+            if let Some(origin) = bvll.origin {
+                line.push_str(&format!(", from {}", origin));
+            }
+            lines.push(line);
+            push_fields(&mut lines, &bvll.fields, 1);
+        }
+        if let Some(npdu) = &self.npdu {
+            let mut line = format!("NPDU: version {}, control 0x{:02X} (", npdu.version, npdu.control);
+            line.push_str(if npdu.is_network_message() { "network message" } else { "APDU" });
+            if npdu.expecting_reply() {
+                line.push_str(", expecting reply");
+            }
+            line.push_str(&format!(", priority {})", priority_name(npdu.priority())));
+            if let Some(dest) = &npdu.destination {
+                line.push_str(&format!(", DNET {} DADR {}", dest.net, mac_text(&dest.mac)));
+            }
+            if let Some(source) = &npdu.source {
+                line.push_str(&format!(", SNET {} SADR {}", source.net, mac_text(&source.mac)));
+            }
+            if let Some(hops) = npdu.hop_count {
+                line.push_str(&format!(", hop count {}", hops));
+            }
+            lines.push(line);
+        }
+        if let Some(message) = &self.network_message {
+            let mut line = format!("Network message: {} (0x{:02X})", network_message_name(message.message_type), message.message_type);
+            if let Some(vendor) = message.vendor_id {
+                line.push_str(&format!(", vendor {}", vendor_text(vendor as u32)));
+            }
+            lines.push(line);
+            push_fields(&mut lines, &message.fields, 1);
+        }
+        if let Some(apdu) = &self.apdu {
+            lines.push(format!("APDU: {}", apdu_text(apdu)));
+            push_fields(&mut lines, &self.parameters, 1);
+        }
+        if let Some(error) = &self.error {
+            lines.push(format!("Decode stopped: {}", error));
+        }
+        lines
+    }
+}
+
+fn push_fields(lines: &mut Vec<String>, fields: &[Field], depth: usize) {
+    for field in fields {
+        let indent = "  ".repeat(depth);
+        if field.value.is_empty() {
+            lines.push(format!("{}{}:", indent, field.name));
+        } else {
+            lines.push(format!("{}{}: {}", indent, field.name, field.value));
+        }
+        push_fields(lines, &field.children, depth + 1);
+    }
+}
+
+fn apdu_text(apdu: &ApduHeader) -> String {
+    let service = |service: String, number: u8| format!("service {} ({})", service, number);
+    match *apdu {
+        ApduHeader::ConfirmedRequest {
+            segmented, more_follows, segmented_response_accepted, max_segments, max_apdu, invoke_id, sequence_number, window_size, service: s,
+        } => {
+            let mut text = format!("Confirmed-Request, invoke {}, {}, max APDU {}, max segments {}", invoke_id, service(confirmed_service_name(s), s), max_apdu, max_segments_text(max_segments));
+            if segmented_response_accepted {
+                text.push_str(", segmented response accepted");
+            }
+            if segmented {
+                text.push_str(&format!(", segment {} window {}{}", sequence_number.unwrap_or(0), window_size.unwrap_or(0), if more_follows { ", more follows" } else { "" }));
+            }
+            text
+        }
+        ApduHeader::UnconfirmedRequest { service: s } => format!("Unconfirmed-Request, {}", service(unconfirmed_service_name(s), s)),
+        ApduHeader::SimpleAck { invoke_id, service: s } => format!("Simple-ACK, invoke {}, {}", invoke_id, service(confirmed_service_name(s), s)),
+        ApduHeader::ComplexAck { segmented, more_follows, invoke_id, sequence_number, window_size, service: s } => {
+            let mut text = format!("Complex-ACK, invoke {}, {}", invoke_id, service(confirmed_service_name(s), s));
+            if segmented {
+                text.push_str(&format!(", segment {} window {}{}", sequence_number.unwrap_or(0), window_size.unwrap_or(0), if more_follows { ", more follows" } else { "" }));
+            }
+            text
+        }
+        ApduHeader::SegmentAck { negative, server, invoke_id, sequence_number, window_size } => format!(
+            "Segment-ACK{}, invoke {}, from {}, sequence {}, window {}",
+            if negative { " (NAK)" } else { "" }, invoke_id, if server { "server" } else { "client" }, sequence_number, window_size
+        ),
+        ApduHeader::Error { invoke_id, service: s } => format!("Error, invoke {}, {}", invoke_id, service(confirmed_service_name(s), s)),
+        ApduHeader::Reject { invoke_id, reason } => format!("Reject, invoke {}, reason {} ({})", invoke_id, reject_reason_name(reason), reason),
+        ApduHeader::Abort { server, invoke_id, reason } => format!(
+            "Abort, invoke {}, from {}, reason {} ({})",
+            invoke_id, if server { "server" } else { "client" }, abort_reason_name(reason), reason
+        ),
+    }
+}
+
+/// Decodes one BACnet/IP UDP payload
+pub fn decode_packet(data: &[u8]) -> DecodedPacket {
+    let mut packet = DecodedPacket::default();
+    if let Err(error) = decode_layers(data, &mut packet) {
+        packet.error = Some(error);
+    }
+    packet
+}
+
+fn decode_layers(data: &[u8], packet: &mut DecodedPacket) -> Result<(), String> {
+    if data.len() < 4 || data[0] != 0x81 {
+        return Err("no BVLL header (type 0x81)".to_string());
+    }
+    let function = data[1];
+    let length = u16::from_be_bytes([data[2], data[3]]);
+    if length as usize != data.len() {
+        return Err(format!("BVLL length {} but {} bytes received", length, data.len()));
+    }
+    let body = &data[4..];
+    let mut bvll = BvllHeader { function, length, origin: None, fields: Vec::new(), bdt: Vec::new() };
+
+    let npdu = match function {
+        // Distribute-Broadcast-To-Network, Original-Unicast-NPDU and Original-Broadcast-NPDU
+        0x09..=0x0B => body,
+        0x04 => {
+            let origin = body.get(..6).ok_or("Forwarded-NPDU too short")?;
+            bvll.origin = Some(SocketAddr::new(
+                IpAddr::V4(Ipv4Addr::new(origin[0], origin[1], origin[2], origin[3])),
+                u16::from_be_bytes([origin[4], origin[5]]),
+            ));
+            &body[6..]
+        }
+        _ => {
+            if matches!(function, 0x01 | 0x03) {
+                bvll.bdt = bdt_entries(body);
+            }
+            bvll.fields = bvll_fields(function, body);
+            packet.bvll = Some(bvll);
+            return Ok(());
+        }
+    };
+    packet.bvll = Some(bvll);
+    decode_npdu(npdu, packet)
+}
+
+/// Fields of the BVLL messages that are not NPDU carriers
+fn bvll_fields(function: u8, body: &[u8]) -> Vec<Field> {
+    let entry = |chunk: &[u8]| format!("{}.{}.{}.{}:{}", chunk[0], chunk[1], chunk[2], chunk[3], u16::from_be_bytes([chunk[4], chunk[5]]));
+    match (function, body) {
+        (0x00, [hi, lo, ..]) => {
+            let code = u16::from_be_bytes([*hi, *lo]);
+            vec![Field::new("Result", format!("{} (0x{:04X})", bvlc_result_name(code), code))]
+        }
+        (0x05, [hi, lo, ..]) => vec![Field::new("Time to live", format!("{} s", u16::from_be_bytes([*hi, *lo])))],
+        (0x01 | 0x03, _) => bdt_entries(body).iter().map(|e| Field::new("BDT entry", format!("{} mask {}", e.address, e.mask))).collect(),
+        // Read-FDT-Ack: address, port, TTL and time remaining per entry
+        (0x07, _) => body.chunks_exact(10).map(|c| {
+            Field::new("FDT entry", format!(
+                "{} TTL {} s, {} s remaining",
+                entry(c), u16::from_be_bytes([c[6], c[7]]), u16::from_be_bytes([c[8], c[9]])
+            ))
+        }).collect(),
+        (0x08, _) if body.len() >= 6 => vec![Field::new("FDT entry", entry(body))],
+        _ => Vec::new(),
+    }
+}
+
+/// Write-BDT and Read-BDT-Ack body: address, port and mask per entry
+pub fn bdt_entries(body: &[u8]) -> Vec<BdtEntry> {
+    body.chunks_exact(10).map(|c| BdtEntry {
+        address: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(c[0], c[1], c[2], c[3])), u16::from_be_bytes([c[4], c[5]])),
+        mask: Ipv4Addr::new(c[6], c[7], c[8], c[9]),
+    }).collect()
+}
+
+fn decode_npdu(data: &[u8], packet: &mut DecodedPacket) -> Result<(), String> {
+    let (&version, rest) = data.split_first().ok_or("missing NPDU")?;
+    let &control = rest.first().ok_or("NPDU too short")?;
+    let mut pos = 2;
+    let address = |pos: &mut usize| -> Result<NetworkAddress, String> {
+        let header = data.get(*pos..*pos + 3).ok_or("NPDU address truncated")?;
+        let net = u16::from_be_bytes([header[0], header[1]]);
+        let len = header[2] as usize;
+        let mac = data.get(*pos + 3..*pos + 3 + len).ok_or("NPDU address truncated")?.to_vec();
+        *pos += 3 + len;
+        Ok(NetworkAddress { net, mac })
+    };
+    let destination = if control & 0x20 != 0 { Some(address(&mut pos)?) } else { None };
+    let source = if control & 0x08 != 0 { Some(address(&mut pos)?) } else { None };
+    let hop_count = if destination.is_some() {
+        let hops = *data.get(pos).ok_or("NPDU hop count missing")?;
+        pos += 1;
+        Some(hops)
+    } else {
+        None
+    };
+    let npdu = NpduHeader { version, control, destination, source, hop_count };
+    let is_network_message = npdu.is_network_message();
+    packet.npdu = Some(npdu);
+    if version != 1 {
+        return Err(format!("unsupported NPDU version {}", version));
+    }
+
+    if is_network_message {
+        let &message_type = data.get(pos).ok_or("network message type missing")?;
+        pos += 1;
+        let vendor_id = if message_type >= 0x80 {
+            let bytes = data.get(pos..pos + 2).ok_or("network message vendor ID missing")?;
+            pos += 2;
+            Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+        } else {
+            None
+        };
+        let fields = network_message_fields(message_type, &data[pos..]);
+        let networks = listed_networks(message_type, &data[pos..]);
+        packet.network_message = Some(NetworkMessage { message_type, vendor_id, fields, networks });
+        return Ok(());
+    }
+    decode_apdu(&data[pos..], packet)
+}
+
+/// Network numbers carried by a network layer message
+fn listed_networks(message_type: u8, data: &[u8]) -> Vec<u16> {
+    let networks = |data: &[u8]| data.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+    match (message_type, data) {
+        (0x00 | 0x01 | 0x04 | 0x05, _) => networks(data),
+        (0x03, [_, rest @ ..]) => networks(rest),
+        (0x02 | 0x13, [hi, lo, ..]) => vec![u16::from_be_bytes([*hi, *lo])],
+        _ => Vec::new(),
+    }
+}
+
+fn network_message_fields(message_type: u8, data: &[u8]) -> Vec<Field> {
+    let networks = |data: &[u8]| -> Vec<Field> {
+        data.chunks_exact(2).map(|c| Field::new("Network", u16::from_be_bytes([c[0], c[1]]).to_string())).collect()
+    };
+    match (message_type, data) {
+        // Who-Is-Router-To-Network, I-Am-Router, Router-Busy/Available: network numbers
+        (0x00 | 0x01 | 0x04 | 0x05, _) => networks(data),
+        (0x02, [hi, lo, performance, ..]) => vec![
+            Field::new("Network", u16::from_be_bytes([*hi, *lo]).to_string()),
+            Field::new("Performance index", performance.to_string()),
+        ],
+        (0x03, [reason, rest @ ..]) => {
+            let mut fields = vec![Field::new("Reason", format!("{} ({})", reject_network_reason_name(*reason), reason))];
+            fields.extend(networks(rest));
+            fields
+        }
+        (0x06 | 0x07, [count, ports @ ..]) => {
+            let mut fields = vec![Field::new("Ports", count.to_string())];
+            let mut pos = 0;
+            while let Some(port) = ports.get(pos..pos + 4) {
+                let info_len = port[3] as usize;
+                fields.push(Field::new("Port", format!("network {}, port ID {}", u16::from_be_bytes([port[0], port[1]]), port[2])));
+                pos += 4 + info_len;
+            }
+            fields
+        }
+        (0x13, [hi, lo, flag, ..]) => vec![
+            Field::new("Network", u16::from_be_bytes([*hi, *lo]).to_string()),
+            Field::new("Configured", if *flag == 1 { "yes" } else { "learned" }),
+        ],
+        (_, []) => Vec::new(),
+        _ => vec![Field::new("Data", hex(data))],
+    }
+}
+
+fn decode_apdu(data: &[u8], packet: &mut DecodedPacket) -> Result<(), String> {
+    let &first = data.first().ok_or("missing APDU")?;
+    let byte = |i: usize| data.get(i).copied().ok_or_else(|| "APDU header truncated".to_string());
+    let (header, params) = match first >> 4 {
+        0 => {
+            let segmented = first & 0x08 != 0;
+            let second = byte(1)?;
+            let (sequence_number, window_size, service, start) = if segmented {
+                (Some(byte(3)?), Some(byte(4)?), byte(5)?, 6)
+            } else {
+                (None, None, byte(3)?, 4)
+            };
+            (ApduHeader::ConfirmedRequest {
+                segmented,
+                more_follows: first & 0x04 != 0,
+                segmented_response_accepted: first & 0x02 != 0,
+                max_segments: (second >> 4) & 0x07,
+                max_apdu: max_apdu_size(second & 0x0F),
+                invoke_id: byte(2)?,
+                sequence_number,
+                window_size,
+                service,
+            }, start)
+        }
+        1 => (ApduHeader::UnconfirmedRequest { service: byte(1)? }, 2),
+        2 => (ApduHeader::SimpleAck { invoke_id: byte(1)?, service: byte(2)? }, 3),
+        3 => {
+            let segmented = first & 0x08 != 0;
+            let (sequence_number, window_size, service, start) = if segmented {
+                (Some(byte(2)?), Some(byte(3)?), byte(4)?, 5)
+            } else {
+                (None, None, byte(2)?, 3)
+            };
+            (ApduHeader::ComplexAck { segmented, more_follows: first & 0x04 != 0, invoke_id: byte(1)?, sequence_number, window_size, service }, start)
+        }
+        4 => (ApduHeader::SegmentAck {
+            negative: first & 0x02 != 0,
+            server: first & 0x01 != 0,
+            invoke_id: byte(1)?,
+            sequence_number: byte(2)?,
+            window_size: byte(3)?,
+        }, 4),
+        5 => (ApduHeader::Error { invoke_id: byte(1)?, service: byte(2)? }, 3),
+        6 => (ApduHeader::Reject { invoke_id: byte(1)?, reason: byte(2)? }, 3),
+        7 => (ApduHeader::Abort { server: first & 0x01 != 0, invoke_id: byte(1)?, reason: byte(2)? }, 3),
+        other => return Err(format!("unknown APDU type {}", other)),
+    };
+    packet.apdu = Some(header);
+
+    let data = &data[params..];
+    // Later segments continue the encoding mid-stream, so only the first is decoded
+    let continuation = matches!(header, ApduHeader::ConfirmedRequest { sequence_number: Some(n), .. } | ApduHeader::ComplexAck { sequence_number: Some(n), .. } if n > 0);
+    if data.is_empty() || continuation {
+        if continuation {
+            packet.parameters.push(Field::new("Segment data", format!("{} bytes", data.len())));
+        }
+        return Ok(());
+    }
+
+    if let ApduHeader::Error { .. } = header
+        && let Some(error) = property::decode_error(data)
+    {
+        packet.parameters = vec![
+            Field::new("Error class", named(error.class, property::error_class_name(error.class))),
+            Field::new("Error code", named(error.code, property::error_code_name(error.code))),
+        ];
+        return Ok(());
+    }
+
+    let mut values = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        match property::decode_value(&data[pos..]) {
+            Some((value, consumed)) => {
+                values.push(value);
+                pos += consumed;
+            }
+            None => break,
+        }
+    }
+    match (header, values.as_slice()) {
+        (ApduHeader::UnconfirmedRequest { service: 0 }, [BacnetValue::ObjectId { object_type: 8, instance }, ..]) => {
+            packet.iam_device = Some(*instance);
+        }
+        (ApduHeader::UnconfirmedRequest { service: 8 }, [BacnetValue::Context { tag: 0, data: low }, BacnetValue::Context { tag: 1, data: high }]) => {
+            packet.who_is_range = unsigned(low).zip(unsigned(high)).map(|(low, high)| (low as u32, high as u32));
+        }
+        _ => {}
+    }
+    let specs = service_parameters(&header);
+    packet.parameters = match specs {
+        Some(Params::Application(specs)) => application_fields(&values, specs),
+        Some(Params::Context(specs)) => context_fields(&values, specs),
+        None => context_fields(&values, &[]),
+    };
+    if pos < data.len() {
+        packet.parameters.push(Field::new("Undecoded", hex(&data[pos..])));
+        return Err(format!("malformed service parameters at byte {}", params + pos));
+    }
+    Ok(())
+}
+
+/// How a service parameter is shown
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Unsigned,
+    Boolean,
+    Enumerated,
+    ObjectId,
+    /// A property identifier; also the context for naming the values that follow it
+    Property,
+    Text,
+    /// Constructed: application-tagged property values
+    Values,
+    /// Constructed: context-tagged fields of their own
+    Sequence(&'static [Spec]),
+    Segmentation,
+    Vendor,
+    /// Any application value, shown as is
+    Value,
+}
+
+/// A service parameter: its context tag (or position, for application-tagged services), name and kind
+#[derive(Debug, Clone, Copy)]
+struct Spec {
+    tag: u8,
+    name: &'static str,
+    kind: Kind,
+}
+
+enum Params {
+    Context(&'static [Spec]),
+    Application(&'static [Spec]),
+}
+
+const fn spec(tag: u8, name: &'static str, kind: Kind) -> Spec {
+    Spec { tag, name, kind }
+}
+
+const PROPERTY_REFERENCE: &[Spec] = &[spec(0, "Property", Kind::Property), spec(1, "Array index", Kind::Unsigned)];
+const PROPERTY_VALUE: &[Spec] = &[
+    spec(0, "Property", Kind::Property),
+    spec(1, "Array index", Kind::Unsigned),
+    spec(2, "Value", Kind::Values),
+    spec(3, "Priority", Kind::Unsigned),
+];
+const RPM_RESULT: &[Spec] = &[
+    spec(2, "Property", Kind::Property),
+    spec(3, "Array index", Kind::Unsigned),
+    spec(4, "Value", Kind::Values),
+    spec(5, "Error", Kind::Values),
+];
+const READ_PROPERTY: &[Spec] = &[
+    spec(0, "Object", Kind::ObjectId),
+    spec(1, "Property", Kind::Property),
+    spec(2, "Array index", Kind::Unsigned),
+    spec(3, "Value", Kind::Values),
+];
+const WRITE_PROPERTY: &[Spec] = &[
+    spec(0, "Object", Kind::ObjectId),
+    spec(1, "Property", Kind::Property),
+    spec(2, "Array index", Kind::Unsigned),
+    spec(3, "Value", Kind::Values),
+    spec(4, "Priority", Kind::Unsigned),
+];
+const READ_PROPERTY_MULTIPLE: &[Spec] = &[spec(0, "Object", Kind::ObjectId), spec(1, "Properties", Kind::Sequence(PROPERTY_REFERENCE))];
+const READ_PROPERTY_MULTIPLE_ACK: &[Spec] = &[spec(0, "Object", Kind::ObjectId), spec(1, "Results", Kind::Sequence(RPM_RESULT))];
+const WRITE_PROPERTY_MULTIPLE: &[Spec] = &[spec(0, "Object", Kind::ObjectId), spec(1, "Values", Kind::Sequence(PROPERTY_VALUE))];
+const SUBSCRIBE_COV: &[Spec] = &[
+    spec(0, "Process ID", Kind::Unsigned),
+    spec(1, "Object", Kind::ObjectId),
+    spec(2, "Confirmed notifications", Kind::Boolean),
+    spec(3, "Lifetime", Kind::Unsigned),
+    spec(4, "Property", Kind::Sequence(PROPERTY_REFERENCE)),
+];
+const COV_NOTIFICATION: &[Spec] = &[
+    spec(0, "Process ID", Kind::Unsigned),
+    spec(1, "Device", Kind::ObjectId),
+    spec(2, "Object", Kind::ObjectId),
+    spec(3, "Time remaining", Kind::Unsigned),
+    spec(4, "Values", Kind::Sequence(PROPERTY_VALUE)),
+];
+const LIST_ELEMENT: &[Spec] = &[
+    spec(0, "Object", Kind::ObjectId),
+    spec(1, "Property", Kind::Property),
+    spec(2, "Array index", Kind::Unsigned),
+    spec(3, "Elements", Kind::Values),
+];
+const READ_RANGE: &[Spec] = &[
+    spec(0, "Object", Kind::ObjectId),
+    spec(1, "Property", Kind::Property),
+    spec(2, "Array index", Kind::Unsigned),
+];
+const READ_RANGE_ACK: &[Spec] = &[
+    spec(0, "Object", Kind::ObjectId),
+    spec(1, "Property", Kind::Property),
+    spec(2, "Array index", Kind::Unsigned),
+    spec(4, "Item count", Kind::Unsigned),
+    spec(5, "Items", Kind::Values),
+    spec(6, "First sequence number", Kind::Unsigned),
+];
+const DEVICE_COMMUNICATION_CONTROL: &[Spec] = &[
+    spec(0, "Duration (minutes)", Kind::Unsigned),
+    spec(1, "Enable/disable", Kind::Enumerated),
+    spec(2, "Password", Kind::Text),
+];
+const REINITIALIZE_DEVICE: &[Spec] = &[spec(0, "State", Kind::Enumerated), spec(1, "Password", Kind::Text)];
+const ACKNOWLEDGE_ALARM: &[Spec] = &[
+    spec(0, "Process ID", Kind::Unsigned),
+    spec(1, "Object", Kind::ObjectId),
+    spec(2, "Event state", Kind::Enumerated),
+    spec(4, "Acknowledged by", Kind::Text),
+];
+const GET_EVENT_INFORMATION: &[Spec] = &[spec(0, "Last object", Kind::ObjectId)];
+const WHO_IS: &[Spec] = &[spec(0, "Low limit", Kind::Unsigned), spec(1, "High limit", Kind::Unsigned)];
+const WHO_HAS: &[Spec] = &[
+    spec(0, "Low limit", Kind::Unsigned),
+    spec(1, "High limit", Kind::Unsigned),
+    spec(2, "Object", Kind::ObjectId),
+    spec(3, "Object name", Kind::Text),
+];
+const I_AM: &[Spec] = &[
+    spec(0, "Device", Kind::Value),
+    spec(1, "Max APDU", Kind::Value),
+    spec(2, "Segmentation", Kind::Segmentation),
+    spec(3, "Vendor", Kind::Vendor),
+];
+const I_HAVE: &[Spec] = &[spec(0, "Device", Kind::Value), spec(1, "Object", Kind::Value), spec(2, "Object name", Kind::Value)];
+const TIME_SYNCHRONIZATION: &[Spec] = &[spec(0, "Date", Kind::Value), spec(1, "Time", Kind::Value)];
+const CREATED_OBJECT: &[Spec] = &[spec(0, "Object", Kind::Value)];
+const DELETE_OBJECT: &[Spec] = &[spec(0, "Object", Kind::Value)];
+
+fn service_parameters(header: &ApduHeader) -> Option<Params> {
+    let params = match *header {
+        ApduHeader::UnconfirmedRequest { service } => match service {
+            0 => Params::Application(I_AM),
+            1 => Params::Application(I_HAVE),
+            2 => Params::Context(COV_NOTIFICATION),
+            6 | 9 => Params::Application(TIME_SYNCHRONIZATION),
+            7 => Params::Context(WHO_HAS),
+            8 => Params::Context(WHO_IS),
+            _ => return None,
+        },
+        ApduHeader::ConfirmedRequest { service, .. } => match service {
+            0 => Params::Context(ACKNOWLEDGE_ALARM),
+            1 => Params::Context(COV_NOTIFICATION),
+            5 | 28 => Params::Context(SUBSCRIBE_COV),
+            8 | 9 => Params::Context(LIST_ELEMENT),
+            11 => Params::Application(DELETE_OBJECT),
+            12 => Params::Context(READ_PROPERTY),
+            14 => Params::Context(READ_PROPERTY_MULTIPLE),
+            15 => Params::Context(WRITE_PROPERTY),
+            16 => Params::Context(WRITE_PROPERTY_MULTIPLE),
+            17 => Params::Context(DEVICE_COMMUNICATION_CONTROL),
+            20 => Params::Context(REINITIALIZE_DEVICE),
+            26 => Params::Context(READ_RANGE),
+            29 => Params::Context(GET_EVENT_INFORMATION),
+            _ => return None,
+        },
+        ApduHeader::ComplexAck { service, .. } => match service {
+            10 => Params::Application(CREATED_OBJECT),
+            12 => Params::Context(READ_PROPERTY),
+            14 => Params::Context(READ_PROPERTY_MULTIPLE_ACK),
+            26 => Params::Context(READ_RANGE_ACK),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(params)
+}
+
+fn application_fields(values: &[BacnetValue], specs: &[Spec]) -> Vec<Field> {
+    values.iter().enumerate().map(|(i, value)| {
+        match specs.iter().find(|s| s.tag as usize == i) {
+            Some(spec) => Field::new(spec.name, application_text(value, spec.kind)),
+            None => value_field(value, None),
+        }
+    }).collect()
+}
+
+fn application_text(value: &BacnetValue, kind: Kind) -> String {
+    match (kind, value) {
+        (Kind::Segmentation, BacnetValue::Enumerated(n)) => format!("{} ({})", property::segmentation_name(*n), n),
+        (Kind::Vendor, BacnetValue::Unsigned(n)) => vendor_text(*n as u32),
+        _ => format::format_value(value),
+    }
+}
+
+fn context_fields(values: &[BacnetValue], specs: &[Spec]) -> Vec<Field> {
+    let mut fields = Vec::new();
+    // Values are named after the property identifier that precedes them
+    let mut property = None;
+    for value in values {
+        let spec = match value {
+            BacnetValue::Context { tag, .. } | BacnetValue::Constructed { tag, .. } => specs.iter().find(|s| s.tag == *tag),
+            _ => {
+                fields.push(value_field(value, property));
+                continue;
+            }
+        };
+        let Some(spec) = spec else {
+            fields.push(value_field(value, property));
+            continue;
+        };
+        let field = match (value, spec.kind) {
+            (BacnetValue::Context { data, .. }, kind) => {
+                let text = context_text(data, kind);
+                if let Kind::Property = kind {
+                    property = unsigned(data).map(|id| id as u32);
+                }
+                Field::new(spec.name, text)
+            }
+            (BacnetValue::Constructed { values, .. }, Kind::Sequence(inner)) => {
+                Field { name: spec.name.to_string(), value: String::new(), children: context_fields(values, inner) }
+            }
+            (BacnetValue::Constructed { values, .. }, _) => match values.as_slice() {
+                [single] if !matches!(single, BacnetValue::Constructed { .. }) => Field::new(spec.name, value_text(single, property)),
+                _ => Field {
+                    name: spec.name.to_string(),
+                    value: String::new(),
+                    children: values.iter().map(|v| value_field(v, property)).collect(),
+                },
+            },
+            _ => value_field(value, property),
+        };
+        fields.push(field);
+    }
+    fields
+}
+
+fn context_text(data: &[u8], kind: Kind) -> String {
+    match kind {
+        Kind::Unsigned => unsigned(data).map_or_else(|| hex(data), |n| n.to_string()),
+        Kind::Boolean => (data.first().is_some_and(|b| *b != 0)).to_string(),
+        Kind::Enumerated => unsigned(data).map_or_else(|| hex(data), |n| n.to_string()),
+        Kind::ObjectId => match <[u8; 4]>::try_from(data) {
+            Ok(bytes) => {
+                let raw = u32::from_be_bytes(bytes);
+                format::format_value(&BacnetValue::ObjectId { object_type: (raw >> 22) as u16, instance: raw & 0x3FFFFF })
+            }
+            Err(_) => hex(data),
+        },
+        Kind::Property => unsigned(data).map_or_else(|| hex(data), |id| format!("{} ({})", property::property_name(id as u32), id)),
+        Kind::Text => property::decode_character_string(data),
+        _ => hex(data),
+    }
+}
+
+fn value_field(value: &BacnetValue, property: Option<u32>) -> Field {
+    match value {
+        BacnetValue::Constructed { tag, values } => Field {
+            name: format!("[{}]", tag),
+            value: String::new(),
+            children: values.iter().map(|v| value_field(v, property)).collect(),
+        },
+        BacnetValue::Context { tag, data } => Field::new(&format!("[{}]", tag), hex(data)),
+        _ => Field::new(type_name(value), value_text(value, property)),
+    }
+}
+
+/// A value as the property browser shows it, when the property is known
+fn value_text(value: &BacnetValue, property: Option<u32>) -> String {
+    match property {
+        Some(id) => format::format_property(&PropertyEntry { id, array_index: None, value: Ok(value.clone()) }),
+        None => format::format_value(value),
+    }
+}
+
+fn type_name(value: &BacnetValue) -> &'static str {
+    match value {
+        BacnetValue::Null => "Null",
+        BacnetValue::Boolean(_) => "Boolean",
+        BacnetValue::Unsigned(_) => "Unsigned",
+        BacnetValue::Signed(_) => "Signed",
+        BacnetValue::Real(_) => "Real",
+        BacnetValue::Double(_) => "Double",
+        BacnetValue::OctetString(_) => "Octet string",
+        BacnetValue::CharacterString(_) => "Character string",
+        BacnetValue::BitString(_) => "Bit string",
+        BacnetValue::Enumerated(_) => "Enumerated",
+        BacnetValue::Date { .. } => "Date",
+        BacnetValue::Time { .. } => "Time",
+        BacnetValue::ObjectId { .. } => "Object",
+        BacnetValue::Context { .. } | BacnetValue::Constructed { .. } | BacnetValue::List(_) => "Value",
+    }
+}
+
+fn unsigned(data: &[u8]) -> Option<u64> {
+    (!data.is_empty() && data.len() <= 8).then(|| data.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64))
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ")
+}
+
+fn mac_text(mac: &[u8]) -> String {
+    if mac.is_empty() { "broadcast".to_string() } else { mac.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(":") }
+}
+
+fn named(value: u32, name: Option<&str>) -> String {
+    match name {
+        Some(name) => format!("{} ({})", name, value),
+        None => value.to_string(),
+    }
+}
+
+fn vendor_text(vendor_id: u32) -> String {
+    let name = u16::try_from(vendor_id).ok().and_then(get_vendor_name);
+    named(vendor_id, name)
+}
+
+fn max_apdu_size(code: u8) -> u16 {
+    match code {
+        0 => 50,
+        1 => 128,
+        2 => 206,
+        3 => 480,
+        4 => 1024,
+        _ => 1476,
+    }
+}
+
+fn max_segments_text(code: u8) -> String {
+    match code {
+        0 => "unspecified".to_string(),
+        7 => "more than 64".to_string(),
+        n => (1u32 << n).to_string(),
+    }
+}
+
+fn priority_name(priority: u8) -> &'static str {
+    match priority {
+        0 => "normal",
+        1 => "urgent",
+        2 => "critical equipment",
+        _ => "life safety",
+    }
+}
+
+pub fn bvll_function_name(function: u8) -> &'static str {
+    match function {
+        0x00 => "BVLC-Result",
+        0x01 => "Write-Broadcast-Distribution-Table",
+        0x02 => "Read-Broadcast-Distribution-Table",
+        0x03 => "Read-Broadcast-Distribution-Table-Ack",
+        0x04 => "Forwarded-NPDU",
+        0x05 => "Register-Foreign-Device",
+        0x06 => "Read-Foreign-Device-Table",
+        0x07 => "Read-Foreign-Device-Table-Ack",
+        0x08 => "Delete-Foreign-Device-Table-Entry",
+        0x09 => "Distribute-Broadcast-To-Network",
+        0x0A => "Original-Unicast-NPDU",
+        0x0B => "Original-Broadcast-NPDU",
+        0x0C => "Secure-BVLL",
+        _ => "Unknown",
+    }
+}
+
+fn bvlc_result_name(code: u16) -> &'static str {
+    match code {
+        0x0000 => "Successful completion",
+        0x0010 => "Write-BDT NAK",
+        0x0020 => "Read-BDT NAK",
+        0x0030 => "Register-Foreign-Device NAK",
+        0x0040 => "Read-FDT NAK",
+        0x0050 => "Delete-FDT-Entry NAK",
+        0x0060 => "Distribute-Broadcast-To-Network NAK",
+        _ => "Unknown",
+    }
+}
+
+pub fn network_message_name(message_type: u8) -> String {
+    const NAMES: &[&str] = &[
+        "Who-Is-Router-To-Network", "I-Am-Router-To-Network", "I-Could-Be-Router-To-Network",
+        "Reject-Message-To-Network", "Router-Busy-To-Network", "Router-Available-To-Network",
+        "Initialize-Routing-Table", "Initialize-Routing-Table-Ack", "Establish-Connection-To-Network",
+        "Disconnect-Connection-To-Network", "Challenge-Request", "Security-Payload", "Security-Response",
+        "Request-Key-Update", "Update-Key-Set", "Update-Distribution-Key", "Request-Master-Key",
+        "Set-Master-Key", "What-Is-Network-Number", "Network-Number-Is",
+    ];
+    match NAMES.get(message_type as usize) {
+        Some(name) => name.to_string(),
+        None if message_type >= 0x80 => format!("Proprietary-Message-{}", message_type),
+        None => format!("Reserved-Message-{}", message_type),
+    }
+}
+
+fn reject_network_reason_name(reason: u8) -> &'static str {
+    match reason {
+        0 => "Other",
+        1 => "Not directly connected and no route",
+        2 => "Router busy",
+        3 => "Unknown network message type",
+        4 => "Message too long",
+        5 => "Security error",
+        6 => "Addressing error",
+        _ => "Unknown",
+    }
+}
+
+/// BACnetConfirmedServiceChoice names (Clause 21)
+pub fn confirmed_service_name(service: u8) -> String {
+    const NAMES: &[&str] = &[
+        "AcknowledgeAlarm", "ConfirmedCOVNotification", "ConfirmedEventNotification", "GetAlarmSummary",
+        "GetEnrollmentSummary", "SubscribeCOV", "AtomicReadFile", "AtomicWriteFile", "AddListElement",
+        "RemoveListElement", "CreateObject", "DeleteObject", "ReadProperty", "ReadPropertyConditional",
+        "ReadPropertyMultiple", "WriteProperty", "WritePropertyMultiple", "DeviceCommunicationControl",
+        "ConfirmedPrivateTransfer", "ConfirmedTextMessage", "ReinitializeDevice", "VT-Open", "VT-Close",
+        "VT-Data", "Authenticate", "RequestKey", "ReadRange", "LifeSafetyOperation", "SubscribeCOVProperty",
+        "GetEventInformation", "SubscribeCOVPropertyMultiple", "ConfirmedCOVNotificationMultiple",
+        "ConfirmedAuditNotification", "AuditLogQuery",
+    ];
+    NAMES.get(service as usize).map_or_else(|| format!("Service-{}", service), |n| n.to_string())
+}
+
+/// BACnetUnconfirmedServiceChoice names (Clause 21)
+pub fn unconfirmed_service_name(service: u8) -> String {
+    const NAMES: &[&str] = &[
+        "I-Am", "I-Have", "UnconfirmedCOVNotification", "UnconfirmedEventNotification",
+        "UnconfirmedPrivateTransfer", "UnconfirmedTextMessage", "TimeSynchronization", "Who-Has", "Who-Is",
+        "UTCTimeSynchronization", "WriteGroup", "UnconfirmedCOVNotificationMultiple",
+        "UnconfirmedAuditNotification", "Who-Am-I", "You-Are",
+    ];
+    NAMES.get(service as usize).map_or_else(|| format!("Service-{}", service), |n| n.to_string())
+}
+
+pub fn reject_reason_name(reason: u8) -> &'static str {
+    const NAMES: &[&str] = &[
+        "Other", "Buffer-Overflow", "Inconsistent-Parameters", "Invalid-Parameter-Data-Type", "Invalid-Tag",
+        "Missing-Required-Parameter", "Parameter-Out-Of-Range", "Too-Many-Arguments", "Undefined-Enumeration",
+        "Unrecognized-Service",
+    ];
+    NAMES.get(reason as usize).copied().unwrap_or("Unknown")
+}
+
+pub fn abort_reason_name(reason: u8) -> &'static str {
+    const NAMES: &[&str] = &[
+        "Other", "Buffer-Overflow", "Invalid-APDU-In-This-State", "Preempted-By-Higher-Priority-Task",
+        "Segmentation-Not-Supported", "Security-Error", "Insufficient-Security", "Window-Size-Out-Of-Range",
+        "Application-Exceeded-Reply-Time", "Out-Of-Resources", "TSM-Timeout", "APDU-Too-Long",
+    ];
+    NAMES.get(reason as usize).copied().unwrap_or("Unknown")
+}
+
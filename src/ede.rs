@@ -0,0 +1,185 @@
+//! EDE (Engineering Data Exchange, BACnet Interest Group Europe) export.
+//!
+//! Writes the three semicolon-separated files of layout 2.2: the data point list
+//! `<base>_EDE.csv`, `<base>_StateTexts.csv` referenced from its
+//! `state-text-reference` column, and `<base>_UnitTexts.csv` naming the codes in
+//! its `unit-code` column. Every device gets a row for its Device object followed
+//! by its points, ordered by type and instance. Identical state text sets share
+//! one reference number.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use anyhow::Result;
+use crate::app::BacnetObject;
+use crate::bacnet::DiscoveredDevice;
+use crate::export::unix_time;
+use crate::property::{self, BacnetValue, PropertyEntry};
+use bacnet_rs::object::ObjectType;
+
+pub const LAYOUT_VERSION: &str = "2.2";
+
+const MAIN_COLUMNS: &[&str] = &[
+    "keyname", "device obj.-instance", "object-name", "object-type", "object-instance", "description",
+    "present-value-default", "min-present-value", "max-present-value", "settable", "supports COV",
+    "hi-limit", "low-limit", "state-text-reference", "unit-code", "vendor-specific-address",
+];
+
+/// Contents of the three EDE files
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdeExport {
+    pub main: String,
+    pub state_texts: String,
+    pub unit_texts: String,
+}
+
+impl EdeExport {
+    pub fn new(
+        project: &str,
+        devices: &HashMap<u32, DiscoveredDevice>,
+        properties: &HashMap<u32, Vec<PropertyEntry>>,
+        objects: &HashMap<u32, Vec<BacnetObject>>,
+    ) -> Self {
+        let mut main = String::new();
+        main.push_str(&line(["#Engineering-Data-Exchange - B.I.G.-EU"]));
+        main.push_str(&line(["PROJECT_NAME", project]));
+        main.push_str(&line(["VERSION_OF_REFERENCEFILE", "1"]));
+        main.push_str(&line(["TIMESTAMP_OF_LAST_CHANGE", &iso_date(unix_time(Instant::now()))]));
+        main.push_str(&line(["AUTHOR_OF_LAST_CHANGE", env!("CARGO_PKG_NAME")]));
+        main.push_str(&line(["VERSION_OF_LAYOUT", LAYOUT_VERSION]));
+        let required = MAIN_COLUMNS.iter().enumerate().map(|(i, _)| if i < 5 { "mandatory" } else { "optional" });
+        main.push_str(&format!("#{}", line(required)));
+        main.push_str(&format!("# {}", line(MAIN_COLUMNS.iter().copied())));
+
+        // State text sets in order of first use; the reference is the 1-based position
+        let mut state_sets: Vec<Vec<String>> = Vec::new();
+        let mut unit_codes: Vec<u32> = Vec::new();
+
+        let mut ids: Vec<_> = devices.keys().cloned().collect();
+        ids.sort();
+        for device_id in ids {
+            let text = |id| match properties.get(&device_id).and_then(|p| property::find(p, id)) {
+                Some(BacnetValue::CharacterString(text)) => text.clone(),
+                _ => String::new(),
+            };
+            let name = Some(text(property::OBJECT_NAME)).filter(|n| !n.is_empty()).unwrap_or_else(|| format!("Device {}", device_id));
+            main.push_str(&line([
+                keyname(device_id, ObjectType::Device, device_id).as_str(),
+                &device_id.to_string(),
+                &clean(&name),
+                &(ObjectType::Device as u16).to_string(),
+                &device_id.to_string(),
+                &clean(&text(property::DESCRIPTION)),
+                "", "", "", "", "", "", "", "", "", "",
+            ]));
+
+            let mut points: Vec<&BacnetObject> = objects.get(&device_id).map(|o| o.iter().collect()).unwrap_or_default();
+            points.sort_by_key(|o| (o.id.object_type as u16, o.id.instance));
+            for obj in points {
+                let state_reference = state_texts(obj).map(|texts| {
+                    let index = state_sets.iter().position(|set| *set == texts).unwrap_or_else(|| {
+                        state_sets.push(texts);
+                        state_sets.len() - 1
+                    });
+                    (index + 1).to_string()
+                });
+                if let Some(code) = obj.units_code
+                    && !unit_codes.contains(&code)
+                {
+                    unit_codes.push(code);
+                }
+                main.push_str(&line([
+                    keyname(device_id, obj.id.object_type, obj.id.instance).as_str(),
+                    &device_id.to_string(),
+                    &clean(&obj.name),
+                    &(obj.id.object_type as u16).to_string(),
+                    &obj.id.instance.to_string(),
+                    &clean(obj.description.as_deref().unwrap_or_default()),
+                    "", "", "", "", "", "", "",
+                    state_reference.as_deref().unwrap_or_default(),
+                    &obj.units_code.map(|c| c.to_string()).unwrap_or_default(),
+                    "",
+                ]));
+            }
+        }
+
+        let widest = state_sets.iter().map(Vec::len).max().unwrap_or(2);
+        let mut state_texts = line(["#EDE-Statetexts - B.I.G.-EU"]);
+        let text_columns: Vec<String> = (1..=widest).map(|i| format!("Text {}", i)).collect();
+        state_texts.push_str(&format!("#{}", line(std::iter::once("Reference Number").chain(text_columns.iter().map(String::as_str)))));
+        for (i, set) in state_sets.iter().enumerate() {
+            let reference = (i + 1).to_string();
+            let texts: Vec<String> = set.iter().map(|t| clean(t)).collect();
+            state_texts.push_str(&line(std::iter::once(reference.as_str()).chain(texts.iter().map(String::as_str))));
+        }
+
+        unit_codes.sort();
+        let mut unit_texts = line(["#EDE-Unittexts - B.I.G.-EU"]);
+        unit_texts.push_str(&format!("#{}", line(["Unit Code", "Unit Text"])));
+        for code in unit_codes {
+            let name = property::units_name(code).unwrap_or_else(|| format!("units-{}", code));
+            unit_texts.push_str(&line([code.to_string().as_str(), &name]));
+        }
+
+        Self { main, state_texts, unit_texts }
+    }
+
+    /// Writes `<base>_EDE.csv`, `<base>_StateTexts.csv` and `<base>_UnitTexts.csv`
+    pub fn write_files(&self, base: &Path) -> Result<[PathBuf; 3]> {
+        let path = |suffix: &str| {
+            let mut name = base.file_name().unwrap_or_default().to_os_string();
+            name.push(suffix);
+            base.with_file_name(name)
+        };
+        let paths = [path("_EDE.csv"), path("_StateTexts.csv"), path("_UnitTexts.csv")];
+        std::fs::write(&paths[0], &self.main)?;
+        std::fs::write(&paths[1], &self.state_texts)?;
+        std::fs::write(&paths[2], &self.unit_texts)?;
+        Ok(paths)
+    }
+}
+
+/// Texts for each state, starting with state 1 (multistate) or inactive (binary)
+fn state_texts(obj: &BacnetObject) -> Option<Vec<String>> {
+    let texts = obj.state_texts.as_ref()?;
+    if !texts.states.is_empty() {
+        return Some(texts.states.clone());
+    }
+    match (&texts.inactive, &texts.active) {
+        (None, None) => None,
+        (inactive, active) => Some(vec![inactive.clone().unwrap_or_default(), active.clone().unwrap_or_default()]),
+    }
+}
+
+fn keyname(device_id: u32, object_type: ObjectType, instance: u32) -> String {
+    match property::object_type_abbreviation(object_type) {
+        Some(abbr) => format!("{}_{}{}", device_id, abbr, instance),
+        None => format!("{}_{}-{}", device_id, object_type as u16, instance),
+    }
+}
+
+/// EDE has no quoting, so separators and line breaks are replaced in text fields
+fn clean(text: &str) -> String {
+    text.replace(';', ",").replace(['\r', '\n'], " ")
+}
+
+fn line<'a>(fields: impl IntoIterator<Item = &'a str>) -> String {
+    let mut line = fields.into_iter().collect::<Vec<_>>().join(";");
+    line.push_str("\r\n");
+    line
+}
+
+/// `YYYY-MM-DD` for a Unix time, in UTC
+fn iso_date(unix_secs: u64) -> String {
+    // Days to civil date (H. Hinnant's algorithm)
+    let z = (unix_secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
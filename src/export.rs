@@ -0,0 +1,357 @@
+//! Export of discovered devices and points to CSV and JSON.
+//!
+//! The schema is versioned by [`SCHEMA_VERSION`]; fields are only ever added, and
+//! a change to the meaning of an existing field bumps the version.
+//!
+//! JSON is one [`ScanExport`] document:
+//!
+//! | Field | Type | Meaning |
+//! | --- | --- | --- |
+//! | `schema_version` | number | [`SCHEMA_VERSION`] |
+//! | `generated_at` | number | Unix time of the export, seconds |
+//! | `devices` | array | [`ExportDevice`] per device, ordered by device ID |
+//! | `devices[].device_id` | number | Device object instance |
+//! | `devices[].name` | string/null | Object_Name of the Device object, if read |
+//! | `devices[].address` | string | `ip:port`, or `mstp:<mac>` for MS/TP stations |
+//! | `devices[].vendor_id`, `vendor_name` | number, string | From I-Am |
+//! | `devices[].max_apdu` | number | Max APDU length accepted |
+//! | `devices[].firmware_revision`, `application_software_version` | string/null | From the Device object, if read |
+//! | `devices[].segmentation` | string | `both`, `transmit`, `receive`, `none` or `unknown` |
+//! | `devices[].online` | bool | False once the device stopped answering |
+//! | `devices[].last_seen` | number | Unix time of the last response, seconds |
+//! | `devices[].duplicate_addresses` | array | Every address answering with this instance, when more than one does; left out otherwise |
+//! | `devices[].previous_address` | string | The address the device answered from before it moved; left out otherwise |
+//! | `devices[].objects` | array | [`ExportObject`] per point, ordered by type and instance |
+//! | `objects[].object_type` | string | e.g. `AnalogInput` |
+//! | `objects[].object_type_id` | number | BACnetObjectType value |
+//! | `objects[].instance` | number | Object instance |
+//! | `objects[].name`, `description`, `units` | string | Empty when unknown |
+//! | `objects[].units_code` | number/null | BACnetEngineeringUnits value of `units` |
+//! | `objects[].present_value` | number/bool/string/null | Typed value: number for analog, multistate and enumerated, bool for binary, null if unread |
+//! | `objects[].present_value_text` | string | Value as shown in the TUI, with state texts applied |
+//! | `objects[].in_alarm`, `fault`, `overridden`, `out_of_service` | bool/null | Status_Flags; null if not read |
+//! | `objects[].reliability` | string/null | Reliability name; null if not read |
+//! | `objects[].properties` | object | Property name to display text, for properties read with `headless-scan --deep --properties`; left out when empty |
+//!
+//! CSV has one row per point, with the device columns repeated. Devices without
+//! points get one row with empty point columns. Columns, in order, are
+//! [`CSV_HEADER`]; `flags` is the space-separated list of `ALARM FAULT OVRD OOS`
+//! and `address_conflict` the text of [`ExportDevice::conflict`].
+//!
+//! The table form is for people, not parsers: one line per device, columns
+//! [`TABLE_HEADER`] padded to align, followed by a line per address conflict.
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use crate::address_book::AddressEvent;
+use crate::app::BacnetObject;
+use crate::bacnet::{DeviceStatus, DiscoveredDevice};
+use crate::format;
+use crate::mstp;
+use crate::property::{self, BacnetValue, PresentValue, PropertyEntry};
+use bacnet_rs::object::ObjectIdentifier;
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+pub const CSV_HEADER: &[&str] = &[
+    "device_id", "device_name", "address", "vendor_id", "vendor_name", "max_apdu", "segmentation", "online",
+    "object_type", "object_type_id", "instance", "name", "description", "units",
+    "present_value", "flags", "reliability", "address_conflict",
+];
+
+/// Columns of [`ScanExport::to_table`]
+pub const TABLE_HEADER: &[&str] = &["DEVICE", "NAME", "ADDRESS", "VENDOR", "MAX APDU", "SEGMENTATION", "STATUS", "OBJECTS"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanExport {
+    pub schema_version: u32,
+    pub generated_at: u64,
+    pub devices: Vec<ExportDevice>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportDevice {
+    pub device_id: u32,
+    pub name: Option<String>,
+    pub address: String,
+    pub vendor_id: u32,
+    pub vendor_name: String,
+    pub max_apdu: u32,
+    pub segmentation: String,
+    #[serde(default)]
+    pub firmware_revision: Option<String>,
+    #[serde(default)]
+    pub application_software_version: Option<String>,
+    pub online: bool,
+    pub last_seen: u64,
+    pub objects: Vec<ExportObject>,
+    /// Every address answering with this instance, when more than one does
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub duplicate_addresses: Vec<String>,
+    /// The address the device answered from before it moved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_address: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportObject {
+    pub object_type: String,
+    pub object_type_id: u16,
+    pub instance: u32,
+    pub name: String,
+    pub description: String,
+    pub units: String,
+    #[serde(default)]
+    pub units_code: Option<u32>,
+    pub present_value: serde_json::Value,
+    pub present_value_text: String,
+    pub in_alarm: Option<bool>,
+    pub fault: Option<bool>,
+    pub overridden: Option<bool>,
+    pub out_of_service: Option<bool>,
+    pub reliability: Option<String>,
+    /// Properties read on request (`headless-scan --deep --properties`), by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, String>,
+}
+
+impl ScanExport {
+    /// Snapshot of the devices, their Device object properties and their points
+    pub fn new(
+        devices: &HashMap<u32, DiscoveredDevice>,
+        properties: &HashMap<u32, Vec<PropertyEntry>>,
+        objects: &HashMap<u32, Vec<BacnetObject>>,
+    ) -> Self {
+        let mut ids: Vec<_> = devices.keys().cloned().collect();
+        ids.sort();
+        let devices = ids.iter().map(|id| {
+            let device = &devices[id];
+            let mut points: Vec<&BacnetObject> = objects.get(id).map(|o| o.iter().collect()).unwrap_or_default();
+            points.sort_by_key(|o| (o.id.object_type as u16, o.id.instance));
+            let text = |property_id| match properties.get(id).and_then(|p| property::find(p, property_id)) {
+                Some(BacnetValue::CharacterString(text)) => Some(text.clone()),
+                _ => None,
+            };
+            ExportDevice {
+                device_id: device.device_id,
+                name: text(property::OBJECT_NAME),
+                address: address_text(&device.address),
+                vendor_id: device.vendor_id,
+                vendor_name: device.vendor_name.clone(),
+                max_apdu: device.max_apdu,
+                segmentation: property::segmentation_name(device.segmentation).to_lowercase(),
+                firmware_revision: text(property::FIRMWARE_REVISION),
+                application_software_version: text(property::APPLICATION_SOFTWARE_VERSION),
+                online: device.status == DeviceStatus::Online,
+                last_seen: unix_time(device.last_seen),
+                objects: points.into_iter().map(ExportObject::new).collect(),
+                duplicate_addresses: Vec::new(),
+                previous_address: None,
+            }
+        }).collect();
+
+        Self { schema_version: SCHEMA_VERSION, generated_at: unix_time(Instant::now()), devices }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(text: &str) -> Result<Self> {
+        Ok(serde_json::from_str(text)?)
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = csv_line(CSV_HEADER.iter().map(|s| s.to_string()));
+        for device in &self.devices {
+            let device_columns = [
+                device.device_id.to_string(),
+                device.name.clone().unwrap_or_default(),
+                device.address.clone(),
+                device.vendor_id.to_string(),
+                device.vendor_name.clone(),
+                device.max_apdu.to_string(),
+                device.segmentation.clone(),
+                device.online.to_string(),
+            ];
+            let conflict = device.conflict().unwrap_or_default();
+            if device.objects.is_empty() {
+                let empty = std::iter::repeat_n(String::new(), CSV_HEADER.len() - device_columns.len() - 1);
+                out.push_str(&csv_line(device_columns.iter().cloned().chain(empty).chain([conflict.clone()])));
+            }
+            for obj in &device.objects {
+                let flags = [(obj.in_alarm, "ALARM"), (obj.fault, "FAULT"), (obj.overridden, "OVRD"), (obj.out_of_service, "OOS")]
+                    .iter()
+                    .filter(|(set, _)| *set == Some(true))
+                    .map(|(_, name)| *name)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                out.push_str(&csv_line(device_columns.iter().cloned().chain([
+                    obj.object_type.clone(),
+                    obj.object_type_id.to_string(),
+                    obj.instance.to_string(),
+                    obj.name.clone(),
+                    obj.description.clone(),
+                    obj.units.clone(),
+                    obj.present_value_text.clone(),
+                    flags,
+                    obj.reliability.clone().unwrap_or_default(),
+                    conflict.clone(),
+                ])));
+            }
+        }
+        out
+    }
+
+    /// One line per device with aligned columns, for reading on a terminal
+    pub fn to_table(&self) -> String {
+        let mut rows = vec![TABLE_HEADER.iter().map(|s| s.to_string()).collect::<Vec<_>>()];
+        rows.extend(self.devices.iter().map(|device| vec![
+            device.device_id.to_string(),
+            device.name.clone().unwrap_or_default(),
+            device.address.clone(),
+            format!("{} ({})", device.vendor_name, device.vendor_id),
+            device.max_apdu.to_string(),
+            device.segmentation.clone(),
+            if device.online { "online" } else { "offline" }.to_string(),
+            device.objects.len().to_string(),
+        ]));
+        let widths: Vec<usize> = (0..TABLE_HEADER.len())
+            .map(|col| rows.iter().map(|row| row[col].chars().count()).max().unwrap_or(0))
+            .collect();
+        let mut out: String = rows.iter().map(|row| {
+            let cells: Vec<String> = row.iter().zip(&widths).map(|(cell, width)| format!("{:<width$}", cell, width = width)).collect();
+            format!("{}\n", cells.join("  ").trim_end())
+        }).collect();
+        for device in &self.devices {
+            if let Some(conflict) = device.conflict() {
+                out.push_str(&format!("CONFLICT: device {} {}\n", device.device_id, conflict));
+            }
+        }
+        out
+    }
+
+    /// Marks the devices with duplicate instances or changed addresses, from
+    /// [`AddressBook::conflicts`](crate::address_book::AddressBook::conflicts).
+    pub fn set_conflicts(&mut self, conflicts: &[AddressEvent]) {
+        for event in conflicts {
+            let device_id = match event {
+                AddressEvent::Duplicate { device_id, .. } | AddressEvent::Changed { device_id, .. } => *device_id,
+            };
+            let Some(device) = self.devices.iter_mut().find(|d| d.device_id == device_id) else { continue };
+            match event {
+                AddressEvent::Duplicate { addresses, .. } => device.duplicate_addresses = addresses.iter().map(address_text).collect(),
+                AddressEvent::Changed { from, .. } => device.previous_address = Some(address_text(from)),
+            }
+        }
+    }
+
+    /// Records properties read from a point, as displayed in the property browser.
+    /// Properties the device returned an error for are left out.
+    pub fn set_properties(&mut self, device_id: u32, object: ObjectIdentifier, props: &[PropertyEntry]) {
+        let Some(obj) = self.devices.iter_mut()
+            .filter(|d| d.device_id == device_id)
+            .flat_map(|d| d.objects.iter_mut())
+            .find(|o| o.object_type_id == object.object_type as u16 && o.instance == object.instance)
+        else { return };
+        for entry in props.iter().filter(|p| p.value.is_ok()) {
+            obj.properties.insert(property::property_name(entry.id), format::format_property(entry));
+        }
+    }
+
+    /// Writes `<base>.csv` and `<base>.json`, returning both paths
+    pub fn write_files(&self, base: &Path) -> Result<(PathBuf, PathBuf)> {
+        let csv_path = base.with_extension("csv");
+        let json_path = base.with_extension("json");
+        std::fs::write(&csv_path, self.to_csv())?;
+        std::fs::write(&json_path, self.to_json()?)?;
+        Ok((csv_path, json_path))
+    }
+}
+
+impl ExportDevice {
+    /// The address conflict as text, e.g. `duplicate instance at <a>, <b>` or `moved from <a>`
+    pub fn conflict(&self) -> Option<String> {
+        if !self.duplicate_addresses.is_empty() {
+            Some(format!("duplicate instance at {}", self.duplicate_addresses.join(", ")))
+        } else {
+            self.previous_address.as_ref().map(|from| format!("moved from {}", from))
+        }
+    }
+}
+
+impl ExportObject {
+    pub fn new(obj: &BacnetObject) -> Self {
+        let flags = obj.status_flags;
+        let text = format::format_present_value(obj);
+        Self {
+            object_type: format!("{:?}", obj.id.object_type),
+            object_type_id: obj.id.object_type as u16,
+            instance: obj.id.instance,
+            name: obj.name.clone(),
+            description: obj.description.clone().unwrap_or_default(),
+            units: obj.units.clone(),
+            units_code: obj.units_code,
+            present_value: json_value(&obj.present_value).unwrap_or_else(|| serde_json::Value::String(text.clone())),
+            present_value_text: text,
+            in_alarm: flags.map(|f| f.in_alarm),
+            fault: flags.map(|f| f.fault),
+            overridden: flags.map(|f| f.overridden),
+            out_of_service: obj.out_of_service.or(flags.map(|f| f.out_of_service)),
+            reliability: obj.reliability.map(|r| {
+                property::reliability_name(r).map_or_else(|| format!("Reliability {}", r), str::to_string)
+            }),
+            properties: BTreeMap::new(),
+        }
+    }
+}
+
+/// Default file name base for an export written now, e.g. `bacnet-export-1760000000`
+pub fn default_base() -> PathBuf {
+    PathBuf::from(format!("bacnet-export-{}", unix_time(Instant::now())))
+}
+
+/// The typed JSON form, or None for values that are exported as their text
+fn json_value(value: &PresentValue) -> Option<serde_json::Value> {
+    match value {
+        PresentValue::Unknown => Some(serde_json::Value::Null),
+        PresentValue::Binary(b) => Some(serde_json::Value::Bool(*b)),
+        PresentValue::Text(text) => Some(serde_json::Value::String(text.clone())),
+        PresentValue::Unsigned(u) => Some(serde_json::Value::from(*u)),
+        PresentValue::Signed(i) => Some(serde_json::Value::from(*i)),
+        PresentValue::Multistate(n) | PresentValue::Enumerated(n) => Some(serde_json::Value::from(*n)),
+        // NaN and infinities have no JSON number form
+        PresentValue::Analog(v) => Some(serde_json::Number::from_f64(*v).map_or(serde_json::Value::Null, serde_json::Value::Number)),
+        PresentValue::Other(_) => None,
+    }
+}
+
+fn address_text(addr: &SocketAddr) -> String {
+    match mstp::addr_to_mac(addr) {
+        Some(mac) => format!("mstp:{}", mac),
+        None => addr.to_string(),
+    }
+}
+
+/// Seconds since the Unix epoch at a monotonic instant
+pub(crate) fn unix_time(at: Instant) -> u64 {
+    let then = SystemTime::now().checked_sub(at.elapsed()).unwrap_or(UNIX_EPOCH);
+    then.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// One CSV record; fields with commas, quotes or line breaks are quoted (RFC 4180)
+pub fn csv_line(fields: impl IntoIterator<Item = String>) -> String {
+    let mut line = fields.into_iter().map(|field| {
+        if field.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field
+        }
+    }).collect::<Vec<_>>().join(",");
+    line.push_str("\r\n");
+    line
+}
@@ -0,0 +1,147 @@
+//! Display text for property values, shared by the TUI, the packet decoder and exports.
+
+use crate::app::BacnetObject;
+use crate::property::{self, BacnetValue, PresentValue, PropertyEntry};
+use bacnet_rs::object::ObjectType;
+
+/// Present value with binary and multistate states shown by their state text when known.
+pub fn format_present_value(obj: &BacnetObject) -> String {
+    let texts = obj.state_texts.as_ref();
+    match &obj.present_value {
+        PresentValue::Unknown => "N/A".to_string(),
+        PresentValue::Analog(v) => format!("{:.2}", v),
+        PresentValue::Binary(active) => {
+            let text = texts.and_then(|t| if *active { t.active.as_ref() } else { t.inactive.as_ref() });
+            match text {
+                Some(text) if !text.is_empty() => text.clone(),
+                _ => if *active { "Active" } else { "Inactive" }.to_string(),
+            }
+        }
+        PresentValue::Multistate(n) => texts
+            .and_then(|t| t.states.get((*n as usize).wrapping_sub(1)))
+            .filter(|text| !text.is_empty())
+            .cloned()
+            .unwrap_or_else(|| format!("State {}", n)),
+        PresentValue::Unsigned(u) => u.to_string(),
+        PresentValue::Signed(i) => i.to_string(),
+        PresentValue::Enumerated(e) => e.to_string(),
+        PresentValue::Text(text) => text.clone(),
+        PresentValue::Other(value) => format_value(value),
+    }
+}
+
+/// Formats a property value, naming the members of enumerations and bitstrings it knows about.
+pub fn format_property(entry: &PropertyEntry) -> String {
+    let value = match &entry.value {
+        Ok(value) => value,
+        Err(e) => {
+            let class = property::error_class_name(e.class).map_or_else(|| e.class.to_string(), str::to_string);
+            let code = property::error_code_name(e.code).map_or_else(|| e.code.to_string(), str::to_string);
+            return format!("Error: {} / {}", class, code);
+        }
+    };
+    match (entry.id, value) {
+        (property::PROTOCOL_SERVICES_SUPPORTED, BacnetValue::BitString(bits)) => {
+            set_bits(bits, |bit| property::service_name(bit).map(str::to_string))
+        }
+        (property::PROTOCOL_OBJECT_TYPES_SUPPORTED, BacnetValue::BitString(bits)) => {
+            set_bits(bits, |bit| ObjectType::try_from(bit as u16).ok().map(|t| format!("{:?}", t)))
+        }
+        (property::SYSTEM_STATUS, BacnetValue::Enumerated(status)) => {
+            property::system_status_name(*status).map_or_else(|| status.to_string(), str::to_string)
+        }
+        // UTC_Offset is minutes to add to local time to get UTC, so the sign is flipped
+        (property::UTC_OFFSET, BacnetValue::Signed(minutes)) => {
+            let offset = -minutes;
+            format!("UTC{}{:02}:{:02}", if offset < 0 { '-' } else { '+' }, offset.abs() / 60, offset.abs() % 60)
+        }
+        (property::APDU_TIMEOUT, BacnetValue::Unsigned(ms)) => format!("{} ms", ms),
+        (property::STATUS_FLAGS, BacnetValue::BitString(bits)) => {
+            let flags = set_bits(bits, |bit| property::STATUS_FLAG_NAMES.get(bit).map(|n| n.to_string()));
+            if flags == "(none)" { "Normal".to_string() } else { flags }
+        }
+        (property::EVENT_ENABLE | property::ACKED_TRANSITIONS, BacnetValue::BitString(bits)) => {
+            set_bits(bits, |bit| property::EVENT_TRANSITION_NAMES.get(bit).map(|n| n.to_string()))
+        }
+        (property::LIMIT_ENABLE, BacnetValue::BitString(bits)) => {
+            set_bits(bits, |bit| property::LIMIT_ENABLE_NAMES.get(bit).map(|n| n.to_string()))
+        }
+        (property::RELIABILITY, BacnetValue::Enumerated(r)) => {
+            property::reliability_name(*r).map_or_else(|| r.to_string(), str::to_string)
+        }
+        (property::EVENT_STATE, BacnetValue::Enumerated(state)) => {
+            property::event_state_name(*state).map_or_else(|| state.to_string(), str::to_string)
+        }
+        (property::UNITS, BacnetValue::Enumerated(units)) => {
+            property::units_name(*units).unwrap_or_else(|| units.to_string())
+        }
+        (property::OBJECT_TYPE, BacnetValue::Enumerated(t)) => match ObjectType::try_from(*t as u16) {
+            Ok(t) => format!("{:?}", t),
+            Err(_) => t.to_string(),
+        },
+        (property::POLARITY, BacnetValue::Enumerated(p)) => match p {
+            0 => "Normal".to_string(),
+            1 => "Reverse".to_string(),
+            _ => p.to_string(),
+        },
+        (property::NOTIFY_TYPE, BacnetValue::Enumerated(n)) => match n {
+            0 => "Alarm".to_string(),
+            1 => "Event".to_string(),
+            2 => "Ack-Notification".to_string(),
+            _ => n.to_string(),
+        },
+        // Only the commanded slots are interesting
+        (property::PRIORITY_ARRAY, BacnetValue::List(slots)) => {
+            let active: Vec<String> = slots.iter().enumerate()
+                .filter(|(_, v)| **v != BacnetValue::Null)
+                .map(|(i, v)| format!("{}={}", i + 1, format_value(v)))
+                .collect();
+            if active.is_empty() { "(relinquished)".to_string() } else { active.join(", ") }
+        }
+        _ => format_value(value),
+    }
+}
+
+fn set_bits(bits: &[bool], name: impl Fn(usize) -> Option<String>) -> String {
+    let names: Vec<String> = bits.iter().enumerate()
+        .filter(|(_, set)| **set)
+        .map(|(bit, _)| name(bit).unwrap_or_else(|| format!("#{}", bit)))
+        .collect();
+    if names.is_empty() { "(none)".to_string() } else { names.join(", ") }
+}
+
+pub fn format_value(value: &BacnetValue) -> String {
+    match value {
+        BacnetValue::Null => "Null".to_string(),
+        BacnetValue::Boolean(b) => if *b { "True".to_string() } else { "False".to_string() },
+        BacnetValue::Unsigned(u) => u.to_string(),
+        BacnetValue::Signed(i) => i.to_string(),
+        BacnetValue::Real(r) => format!("{:.2}", r),
+        BacnetValue::Double(d) => format!("{:.2}", d),
+        BacnetValue::OctetString(bytes) => bytes.iter().map(|b| format!("{:02X}", b)).collect(),
+        BacnetValue::CharacterString(s) => s.clone(),
+        BacnetValue::BitString(bits) => bits.iter().map(|b| if *b { '1' } else { '0' }).collect(),
+        BacnetValue::Enumerated(e) => e.to_string(),
+        BacnetValue::Date { year, month, day, .. } => {
+            let field = |v: u16, width: usize| if v == 255 { "*".repeat(width) } else { format!("{:0width$}", v, width = width) };
+            format!("{}-{}-{}", field(*year, 4), field(*month as u16, 2), field(*day as u16, 2))
+        }
+        BacnetValue::Time { hour, minute, second, .. } => {
+            let field = |v: u8| if v == 255 { "**".to_string() } else { format!("{:02}", v) };
+            format!("{}:{}:{}", field(*hour), field(*minute), field(*second))
+        }
+        BacnetValue::ObjectId { object_type, instance } => match ObjectType::try_from(*object_type) {
+            Ok(t) => format!("{:?}:{}", t, instance),
+            Err(_) => format!("{}:{}", object_type, instance),
+        },
+        BacnetValue::Context { tag, data } => {
+            format!("[{}] {}", tag, data.iter().map(|b| format!("{:02X}", b)).collect::<String>())
+        }
+        BacnetValue::Constructed { tag, values } => {
+            format!("[{}] {{{}}}", tag, values.iter().map(format_value).collect::<Vec<_>>().join(", "))
+        }
+        BacnetValue::List(values) => {
+            format!("{{{}}}", values.iter().map(format_value).collect::<Vec<_>>().join(", "))
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Per-device health statistics gathered from confirmed requests: outcome counts,
+//! round-trip times and jitter, ranked so the worst-behaved devices come first.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// Failure rate at which a device is graded poor
+pub const POOR_FAILURE_RATE: f64 = 0.2;
+/// Average round trip above which an otherwise healthy device is graded fair
+pub const SLOW_RTT: Duration = Duration::from_millis(500);
+
+/// How a confirmed request ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestOutcome {
+    Ack,
+    Error,
+    Reject,
+    Abort,
+    Timeout,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HealthGrade {
+    Good,
+    Fair,
+    Poor,
+}
+
+impl HealthGrade {
+    pub fn label(self) -> &'static str {
+        match self {
+            HealthGrade::Good => "Good",
+            HealthGrade::Fair => "Fair",
+            HealthGrade::Poor => "Poor",
+        }
+    }
+}
+
+/// Request counters and round-trip statistics for one device address
+#[derive(Debug, Clone, Default)]
+pub struct DeviceHealth {
+    pub requests: u64,
+    pub acks: u64,
+    pub timeouts: u64,
+    pub errors: u64,
+    pub rejects: u64,
+    pub aborts: u64,
+    pub min_rtt: Option<Duration>,
+    pub max_rtt: Option<Duration>,
+    /// Set when the last request timed out, cleared by any response
+    pub last_timed_out: bool,
+    rtt_total: Duration,
+    rtt_count: u32,
+    last_rtt: Option<Duration>,
+    /// Sum of the differences between consecutive round trips
+    jitter_total: Duration,
+    jitter_count: u32,
+}
+
+impl DeviceHealth {
+    /// Counts a finished request; `rtt` is the time to the response, if one arrived
+    pub fn record(&mut self, outcome: RequestOutcome, rtt: Option<Duration>) {
+        self.requests += 1;
+        match outcome {
+            RequestOutcome::Ack => self.acks += 1,
+            RequestOutcome::Error => self.errors += 1,
+            RequestOutcome::Reject => self.rejects += 1,
+            RequestOutcome::Abort => self.aborts += 1,
+            RequestOutcome::Timeout => self.timeouts += 1,
+        }
+        self.last_timed_out = outcome == RequestOutcome::Timeout;
+        let Some(rtt) = rtt else { return };
+        self.min_rtt = Some(self.min_rtt.map_or(rtt, |m| m.min(rtt)));
+        self.max_rtt = Some(self.max_rtt.map_or(rtt, |m| m.max(rtt)));
+        self.rtt_total += rtt;
+        self.rtt_count += 1;
+        if let Some(last) = self.last_rtt {
+            self.jitter_total += rtt.abs_diff(last);
+            self.jitter_count += 1;
+        }
+        self.last_rtt = Some(rtt);
+    }
+
+    /// Requests answered with an error, reject or abort, or not answered at all
+    pub fn failures(&self) -> u64 {
+        self.timeouts + self.errors + self.rejects + self.aborts
+    }
+
+    pub fn failure_rate(&self) -> f64 {
+        if self.requests == 0 { 0.0 } else { self.failures() as f64 / self.requests as f64 }
+    }
+
+    pub fn avg_rtt(&self) -> Option<Duration> {
+        (self.rtt_count > 0).then(|| self.rtt_total / self.rtt_count)
+    }
+
+    /// Mean difference between consecutive round trips
+    pub fn jitter(&self) -> Option<Duration> {
+        (self.jitter_count > 0).then(|| self.jitter_total / self.jitter_count)
+    }
+
+    pub fn grade(&self) -> HealthGrade {
+        if self.failure_rate() >= POOR_FAILURE_RATE || self.last_timed_out {
+            HealthGrade::Poor
+        } else if self.failures() > 0 || self.avg_rtt().is_some_and(|rtt| rtt > SLOW_RTT) {
+            HealthGrade::Fair
+        } else {
+            HealthGrade::Good
+        }
+    }
+
+    /// Short text for the device list, e.g. "Good 12 ms" or "Poor 40% fail"
+    pub fn summary(&self) -> String {
+        let grade = self.grade();
+        match (grade, self.avg_rtt()) {
+            (HealthGrade::Good, Some(rtt)) => format!("{} {}", grade.label(), format_rtt(Some(rtt))),
+            (HealthGrade::Good, None) => grade.label().to_string(),
+            _ => format!("{} {:.0}% fail", grade.label(), self.failure_rate() * 100.0),
+        }
+    }
+
+    /// Worst first: grade, then failure rate, then average and worst round trip
+    fn compare_worst(&self, other: &DeviceHealth) -> Ordering {
+        other.grade().cmp(&self.grade())
+            .then_with(|| other.failure_rate().total_cmp(&self.failure_rate()))
+            .then_with(|| other.avg_rtt().cmp(&self.avg_rtt()))
+            .then_with(|| other.max_rtt.cmp(&self.max_rtt))
+    }
+}
+
+/// Health of every device a confirmed request was sent to, keyed by address
+#[derive(Debug, Clone, Default)]
+pub struct HealthStats {
+    devices: HashMap<SocketAddr, DeviceHealth>,
+}
+
+impl HealthStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, addr: SocketAddr, outcome: RequestOutcome, rtt: Option<Duration>) {
+        self.devices.entry(addr).or_default().record(outcome, rtt);
+    }
+
+    pub fn get(&self, addr: &SocketAddr) -> Option<&DeviceHealth> {
+        self.devices.get(addr)
+    }
+
+    pub fn len(&self) -> usize {
+        self.devices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.devices.clear();
+    }
+
+    /// Every device, worst first; ties fall back to the address
+    pub fn ranked(&self) -> Vec<(SocketAddr, &DeviceHealth)> {
+        let mut ranked: Vec<(SocketAddr, &DeviceHealth)> = self.devices.iter().map(|(a, h)| (*a, h)).collect();
+        ranked.sort_by(|(aa, a), (ab, b)| a.compare_worst(b).then(aa.cmp(ab)));
+        ranked
+    }
+}
+
+/// Round trip in milliseconds, or "-" when none was measured
+pub fn format_rtt(rtt: Option<Duration>) -> String {
+    match rtt {
+        Some(rtt) if rtt < Duration::from_millis(10) => format!("{:.1} ms", rtt.as_secs_f64() * 1000.0),
+        Some(rtt) => format!("{} ms", rtt.as_millis()),
+        None => "-".to_string(),
+    }
+}
@@ -0,0 +1,83 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Samples kept per point; one hour at the default 5 s poll interval.
+pub const HISTORY_CAPACITY: usize = 720;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub at: Instant,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HistoryStats {
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+    pub count: usize,
+}
+
+/// Bounded ring buffer of timestamped samples; the oldest sample is dropped when full.
+#[derive(Debug, Clone)]
+pub struct PointHistory {
+    samples: VecDeque<Sample>,
+    capacity: usize,
+}
+
+impl Default for PointHistory {
+    fn default() -> Self {
+        Self::with_capacity(HISTORY_CAPACITY)
+    }
+}
+
+impl PointHistory {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { samples: VecDeque::new(), capacity: capacity.max(1) }
+    }
+
+    pub fn push(&mut self, at: Instant, value: f64) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(Sample { at, value });
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Samples oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &Sample> {
+        self.samples.iter()
+    }
+
+    pub fn latest(&self) -> Option<&Sample> {
+        self.samples.back()
+    }
+
+    /// Time covered from the oldest to the newest sample
+    pub fn span(&self) -> Duration {
+        match (self.samples.front(), self.samples.back()) {
+            (Some(first), Some(last)) => last.at.duration_since(first.at),
+            _ => Duration::ZERO,
+        }
+    }
+
+    pub fn stats(&self) -> Option<HistoryStats> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let (mut min, mut max, mut sum) = (f64::INFINITY, f64::NEG_INFINITY, 0.0);
+        for sample in &self.samples {
+            min = min.min(sample.value);
+            max = max.max(sample.value);
+            sum += sample.value;
+        }
+        Some(HistoryStats { min, max, avg: sum / self.samples.len() as f64, count: self.samples.len() })
+    }
+}
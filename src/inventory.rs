@@ -0,0 +1,83 @@
+//! Deep scan of one device: the Device object, the object list and a property
+//! set from every object, as `headless-scan --deep` collects them.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+use bacnet_rs::object::{ObjectIdentifier, ObjectType};
+use tracing::{info, warn};
+use crate::app::BacnetObject;
+use crate::bacnet::{detail_properties, is_timeout, read_device_objects, read_property_set, DiscoveredDevice, PointReading, RequestRegistrar};
+use crate::network::DataLink;
+use crate::property::{self, PropertyEntry};
+
+/// Hands out invoke IDs 1..=255 to requests running in parallel
+#[derive(Default)]
+pub struct InvokeIds(AtomicU8);
+
+impl InvokeIds {
+    pub fn next(&self) -> u8 {
+        loop {
+            let id = self.0.fetch_add(1, Ordering::Relaxed).wrapping_add(1);
+            if id != 0 {
+                return id;
+            }
+        }
+    }
+}
+
+/// What a deep scan read from one device
+pub struct DeviceInventory {
+    pub device_id: u32,
+    pub properties: Vec<PropertyEntry>,
+    /// Points with the properties read from each
+    pub objects: Vec<(BacnetObject, Vec<PropertyEntry>)>,
+}
+
+/// Reads the Device object, the object list and the property set of every point of one device.
+/// Requests to one device run one at a time; a timeout ends the inventory of that device.
+pub async fn inventory_device(
+    socket: &(impl DataLink + ?Sized),
+    device: &DiscoveredDevice,
+    property_set: Option<&[u32]>,
+    invoke_ids: &InvokeIds,
+    tx_register: &RequestRegistrar,
+) -> DeviceInventory {
+    let (device_id, addr) = (device.device_id, device.address);
+    let mut inventory = DeviceInventory { device_id, properties: Vec::new(), objects: Vec::new() };
+
+    let device_obj = ObjectIdentifier::new(ObjectType::Device, device_id);
+    match read_property_set(socket, addr, device_obj, property::DEVICE_PROPERTIES, invoke_ids.next(), tx_register).await {
+        Ok(props) => inventory.properties = props,
+        Err(e) => warn!("Device {}: could not read the Device object: {}", device_id, e),
+    }
+    let points = match read_device_objects(socket, addr, device_id, invoke_ids.next(), tx_register).await {
+        Ok(points) => points,
+        Err(e) => {
+            warn!("Device {}: could not read the object list: {}", device_id, e);
+            return inventory;
+        }
+    };
+
+    let total = points.len();
+    for mut point in points {
+        let props = match property_set {
+            Some(set) => set.to_vec(),
+            None => detail_properties(point.id.object_type),
+        };
+        match read_property_set(socket, addr, point.id, &props, invoke_ids.next(), tx_register).await {
+            Ok(props) => {
+                point.apply_reading(PointReading::from_properties(point.id.object_type, &props));
+                inventory.objects.push((point, props));
+            }
+            Err(e) if is_timeout(&e) => {
+                warn!("Device {}: stopped after {} of {} objects: {}", device_id, inventory.objects.len(), total, e);
+                return inventory;
+            }
+            Err(e) => {
+                warn!("Device {}: {:?}:{}: {}", device_id, point.id.object_type, point.id.instance, e);
+                inventory.objects.push((point, Vec::new()));
+            }
+        }
+    }
+    info!("Device {}: read {} objects", device_id, inventory.objects.len());
+    inventory
+}
@@ -1,4 +1,22 @@
+pub mod address_book;
 pub mod app;
 pub mod bacnet;
+pub mod cli;
+pub mod decode;
+pub mod ede;
+pub mod export;
+pub mod format;
+pub mod health;
+pub mod history;
+pub mod inventory;
+pub mod mstp;
 pub mod network;
+pub mod probe;
+pub mod pcap;
+pub mod property;
+pub mod scan_diff;
+pub mod site_db;
+pub mod storm;
+pub mod topology;
+pub mod traffic;
 pub mod ui;
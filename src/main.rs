@@ -1,34 +1,133 @@
-mod app;
-mod bacnet;
-mod network;
-mod ui;
-
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, MouseButton, MouseEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
-use std::{collections::HashMap, io, net::UdpSocket, sync::{Arc, Mutex}, time::Duration};
+use std::{collections::HashMap, io, net::{SocketAddr, UdpSocket}, path::{Path, PathBuf}, sync::{Arc, Mutex}, time::{Duration, SystemTime, UNIX_EPOCH}};
 use tokio::sync::{mpsc, oneshot};
-use tracing::{info, error, debug};
+use tracing::{info, error, warn};
+
+use bacnet_discovery::{app, bacnet, export, scan_diff, site_db, ui};
+use bacnet_discovery::site_db::SiteDb;
+use bacnet_discovery::probe::READ_BDT;
+use bacnet_discovery::topology::send_who_is_router;
+use bacnet_discovery::traffic::{Datagram, LoggedLink, received_destination};
+use bacnet_rs::object::ObjectIdentifier;
+use bacnet_discovery::app::{App, ViewState};
+use bacnet_discovery::mstp::{self, MstpConfig, MstpPort};
+use bacnet_discovery::network::{create_shared_socket, DataLink};
+use bacnet_discovery::property::{self, PropertyEntry};
+use bacnet_discovery::bacnet::{ConfirmedResponse, send_whois_to, send_directed_whois, process_response, read_device_objects, read_property_set, read_all_properties, read_point, PendingRequests, PointReading, get_interface_broadcast, parse_confirmed_response, is_timeout};
 
-use crate::app::{App, ViewState};
-use crate::network::create_shared_socket;
-use crate::bacnet::{send_whois_to, process_response, read_device_objects, read_present_value, get_interface_broadcast, parse_confirmed_response};
+/// How often offline devices are re-checked with a directed Who-Is
+const OFFLINE_RECHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Rows moved per mouse wheel notch
+const MOUSE_WHEEL_ROWS: isize = 3;
 
 enum AppEvent {
     Input(Event),
     Tick,
     DeviceDiscovered(bacnet::DiscoveredDevice),
+    DevicePropertiesRead(u32, Vec<PropertyEntry>),
+    ObjectPropertiesRead(u32, ObjectIdentifier, Vec<PropertyEntry>),
     PointsDiscovered(u32, Vec<app::BacnetObject>),
-    PointUpdated(u32, bacnet_rs::object::ObjectIdentifier, String),
+    PointUpdated(u32, ObjectIdentifier, PointReading),
+    RequestTimedOut(u32),
     StatusUpdate(String),
 }
 
+/// Reads every property of an object for the property browser in the background.
+fn spawn_property_browser_read(
+    app: &App,
+    app_arc: &Arc<Mutex<App>>,
+    cs: &Arc<dyn DataLink>,
+    tx: &mpsc::Sender<AppEvent>,
+    tx_register: &bacnet::RequestRegistrar,
+    device_id: u32,
+    object_id: ObjectIdentifier,
+) {
+    let device = { let d = app.devices.lock().unwrap(); d.get(&device_id).cloned() };
+    let Some(device) = device else { return };
+    let s_props = Arc::clone(cs);
+    let tx_props = tx.clone();
+    let tx_reg_props = tx_register.clone();
+    let app_ids = Arc::clone(app_arc);
+    tokio::spawn(async move {
+        let next_invoke_id = || app_ids.lock().unwrap().get_next_invoke_id();
+        match read_all_properties(&*s_props, device.address, object_id, next_invoke_id, &tx_reg_props).await {
+            Ok(props) => { let _ = tx_props.send(AppEvent::ObjectPropertiesRead(device_id, object_id, props)).await; }
+            Err(e) => {
+                if is_timeout(&e) { let _ = tx_props.send(AppEvent::RequestTimedOut(device_id)).await; }
+                let _ = tx_props.send(AppEvent::StatusUpdate(format!("Error: {}", e))).await;
+            }
+        }
+    });
+}
+
+/// Broadcasts a Who-Is and a Who-Is-Router-To-Network and, on B/IP, sends a Read-BDT
+/// to every known address, so the topology view learns devices, routed networks and BBMDs.
+fn probe_topology(app: &App, link: &Arc<dyn DataLink>, broadcast: SocketAddr) {
+    let targets = if app.is_mstp_selected() { Vec::new() } else { app.topology.lock().unwrap().bdt_targets() };
+    let link = Arc::clone(link);
+    tokio::spawn(async move {
+        if let Err(e) = send_whois_to(&*link, broadcast) { error!("Who-Is failed: {}", e); }
+        if let Err(e) = send_who_is_router(&*link, broadcast) { error!("Who-Is-Router-To-Network failed: {}", e); }
+        for target in targets {
+            if let Err(e) = link.send_to(&READ_BDT, target) { error!("Read-BDT to {} failed: {}", target, e); }
+        }
+    });
+}
+
+struct Args {
+    mstp: Option<(String, MstpConfig)>,
+    /// Site database to load at startup and save on exit; None with `--no-site-db`
+    site_db: Option<PathBuf>,
+    /// Exported scan to compare against with 'c'
+    baseline: Option<PathBuf>,
+}
+
+/// Parses `[--mstp <device> [--mac <n>] [--baud <rate>] [--max-master <n>]]
+/// [--site-db <path> | --no-site-db] [--baseline <scan.json>]`.
+fn parse_args() -> Result<Args> {
+    let mut args = std::env::args().skip(1);
+    let mut port = None;
+    let mut config = MstpConfig::default();
+    let mut site_db = Some(PathBuf::from(site_db::DEFAULT_PATH));
+    let mut baseline = None;
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| anyhow!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--mstp" => port = Some(value()?),
+            "--mac" => config.mac = value()?.parse()?,
+            "--baud" => config.baud_rate = value()?.parse()?,
+            "--max-master" => config.max_master = value()?.parse()?,
+            "--site-db" => site_db = Some(PathBuf::from(value()?)),
+            "--no-site-db" => site_db = None,
+            "--baseline" => baseline = Some(PathBuf::from(value()?)),
+            _ => return Err(anyhow!("Unknown argument: {}", arg)),
+        }
+    }
+    config.validate()?;
+    Ok(Args { mstp: port.map(|p| (p, config)), site_db, baseline })
+}
+
+/// Saves the current scan into the site database at `path`
+fn save_site(app: &App, path: &Path) -> Result<()> {
+    let mut db = match SiteDb::load(path)? {
+        Some(db) => db,
+        None => SiteDb::new(&path.file_stem().unwrap_or_default().to_string_lossy()),
+    };
+    db.update(app.export());
+    db.save(path)
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args = parse_args()?;
+    let mstp_args = args.mstp;
+
     let file_appender = std::fs::File::create("bacnet-discovery.log")?;
     tracing_subscriber::fmt()
         .with_writer(Arc::new(file_appender))
@@ -37,41 +136,60 @@ async fn main() -> Result<()> {
     
     info!("Starting BACnet Discovery Tool");
 
+    let mut app = App::new();
+    app.mstp_port = mstp_args.as_ref().map(|(port, _)| port.clone());
+    if let Some(path) = &args.site_db {
+        match SiteDb::load(path) {
+            Ok(Some(db)) => {
+                info!("Loaded site database {} ({} devices)", path.display(), db.devices.len());
+                app.load_site(db);
+            }
+            Ok(None) => info!("No site database at {} yet", path.display()),
+            Err(e) => {
+                // Do not overwrite a database we could not read
+                error!("Failed to load site database {}: {}", path.display(), e);
+                return Err(e);
+            }
+        }
+    }
+    if let Some(path) = &args.baseline {
+        app.baseline = Some(scan_diff::load_scan(path).map_err(|e| anyhow!("Failed to load baseline {}: {}", path.display(), e))?);
+    }
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let app_arc = Arc::new(Mutex::new(App::new()));
+    let (tx_register, rx_register) = mpsc::channel::<(SocketAddr, u8, oneshot::Sender<ConfirmedResponse>)>(100);
+    let tx_register = bacnet::RequestRegistrar::new(tx_register).with_health(Arc::clone(&app.health));
+    let app_arc = Arc::new(Mutex::new(app));
     let (tx, mut rx) = mpsc::channel(100);
-    
-    let pending_requests: Arc<Mutex<HashMap<u8, oneshot::Sender<Vec<u8>>>>> = Arc::new(Mutex::new(HashMap::new()));
-    let (tx_register, mut rx_register) = mpsc::channel::<(u8, oneshot::Sender<Vec<u8>>)>(100);
 
-    let pending_requests_reg = Arc::clone(&pending_requests);
-    tokio::spawn(async move {
-        while let Some((invoke_id, tx_res)) = rx_register.recv().await {
-            pending_requests_reg.lock().unwrap().insert(invoke_id, tx_res);
-        }
-    });
+    // Registrations are drained by the receive loop itself, so a response can never
+    // be looked up before the request that caused it is registered
+    let pending_requests: Arc<Mutex<PendingRequests>> = Arc::new(Mutex::new(HashMap::new()));
+    let rx_register = Arc::new(Mutex::new(rx_register));
 
     let tx_input = tx.clone();
     tokio::spawn(async move {
         loop {
-            if event::poll(Duration::from_millis(100)).unwrap_or(false) {
-                if let Ok(e) = event::read() {
-                    let _ = tx_input.send(AppEvent::Input(e)).await;
-                }
+            if event::poll(Duration::from_millis(100)).unwrap_or(false)
+                && let Ok(e) = event::read()
+            {
+                let _ = tx_input.send(AppEvent::Input(e)).await;
             }
             let _ = tx_input.send(AppEvent::Tick).await;
         }
     });
 
-    let mut discovery_socket: Option<Arc<UdpSocket>> = None;
-    let mut client_socket: Option<Arc<UdpSocket>> = None;
+    let mut discovery_socket: Option<Arc<dyn DataLink>> = None;
+    let mut client_socket: Option<Arc<dyn DataLink>> = None;
+    let mut broadcast_addr: Option<SocketAddr> = None;
     let mut receiver_handle: Option<tokio::task::JoinHandle<()>> = None;
     let mut polling_handle: Option<tokio::task::JoinHandle<()>> = None;
+    let mut recheck_handle: Option<tokio::task::JoinHandle<()>> = None;
 
     loop {
         terminal.draw(|f| ui::render(f, &mut app_arc.lock().unwrap()))?;
@@ -80,52 +198,142 @@ async fn main() -> Result<()> {
             match event {
                 AppEvent::Input(Event::Key(key)) => {
                     let mut app = app_arc.lock().unwrap();
+                    if app.search_editing {
+                        match key.code {
+                            KeyCode::Enter => app.finish_search(true),
+                            KeyCode::Esc => app.finish_search(false),
+                            KeyCode::Backspace => app.pop_search_char(),
+                            KeyCode::Char(c) => app.push_search_char(c),
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    if let Some(input) = app.traffic_filter_input.as_mut() {
+                        match key.code {
+                            KeyCode::Enter => app.finish_traffic_filter(true),
+                            KeyCode::Esc => app.finish_traffic_filter(false),
+                            KeyCode::Backspace => { input.pop(); }
+                            KeyCode::Char(c) => input.push(c),
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    if app.jump_input.is_some() {
+                        match key.code {
+                            KeyCode::Enter => app.finish_jump(true),
+                            KeyCode::Esc => app.finish_jump(false),
+                            KeyCode::Backspace => app.pop_jump_digit(),
+                            KeyCode::Char(c) if c.is_ascii_digit() => app.push_jump_digit(c),
+                            _ => {}
+                        }
+                        continue;
+                    }
                     match key.code {
-                        KeyCode::Char('q') => break,
+                        KeyCode::Char('q') => {
+                            if let Some(path) = &args.site_db
+                                && let Err(e) = save_site(&app, path)
+                            {
+                                error!("Failed to save site database {}: {}", path.display(), e);
+                            }
+                            break;
+                        }
+                        KeyCode::Char('w') => {
+                            app.status_message = match &args.site_db {
+                                Some(path) => match save_site(&app, path) {
+                                    Ok(()) => format!("Site database saved to {}.", path.display()),
+                                    Err(e) => format!("Saving the site database failed: {}", e),
+                                },
+                                None => "No site database (started with --no-site-db).".to_string(),
+                            };
+                        }
                         KeyCode::Enter => {
                             if let ViewState::InterfaceSelect = app.view_state {
                                 app.select_interface();
                                 if let Some(idx) = app.selected_interface_index {
-                                    let iface = app.interfaces[idx].clone();
-                                    
-                                    // 1. Discovery Socket (47808) for Who-Is/I-Am
-                                    let ds = match create_shared_socket(47808) {
-                                        Ok(s) => Arc::new(s),
-                                        Err(e) => {
-                                            error!("Failed to bind discovery socket: {}. Using random port.", e);
-                                            Arc::new(UdpSocket::bind("0.0.0.0:0").unwrap())
+                                    // Local addresses of the client and discovery links, for the traffic view
+                                    let (ds, cs, locals): (Arc<dyn DataLink>, Arc<dyn DataLink>, [SocketAddr; 2]) = if app.is_mstp_selected() {
+                                        // MS/TP: one serial link carries both discovery and requests
+                                        let (port, config) = mstp_args.clone().expect("MS/TP entry without a port");
+                                        let local = mstp::mac_to_addr(config.mac);
+                                        match MstpPort::open(&port, config) {
+                                            Ok(link) => {
+                                                let link: Arc<dyn DataLink> = Arc::new(link);
+                                                broadcast_addr = Some(mstp::mac_to_addr(mstp::BROADCAST_MAC));
+                                                (Arc::clone(&link), link, [local; 2])
+                                            }
+                                            Err(e) => {
+                                                error!("{}", e);
+                                                app.exit_view();
+                                                app.status_message = format!("Error: {}", e);
+                                                continue;
+                                            }
                                         }
+                                    } else {
+                                        let iface = app.interfaces[idx].clone();
+                                        broadcast_addr = Some(get_interface_broadcast(&iface).unwrap_or_else(|| "255.255.255.255:47808".parse().unwrap()));
+
+                                        // 1. Discovery Socket (47808) for Who-Is/I-Am
+                                        let ds = match create_shared_socket(47808) {
+                                            Ok(s) => s,
+                                            Err(e) => {
+                                                error!("Failed to bind discovery socket: {}. Using random port.", e);
+                                                UdpSocket::bind("0.0.0.0:0").unwrap()
+                                            }
+                                        };
+
+                                        // 2. Client Socket (Random Port) for Unicast Requests
+                                        // This bypasses SO_REUSEPORT load balancing for responses.
+                                        let cs = UdpSocket::bind("0.0.0.0:0").expect("Failed to bind client socket");
+                                        let local = |socket: &UdpSocket| SocketAddr::new(iface.ip(), socket.local_addr().map_or(0, |a| a.port()));
+                                        let locals = [local(&cs), local(&ds)];
+                                        (Arc::new(ds), Arc::new(cs), locals)
                                     };
+                                    // Sent datagrams are logged for the traffic view; storm and topology only count what is received
+                                    let ds: Arc<dyn DataLink> = Arc::new(LoggedLink::new(ds, locals[1], Arc::clone(&app.traffic)));
+                                    let cs: Arc<dyn DataLink> = Arc::new(LoggedLink::new(cs, locals[0], Arc::clone(&app.traffic)));
                                     discovery_socket = Some(Arc::clone(&ds));
-
-                                    // 2. Client Socket (Random Port) for Unicast Requests
-                                    // This bypasses SO_REUSEPORT load balancing for responses.
-                                    let cs = Arc::new(UdpSocket::bind("0.0.0.0:0").expect("Failed to bind client socket"));
                                     client_socket = Some(Arc::clone(&cs));
 
                                     let tx_recv = tx.clone();
                                     let ds_recv = Arc::clone(&ds);
                                     let cs_recv = Arc::clone(&cs);
                                     let pending_recv = Arc::clone(&pending_requests);
-                                    
+                                    let register_recv = Arc::clone(&rx_register);
+                                    let traffic_recv = Arc::clone(&app.traffic);
+                                    let broadcasts_recv = Arc::clone(&app.broadcasts);
+                                    let topology_recv = Arc::clone(&app.topology);
+                                    let broadcast_recv = broadcast_addr.unwrap_or_else(|| "255.255.255.255:47808".parse().unwrap());
+
                                     if let Some(h) = receiver_handle.take() { h.abort(); }
                                     receiver_handle = Some(tokio::spawn(async move {
                                         let mut buf = [0u8; 1500];
+                                        cs_recv.set_nonblocking(true).ok();
+                                        ds_recv.set_nonblocking(true).ok();
+                                        let drain_registrations = || {
+                                            let mut map = pending_recv.lock().unwrap();
+                                            let mut registrations = register_recv.lock().unwrap();
+                                            while let Ok((addr, id, tx_res)) = registrations.try_recv() { map.insert((addr, id), tx_res); }
+                                            map.retain(|_, tx_res| !tx_res.is_closed());
+                                        };
                                         loop {
-                                            // Listen on BOTH sockets
+                                            // Also drained when idle, so requests that time out never fill the channel
+                                            drain_registrations();
+                                            // Listen on BOTH sockets (the same link on MS/TP)
                                             // Priority 1: Client socket (responses)
-                                            cs_recv.set_nonblocking(true).ok();
-                                            if let Ok((len, _addr)) = cs_recv.recv_from(&mut buf) {
-                                                if let Some((id, sdata)) = parse_confirmed_response(&buf[..len]) {
-                                                    let mut map = pending_recv.lock().unwrap();
-                                                    if let Some(tx_res) = map.remove(&id) { let _ = tx_res.send(sdata); }
-                                                }
-                                            }
-
                                             // Priority 2: Discovery socket (I-Am)
-                                            ds_recv.set_nonblocking(true).ok();
-                                            if let Ok((len, addr)) = ds_recv.recv_from(&mut buf) {
-                                                if let Some(device) = process_response(&buf[..len], addr) {
+                                            for (link, local) in [(&cs_recv, locals[0]), (&ds_recv, locals[1])] {
+                                                let Ok((len, addr)) = link.recv_from(&mut buf) else { continue };
+                                                let at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+                                                let destination = received_destination(&buf[..len], local, broadcast_recv);
+                                                let datagram = Datagram::new(at, addr, destination, &buf[..len]);
+                                                broadcasts_recv.lock().unwrap().record(&datagram);
+                                                topology_recv.lock().unwrap().record(&datagram);
+                                                traffic_recv.lock().unwrap().push(datagram);
+                                                if let Some((id, sdata)) = parse_confirmed_response(&buf[..len]) {
+                                                    drain_registrations();
+                                                    let tx_res = pending_recv.lock().unwrap().remove(&(addr, id));
+                                                    if let Some(tx_res) = tx_res { let _ = tx_res.send(sdata); }
+                                                } else if let Some(device) = process_response(&buf[..len], addr) {
                                                     let _ = tx_recv.send(AppEvent::DeviceDiscovered(device)).await;
                                                 }
                                             }
@@ -143,14 +351,22 @@ async fn main() -> Result<()> {
                                     polling_handle = Some(tokio::spawn(async move {
                                         loop {
                                             tokio::time::sleep(Duration::from_secs(5)).await;
-                                            let objects = objects_poll.lock().unwrap().clone();
+                                            // Only what the poll needs; the objects carry their history
+                                            let objects: Vec<(u32, Vec<(ObjectIdentifier, bool)>)> = objects_poll.lock().unwrap().iter()
+                                                .map(|(device_id, points)| (*device_id, points.iter().map(|p| (p.id, p.needs_details())).collect()))
+                                                .collect();
                                             let devices = devices_poll.lock().unwrap().clone();
                                             for (device_id, points) in objects {
                                                 if let Some(device) = devices.get(&device_id) {
-                                                    for point in points {
+                                                    for (point_id, needs_details) in points {
+                                                        // Offline devices are left to the Who-Is re-check
+                                                        let online = devices_poll.lock().unwrap().get(&device_id).is_some_and(|d| d.is_online());
+                                                        if !online { break; }
                                                         let invoke_id = app_poll.lock().unwrap().get_next_invoke_id();
-                                                        if let Ok(val) = read_present_value(&cs_poll, device.address, point.id, invoke_id, &tx_reg_poll).await {
-                                                            let _ = tx_poll.send(AppEvent::PointUpdated(device_id, point.id, val)).await;
+                                                        match read_point(&*cs_poll, device.address, point_id, needs_details, invoke_id, &tx_reg_poll).await {
+                                                            Ok(reading) => { let _ = tx_poll.send(AppEvent::PointUpdated(device_id, point_id, reading)).await; }
+                                                            Err(e) if is_timeout(&e) => { let _ = tx_poll.send(AppEvent::RequestTimedOut(device_id)).await; }
+                                                            Err(_) => {}
                                                         }
                                                         tokio::time::sleep(Duration::from_millis(100)).await;
                                                     }
@@ -158,23 +374,67 @@ async fn main() -> Result<()> {
                                             }
                                         }
                                     }));
+
+                                    let ds_recheck = Arc::clone(&ds);
+                                    let devices_recheck = Arc::clone(&app.devices);
+                                    if let Some(h) = recheck_handle.take() { h.abort(); }
+                                    recheck_handle = Some(tokio::spawn(async move {
+                                        loop {
+                                            tokio::time::sleep(OFFLINE_RECHECK_INTERVAL).await;
+                                            let offline: Vec<_> = devices_recheck.lock().unwrap().values()
+                                                .filter(|d| !d.is_online())
+                                                .map(|d| (d.device_id, d.address))
+                                                .collect();
+                                            for (device_id, address) in offline {
+                                                if let Err(e) = send_directed_whois(&*ds_recheck, address, device_id) {
+                                                    error!("Re-check of device {} failed: {}", device_id, e);
+                                                }
+                                            }
+                                        }
+                                    }));
+                                }
+                            } else if let ViewState::ObjectList(_) = app.view_state {
+                                app.enter_object();
+                                if let ViewState::ObjectProperties(device_id, object_id) = app.view_state
+                                    && let Some(ref cs) = client_socket
+                                {
+                                    spawn_property_browser_read(&app, &app_arc, cs, &tx, &tx_register, device_id, object_id);
                                 }
                             } else {
                                 app.enter_device();
+                                if let ViewState::ObjectList(device_id) = app.view_state
+                                    && let Some(ref cs) = client_socket
+                                {
+                                    let device = { let d = app.devices.lock().unwrap(); d.get(&device_id).cloned() };
+                                    if let Some(device) = device {
+                                        let s_props = Arc::clone(cs);
+                                        let tx_props = tx.clone();
+                                        let tx_reg_props = tx_register.clone();
+                                        let invoke_id = app.get_next_invoke_id();
+                                        tokio::spawn(async move {
+                                            let obj = bacnet_rs::object::ObjectIdentifier::new(bacnet_rs::object::ObjectType::Device, device_id);
+                                            match read_property_set(&*s_props, device.address, obj, property::DEVICE_PROPERTIES, invoke_id, &tx_reg_props).await {
+                                                Ok(props) => { let _ = tx_props.send(AppEvent::DevicePropertiesRead(device_id, props)).await; }
+                                                Err(e) => {
+                                                    error!("Reading device properties of {} failed: {}", device_id, e);
+                                                    if is_timeout(&e) { let _ = tx_props.send(AppEvent::RequestTimedOut(device_id)).await; }
+                                                }
+                                            }
+                                        });
+                                    }
+                                }
                             }
                         }
                         KeyCode::Esc => app.exit_view(),
                         KeyCode::Char('d') => {
                             match app.view_state {
                                 ViewState::DeviceList => {
-                                    if let Some(ref ds) = discovery_socket {
+                                    if let (Some(ds), Some(broadcast_addr)) = (&discovery_socket, broadcast_addr) {
                                         app.clear();
                                         let s_send = Arc::clone(ds);
                                         let tx_status = tx.clone();
-                                        let iface = app.interfaces[app.selected_interface_index.unwrap()].clone();
                                         tokio::spawn(async move {
-                                            let broadcast_addr = get_interface_broadcast(&iface).unwrap_or_else(|| "255.255.255.255:47808".parse().unwrap());
-                                            if let Err(e) = send_whois_to(&s_send, broadcast_addr) { error!("Discovery failed: {}", e); }
+                                            if let Err(e) = send_whois_to(&*s_send, broadcast_addr) { error!("Discovery failed: {}", e); }
                                             tokio::time::sleep(Duration::from_secs(3)).await;
                                             let _ = tx_status.send(AppEvent::StatusUpdate("Scan complete.".to_string())).await;
                                         });
@@ -190,45 +450,142 @@ async fn main() -> Result<()> {
                                             let tx_reg_points = tx_register.clone();
                                             let invoke_id = app.get_next_invoke_id();
                                             tokio::spawn(async move {
-                                                match read_device_objects(&s_points, device.address, device_id, invoke_id, &tx_reg_points).await {
+                                                match read_device_objects(&*s_points, device.address, device_id, invoke_id, &tx_reg_points).await {
                                                     Ok(points) => { let _ = tx_points.send(AppEvent::PointsDiscovered(device_id, points)).await; }
-                                                    Err(e) => { let _ = tx_points.send(AppEvent::StatusUpdate(format!("Error: {}", e))).await; }
+                                                    Err(e) => {
+                                                        if is_timeout(&e) { let _ = tx_points.send(AppEvent::RequestTimedOut(device_id)).await; }
+                                                        let _ = tx_points.send(AppEvent::StatusUpdate(format!("Error: {}", e))).await;
+                                                    }
                                                 }
                                             });
                                         }
                                     }
                                 }
+                                ViewState::ObjectProperties(device_id, object_id) => {
+                                    if let Some(ref cs) = client_socket {
+                                        app.object_properties.lock().unwrap().remove(&(device_id, object_id));
+                                        spawn_property_browser_read(&app, &app_arc, cs, &tx, &tx_register, device_id, object_id);
+                                    }
+                                }
+                                ViewState::Topology => {
+                                    if let (Some(ds), Some(broadcast_addr)) = (&discovery_socket, broadcast_addr) {
+                                        probe_topology(&app, ds, broadcast_addr);
+                                        app.status_message = "Asking for devices, routers and BBMD tables...".to_string();
+                                    }
+                                }
                                 _ => {}
                             }
                         }
+                        KeyCode::Char('c') => match app.view_state {
+                            ViewState::DeviceList => app.compare_with_baseline(),
+                            ViewState::Traffic => app.clear_traffic(),
+                            ViewState::Health => app.clear_health(),
+                            ViewState::Topology => app.clear_topology(),
+                            _ => {}
+                        },
+                        KeyCode::Char('p') => {
+                            if let ViewState::Traffic = app.view_state {
+                                app.toggle_traffic_pause();
+                            }
+                        }
+                        KeyCode::Char('T') => app.show_traffic(),
+                        KeyCode::Char('H') => app.show_health(),
+                        KeyCode::Char('N') => {
+                            app.show_topology();
+                            if let (ViewState::Topology, Some(ds), Some(broadcast_addr)) = (&app.view_state, &discovery_socket, broadcast_addr) {
+                                probe_topology(&app, ds, broadcast_addr);
+                            }
+                        }
+                        KeyCode::Char('x') if matches!(app.view_state, ViewState::Topology) => {
+                            app.export_topology(&export::default_base());
+                        }
+                        KeyCode::Char('x') => {
+                            app.status_message = match app.export().write_files(&export::default_base()) {
+                                Ok((csv, json)) => format!("Exported to {} and {}.", csv.display(), json.display()),
+                                Err(e) => format!("Export failed: {}", e),
+                            };
+                        }
+                        KeyCode::Char('e') => {
+                            let base = export::default_base();
+                            let project = base.display().to_string();
+                            app.status_message = match app.export_ede(&project).write_files(&base) {
+                                Ok([main, ..]) => format!("EDE files written next to {}.", main.display()),
+                                Err(e) => format!("EDE export failed: {}", e),
+                            };
+                        }
+                        KeyCode::Char('/') => app.start_search(),
+                        KeyCode::Char('s') => app.cycle_sort_key(),
+                        KeyCode::Char('S') => app.toggle_sort_direction(),
+                        KeyCode::Char('t') => app.cycle_type_filter(),
+                        KeyCode::Char('a') => app.toggle_problems_filter(),
+                        KeyCode::Char(c) if c.is_ascii_digit() => app.push_jump_digit(c),
                         KeyCode::Down => app.next(),
                         KeyCode::Up => app.previous(),
+                        KeyCode::PageDown => app.page_down(),
+                        KeyCode::PageUp => app.page_up(),
+                        KeyCode::Home => app.select_first(),
+                        KeyCode::End => app.select_last(),
+                        _ => {}
+                    }
+                },
+                AppEvent::Input(Event::Mouse(mouse)) => {
+                    let mut app = app_arc.lock().unwrap();
+                    match mouse.kind {
+                        MouseEventKind::ScrollDown => app.move_selection(MOUSE_WHEEL_ROWS),
+                        MouseEventKind::ScrollUp => app.move_selection(-MOUSE_WHEEL_ROWS),
+                        MouseEventKind::Down(MouseButton::Left) => { app.click(mouse.column, mouse.row); }
                         _ => {}
                     }
                 },
                 AppEvent::DeviceDiscovered(device) => {
+                    let mut app = app_arc.lock().unwrap();
+                    if let Some(event) = app.record_device(device) {
+                        warn!("{}", event);
+                        app.status_message = format!("WARNING: {}", event);
+                    }
+                }
+                AppEvent::DevicePropertiesRead(device_id, props) => {
                     let app = app_arc.lock().unwrap();
-                    let mut devices = app.devices.lock().unwrap();
-                    devices.insert(device.device_id, device);
+                    app.mark_device_seen(device_id);
+                    app.device_properties.lock().unwrap().insert(device_id, props);
+                }
+                AppEvent::ObjectPropertiesRead(device_id, object_id, props) => {
+                    let mut app = app_arc.lock().unwrap();
+                    app.mark_device_seen(device_id);
+                    app.status_message = format!("Read {} properties of {:?}:{}.", props.len(), object_id.object_type, object_id.instance);
+                    app.object_properties.lock().unwrap().insert((device_id, object_id), props);
                 }
                 AppEvent::PointsDiscovered(device_id, points) => {
                     let app = app_arc.lock().unwrap();
+                    app.mark_device_seen(device_id);
                     let mut objects = app.device_objects.lock().unwrap();
                     objects.insert(device_id, points);
                 }
-                AppEvent::PointUpdated(device_id, object_id, value) => {
+                AppEvent::PointUpdated(device_id, object_id, reading) => {
                     let app = app_arc.lock().unwrap();
+                    app.mark_device_seen(device_id);
                     let mut objects = app.device_objects.lock().unwrap();
-                    if let Some(device_objs) = objects.get_mut(&device_id) {
-                        if let Some(point) = device_objs.iter_mut().find(|o| o.id == object_id) {
-                            point.present_value = value;
-                            point.last_updated = std::time::Instant::now();
-                        }
+                    if let Some(device_objs) = objects.get_mut(&device_id)
+                        && let Some(point) = device_objs.iter_mut().find(|o| o.id == object_id)
+                    {
+                        point.apply_reading(reading);
+                    }
+                }
+                AppEvent::RequestTimedOut(device_id) => {
+                    let mut app = app_arc.lock().unwrap();
+                    if app.record_device_timeout(device_id) {
+                        app.status_message = format!("Device {} is not responding and is marked offline.", device_id);
                     }
                 }
                 AppEvent::StatusUpdate(msg) => {
                     app_arc.lock().unwrap().status_message = msg;
                 }
+                AppEvent::Tick => {
+                    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+                    for warning in app_arc.lock().unwrap().check_broadcasts(now) {
+                        warn!("{}", warning);
+                    }
+                }
                 _ => {}
             }
         }
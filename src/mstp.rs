@@ -0,0 +1,774 @@
+//! BACnet MS/TP data link (ASHRAE 135 Clause 9) over a local serial device.
+//!
+//! An [`MstpPort`] runs the master node state machine on a background thread and
+//! implements [`DataLink`], so the discovery and polling code keeps speaking
+//! BVLL-framed messages. MS/TP stations are addressed as `0.0.0.<mac>:0`, a range
+//! that is never a valid BACnet/IP peer; MAC 255 is the MS/TP broadcast.
+
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use anyhow::{Result, anyhow};
+use serialport::SerialPort;
+use tracing::{debug, info, warn};
+use crate::network::DataLink;
+
+pub const BROADCAST_MAC: u8 = 255;
+pub const MAX_DATA_LENGTH: usize = 501;
+
+const PREAMBLE_55: u8 = 0x55;
+const PREAMBLE_FF: u8 = 0xFF;
+
+// Timing parameters from Clause 9.5.3
+const T_NO_TOKEN: Duration = Duration::from_millis(500);
+const T_REPLY_TIMEOUT: Duration = Duration::from_millis(255);
+const T_USAGE_TIMEOUT: Duration = Duration::from_millis(50);
+const T_SLOT: Duration = Duration::from_millis(10);
+const T_FRAME_ABORT: Duration = Duration::from_millis(100);
+const N_POLL: u8 = 50;
+const N_RETRY_TOKEN: u8 = 1;
+
+/// How long the worker blocks on the serial port before re-checking its timers.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+/// Matches the read timeout `create_shared_socket` puts on UDP sockets.
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameType {
+    Token = 0,
+    PollForMaster = 1,
+    ReplyToPollForMaster = 2,
+    TestRequest = 3,
+    TestResponse = 4,
+    DataExpectingReply = 5,
+    DataNotExpectingReply = 6,
+    ReplyPostponed = 7,
+}
+
+impl FrameType {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Token),
+            1 => Some(Self::PollForMaster),
+            2 => Some(Self::ReplyToPollForMaster),
+            3 => Some(Self::TestRequest),
+            4 => Some(Self::TestResponse),
+            5 => Some(Self::DataExpectingReply),
+            6 => Some(Self::DataNotExpectingReply),
+            7 => Some(Self::ReplyPostponed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub frame_type: FrameType,
+    pub destination: u8,
+    pub source: u8,
+    pub data: Vec<u8>,
+}
+
+impl Frame {
+    pub fn new(frame_type: FrameType, destination: u8, source: u8, data: Vec<u8>) -> Self {
+        Self { frame_type, destination, source, data }
+    }
+
+    pub fn token(destination: u8, source: u8) -> Self {
+        Self::new(FrameType::Token, destination, source, Vec::new())
+    }
+
+    pub fn poll_for_master(destination: u8, source: u8) -> Self {
+        Self::new(FrameType::PollForMaster, destination, source, Vec::new())
+    }
+
+    /// Encodes the frame including preamble, header CRC and (if present) data CRC.
+    pub fn encode(&self) -> Vec<u8> {
+        let len = self.data.len() as u16;
+        let header = [
+            self.frame_type as u8,
+            self.destination,
+            self.source,
+            (len >> 8) as u8,
+            (len & 0xFF) as u8,
+        ];
+
+        let mut frame = vec![PREAMBLE_55, PREAMBLE_FF];
+        frame.extend_from_slice(&header);
+        frame.push(header_crc(&header));
+        if !self.data.is_empty() {
+            frame.extend_from_slice(&self.data);
+            // Data CRC is transmitted least significant octet first
+            frame.extend_from_slice(&data_crc(&self.data).to_le_bytes());
+        }
+        frame
+    }
+}
+
+fn calc_header_crc(data: u8, crc: u8) -> u8 {
+    let mut crc = (crc ^ data) as u16;
+    crc = crc ^ (crc << 1) ^ (crc << 2) ^ (crc << 3) ^ (crc << 4) ^ (crc << 5) ^ (crc << 6) ^ (crc << 7);
+    ((crc & 0xFE) ^ ((crc >> 8) & 1)) as u8
+}
+
+fn calc_data_crc(data: u8, crc: u16) -> u16 {
+    let low = (crc & 0xFF) ^ data as u16;
+    (crc >> 8) ^ (low << 8) ^ (low << 3) ^ (low << 12) ^ (low >> 4) ^ (low & 0x0F) ^ ((low & 0x0F) << 7)
+}
+
+/// Computes the header CRC octet (already ones-complemented) over frame type..length.
+pub fn header_crc(header: &[u8]) -> u8 {
+    !header.iter().fold(0xFF, |crc, &b| calc_header_crc(b, crc))
+}
+
+/// Computes the data CRC (already ones-complemented) over the frame data.
+pub fn data_crc(data: &[u8]) -> u16 {
+    !data.iter().fold(0xFFFF, |crc, &b| calc_data_crc(b, crc))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReceiveError {
+    HeaderCrc,
+    DataCrc,
+    DataTooLong(usize),
+    UnknownFrameType(u8),
+}
+
+#[derive(Debug, Default)]
+enum ReceiveState {
+    #[default]
+    Idle,
+    Preamble,
+    Header,
+    Data,
+}
+
+/// Octet-at-a-time frame parser following the Clause 9.5.4 receive state machine.
+#[derive(Debug, Default)]
+pub struct FrameReceiver {
+    state: ReceiveState,
+    header: Vec<u8>,
+    data: Vec<u8>,
+    data_length: usize,
+}
+
+impl FrameReceiver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// True while part of a frame has been received.
+    pub fn in_frame(&self) -> bool {
+        !matches!(self.state, ReceiveState::Idle)
+    }
+
+    pub fn reset(&mut self) {
+        self.state = ReceiveState::Idle;
+        self.header.clear();
+        self.data.clear();
+        self.data_length = 0;
+    }
+
+    /// Feeds one octet; returns a result once a complete frame has been seen.
+    pub fn push(&mut self, byte: u8) -> Option<std::result::Result<Frame, ReceiveError>> {
+        match self.state {
+            ReceiveState::Idle => {
+                if byte == PREAMBLE_55 {
+                    self.state = ReceiveState::Preamble;
+                }
+                None
+            }
+            ReceiveState::Preamble => {
+                self.state = match byte {
+                    PREAMBLE_FF => ReceiveState::Header,
+                    // A repeated first preamble octet keeps us waiting for the second
+                    PREAMBLE_55 => ReceiveState::Preamble,
+                    _ => ReceiveState::Idle,
+                };
+                None
+            }
+            ReceiveState::Header => {
+                self.header.push(byte);
+                if self.header.len() < 6 {
+                    return None;
+                }
+                let header_ok = self.header.iter().fold(0xFF, |crc, &b| calc_header_crc(b, crc)) == 0x55;
+                self.data_length = ((self.header[3] as usize) << 8) | self.header[4] as usize;
+                if !header_ok {
+                    self.reset();
+                    return Some(Err(ReceiveError::HeaderCrc));
+                }
+                if self.data_length > MAX_DATA_LENGTH {
+                    let len = self.data_length;
+                    self.reset();
+                    return Some(Err(ReceiveError::DataTooLong(len)));
+                }
+                if self.data_length == 0 {
+                    return Some(self.finish());
+                }
+                self.state = ReceiveState::Data;
+                None
+            }
+            ReceiveState::Data => {
+                self.data.push(byte);
+                if self.data.len() < self.data_length + 2 {
+                    return None;
+                }
+                if self.data.iter().fold(0xFFFF, |crc, &b| calc_data_crc(b, crc)) != 0xF0B8 {
+                    self.reset();
+                    return Some(Err(ReceiveError::DataCrc));
+                }
+                self.data.truncate(self.data_length);
+                Some(self.finish())
+            }
+        }
+    }
+
+    fn finish(&mut self) -> std::result::Result<Frame, ReceiveError> {
+        let raw_type = self.header[0];
+        let frame = FrameType::from_u8(raw_type).map(|frame_type| {
+            Frame::new(frame_type, self.header[1], self.header[2], std::mem::take(&mut self.data))
+        });
+        self.reset();
+        frame.ok_or(ReceiveError::UnknownFrameType(raw_type))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MstpConfig {
+    /// This station's MAC address (0-127 for a master node)
+    pub mac: u8,
+    pub baud_rate: u32,
+    /// Highest master address polled when looking for the next station
+    pub max_master: u8,
+    /// Frames sent per token hold
+    pub max_info_frames: u8,
+}
+
+impl Default for MstpConfig {
+    fn default() -> Self {
+        Self { mac: 127, baud_rate: 38400, max_master: 127, max_info_frames: 1 }
+    }
+}
+
+impl MstpConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.max_master > 127 {
+            return Err(anyhow!("Max_Master must be 0-127, got {}", self.max_master));
+        }
+        if self.mac > self.max_master {
+            return Err(anyhow!("MAC {} is above Max_Master {}", self.mac, self.max_master));
+        }
+        if self.max_info_frames == 0 {
+            return Err(anyhow!("Max_Info_Frames must be at least 1"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MasterState {
+    Idle,
+    UseToken,
+    WaitForReply,
+    DoneWithToken,
+    PassToken,
+    NoToken,
+    PollForMaster,
+}
+
+/// The Clause 9.5.6 master node state machine, driven by received frames and a clock.
+///
+/// Every method returns the frames that must be transmitted, in order. The caller
+/// reports octet activity through [`MasterNode::activity`] so the silence timer
+/// matches the wire.
+#[derive(Debug)]
+pub struct MasterNode {
+    config: MstpConfig,
+    state: MasterState,
+    next_station: u8,
+    poll_station: u8,
+    token_count: u8,
+    frame_count: u8,
+    retry_count: u8,
+    sole_master: bool,
+    silence_start: Instant,
+    outgoing: VecDeque<Frame>,
+    received: VecDeque<Frame>,
+}
+
+impl MasterNode {
+    pub fn new(config: MstpConfig, now: Instant) -> Self {
+        let mac = config.mac;
+        Self {
+            config,
+            state: MasterState::Idle,
+            next_station: mac,
+            poll_station: mac,
+            // Start with a maintenance poll the first time we hold the token
+            token_count: N_POLL,
+            frame_count: 0,
+            retry_count: 0,
+            sole_master: false,
+            silence_start: now,
+            outgoing: VecDeque::new(),
+            received: VecDeque::new(),
+        }
+    }
+
+    pub fn state(&self) -> MasterState {
+        self.state
+    }
+
+    pub fn next_station(&self) -> u8 {
+        self.next_station
+    }
+
+    pub fn is_sole_master(&self) -> bool {
+        self.sole_master
+    }
+
+    /// Queues an NPDU to be sent the next time this node holds the token.
+    pub fn queue_data(&mut self, destination: u8, npdu: Vec<u8>, expecting_reply: bool) {
+        let frame_type = if expecting_reply && destination != BROADCAST_MAC {
+            FrameType::DataExpectingReply
+        } else {
+            FrameType::DataNotExpectingReply
+        };
+        self.outgoing.push_back(Frame::new(frame_type, destination, self.config.mac, npdu));
+    }
+
+    /// Pops the next data frame addressed to this station (or broadcast).
+    pub fn take_received(&mut self) -> Option<Frame> {
+        self.received.pop_front()
+    }
+
+    /// Resets the silence timer; call for every octet seen on the wire.
+    pub fn activity(&mut self, now: Instant) {
+        self.silence_start = now;
+    }
+
+    pub fn handle_frame(&mut self, frame: Frame, now: Instant) -> Vec<Frame> {
+        self.silence_start = now;
+        let mut out = Vec::new();
+        let to_us = frame.destination == self.config.mac;
+
+        match self.state {
+            // SawTokenUser / SawFrame: someone else is active, process it as Idle
+            MasterState::Idle | MasterState::PassToken | MasterState::NoToken => {
+                self.state = MasterState::Idle;
+                self.receive_in_idle(frame, now, &mut out);
+            }
+            MasterState::WaitForReply => match frame.frame_type {
+                FrameType::DataNotExpectingReply if to_us => {
+                    self.received.push_back(frame);
+                    self.state = MasterState::DoneWithToken;
+                }
+                // A Test_Response carries no NPDU, so it only ends the wait
+                FrameType::TestResponse | FrameType::ReplyPostponed if to_us => self.state = MasterState::DoneWithToken,
+                _ => self.state = MasterState::Idle,
+            },
+            MasterState::PollForMaster => {
+                if to_us && frame.frame_type == FrameType::ReplyToPollForMaster {
+                    self.sole_master = false;
+                    self.next_station = frame.source;
+                    self.poll_station = self.config.mac;
+                    self.token_count = 0;
+                    self.retry_count = 0;
+                    self.send(&mut out, Frame::token(self.next_station, self.config.mac), now);
+                    self.state = MasterState::PassToken;
+                } else {
+                    self.state = MasterState::Idle;
+                }
+            }
+            // Transient states resolve inside `poll` and never wait on the wire
+            MasterState::UseToken | MasterState::DoneWithToken => {}
+        }
+
+        out.extend(self.poll(now));
+        out
+    }
+
+    /// A frame with a bad CRC counts as activity but is otherwise dropped.
+    pub fn handle_invalid_frame(&mut self, now: Instant) -> Vec<Frame> {
+        self.silence_start = now;
+        if self.state == MasterState::PollForMaster && self.sole_master {
+            self.frame_count = 0;
+            self.state = MasterState::UseToken;
+        }
+        self.poll(now)
+    }
+
+    /// Runs timer-driven and immediate transitions until the node waits on the wire.
+    pub fn poll(&mut self, now: Instant) -> Vec<Frame> {
+        let mut out = Vec::new();
+        loop {
+            let before = self.state;
+            self.step(now, &mut out);
+            if self.state == before {
+                return out;
+            }
+        }
+    }
+
+    fn step(&mut self, now: Instant, out: &mut Vec<Frame>) {
+        let silence = now.saturating_duration_since(self.silence_start);
+        let mac = self.config.mac;
+
+        match self.state {
+            MasterState::Idle => {
+                if silence >= T_NO_TOKEN {
+                    debug!("MS/TP: token lost");
+                    self.state = MasterState::NoToken;
+                }
+            }
+            MasterState::NoToken => {
+                // GenerateToken: each station waits an extra slot per MAC
+                if silence >= T_NO_TOKEN + T_SLOT * mac as u32 {
+                    self.poll_station = self.following(mac);
+                    self.next_station = mac;
+                    self.token_count = 0;
+                    self.retry_count = 0;
+                    self.send(out, Frame::poll_for_master(self.poll_station, mac), now);
+                    self.state = MasterState::PollForMaster;
+                }
+            }
+            MasterState::UseToken => match self.outgoing.pop_front() {
+                None => {
+                    self.frame_count = self.config.max_info_frames;
+                    self.state = MasterState::DoneWithToken;
+                }
+                Some(frame) => {
+                    let expecting_reply = frame.frame_type == FrameType::DataExpectingReply;
+                    self.frame_count += 1;
+                    self.send(out, frame, now);
+                    self.state = if expecting_reply { MasterState::WaitForReply } else { MasterState::DoneWithToken };
+                }
+            },
+            MasterState::WaitForReply => {
+                if silence >= T_REPLY_TIMEOUT {
+                    debug!("MS/TP: reply timeout");
+                    self.frame_count = self.config.max_info_frames;
+                    self.state = MasterState::DoneWithToken;
+                }
+            }
+            MasterState::DoneWithToken => self.done_with_token(now, out),
+            MasterState::PassToken => {
+                if silence >= T_USAGE_TIMEOUT {
+                    if self.retry_count < N_RETRY_TOKEN {
+                        self.retry_count += 1;
+                        self.send(out, Frame::token(self.next_station, mac), now);
+                    } else {
+                        // FindNewSuccessor
+                        debug!("MS/TP: station {} did not use the token", self.next_station);
+                        self.poll_station = self.following(self.next_station);
+                        self.next_station = mac;
+                        self.retry_count = 0;
+                        self.token_count = 0;
+                        self.send(out, Frame::poll_for_master(self.poll_station, mac), now);
+                        self.state = MasterState::PollForMaster;
+                    }
+                }
+            }
+            MasterState::PollForMaster => {
+                if silence >= T_USAGE_TIMEOUT {
+                    if self.sole_master {
+                        self.frame_count = 0;
+                        self.state = MasterState::UseToken;
+                    } else if self.next_station != mac {
+                        // DoneWithPFM
+                        self.retry_count = 0;
+                        self.send(out, Frame::token(self.next_station, mac), now);
+                        self.state = MasterState::PassToken;
+                    } else if self.following(self.poll_station) != mac {
+                        // SendNextPFM
+                        self.poll_station = self.following(self.poll_station);
+                        self.retry_count = 0;
+                        self.send(out, Frame::poll_for_master(self.poll_station, mac), now);
+                    } else {
+                        info!("MS/TP: no other masters found, declaring sole master");
+                        self.sole_master = true;
+                        self.frame_count = 0;
+                        self.state = MasterState::UseToken;
+                    }
+                }
+            }
+        }
+    }
+
+    fn done_with_token(&mut self, now: Instant, out: &mut Vec<Frame>) {
+        let mac = self.config.mac;
+        if self.frame_count < self.config.max_info_frames {
+            self.state = MasterState::UseToken;
+        } else if self.token_count < N_POLL - 1 {
+            self.token_count += 1;
+            if self.sole_master {
+                self.frame_count = 0;
+                self.state = MasterState::UseToken;
+            } else {
+                self.retry_count = 0;
+                self.send(out, Frame::token(self.next_station, mac), now);
+                self.state = MasterState::PassToken;
+            }
+        } else if self.following(self.poll_station) != self.next_station {
+            // SendMaintenancePFM
+            self.poll_station = self.following(self.poll_station);
+            self.retry_count = 0;
+            self.send(out, Frame::poll_for_master(self.poll_station, mac), now);
+            self.state = MasterState::PollForMaster;
+        } else if !self.sole_master {
+            // ResetMaintenancePFM
+            self.poll_station = mac;
+            self.retry_count = 0;
+            self.token_count = 1;
+            self.send(out, Frame::token(self.next_station, mac), now);
+            self.state = MasterState::PassToken;
+        } else {
+            // SoleMasterRestartMaintenancePFM
+            self.poll_station = self.following(self.next_station);
+            self.next_station = mac;
+            self.retry_count = 0;
+            self.token_count = 1;
+            self.send(out, Frame::poll_for_master(self.poll_station, mac), now);
+            self.state = MasterState::PollForMaster;
+        }
+    }
+
+    fn receive_in_idle(&mut self, frame: Frame, now: Instant, out: &mut Vec<Frame>) {
+        let mac = self.config.mac;
+        let to_us = frame.destination == mac;
+        let broadcast = frame.destination == BROADCAST_MAC;
+
+        match frame.frame_type {
+            FrameType::Token if to_us => {
+                self.frame_count = 0;
+                self.sole_master = false;
+                self.state = MasterState::UseToken;
+            }
+            FrameType::PollForMaster if to_us => {
+                self.send(out, Frame::new(FrameType::ReplyToPollForMaster, frame.source, mac, Vec::new()), now);
+            }
+            FrameType::TestRequest if to_us => {
+                self.send(out, Frame::new(FrameType::TestResponse, frame.source, mac, frame.data), now);
+            }
+            FrameType::DataNotExpectingReply | FrameType::DataExpectingReply if broadcast => {
+                self.received.push_back(frame);
+            }
+            FrameType::DataNotExpectingReply if to_us => self.received.push_back(frame),
+            FrameType::DataExpectingReply if to_us => {
+                // We are a client: any answer goes out later when we hold the token
+                let source = frame.source;
+                self.received.push_back(frame);
+                self.send(out, Frame::new(FrameType::ReplyPostponed, source, mac, Vec::new()), now);
+            }
+            _ => {}
+        }
+    }
+
+    fn send(&mut self, out: &mut Vec<Frame>, frame: Frame, now: Instant) {
+        self.silence_start = now;
+        out.push(frame);
+    }
+
+    fn following(&self, station: u8) -> u8 {
+        ((station as u16 + 1) % (self.config.max_master as u16 + 1)) as u8
+    }
+}
+
+/// Maps an MS/TP MAC address into the socket address space used by the rest of the tool.
+pub fn mac_to_addr(mac: u8) -> SocketAddr {
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, mac)), 0)
+}
+
+/// Recovers the MS/TP MAC from an address produced by [`mac_to_addr`].
+pub fn addr_to_mac(addr: &SocketAddr) -> Option<u8> {
+    match addr.ip() {
+        IpAddr::V4(v4) if addr.port() == 0 && v4.octets()[..3] == [0, 0, 0] => Some(v4.octets()[3]),
+        _ => None,
+    }
+}
+
+/// A local MS/TP port running as a master node on a serial device.
+pub struct MstpPort {
+    mac: u8,
+    tx_outgoing: Sender<(u8, Vec<u8>, bool)>,
+    rx_incoming: Mutex<Receiver<Frame>>,
+    nonblocking: AtomicBool,
+    running: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl MstpPort {
+    /// Opens a serial device (e.g. `/dev/ttyUSB0`) at the configured baud rate, 8N1.
+    pub fn open(path: &str, config: MstpConfig) -> Result<Self> {
+        let port = serialport::new(path, config.baud_rate)
+            .timeout(POLL_INTERVAL)
+            .open()
+            .map_err(|e| anyhow!("Failed to open {}: {}", path, e))?;
+        info!("Opened MS/TP port {} at {} baud, MAC {}", path, config.baud_rate, config.mac);
+        Self::from_port(port, config)
+    }
+
+    /// Runs the data link over an already opened port, such as one end of a pty pair.
+    pub fn from_port(mut port: Box<dyn SerialPort>, config: MstpConfig) -> Result<Self> {
+        config.validate()?;
+        port.set_timeout(POLL_INTERVAL)?;
+
+        let (tx_outgoing, rx_outgoing) = mpsc::channel();
+        let (tx_incoming, rx_incoming) = mpsc::channel();
+        let running = Arc::new(AtomicBool::new(true));
+        let mac = config.mac;
+        let node = MasterNode::new(config, Instant::now());
+        let worker_running = Arc::clone(&running);
+        let worker = std::thread::spawn(move || run_worker(port, node, rx_outgoing, tx_incoming, worker_running));
+
+        Ok(Self {
+            mac,
+            tx_outgoing,
+            rx_incoming: Mutex::new(rx_incoming),
+            nonblocking: AtomicBool::new(false),
+            running,
+            worker: Some(worker),
+        })
+    }
+
+    pub fn mac(&self) -> u8 {
+        self.mac
+    }
+}
+
+impl Drop for MstpPort {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl DataLink for MstpPort {
+    /// Strips the BVLL header and queues the NPDU for the next token hold.
+    fn send_to(&self, buf: &[u8], dest: SocketAddr) -> io::Result<usize> {
+        if buf.len() < 6 || buf[0] != 0x81 || !matches!(buf[1], 0x0A | 0x0B) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "expected an Original-Unicast/Broadcast BVLL message"));
+        }
+        let npdu = &buf[4..];
+        if npdu.len() > MAX_DATA_LENGTH {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "NPDU exceeds the MS/TP maximum frame length"));
+        }
+        let destination = if buf[1] == 0x0B {
+            BROADCAST_MAC
+        } else {
+            addr_to_mac(&dest)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("{} is not an MS/TP address", dest)))?
+        };
+        let expecting_reply = npdu[1] & 0x04 != 0;
+
+        self.tx_outgoing
+            .send((destination, npdu.to_vec(), expecting_reply))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "MS/TP worker has stopped"))?;
+        Ok(buf.len())
+    }
+
+    /// Returns the next received NPDU wrapped as an Original-Unicast/Broadcast BVLL message.
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let rx = self.rx_incoming.lock().unwrap();
+        let frame = if self.nonblocking.load(Ordering::SeqCst) {
+            match rx.try_recv() {
+                Ok(frame) => frame,
+                Err(TryRecvError::Empty) => return Err(io::ErrorKind::WouldBlock.into()),
+                Err(TryRecvError::Disconnected) => return Err(io::ErrorKind::BrokenPipe.into()),
+            }
+        } else {
+            match rx.recv_timeout(READ_TIMEOUT) {
+                Ok(frame) => frame,
+                Err(RecvTimeoutError::Timeout) => return Err(io::ErrorKind::WouldBlock.into()),
+                Err(RecvTimeoutError::Disconnected) => return Err(io::ErrorKind::BrokenPipe.into()),
+            }
+        };
+
+        let bvlc_func = if frame.destination == BROADCAST_MAC { 0x0B } else { 0x0A };
+        let total_len = frame.data.len() + 4;
+        if total_len > buf.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "receive buffer too small"));
+        }
+        buf[..4].copy_from_slice(&[0x81, bvlc_func, (total_len >> 8) as u8, (total_len & 0xFF) as u8]);
+        buf[4..total_len].copy_from_slice(&frame.data);
+        Ok((total_len, mac_to_addr(frame.source)))
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.nonblocking.store(nonblocking, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+fn run_worker(
+    mut port: Box<dyn SerialPort>,
+    mut node: MasterNode,
+    rx_outgoing: Receiver<(u8, Vec<u8>, bool)>,
+    tx_incoming: Sender<Frame>,
+    running: Arc<AtomicBool>,
+) {
+    let mut receiver = FrameReceiver::new();
+    let mut buf = [0u8; 512];
+    let mut last_octet = Instant::now();
+
+    while running.load(Ordering::SeqCst) {
+        while let Ok((destination, npdu, expecting_reply)) = rx_outgoing.try_recv() {
+            node.queue_data(destination, npdu, expecting_reply);
+        }
+
+        let mut out = Vec::new();
+        match port.read(&mut buf) {
+            Ok(len) if len > 0 => {
+                let now = Instant::now();
+                last_octet = now;
+                node.activity(now);
+                for &byte in &buf[..len] {
+                    match receiver.push(byte) {
+                        Some(Ok(frame)) => out.extend(node.handle_frame(frame, now)),
+                        Some(Err(e)) => {
+                            debug!("MS/TP: dropped invalid frame: {:?}", e);
+                            out.extend(node.handle_invalid_frame(now));
+                        }
+                        None => {}
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::TimedOut || e.kind() == io::ErrorKind::WouldBlock => {}
+            Err(e) => {
+                warn!("MS/TP: serial read failed, stopping data link: {}", e);
+                break;
+            }
+        }
+
+        if receiver.in_frame() && last_octet.elapsed() >= T_FRAME_ABORT {
+            debug!("MS/TP: frame aborted after inter-octet gap");
+            receiver.reset();
+            out.extend(node.handle_invalid_frame(Instant::now()));
+        }
+        out.extend(node.poll(Instant::now()));
+
+        for frame in out {
+            if let Err(e) = port.write_all(&frame.encode()) {
+                warn!("MS/TP: serial write failed: {}", e);
+            }
+            // Silence starts once the last octet has left the UART
+            let _ = port.flush();
+            node.activity(Instant::now());
+        }
+
+        while let Some(frame) = node.take_received() {
+            if tx_incoming.send(frame).is_err() {
+                return;
+            }
+        }
+    }
+}
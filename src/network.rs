@@ -25,3 +25,27 @@ pub fn create_shared_socket(port: u16) -> io::Result<UdpSocket> {
     
     Ok(socket.into())
 }
+
+/// A BACnet data link that carries BVLL-framed messages.
+///
+/// The protocol layer is written against this trait so the same Who-Is and
+/// confirmed-request code runs over a BACnet/IP socket or a local MS/TP port.
+pub trait DataLink: Send + Sync {
+    fn send_to(&self, buf: &[u8], dest: SocketAddr) -> io::Result<usize>;
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)>;
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()>;
+}
+
+impl DataLink for UdpSocket {
+    fn send_to(&self, buf: &[u8], dest: SocketAddr) -> io::Result<usize> {
+        UdpSocket::send_to(self, buf, dest)
+    }
+
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        UdpSocket::recv_from(self, buf)
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        UdpSocket::set_nonblocking(self, nonblocking)
+    }
+}
@@ -0,0 +1,343 @@
+//! Packet capture files for the sniffer.
+//!
+//! [`PcapWriter`] saves received BACnet/IP datagrams as a classic libpcap file
+//! (Ethernet link type, microsecond timestamps) that Wireshark and tcpdump open
+//! directly. The socket only hands over the UDP payload, so the Ethernet, IPv4 and
+//! UDP headers are synthesised around it; the Ethernet addresses are placeholders.
+//!
+//! [`read_capture`] reads classic pcap (either byte order, micro- or nanosecond
+//! timestamps) and pcapng files and returns the IPv4/UDP datagrams they contain,
+//! skipping everything else. Ethernet (with 802.1Q tags), raw IP and Linux cooked
+//! captures are understood.
+
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::path::Path;
+use std::time::Duration;
+use anyhow::{Result, anyhow};
+
+const PCAP_MAGIC: u32 = 0xA1B2_C3D4;
+const PCAP_MAGIC_NANOS: u32 = 0xA1B2_3C4D;
+const PCAPNG_SECTION_HEADER: u32 = 0x0A0D_0D0A;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+
+/// Largest datagram written or read
+const SNAPLEN: u32 = 65535;
+
+/// A UDP datagram from a capture file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedPacket {
+    /// Time since the Unix epoch
+    pub timestamp: Duration,
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+    pub payload: Vec<u8>,
+}
+
+/// Writes datagrams to a classic pcap file
+pub struct PcapWriter<W: Write> {
+    out: W,
+    ip_id: u16,
+}
+
+impl PcapWriter<std::io::BufWriter<std::fs::File>> {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = std::fs::File::create(path).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+        Self::new(std::io::BufWriter::new(file))
+    }
+}
+
+impl<W: Write> PcapWriter<W> {
+    /// Writes the file header
+    pub fn new(mut out: W) -> Result<Self> {
+        let mut header = Vec::with_capacity(24);
+        header.extend_from_slice(&PCAP_MAGIC.to_le_bytes());
+        header.extend_from_slice(&2u16.to_le_bytes());
+        header.extend_from_slice(&4u16.to_le_bytes());
+        header.extend_from_slice(&0i32.to_le_bytes()); // timezone offset
+        header.extend_from_slice(&0u32.to_le_bytes()); // timestamp accuracy
+        header.extend_from_slice(&SNAPLEN.to_le_bytes());
+        header.extend_from_slice(&LINKTYPE_ETHERNET.to_le_bytes());
+        out.write_all(&header)?;
+        Ok(Self { out, ip_id: 0 })
+    }
+
+    /// Writes one datagram. Only IPv4 addresses can be encapsulated.
+    pub fn write_packet(&mut self, timestamp: Duration, source: SocketAddr, destination: SocketAddr, payload: &[u8]) -> Result<()> {
+        let (SocketAddr::V4(source), SocketAddr::V4(destination)) = (source, destination) else {
+            return Err(anyhow!("Only IPv4 datagrams can be written to a capture"));
+        };
+        if payload.len() > SNAPLEN as usize - 42 {
+            return Err(anyhow!("Datagram of {} bytes is too large to capture", payload.len()));
+        }
+        self.ip_id = self.ip_id.wrapping_add(1);
+        let frame = ethernet_frame(source, destination, self.ip_id, payload);
+
+        let mut record = Vec::with_capacity(16 + frame.len());
+        record.extend_from_slice(&(timestamp.as_secs() as u32).to_le_bytes());
+        record.extend_from_slice(&timestamp.subsec_micros().to_le_bytes());
+        record.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        record.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        record.extend_from_slice(&frame);
+        self.out.write_all(&record)?;
+        Ok(())
+    }
+
+    /// Flushes buffered packets, so the file is usable while capturing continues
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.out.flush()?)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// Ethernet II + IPv4 + UDP around a payload
+fn ethernet_frame(source: SocketAddrV4, destination: SocketAddrV4, ip_id: u16, payload: &[u8]) -> Vec<u8> {
+    let udp_len = 8 + payload.len() as u16;
+    let total_len = 20 + udp_len;
+    let mut frame = Vec::with_capacity(14 + total_len as usize);
+
+    // Broadcasts get the broadcast MAC; otherwise locally administered placeholders
+    let dest_mac = if destination.ip().is_broadcast() || destination.ip().octets()[3] == 255 {
+        [0xFF; 6]
+    } else {
+        [0x02, 0x00, 0x00, 0x00, 0x00, 0x02]
+    };
+    frame.extend_from_slice(&dest_mac);
+    frame.extend_from_slice(&[0x02, 0x00, 0x00, 0x00, 0x00, 0x01]);
+    frame.extend_from_slice(&0x0800u16.to_be_bytes());
+
+    let mut ip = [0u8; 20];
+    ip[0] = 0x45;
+    ip[2..4].copy_from_slice(&total_len.to_be_bytes());
+    ip[4..6].copy_from_slice(&ip_id.to_be_bytes());
+    ip[6] = 0x40; // don't fragment
+    ip[8] = 64; // TTL
+    ip[9] = 17; // UDP
+    ip[12..16].copy_from_slice(&source.ip().octets());
+    ip[16..20].copy_from_slice(&destination.ip().octets());
+    let checksum = ip_checksum(&ip);
+    ip[10..12].copy_from_slice(&checksum.to_be_bytes());
+    frame.extend_from_slice(&ip);
+
+    frame.extend_from_slice(&source.port().to_be_bytes());
+    frame.extend_from_slice(&destination.port().to_be_bytes());
+    frame.extend_from_slice(&udp_len.to_be_bytes());
+    frame.extend_from_slice(&0u16.to_be_bytes()); // no UDP checksum, allowed over IPv4
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn ip_checksum(header: &[u8]) -> u16 {
+    let mut sum: u32 = header.chunks(2).map(|c| u16::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)]) as u32).sum();
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// Reads a pcap or pcapng file
+pub fn read_capture_file(path: &Path) -> Result<Vec<CapturedPacket>> {
+    let file = std::fs::File::open(path).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+    read_capture(std::io::BufReader::new(file)).map_err(|e| anyhow!("{}: {}", path.display(), e))
+}
+
+/// Reads the UDP datagrams of a pcap or pcapng capture, in file order
+pub fn read_capture(mut input: impl Read) -> Result<Vec<CapturedPacket>> {
+    let mut data = Vec::new();
+    input.read_to_end(&mut data)?;
+    let magic = data.get(..4).ok_or_else(|| anyhow!("Not a capture file (too short)"))?;
+    let magic_le = u32::from_le_bytes(magic.try_into()?);
+    match magic_le {
+        PCAP_MAGIC | PCAP_MAGIC_NANOS => read_pcap(&data, Endian::Little),
+        m if m.swap_bytes() == PCAP_MAGIC || m.swap_bytes() == PCAP_MAGIC_NANOS => read_pcap(&data, Endian::Big),
+        PCAPNG_SECTION_HEADER => read_pcapng(&data),
+        _ => Err(anyhow!("Not a pcap or pcapng file (magic {:08X})", magic_le)),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Endian {
+    Little,
+    Big,
+}
+
+impl Endian {
+    fn u16(self, data: &[u8], pos: usize) -> Result<u16> {
+        let bytes: [u8; 2] = data.get(pos..pos + 2).ok_or_else(truncated)?.try_into()?;
+        Ok(match self {
+            Endian::Little => u16::from_le_bytes(bytes),
+            Endian::Big => u16::from_be_bytes(bytes),
+        })
+    }
+
+    fn u32(self, data: &[u8], pos: usize) -> Result<u32> {
+        let bytes: [u8; 4] = data.get(pos..pos + 4).ok_or_else(truncated)?.try_into()?;
+        Ok(match self {
+            Endian::Little => u32::from_le_bytes(bytes),
+            Endian::Big => u32::from_be_bytes(bytes),
+        })
+    }
+}
+
+fn truncated() -> anyhow::Error {
+    anyhow!("Capture file is truncated")
+}
+
+fn read_pcap(data: &[u8], endian: Endian) -> Result<Vec<CapturedPacket>> {
+    let nanos = endian.u32(data, 0)? == PCAP_MAGIC_NANOS;
+    let link_type = endian.u32(data, 20)? & 0x0FFF_FFFF;
+    let mut packets = Vec::new();
+    let mut pos = 24;
+    while pos < data.len() {
+        let secs = endian.u32(data, pos)?;
+        let fraction = endian.u32(data, pos + 4)?;
+        let captured = endian.u32(data, pos + 8)? as usize;
+        let frame = data.get(pos + 16..pos + 16 + captured).ok_or_else(truncated)?;
+        pos += 16 + captured;
+
+        let timestamp = Duration::new(secs as u64, if nanos { fraction } else { fraction.saturating_mul(1000) });
+        if let Some(packet) = udp_datagram(link_type, frame, timestamp) {
+            packets.push(packet);
+        }
+    }
+    Ok(packets)
+}
+
+/// Link type and timestamp resolution of a pcapng interface
+struct Interface {
+    link_type: u32,
+    /// Timestamp units per second
+    units_per_second: u64,
+}
+
+fn read_pcapng(data: &[u8]) -> Result<Vec<CapturedPacket>> {
+    let mut packets = Vec::new();
+    let mut interfaces: Vec<Interface> = Vec::new();
+    let mut endian = Endian::Little;
+    let mut pos = 0;
+    while pos < data.len() {
+        // The section header sets the byte order of everything up to the next one
+        if Endian::Little.u32(data, pos)? == PCAPNG_SECTION_HEADER {
+            endian = match Endian::Little.u32(data, pos + 8)? {
+                PCAPNG_BYTE_ORDER_MAGIC => Endian::Little,
+                m if m.swap_bytes() == PCAPNG_BYTE_ORDER_MAGIC => Endian::Big,
+                _ => return Err(anyhow!("Invalid pcapng byte-order magic")),
+            };
+            interfaces.clear();
+        }
+        let block_type = endian.u32(data, pos)?;
+        let block_len = endian.u32(data, pos + 4)? as usize;
+        if block_len < 12 || !block_len.is_multiple_of(4) {
+            return Err(anyhow!("Invalid pcapng block length {} at offset {}", block_len, pos));
+        }
+        let block = data.get(pos..pos + block_len).ok_or_else(truncated)?;
+        let body = &block[8..block_len - 4];
+        pos += block_len;
+
+        match block_type {
+            // Interface Description Block
+            1 => interfaces.push(Interface {
+                link_type: endian.u16(body, 0)? as u32,
+                units_per_second: timestamp_resolution(body.get(8..).unwrap_or(&[]), endian),
+            }),
+            // Enhanced Packet Block
+            6 => {
+                let interface = interfaces.get(endian.u32(body, 0)? as usize)
+                    .ok_or_else(|| anyhow!("Packet refers to an undefined interface"))?;
+                let ticks = ((endian.u32(body, 4)? as u64) << 32) | endian.u32(body, 8)? as u64;
+                let captured = endian.u32(body, 12)? as usize;
+                let frame = body.get(20..20 + captured).ok_or_else(truncated)?;
+                let timestamp = Duration::from_nanos(
+                    (ticks as u128 * 1_000_000_000 / interface.units_per_second as u128) as u64,
+                );
+                if let Some(packet) = udp_datagram(interface.link_type, frame, timestamp) {
+                    packets.push(packet);
+                }
+            }
+            // Simple Packet Block: no timestamp, always interface 0
+            3 => {
+                let interface = interfaces.first().ok_or_else(|| anyhow!("Packet refers to an undefined interface"))?;
+                let original = endian.u32(body, 0)? as usize;
+                let frame = &body[4..(4 + original).min(body.len())];
+                if let Some(packet) = udp_datagram(interface.link_type, frame, Duration::ZERO) {
+                    packets.push(packet);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(packets)
+}
+
+/// The `if_tsresol` option of an interface description, as units per second (default microseconds)
+fn timestamp_resolution(mut options: &[u8], endian: Endian) -> u64 {
+    while let (Ok(code), Ok(len)) = (endian.u16(options, 0), endian.u16(options, 2)) {
+        let len = len as usize;
+        if code == 0 {
+            break;
+        }
+        if code == 9
+            && let Some(&resolution) = options.get(4)
+        {
+            let exponent = (resolution & 0x7F) as u32;
+            return if resolution & 0x80 != 0 { 2u64.saturating_pow(exponent) } else { 10u64.saturating_pow(exponent) };
+        }
+        options = options.get(4 + len.div_ceil(4) * 4..).unwrap_or(&[]);
+    }
+    1_000_000
+}
+
+/// The IPv4/UDP datagram in a link-layer frame, if it carries one
+fn udp_datagram(link_type: u32, frame: &[u8], timestamp: Duration) -> Option<CapturedPacket> {
+    let ip = match link_type {
+        LINKTYPE_ETHERNET => {
+            let mut pos = 12;
+            let mut ether_type = u16::from_be_bytes(frame.get(pos..pos + 2)?.try_into().ok()?);
+            // 802.1Q / 802.1ad VLAN tags
+            while ether_type == 0x8100 || ether_type == 0x88A8 {
+                pos += 4;
+                ether_type = u16::from_be_bytes(frame.get(pos..pos + 2)?.try_into().ok()?);
+            }
+            (ether_type == 0x0800).then_some(frame.get(pos + 2..)?)?
+        }
+        LINKTYPE_LINUX_SLL => {
+            let protocol = u16::from_be_bytes(frame.get(14..16)?.try_into().ok()?);
+            (protocol == 0x0800).then_some(frame.get(16..)?)?
+        }
+        LINKTYPE_RAW | LINKTYPE_IPV4 => frame,
+        _ => return None,
+    };
+
+    let version_ihl = *ip.first()?;
+    let header_len = (version_ihl & 0x0F) as usize * 4;
+    if version_ihl >> 4 != 4 || header_len < 20 || *ip.get(9)? != 17 {
+        return None;
+    }
+    // Only the first fragment has the UDP header
+    let fragment_offset = u16::from_be_bytes(ip.get(6..8)?.try_into().ok()?) & 0x1FFF;
+    if fragment_offset != 0 {
+        return None;
+    }
+    let total_len = (u16::from_be_bytes(ip.get(2..4)?.try_into().ok()?) as usize).min(ip.len());
+    let source_ip = Ipv4Addr::from(<[u8; 4]>::try_from(ip.get(12..16)?).ok()?);
+    let destination_ip = Ipv4Addr::from(<[u8; 4]>::try_from(ip.get(16..20)?).ok()?);
+
+    let udp = ip.get(header_len..total_len)?;
+    let source_port = u16::from_be_bytes(udp.get(0..2)?.try_into().ok()?);
+    let destination_port = u16::from_be_bytes(udp.get(2..4)?.try_into().ok()?);
+    let udp_len = (u16::from_be_bytes(udp.get(4..6)?.try_into().ok()?) as usize).clamp(8, udp.len());
+    Some(CapturedPacket {
+        timestamp,
+        source: SocketAddr::V4(SocketAddrV4::new(source_ip, source_port)),
+        destination: SocketAddr::V4(SocketAddrV4::new(destination_ip, destination_port)),
+        payload: udp.get(8..udp_len)?.to_vec(),
+    })
+}
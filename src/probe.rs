@@ -0,0 +1,357 @@
+//! Active network probes behind the `diagnostics` binary.
+//!
+//! Each probe produces a [`CheckResult`] graded pass, warn or fail; the binary
+//! runs them in order and prints the [`ProbeReport`] as text or JSON. The socket
+//! work is kept in small functions here so the probes can be exercised on the
+//! loopback interface.
+
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, ErrorKind};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use anyhow::Result;
+use serde::Serialize;
+use crate::address_book::{AddressBook, AddressEvent};
+use crate::bacnet::{process_response, DiscoveredDevice};
+use crate::decode::bdt_entries;
+pub use crate::decode::BdtEntry;
+use crate::network::create_shared_socket;
+use crate::storm::BroadcastMonitor;
+
+/// BVLL Read-Broadcast-Distribution-Table
+pub const READ_BDT: [u8; 4] = [0x81, 0x02, 0x00, 0x04];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl Status {
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::Pass => "PASS",
+            Status::Warn => "WARN",
+            Status::Fail => "FAIL",
+        }
+    }
+}
+
+/// Outcome of one probe
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: Status,
+    pub summary: String,
+    /// Findings and hints, one per line
+    pub details: Vec<String>,
+}
+
+impl CheckResult {
+    pub fn new(name: impl Into<String>, status: Status, summary: impl Into<String>) -> Self {
+        Self { name: name.into(), status, summary: summary.into(), details: Vec::new() }
+    }
+
+    pub fn with_details(mut self, details: impl IntoIterator<Item = String>) -> Self {
+        self.details.extend(details);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProbeReport {
+    /// Unix time the report was made
+    pub generated_at: u64,
+    pub checks: Vec<CheckResult>,
+}
+
+impl ProbeReport {
+    pub fn new() -> Self {
+        let generated_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        Self { generated_at, checks: Vec::new() }
+    }
+
+    pub fn push(&mut self, check: CheckResult) {
+        self.checks.push(check);
+    }
+
+    /// The worst status of any check; pass for an empty report
+    pub fn status(&self) -> Status {
+        self.checks.iter().map(|c| c.status).max().unwrap_or(Status::Pass)
+    }
+
+    pub fn count(&self, status: Status) -> usize {
+        self.checks.iter().filter(|c| c.status == status).count()
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for check in &self.checks {
+            out.push_str(&format!("[{}] {}: {}\n", check.status.label(), check.name, check.summary));
+            for line in &check.details {
+                out.push_str(&format!("       {}\n", line));
+            }
+        }
+        out.push_str(&format!(
+            "\n{} passed, {} warnings, {} failed\n",
+            self.count(Status::Pass), self.count(Status::Warn), self.count(Status::Fail)
+        ));
+        out
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// An IPv4 interface with its subnet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalInterface {
+    pub name: String,
+    pub ip: Ipv4Addr,
+    pub netmask: Ipv4Addr,
+    pub broadcast: Option<Ipv4Addr>,
+}
+
+impl LocalInterface {
+    /// Every IPv4 interface except loopback
+    pub fn list() -> Vec<LocalInterface> {
+        if_addrs::get_if_addrs().unwrap_or_default().into_iter()
+            .filter(|iface| !iface.is_loopback())
+            .filter_map(|iface| match iface.addr {
+                if_addrs::IfAddr::V4(v4) => Some(LocalInterface { name: iface.name, ip: v4.ip, netmask: v4.netmask, broadcast: v4.broadcast }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let IpAddr::V4(ip) = ip else { return false };
+        let mask = u32::from(self.netmask);
+        u32::from(ip) & mask == u32::from(self.ip) & mask
+    }
+
+    /// Address/prefix, e.g. 192.168.1.10/24
+    pub fn cidr(&self) -> String {
+        format!("{}/{}", self.ip, u32::from(self.netmask).count_ones())
+    }
+}
+
+/// A device that answered the Who-Is
+#[derive(Debug, Clone)]
+pub struct Responder {
+    pub device: DiscoveredDevice,
+    /// The BBMD that relayed the I-Am as a Forwarded-NPDU; None when it arrived directly
+    pub via: Option<SocketAddr>,
+}
+
+/// Collects I-Am replies arriving on `socket` for `duration`. A device that answers
+/// more than once from the same address is listed once.
+pub fn collect_responders(socket: &UdpSocket, duration: Duration) -> Vec<Responder> {
+    let _ = socket.set_read_timeout(Some(Duration::from_millis(100)));
+    let mut responders: Vec<Responder> = Vec::new();
+    let mut buf = [0u8; 1500];
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        let Ok((len, source)) = socket.recv_from(&mut buf) else { continue };
+        let data = &buf[..len];
+        let Some(device) = process_response(data, source) else { continue };
+        let known = responders.iter().any(|r| r.device.device_id == device.device_id && r.device.address == device.address);
+        if !known {
+            let via = (data[1] == 0x04).then_some(source);
+            responders.push(Responder { device, via });
+        }
+    }
+    responders
+}
+
+/// Answer to a Read-BDT
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BdtReply {
+    Table(Vec<BdtEntry>),
+    /// BVLC-Result with this code; 0x0020 is a Read-BDT NAK from a device that is no BBMD
+    Nak(u16),
+    /// The Read-BDT could not be sent, e.g. because there is no route to the target
+    SendFailed(String),
+}
+
+/// Parses a Read-BDT-Ack or BVLC-Result; None for any other datagram
+pub fn parse_bdt_reply(data: &[u8]) -> Option<BdtReply> {
+    if data.len() < 4 || data[0] != 0x81 {
+        return None;
+    }
+    let length = (u16::from_be_bytes([data[2], data[3]]) as usize).min(data.len());
+    let body = data.get(4..length)?;
+    match data[1] {
+        0x03 => Some(BdtReply::Table(bdt_entries(body))),
+        0x00 if body.len() >= 2 => Some(BdtReply::Nak(u16::from_be_bytes([body[0], body[1]]))),
+        _ => None,
+    }
+}
+
+/// Sends a Read-BDT to each target and waits up to `timeout` for the answers. Targets
+/// that did not answer map to None, targets the request could not be sent to to
+/// `BdtReply::SendFailed`.
+pub fn read_bdts(socket: &UdpSocket, targets: &[SocketAddr], timeout: Duration) -> io::Result<BTreeMap<SocketAddr, Option<BdtReply>>> {
+    let mut replies: BTreeMap<SocketAddr, Option<BdtReply>> = BTreeMap::new();
+    for target in targets {
+        let reply = socket.send_to(&READ_BDT, target).err().map(|e| BdtReply::SendFailed(e.to_string()));
+        replies.insert(*target, reply);
+    }
+    socket.set_read_timeout(Some(Duration::from_millis(100)))?;
+    let mut buf = [0u8; 1500];
+    let start = Instant::now();
+    while start.elapsed() < timeout && replies.values().any(Option::is_none) {
+        let Ok((len, source)) = socket.recv_from(&mut buf) else { continue };
+        if let Some(slot) = replies.get_mut(&source)
+            && slot.is_none()
+        {
+            *slot = parse_bdt_reply(&buf[..len]);
+        }
+    }
+    Ok(replies)
+}
+
+/// Whether another process holds `port`, and whether it can be shared with SO_REUSEPORT
+pub fn check_port(port: u16) -> Vec<CheckResult> {
+    let owner = match UdpSocket::bind(SocketAddr::from(([0, 0, 0, 0], port))) {
+        Ok(_) => CheckResult::new(format!("Port {}", port), Status::Pass, "No other process is bound to the port"),
+        Err(e) if e.kind() == ErrorKind::AddrInUse => {
+            CheckResult::new(format!("Port {}", port), Status::Warn, "Another process is bound to the port")
+                .with_details(["Another BACnet application or service is running; it may take datagrams meant for this host.".to_string()])
+        }
+        Err(e) => CheckResult::new(format!("Port {}", port), Status::Fail, format!("Could not bind: {}", e)),
+    };
+
+    // Two shared sockets at once, as the discovery tool and a sniffer would use
+    let sharing = match create_shared_socket(port).and_then(|first| create_shared_socket(port).map(|second| (first, second))) {
+        Ok(_) => CheckResult::new("Port sharing", Status::Pass, "Several sockets can share the port with SO_REUSEPORT")
+            .with_details(["Broadcasts reach every shared socket; unicast replies to the port may go to any one of them.".to_string()]),
+        Err(e) if owner.status == Status::Warn => {
+            CheckResult::new("Port sharing", Status::Fail, format!("The port is held exclusively: {}", e))
+                .with_details(["Close the application holding the port, or start it with port sharing enabled.".to_string()])
+        }
+        Err(e) => CheckResult::new("Port sharing", Status::Fail, format!("SO_REUSEPORT sharing does not work: {}", e)),
+    };
+    vec![owner, sharing]
+}
+
+/// Devices that answered directly from inside each interface's subnet. Interfaces
+/// nobody answered on are a warning.
+pub fn check_interfaces(interfaces: &[LocalInterface], responders: &[Responder]) -> Vec<CheckResult> {
+    if interfaces.is_empty() {
+        return vec![CheckResult::new("Interfaces", Status::Fail, "No IPv4 interface besides loopback")];
+    }
+    interfaces.iter().map(|iface| {
+        let local: Vec<&Responder> = responders.iter()
+            .filter(|r| r.via.is_none() && iface.contains(r.device.address.ip()))
+            .collect();
+        let name = format!("Who-Is on {}", iface.name);
+        let broadcast = iface.broadcast.map_or_else(|| "no broadcast address".to_string(), |b| format!("broadcast {}", b));
+        let details = local.iter().map(|r| format!("Device {} at {}", r.device.device_id, r.device.address));
+        if local.is_empty() {
+            CheckResult::new(name, Status::Warn, format!("No device answered in {} ({})", iface.cidr(), broadcast))
+        } else {
+            CheckResult::new(name, Status::Pass, format!("{} device(s) answered in {} ({})", local.len(), iface.cidr(), broadcast))
+                .with_details(details)
+        }
+    }).collect()
+}
+
+/// Device instances answering from more than one address
+pub fn check_duplicates(responders: &[Responder]) -> CheckResult {
+    let mut book = AddressBook::new();
+    let now = Instant::now();
+    for responder in responders {
+        book.record(responder.device.device_id, responder.device.address, now);
+    }
+    let duplicates: Vec<String> = book.conflicts().into_iter()
+        .filter(|event| matches!(event, AddressEvent::Duplicate { .. }))
+        .map(|event| event.to_string())
+        .collect();
+    if duplicates.is_empty() {
+        CheckResult::new("Duplicate device IDs", Status::Pass, format!("{} device instance(s), all unique", responders.len()))
+    } else {
+        CheckResult::new("Duplicate device IDs", Status::Fail, format!("{} duplicate instance(s)", duplicates.len()))
+            .with_details(duplicates.into_iter().chain(["Every device instance must be unique across the internetwork; renumber duplicates.".to_string()]))
+    }
+}
+
+/// Devices that answered directly although their address is outside every local
+/// subnet: the device or this host has the wrong IP address or mask. Replies relayed
+/// by a BBMD come from remote subnets and are not counted.
+pub fn check_subnets(interfaces: &[LocalInterface], responders: &[Responder]) -> CheckResult {
+    let mismatched: Vec<String> = responders.iter()
+        .filter(|r| r.via.is_none() && !interfaces.iter().any(|i| i.contains(r.device.address.ip())))
+        .map(|r| {
+            let local: Vec<String> = interfaces.iter().map(LocalInterface::cidr).collect();
+            format!("Device {} at {} is outside {}", r.device.device_id, r.device.address, local.join(", "))
+        })
+        .collect();
+    if mismatched.is_empty() {
+        CheckResult::new("IP/subnet match", Status::Pass, "Every directly answering device is in a local subnet")
+    } else {
+        CheckResult::new("IP/subnet match", Status::Warn, format!("{} device(s) answered from outside the local subnets", mismatched.len()))
+            .with_details(mismatched.into_iter().chain(["Check the IP address and subnet mask of these devices and of this host.".to_string()]))
+    }
+}
+
+/// BBMDs found by Read-BDT. A BBMD whose table does not list itself is a warning.
+pub fn check_bbmds(replies: &BTreeMap<SocketAddr, Option<BdtReply>>) -> CheckResult {
+    let mut details = Vec::new();
+    let mut bbmds = 0;
+    let mut incomplete = false;
+    let mut unsent = 0;
+    for (addr, reply) in replies {
+        if let Some(BdtReply::SendFailed(e)) = reply {
+            unsent += 1;
+            details.push(format!("Could not send a Read-BDT to {}: {}", addr, e));
+            continue;
+        }
+        let Some(BdtReply::Table(entries)) = reply else { continue };
+        bbmds += 1;
+        details.push(format!("BBMD {} with {} BDT entries", addr, entries.len()));
+        details.extend(entries.iter().map(|e| format!("  {} mask {}", e.address, e.mask)));
+        if !entries.is_empty() && !entries.iter().any(|e| e.address == *addr) {
+            incomplete = true;
+            details.push(format!("  The table of {} does not list the BBMD itself", addr));
+        }
+    }
+    let naks = replies.values().filter(|r| matches!(r, Some(BdtReply::Nak(_)))).count();
+    let silent = replies.values().filter(|r| r.is_none()).count();
+    let mut summary = format!("{} BBMD(s) among {} probed; {} refused, {} did not answer", bbmds, replies.len(), naks, silent);
+    if unsent > 0 {
+        summary.push_str(&format!(", {} could not be reached", unsent));
+    }
+    let status = if incomplete || unsent > 0 { Status::Warn } else { Status::Pass };
+    CheckResult::new("BBMDs", status, summary).with_details(details)
+}
+
+/// Broadcast storm and chatty-device warnings over the monitored period
+pub fn check_broadcasts(monitor: &BroadcastMonitor, now: Duration) -> CheckResult {
+    let warnings = monitor.warnings(now);
+    let status = if warnings.is_empty() { Status::Pass } else { Status::Warn };
+    let summary = if warnings.is_empty() {
+        format!("Broadcast rates are within limits ({:.1}/s)", monitor.total_rate(now))
+    } else {
+        format!("{} broadcast threshold(s) exceeded", warnings.len())
+    };
+    let mut details: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+    if !warnings.is_empty() {
+        details.push("A device flooding I-Am or Who-Is usually has a misconfigured discovery or COV setting; check the top sources.".to_string());
+    }
+    details.extend(monitor.lines(now).into_iter().filter(|l| !l.is_empty()));
+    CheckResult::new("Broadcast rates", status, summary).with_details(details)
+}
+
+/// Distinct addresses of the devices, and of the BBMDs that relayed them
+pub fn probe_targets(responders: &[Responder]) -> Vec<SocketAddr> {
+    let mut seen = HashSet::new();
+    responders.iter()
+        .flat_map(|r| [Some(r.device.address), r.via])
+        .flatten()
+        .filter(|addr| seen.insert(*addr))
+        .collect()
+}
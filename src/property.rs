@@ -0,0 +1,774 @@
+//! Decoding of application-tagged property values and ReadPropertyMultiple results.
+
+use bacnet_rs::object::{ObjectIdentifier, ObjectType};
+
+pub const ACKED_TRANSITIONS: u32 = 0;
+pub const ACTIVE_TEXT: u32 = 4;
+pub const ALL: u32 = 8;
+pub const APDU_TIMEOUT: u32 = 11;
+pub const APPLICATION_SOFTWARE_VERSION: u32 = 12;
+pub const NOTIFICATION_CLASS: u32 = 17;
+pub const COV_INCREMENT: u32 = 22;
+pub const DEADBAND: u32 = 25;
+pub const DESCRIPTION: u32 = 28;
+pub const EVENT_ENABLE: u32 = 35;
+pub const EVENT_STATE: u32 = 36;
+pub const FIRMWARE_REVISION: u32 = 44;
+pub const HIGH_LIMIT: u32 = 45;
+pub const INACTIVE_TEXT: u32 = 46;
+pub const LIMIT_ENABLE: u32 = 52;
+pub const LOCAL_DATE: u32 = 56;
+pub const LOCAL_TIME: u32 = 57;
+pub const LOCATION: u32 = 58;
+pub const LOW_LIMIT: u32 = 59;
+pub const MAX_MASTER: u32 = 64;
+pub const MAX_PRES_VALUE: u32 = 65;
+pub const MIN_PRES_VALUE: u32 = 69;
+pub const MODEL_NAME: u32 = 70;
+pub const NOTIFY_TYPE: u32 = 72;
+pub const NUMBER_OF_STATES: u32 = 74;
+pub const OBJECT_IDENTIFIER: u32 = 75;
+pub const OBJECT_LIST: u32 = 76;
+pub const OBJECT_NAME: u32 = 77;
+pub const OBJECT_TYPE: u32 = 79;
+pub const OPTIONAL: u32 = 80;
+pub const OUT_OF_SERVICE: u32 = 81;
+pub const POLARITY: u32 = 84;
+pub const PRESENT_VALUE: u32 = 85;
+pub const PRIORITY_ARRAY: u32 = 87;
+pub const PROTOCOL_OBJECT_TYPES_SUPPORTED: u32 = 96;
+pub const PROTOCOL_SERVICES_SUPPORTED: u32 = 97;
+pub const PROTOCOL_VERSION: u32 = 98;
+pub const RELIABILITY: u32 = 103;
+pub const RELINQUISH_DEFAULT: u32 = 104;
+pub const REQUIRED: u32 = 105;
+pub const RESOLUTION: u32 = 106;
+pub const STATE_TEXT: u32 = 110;
+pub const STATUS_FLAGS: u32 = 111;
+pub const SYSTEM_STATUS: u32 = 112;
+pub const TIME_DELAY: u32 = 113;
+pub const UNITS: u32 = 117;
+pub const UTC_OFFSET: u32 = 119;
+pub const PROTOCOL_REVISION: u32 = 139;
+pub const PROPERTY_LIST: u32 = 371;
+
+/// Device object properties read when a device is entered, in display order
+pub const DEVICE_PROPERTIES: &[u32] = &[
+    OBJECT_NAME,
+    MODEL_NAME,
+    FIRMWARE_REVISION,
+    APPLICATION_SOFTWARE_VERSION,
+    LOCATION,
+    DESCRIPTION,
+    PROTOCOL_VERSION,
+    PROTOCOL_REVISION,
+    SYSTEM_STATUS,
+    APDU_TIMEOUT,
+    MAX_MASTER,
+    UTC_OFFSET,
+    LOCAL_DATE,
+    LOCAL_TIME,
+    PROTOCOL_SERVICES_SUPPORTED,
+    PROTOCOL_OBJECT_TYPES_SUPPORTED,
+];
+
+/// A decoded property value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BacnetValue {
+    Null,
+    Boolean(bool),
+    Unsigned(u64),
+    Signed(i64),
+    Real(f32),
+    Double(f64),
+    OctetString(Vec<u8>),
+    CharacterString(String),
+    BitString(Vec<bool>),
+    Enumerated(u32),
+    /// 255 in any field means unspecified
+    Date { year: u16, month: u8, day: u8, weekday: u8 },
+    /// 255 in any field means unspecified
+    Time { hour: u8, minute: u8, second: u8, hundredths: u8 },
+    ObjectId { object_type: u16, instance: u32 },
+    /// Context-tagged primitive; its type depends on the enclosing construct
+    Context { tag: u8, data: Vec<u8> },
+    /// Values enclosed in an opening/closing tag pair
+    Constructed { tag: u8, values: Vec<BacnetValue> },
+    /// Several values for one property, e.g. an array or list
+    List(Vec<BacnetValue>),
+}
+
+/// Error class and code returned in place of a property value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PropertyError {
+    pub class: u32,
+    pub code: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyEntry {
+    pub id: u32,
+    pub array_index: Option<u32>,
+    pub value: Result<BacnetValue, PropertyError>,
+}
+
+/// One ReadAccessResult from a ReadPropertyMultiple-ACK.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectProperties {
+    pub object: ObjectIdentifier,
+    pub properties: Vec<PropertyEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TagKind {
+    Opening,
+    Closing,
+    /// Length of the content; for application booleans this is the value itself
+    Primitive(usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Tag {
+    number: u8,
+    context: bool,
+    kind: TagKind,
+}
+
+/// Decodes a tag header, returning it with the header length.
+fn decode_tag(data: &[u8]) -> Option<(Tag, usize)> {
+    let first = *data.first()?;
+    let context = first & 0x08 != 0;
+    let mut pos = 1;
+    let number = if first >> 4 == 0x0F {
+        pos += 1;
+        *data.get(1)?
+    } else {
+        first >> 4
+    };
+
+    let kind = match first & 0x07 {
+        6 if context => TagKind::Opening,
+        7 if context => TagKind::Closing,
+        5 => {
+            let len = *data.get(pos)?;
+            pos += 1;
+            let len = match len {
+                254 => {
+                    let bytes = data.get(pos..pos + 2)?;
+                    pos += 2;
+                    u16::from_be_bytes([bytes[0], bytes[1]]) as usize
+                }
+                255 => {
+                    let bytes = data.get(pos..pos + 4)?;
+                    pos += 4;
+                    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize
+                }
+                n => n as usize,
+            };
+            TagKind::Primitive(len)
+        }
+        n => TagKind::Primitive(n as usize),
+    };
+
+    Some((Tag { number, context, kind }, pos))
+}
+
+fn be_unsigned(bytes: &[u8]) -> Option<u64> {
+    if bytes.is_empty() || bytes.len() > 8 {
+        return None;
+    }
+    Some(bytes.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64))
+}
+
+fn be_signed(bytes: &[u8]) -> Option<i64> {
+    let raw = be_unsigned(bytes)?;
+    let shift = 64 - 8 * bytes.len() as u32;
+    Some(((raw << shift) as i64) >> shift)
+}
+
+pub fn decode_character_string(bytes: &[u8]) -> String {
+    let Some((&charset, text)) = bytes.split_first() else { return String::new() };
+    match charset {
+        // ISO 10646 (UCS-2)
+        4 => {
+            let units: Vec<u16> = text.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+            String::from_utf16_lossy(&units)
+        }
+        // ISO 8859-1
+        5 => text.iter().map(|&b| b as char).collect(),
+        _ => String::from_utf8_lossy(text).into_owned(),
+    }
+}
+
+fn decode_application(number: u8, content: &[u8], lvt: usize) -> Option<BacnetValue> {
+    let value = match number {
+        0 => BacnetValue::Null,
+        1 => BacnetValue::Boolean(lvt != 0),
+        2 => BacnetValue::Unsigned(be_unsigned(content)?),
+        3 => BacnetValue::Signed(be_signed(content)?),
+        4 => BacnetValue::Real(f32::from_be_bytes(content.try_into().ok()?)),
+        5 => BacnetValue::Double(f64::from_be_bytes(content.try_into().ok()?)),
+        6 => BacnetValue::OctetString(content.to_vec()),
+        7 => BacnetValue::CharacterString(decode_character_string(content)),
+        8 => {
+            let (&unused, bytes) = content.split_first()?;
+            let mut bits: Vec<bool> = bytes.iter()
+                .flat_map(|b| (0..8).rev().map(move |i| b & (1 << i) != 0))
+                .collect();
+            bits.truncate(bits.len().saturating_sub(unused as usize));
+            BacnetValue::BitString(bits)
+        }
+        9 => BacnetValue::Enumerated(be_unsigned(content)? as u32),
+        10 => match content {
+            &[year, month, day, weekday] => BacnetValue::Date {
+                year: if year == 255 { 255 } else { 1900 + year as u16 },
+                month,
+                day,
+                weekday,
+            },
+            _ => return None,
+        },
+        11 => match content {
+            &[hour, minute, second, hundredths] => BacnetValue::Time { hour, minute, second, hundredths },
+            _ => return None,
+        },
+        12 => {
+            let raw = u32::from_be_bytes(content.try_into().ok()?);
+            BacnetValue::ObjectId { object_type: (raw >> 22) as u16, instance: raw & 0x3FFFFF }
+        }
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Deepest nesting of constructed values decoded; anything deeper is rejected
+/// rather than recursed into.
+const MAX_NESTING: usize = 32;
+
+/// Decodes one tagged element, returning it with the number of bytes consumed.
+pub fn decode_value(data: &[u8]) -> Option<(BacnetValue, usize)> {
+    decode_nested(data, 0)
+}
+
+fn decode_nested(data: &[u8], depth: usize) -> Option<(BacnetValue, usize)> {
+    let (tag, header) = decode_tag(data)?;
+    match tag.kind {
+        TagKind::Closing => None,
+        TagKind::Opening => {
+            let (values, consumed) = decode_until_closing(&data[header..], tag.number, depth + 1)?;
+            Some((BacnetValue::Constructed { tag: tag.number, values }, header + consumed))
+        }
+        TagKind::Primitive(lvt) => {
+            let len = if !tag.context && tag.number == 1 { 0 } else { lvt };
+            let content = data.get(header..header + len)?;
+            let value = if tag.context {
+                BacnetValue::Context { tag: tag.number, data: content.to_vec() }
+            } else {
+                decode_application(tag.number, content, lvt)?
+            };
+            Some((value, header + len))
+        }
+    }
+}
+
+/// Decodes every element up to and including the closing tag `number`, `depth`
+/// constructed values deep.
+fn decode_until_closing(data: &[u8], number: u8, depth: usize) -> Option<(Vec<BacnetValue>, usize)> {
+    if depth > MAX_NESTING {
+        return None;
+    }
+    let mut values = Vec::new();
+    let mut pos = 0;
+    loop {
+        let (tag, header) = decode_tag(&data[pos..])?;
+        if tag.kind == TagKind::Closing {
+            return (tag.number == number).then_some((values, pos + header));
+        }
+        let (value, consumed) = decode_nested(&data[pos..], depth)?;
+        values.push(value);
+        pos += consumed;
+    }
+}
+
+/// Decodes a context-tagged unsigned at the start of `data` if it carries tag `number`.
+fn decode_context_unsigned(data: &[u8], number: u8) -> Option<(u64, usize)> {
+    let (tag, header) = decode_tag(data)?;
+    match tag.kind {
+        TagKind::Primitive(len) if tag.context && tag.number == number => {
+            Some((be_unsigned(data.get(header..header + len)?)?, header + len))
+        }
+        _ => None,
+    }
+}
+
+fn expect_tag(data: &[u8], number: u8, kind: TagKind) -> Option<usize> {
+    let (tag, header) = decode_tag(data)?;
+    (tag.context && tag.number == number && tag.kind == kind).then_some(header)
+}
+
+/// Parses the service data of a ReadPropertyMultiple-ACK.
+pub fn parse_rpm_ack(data: &[u8]) -> Option<Vec<ObjectProperties>> {
+    let mut results = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let (raw, consumed) = decode_context_unsigned(&data[pos..], 0)?;
+        pos += consumed;
+        let object_type = ObjectType::try_from((raw >> 22) as u16).ok()?;
+        let object = ObjectIdentifier::new(object_type, (raw & 0x3FFFFF) as u32);
+
+        pos += expect_tag(&data[pos..], 1, TagKind::Opening)?;
+        let mut properties = Vec::new();
+        while let Some((tag, header)) = decode_tag(&data[pos..]) {
+            if tag.context && tag.number == 1 && tag.kind == TagKind::Closing {
+                pos += header;
+                break;
+            }
+
+            let (id, consumed) = decode_context_unsigned(&data[pos..], 2)?;
+            pos += consumed;
+            let array_index = match decode_context_unsigned(&data[pos..], 3) {
+                Some((index, consumed)) => {
+                    pos += consumed;
+                    Some(index as u32)
+                }
+                None => None,
+            };
+
+            let (tag, header) = decode_tag(&data[pos..])?;
+            pos += header;
+            let value = match (tag.number, tag.kind) {
+                (4, TagKind::Opening) => {
+                    let (mut values, consumed) = decode_until_closing(&data[pos..], 4, 1)?;
+                    pos += consumed;
+                    if values.len() == 1 { Ok(values.remove(0)) } else { Ok(BacnetValue::List(values)) }
+                }
+                (5, TagKind::Opening) => {
+                    let (values, consumed) = decode_until_closing(&data[pos..], 5, 1)?;
+                    pos += consumed;
+                    match values.as_slice() {
+                        [BacnetValue::Enumerated(class), BacnetValue::Enumerated(code)] => {
+                            Err(PropertyError { class: *class, code: *code })
+                        }
+                        _ => return None,
+                    }
+                }
+                _ => return None,
+            };
+            properties.push(PropertyEntry { id: id as u32, array_index, value });
+        }
+        results.push(ObjectProperties { object, properties });
+    }
+    Some(results)
+}
+
+/// Parses the service data of a ReadProperty-ACK into the property entry it carries.
+pub fn parse_read_property_ack(data: &[u8]) -> Option<(ObjectIdentifier, PropertyEntry)> {
+    let (raw, mut pos) = decode_context_unsigned(data, 0)?;
+    let object_type = ObjectType::try_from((raw >> 22) as u16).ok()?;
+    let object = ObjectIdentifier::new(object_type, (raw & 0x3FFFFF) as u32);
+
+    let (id, consumed) = decode_context_unsigned(&data[pos..], 1)?;
+    pos += consumed;
+    let array_index = match decode_context_unsigned(&data[pos..], 2) {
+        Some((index, consumed)) => {
+            pos += consumed;
+            Some(index as u32)
+        }
+        None => None,
+    };
+
+    pos += expect_tag(&data[pos..], 3, TagKind::Opening)?;
+    let (mut values, _) = decode_until_closing(&data[pos..], 3, 1)?;
+    let value = if values.len() == 1 { values.remove(0) } else { BacnetValue::List(values) };
+    Some((object, PropertyEntry { id: id as u32, array_index, value: Ok(value) }))
+}
+
+/// Decodes the error class and code of an Error PDU. Some services, such as
+/// ReadPropertyMultiple, wrap them in context tag 0.
+pub fn decode_error(data: &[u8]) -> Option<PropertyError> {
+    let (first, consumed) = decode_value(data)?;
+    let values = match first {
+        BacnetValue::Constructed { tag: 0, values } => values,
+        first => vec![first, decode_value(&data[consumed..])?.0],
+    };
+    match values.as_slice() {
+        [BacnetValue::Enumerated(class), BacnetValue::Enumerated(code), ..] => Some(PropertyError { class: *class, code: *code }),
+        _ => None,
+    }
+}
+
+/// Looks up a property in a read result, ignoring properties that returned an error.
+pub fn find(properties: &[PropertyEntry], id: u32) -> Option<&BacnetValue> {
+    properties.iter()
+        .find(|p| p.id == id)
+        .and_then(|p| p.value.as_ref().ok())
+}
+
+/// Standard property identifiers (Clause 21, BACnetPropertyIdentifier)
+const PROPERTY_NAMES: &[(u32, &str)] = &[
+    (0, "Acked_Transitions"), (1, "Ack_Required"), (2, "Action"), (3, "Action_Text"),
+    (4, "Active_Text"), (5, "Active_VT_Sessions"), (6, "Alarm_Value"), (7, "Alarm_Values"),
+    (8, "All"), (9, "All_Writes_Successful"), (10, "APDU_Segment_Timeout"), (11, "APDU_Timeout"),
+    (12, "Application_Software_Version"), (13, "Archive"), (14, "Bias"), (15, "Change_Of_State_Count"),
+    (16, "Change_Of_State_Time"), (17, "Notification_Class"), (19, "Controlled_Variable_Reference"),
+    (20, "Controlled_Variable_Units"), (21, "Controlled_Variable_Value"), (22, "COV_Increment"),
+    (23, "Date_List"), (24, "Daylight_Savings_Status"), (25, "Deadband"), (26, "Derivative_Constant"),
+    (27, "Derivative_Constant_Units"), (28, "Description"), (29, "Description_Of_Halt"),
+    (30, "Device_Address_Binding"), (31, "Device_Type"), (32, "Effective_Period"),
+    (33, "Elapsed_Active_Time"), (34, "Error_Limit"), (35, "Event_Enable"), (36, "Event_State"),
+    (37, "Event_Type"), (38, "Exception_Schedule"), (39, "Fault_Values"), (40, "Feedback_Value"),
+    (41, "File_Access_Method"), (42, "File_Size"), (43, "File_Type"), (44, "Firmware_Revision"),
+    (45, "High_Limit"), (46, "Inactive_Text"), (47, "In_Process"), (48, "Instance_Of"),
+    (49, "Integral_Constant"), (50, "Integral_Constant_Units"), (52, "Limit_Enable"),
+    (53, "List_Of_Group_Members"), (54, "List_Of_Object_Property_References"), (56, "Local_Date"),
+    (57, "Local_Time"), (58, "Location"), (59, "Low_Limit"), (60, "Manipulated_Variable_Reference"),
+    (61, "Maximum_Output"), (62, "Max_APDU_Length_Accepted"), (63, "Max_Info_Frames"), (64, "Max_Master"),
+    (65, "Max_Pres_Value"), (66, "Minimum_Off_Time"), (67, "Minimum_On_Time"), (68, "Minimum_Output"),
+    (69, "Min_Pres_Value"), (70, "Model_Name"), (71, "Modification_Date"), (72, "Notify_Type"),
+    (73, "Number_Of_APDU_Retries"), (74, "Number_Of_States"), (75, "Object_Identifier"),
+    (76, "Object_List"), (77, "Object_Name"), (78, "Object_Property_Reference"), (79, "Object_Type"),
+    (80, "Optional"), (81, "Out_Of_Service"), (82, "Output_Units"), (83, "Event_Parameters"),
+    (84, "Polarity"), (85, "Present_Value"), (86, "Priority"), (87, "Priority_Array"),
+    (88, "Priority_For_Writing"), (89, "Process_Identifier"), (90, "Program_Change"),
+    (91, "Program_Location"), (92, "Program_State"), (93, "Proportional_Constant"),
+    (94, "Proportional_Constant_Units"), (96, "Protocol_Object_Types_Supported"),
+    (97, "Protocol_Services_Supported"), (98, "Protocol_Version"), (99, "Read_Only"),
+    (100, "Reason_For_Halt"), (102, "Recipient_List"), (103, "Reliability"), (104, "Relinquish_Default"),
+    (105, "Required"), (106, "Resolution"), (107, "Segmentation_Supported"), (108, "Setpoint"),
+    (109, "Setpoint_Reference"), (110, "State_Text"), (111, "Status_Flags"), (112, "System_Status"),
+    (113, "Time_Delay"), (114, "Time_Of_Active_Time_Reset"), (115, "Time_Of_State_Count_Reset"),
+    (116, "Time_Synchronization_Recipients"), (117, "Units"), (118, "Update_Interval"), (119, "UTC_Offset"),
+    (120, "Vendor_Identifier"), (121, "Vendor_Name"), (122, "VT_Classes_Supported"), (123, "Weekly_Schedule"),
+    (124, "Attempted_Samples"), (125, "Average_Value"), (126, "Buffer_Size"), (127, "Client_COV_Increment"),
+    (128, "COV_Resubscription_Interval"), (130, "Event_Time_Stamps"), (131, "Log_Buffer"),
+    (132, "Log_Device_Object_Property"), (133, "Enable"), (134, "Log_Interval"), (135, "Maximum_Value"),
+    (136, "Minimum_Value"), (137, "Notification_Threshold"), (139, "Protocol_Revision"),
+    (140, "Records_Since_Notification"), (141, "Record_Count"), (142, "Start_Time"), (143, "Stop_Time"),
+    (144, "Stop_When_Full"), (145, "Total_Record_Count"), (146, "Valid_Samples"), (147, "Window_Interval"),
+    (148, "Window_Samples"), (149, "Maximum_Value_Timestamp"), (150, "Minimum_Value_Timestamp"),
+    (151, "Variance_Value"), (152, "Active_COV_Subscriptions"), (153, "Backup_Failure_Timeout"),
+    (154, "Configuration_Files"), (155, "Database_Revision"), (156, "Direct_Reading"),
+    (157, "Last_Restore_Time"), (158, "Maintenance_Required"), (159, "Member_Of"), (160, "Mode"),
+    (161, "Operation_Expected"), (162, "Setting"), (163, "Silenced"), (164, "Tracking_Value"),
+    (165, "Zone_Members"), (166, "Life_Safety_Alarm_Values"), (167, "Max_Segments_Accepted"),
+    (168, "Profile_Name"), (169, "Auto_Slave_Discovery"), (170, "Manual_Slave_Address_Binding"),
+    (171, "Slave_Address_Binding"), (172, "Slave_Proxy_Enable"), (173, "Last_Notify_Record"),
+    (174, "Schedule_Default"), (175, "Accepted_Modes"), (176, "Adjust_Value"), (177, "Count"),
+    (178, "Count_Before_Change"), (179, "Count_Change_Time"), (180, "COV_Period"), (181, "Input_Reference"),
+    (182, "Limit_Monitoring_Interval"), (183, "Logging_Object"), (184, "Logging_Record"), (185, "Prescale"),
+    (186, "Pulse_Rate"), (187, "Scale"), (188, "Scale_Factor"), (189, "Update_Time"),
+    (190, "Value_Before_Change"), (191, "Value_Set"), (192, "Value_Change_Time"), (193, "Align_Intervals"),
+    (195, "Interval_Offset"), (196, "Last_Restart_Reason"), (197, "Logging_Type"),
+    (202, "Restart_Notification_Recipients"), (203, "Time_Of_Device_Restart"),
+    (204, "Time_Synchronization_Interval"), (205, "Trigger"), (206, "UTC_Time_Synchronization_Recipients"),
+    (207, "Node_Subtype"), (208, "Node_Type"), (209, "Structured_Object_List"),
+    (210, "Subordinate_Annotations"), (211, "Subordinate_List"), (351, "Event_Message_Texts"),
+    (352, "Event_Message_Texts_Config"), (353, "Event_Detection_Enable"), (354, "Event_Algorithm_Inhibit"),
+    (355, "Event_Algorithm_Inhibit_Ref"), (356, "Time_Delay_Normal"), (357, "Reliability_Evaluation_Inhibit"),
+    (358, "Fault_Parameters"), (359, "Fault_Type"), (360, "Local_Forwarding_Only"),
+    (361, "Process_Identifier_Filter"), (362, "Subscribed_Recipients"), (363, "Port_Filter"),
+    (364, "Authorization_Exemptions"), (365, "Allow_Group_Delay_Inhibit"), (366, "Channel_Number"),
+    (367, "Control_Groups"), (368, "Execution_Delay"), (369, "Last_Priority"), (370, "Write_Status"),
+    (371, "Property_List"), (372, "Serial_Number"), (399, "APDU_Length"), (400, "IP_Address"),
+    (401, "IP_Default_Gateway"), (402, "IP_DHCP_Enable"), (403, "IP_DHCP_Lease_Time"),
+    (404, "IP_DHCP_Lease_Time_Remaining"), (405, "IP_DHCP_Server"), (406, "IP_DNS_Server"),
+    (407, "BACnet_IP_Global_Address"), (408, "BACnet_IP_Mode"), (409, "BACnet_IP_Multicast_Address"),
+    (410, "BACnet_IP_NAT_Traversal"), (411, "IP_Subnet_Mask"), (412, "BACnet_IP_UDP_Port"),
+    (413, "BBMD_Accept_FD_Registrations"), (414, "BBMD_Broadcast_Distribution_Table"),
+    (415, "BBMD_Foreign_Device_Table"), (416, "Changes_Pending"), (417, "Command"),
+    (418, "FD_BBMD_Address"), (419, "FD_Subscription_Lifetime"), (420, "Link_Speed"), (421, "Link_Speeds"),
+    (422, "Link_Speed_Autonegotiate"), (423, "MAC_Address"), (424, "Network_Interface_Name"),
+    (425, "Network_Number"), (426, "Network_Number_Quality"), (427, "Network_Type"), (428, "Routing_Table"),
+    (429, "Virtual_MAC_Address_Table"), (430, "Command_Time_Array"), (431, "Current_Command_Priority"),
+    (432, "Last_Command_Time"), (433, "Value_Source"), (434, "Value_Source_Array"),
+];
+
+pub fn property_name(id: u32) -> String {
+    match PROPERTY_NAMES.iter().find(|(n, _)| *n == id) {
+        Some((_, name)) => name.to_string(),
+        // 512-4194303 are reserved for vendors
+        None if (512..4_194_304).contains(&id) => format!("Proprietary_{}", id),
+        None => format!("Property_{}", id),
+    }
+}
+
+/// Property identifier for a name as written in the standard (`Present_Value`),
+/// in lower case or with dashes (`present-value`), or as a number
+pub fn property_id(name: &str) -> Option<u32> {
+    if let Ok(id) = name.parse() {
+        return Some(id);
+    }
+    let wanted = name.replace('-', "_");
+    PROPERTY_NAMES.iter().find(|(_, n)| n.eq_ignore_ascii_case(&wanted)).map(|(id, _)| *id)
+}
+
+/// Properties an object of this type is expected to have, used when a device
+/// supports neither the ALL property nor Property_List.
+pub fn standard_properties(object_type: ObjectType) -> Vec<u32> {
+    let mut props = vec![OBJECT_IDENTIFIER, OBJECT_NAME, OBJECT_TYPE];
+    match object_type {
+        ObjectType::Device => {
+            props.extend_from_slice(&[120, 121, 107, 62]);
+            props.extend(DEVICE_PROPERTIES.iter().filter(|p| **p != OBJECT_NAME));
+            return props;
+        }
+        ObjectType::AnalogInput | ObjectType::AnalogOutput | ObjectType::AnalogValue => {
+            props.extend_from_slice(&[
+                PRESENT_VALUE, DESCRIPTION, STATUS_FLAGS, EVENT_STATE, RELIABILITY, OUT_OF_SERVICE, UNITS,
+                COV_INCREMENT, MIN_PRES_VALUE, MAX_PRES_VALUE, RESOLUTION, HIGH_LIMIT, LOW_LIMIT, DEADBAND,
+                LIMIT_ENABLE, EVENT_ENABLE, NOTIFICATION_CLASS, TIME_DELAY,
+            ]);
+        }
+        ObjectType::BinaryInput | ObjectType::BinaryOutput | ObjectType::BinaryValue => {
+            props.extend_from_slice(&[
+                PRESENT_VALUE, DESCRIPTION, STATUS_FLAGS, EVENT_STATE, RELIABILITY, OUT_OF_SERVICE,
+                POLARITY, INACTIVE_TEXT, ACTIVE_TEXT,
+            ]);
+        }
+        ObjectType::MultiStateInput | ObjectType::MultiStateOutput | ObjectType::MultiStateValue => {
+            props.extend_from_slice(&[
+                PRESENT_VALUE, DESCRIPTION, STATUS_FLAGS, EVENT_STATE, RELIABILITY, OUT_OF_SERVICE,
+                NUMBER_OF_STATES, STATE_TEXT,
+            ]);
+        }
+        _ => {
+            props.extend_from_slice(&[PRESENT_VALUE, DESCRIPTION, STATUS_FLAGS, EVENT_STATE, RELIABILITY, OUT_OF_SERVICE]);
+        }
+    }
+    if matches!(
+        object_type,
+        ObjectType::AnalogOutput | ObjectType::AnalogValue | ObjectType::BinaryOutput | ObjectType::BinaryValue
+            | ObjectType::MultiStateOutput | ObjectType::MultiStateValue
+    ) {
+        props.extend_from_slice(&[PRIORITY_ARRAY, RELINQUISH_DEFAULT]);
+    }
+    props
+}
+
+/// BACnetServicesSupported bit positions (Clause 21)
+pub fn service_name(bit: usize) -> Option<&'static str> {
+    const NAMES: &[&str] = &[
+        "AcknowledgeAlarm", "ConfirmedCOVNotification", "ConfirmedEventNotification", "GetAlarmSummary",
+        "GetEnrollmentSummary", "SubscribeCOV", "AtomicReadFile", "AtomicWriteFile",
+        "AddListElement", "RemoveListElement", "CreateObject", "DeleteObject",
+        "ReadProperty", "ReadPropertyConditional", "ReadPropertyMultiple", "WriteProperty",
+        "WritePropertyMultiple", "DeviceCommunicationControl", "ConfirmedPrivateTransfer", "ConfirmedTextMessage",
+        "ReinitializeDevice", "VtOpen", "VtClose", "VtData",
+        "Authenticate", "RequestKey", "I-Am", "I-Have",
+        "UnconfirmedCOVNotification", "UnconfirmedEventNotification", "UnconfirmedPrivateTransfer", "UnconfirmedTextMessage",
+        "TimeSynchronization", "Who-Has", "Who-Is", "ReadRange",
+        "UtcTimeSynchronization", "LifeSafetyOperation", "SubscribeCOVProperty", "GetEventInformation",
+        "WriteGroup", "SubscribeCOVPropertyMultiple", "ConfirmedCOVNotificationMultiple", "UnconfirmedCOVNotificationMultiple",
+        "ConfirmedAuditNotification", "AuditLogQuery", "UnconfirmedAuditNotification", "Who-Am-I",
+        "You-Are",
+    ];
+    NAMES.get(bit).copied()
+}
+
+/// Common short form of an object type, e.g. "AI" for Analog Input
+pub fn object_type_abbreviation(object_type: ObjectType) -> Option<&'static str> {
+    match object_type {
+        ObjectType::AnalogInput => Some("AI"),
+        ObjectType::AnalogOutput => Some("AO"),
+        ObjectType::AnalogValue => Some("AV"),
+        ObjectType::BinaryInput => Some("BI"),
+        ObjectType::BinaryOutput => Some("BO"),
+        ObjectType::BinaryValue => Some("BV"),
+        ObjectType::MultiStateInput => Some("MSI"),
+        ObjectType::MultiStateOutput => Some("MSO"),
+        ObjectType::MultiStateValue => Some("MSV"),
+        ObjectType::Device => Some("DEV"),
+        _ => None,
+    }
+}
+
+/// BACnetSegmentation
+pub fn segmentation_name(value: u32) -> &'static str {
+    match value {
+        0 => "Both",
+        1 => "Transmit",
+        2 => "Receive",
+        3 => "None",
+        _ => "Unknown",
+    }
+}
+
+/// BACnetDeviceStatus
+pub fn system_status_name(value: u32) -> Option<&'static str> {
+    match value {
+        0 => Some("Operational"),
+        1 => Some("Operational-Read-Only"),
+        2 => Some("Download-Required"),
+        3 => Some("Download-In-Progress"),
+        4 => Some("Non-Operational"),
+        5 => Some("Backup-In-Progress"),
+        _ => None,
+    }
+}
+
+/// BACnetReliability
+pub fn reliability_name(value: u32) -> Option<&'static str> {
+    const NAMES: &[&str] = &[
+        "No-Fault-Detected", "No-Sensor", "Over-Range", "Under-Range", "Open-Loop", "Shorted-Loop",
+        "No-Output", "Unreliable-Other", "Process-Error", "Multi-State-Fault", "Configuration-Error",
+        "Member-Fault", "Communication-Failure", "Tripped", "Lamp-Failure", "Activation-Failure",
+        "Renew-DHCP-Failure", "Renew-FD-Registration-Failure", "Restart-Auto-Negotiation-Failure",
+        "Restart-Failure", "Proprietary-Command-Failure", "Faults-Listed", "Referenced-Object-Fault",
+    ];
+    NAMES.get(value as usize).copied()
+}
+
+/// BACnetEventState
+pub fn event_state_name(value: u32) -> Option<&'static str> {
+    const NAMES: &[&str] = &["Normal", "Fault", "Offnormal", "High-Limit", "Low-Limit", "Life-Safety-Alarm"];
+    NAMES.get(value as usize).copied()
+}
+
+/// Present_Value interpreted for the object type, so it can be trended, sorted
+/// and exported without re-parsing display text.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum PresentValue {
+    /// Not read yet, or nothing usable came back
+    #[default]
+    Unknown,
+    Analog(f64),
+    /// Active (true) or inactive
+    Binary(bool),
+    /// 1-based state of a multistate object
+    Multistate(u32),
+    Unsigned(u64),
+    Signed(i64),
+    Enumerated(u32),
+    Text(String),
+    Other(BacnetValue),
+}
+
+impl PresentValue {
+    pub fn from_value(object_type: ObjectType, value: &BacnetValue) -> Self {
+        let binary = matches!(object_type, ObjectType::BinaryInput | ObjectType::BinaryOutput | ObjectType::BinaryValue);
+        let multistate = matches!(
+            object_type,
+            ObjectType::MultiStateInput | ObjectType::MultiStateOutput | ObjectType::MultiStateValue
+        );
+        match value {
+            BacnetValue::Real(r) => PresentValue::Analog(*r as f64),
+            BacnetValue::Double(d) => PresentValue::Analog(*d),
+            BacnetValue::Boolean(b) => PresentValue::Binary(*b),
+            BacnetValue::Enumerated(e) if binary => PresentValue::Binary(*e != 0),
+            BacnetValue::Unsigned(u) if multistate => PresentValue::Multistate(*u as u32),
+            BacnetValue::Unsigned(u) => PresentValue::Unsigned(*u),
+            BacnetValue::Signed(i) => PresentValue::Signed(*i),
+            BacnetValue::Enumerated(e) => PresentValue::Enumerated(*e),
+            BacnetValue::CharacterString(s) => PresentValue::Text(s.clone()),
+            other => PresentValue::Other(other.clone()),
+        }
+    }
+
+    /// Numeric form for trending and sorting; binary values count as 0/1.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            PresentValue::Analog(v) => Some(*v),
+            PresentValue::Binary(b) => Some(if *b { 1.0 } else { 0.0 }),
+            PresentValue::Multistate(n) => Some(*n as f64),
+            PresentValue::Unsigned(u) => Some(*u as f64),
+            PresentValue::Signed(i) => Some(*i as f64),
+            PresentValue::Enumerated(e) => Some(*e as f64),
+            _ => None,
+        }
+    }
+}
+
+/// Active_Text/Inactive_Text of binary objects and State_Text of multistate objects.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StateTexts {
+    pub inactive: Option<String>,
+    pub active: Option<String>,
+    /// Text for state 1 first
+    pub states: Vec<String>,
+}
+
+/// Properties holding the state texts for this object type, if it has any
+pub fn state_text_properties(object_type: ObjectType) -> &'static [u32] {
+    match object_type {
+        ObjectType::BinaryInput | ObjectType::BinaryOutput | ObjectType::BinaryValue => &[INACTIVE_TEXT, ACTIVE_TEXT],
+        ObjectType::MultiStateInput | ObjectType::MultiStateOutput | ObjectType::MultiStateValue => &[STATE_TEXT],
+        _ => &[],
+    }
+}
+
+/// True for object types with a Units property
+pub fn has_units(object_type: ObjectType) -> bool {
+    matches!(
+        object_type,
+        ObjectType::AnalogInput | ObjectType::AnalogOutput | ObjectType::AnalogValue
+            | ObjectType::Accumulator | ObjectType::PulseConverter | ObjectType::Loop
+    )
+}
+
+/// BACnetStatusFlags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StatusFlags {
+    pub in_alarm: bool,
+    pub fault: bool,
+    pub overridden: bool,
+    pub out_of_service: bool,
+}
+
+impl StatusFlags {
+    pub fn from_value(value: &BacnetValue) -> Option<Self> {
+        match value {
+            BacnetValue::BitString(bits) => {
+                let bit = |i: usize| bits.get(i).copied().unwrap_or(false);
+                Some(Self { in_alarm: bit(0), fault: bit(1), overridden: bit(2), out_of_service: bit(3) })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_normal(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// BACnetStatusFlags bit names
+pub const STATUS_FLAG_NAMES: [&str; 4] = ["In-Alarm", "Fault", "Overridden", "Out-Of-Service"];
+
+/// BACnetEventTransitionBits, used by Event_Enable and Acked_Transitions
+pub const EVENT_TRANSITION_NAMES: [&str; 3] = ["To-Offnormal", "To-Fault", "To-Normal"];
+
+/// BACnetLimitEnable bit names
+pub const LIMIT_ENABLE_NAMES: [&str; 2] = ["Low-Limit", "High-Limit"];
+
+/// BACnetEngineeringUnits, using the names bacnet-rs knows
+pub fn units_name(value: u32) -> Option<String> {
+    let mut encoded = vec![0x94];
+    encoded.extend_from_slice(&value.to_be_bytes());
+    bacnet_rs::property::decode_units(&encoded)
+        .map(|(name, _)| name)
+        .filter(|name| !name.starts_with("unknown"))
+}
+
+/// Error class (Clause 18)
+pub fn error_class_name(class: u32) -> Option<&'static str> {
+    const NAMES: &[&str] = &["Device", "Object", "Property", "Resources", "Security", "Services", "VT", "Communication"];
+    NAMES.get(class as usize).copied()
+}
+
+/// Common error codes (Clause 18)
+pub fn error_code_name(code: u32) -> Option<&'static str> {
+    match code {
+        0 => Some("Other"),
+        2 => Some("Configuration-In-Progress"),
+        3 => Some("Device-Busy"),
+        9 => Some("Invalid-Data-Type"),
+        25 => Some("Operational-Problem"),
+        27 => Some("Read-Access-Denied"),
+        31 => Some("Unknown-Object"),
+        32 => Some("Unknown-Property"),
+        37 => Some("Value-Out-Of-Range"),
+        40 => Some("Write-Access-Denied"),
+        42 => Some("Invalid-Array-Index"),
+        50 => Some("Property-Is-Not-An-Array"),
+        _ => None,
+    }
+}
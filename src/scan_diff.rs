@@ -0,0 +1,244 @@
+//! Comparison of two saved scans, e.g. before and after a controller swap or a
+//! firmware update.
+//!
+//! Devices are matched by device ID and points by object type and instance.
+//! Values that one of the scans did not read (an empty name, units not yet
+//! polled, firmware of a device that was never opened) are not reported as
+//! changes, and points are only compared when both scans discovered the points
+//! of the device.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use crate::export::{ExportDevice, ExportObject, ScanExport};
+use crate::site_db::SiteDb;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanDiff {
+    /// Unix time of the older scan
+    pub old_generated_at: u64,
+    /// Unix time of the newer scan
+    pub new_generated_at: u64,
+    pub added_devices: Vec<DeviceSummary>,
+    pub removed_devices: Vec<DeviceSummary>,
+    /// Devices in both scans with at least one change, ordered by device ID
+    pub changed_devices: Vec<DeviceChanges>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceSummary {
+    pub device_id: u32,
+    pub name: Option<String>,
+    pub address: String,
+    pub vendor_name: String,
+    pub objects: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceChanges {
+    pub device_id: u32,
+    /// Name in the newer scan, or the older one if the newer did not read it
+    pub name: Option<String>,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    Address { old: String, new: String },
+    DeviceName { old: String, new: String },
+    Vendor { old: String, new: String },
+    FirmwareRevision { old: String, new: String },
+    ApplicationSoftwareVersion { old: String, new: String },
+    ObjectAdded { object_type: String, instance: u32, name: String },
+    ObjectRemoved { object_type: String, instance: u32, name: String },
+    ObjectRenamed { object_type: String, instance: u32, old: String, new: String },
+    UnitsChanged { object_type: String, instance: u32, old: String, new: String },
+}
+
+impl ScanDiff {
+    pub fn new(old: &ScanExport, new: &ScanExport) -> Self {
+        let old_devices: BTreeMap<u32, &ExportDevice> = old.devices.iter().map(|d| (d.device_id, d)).collect();
+        let new_devices: BTreeMap<u32, &ExportDevice> = new.devices.iter().map(|d| (d.device_id, d)).collect();
+
+        let added_devices = new_devices.iter()
+            .filter(|(id, _)| !old_devices.contains_key(id))
+            .map(|(_, d)| DeviceSummary::new(d))
+            .collect();
+        let removed_devices = old_devices.iter()
+            .filter(|(id, _)| !new_devices.contains_key(id))
+            .map(|(_, d)| DeviceSummary::new(d))
+            .collect();
+        let changed_devices = new_devices.iter()
+            .filter_map(|(id, new)| {
+                let old = old_devices.get(id)?;
+                let changes = device_changes(old, new);
+                (!changes.is_empty()).then(|| DeviceChanges {
+                    device_id: *id,
+                    name: new.name.clone().or_else(|| old.name.clone()),
+                    changes,
+                })
+            })
+            .collect();
+
+        Self { old_generated_at: old.generated_at, new_generated_at: new.generated_at, added_devices, removed_devices, changed_devices }
+    }
+
+    /// True when the scans match
+    pub fn is_empty(&self) -> bool {
+        self.added_devices.is_empty() && self.removed_devices.is_empty() && self.changed_devices.is_empty()
+    }
+
+    /// The report as lines of text, as printed by `scan-diff` and shown in the TUI
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for device in &self.added_devices {
+            lines.push(format!("+ {}", device.describe()));
+        }
+        for device in &self.removed_devices {
+            lines.push(format!("- {}", device.describe()));
+        }
+        for device in &self.changed_devices {
+            lines.push(format!("~ {}", device_label(device.device_id, &device.name)));
+            lines.extend(device.changes.iter().map(|c| format!("    {}", c.describe())));
+        }
+        lines.push(if self.is_empty() {
+            "No differences.".to_string()
+        } else {
+            format!(
+                "{} device(s) added, {} removed, {} changed.",
+                self.added_devices.len(), self.removed_devices.len(), self.changed_devices.len()
+            )
+        });
+        lines
+    }
+
+    pub fn to_text(&self) -> String {
+        self.lines().iter().map(|line| format!("{}\n", line)).collect()
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+impl DeviceSummary {
+    fn new(device: &ExportDevice) -> Self {
+        Self {
+            device_id: device.device_id,
+            name: device.name.clone(),
+            address: device.address.clone(),
+            vendor_name: device.vendor_name.clone(),
+            objects: device.objects.len(),
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("{} at {} ({}, {} objects)", device_label(self.device_id, &self.name), self.address, self.vendor_name, self.objects)
+    }
+}
+
+impl Change {
+    pub fn describe(&self) -> String {
+        match self {
+            Change::Address { old, new } => format!("address {} -> {}", old, new),
+            Change::DeviceName { old, new } => format!("renamed \"{}\" -> \"{}\"", old, new),
+            Change::Vendor { old, new } => format!("vendor {} -> {}", old, new),
+            Change::FirmwareRevision { old, new } => format!("firmware {} -> {}", old, new),
+            Change::ApplicationSoftwareVersion { old, new } => format!("application software {} -> {}", old, new),
+            Change::ObjectAdded { object_type, instance, name } => format!("+ {} {} \"{}\"", object_type, instance, name),
+            Change::ObjectRemoved { object_type, instance, name } => format!("- {} {} \"{}\"", object_type, instance, name),
+            Change::ObjectRenamed { object_type, instance, old, new } => {
+                format!("{} {} renamed \"{}\" -> \"{}\"", object_type, instance, old, new)
+            }
+            Change::UnitsChanged { object_type, instance, old, new } => {
+                format!("{} {} units {} -> {}", object_type, instance, old, new)
+            }
+        }
+    }
+}
+
+/// Reads a scan exported with `x`/`--export`, or a site database
+pub fn load_scan(path: &Path) -> Result<ScanExport> {
+    let text = std::fs::read_to_string(path)?;
+    let value: serde_json::Value = serde_json::from_str(&text)?;
+    if value.get("db_version").is_some() {
+        let db = SiteDb::load(path)?.ok_or_else(|| anyhow!("{} disappeared while reading", path.display()))?;
+        return Ok(db.to_scan());
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+fn device_label(device_id: u32, name: &Option<String>) -> String {
+    match name {
+        Some(name) => format!("Device {} \"{}\"", device_id, name),
+        None => format!("Device {}", device_id),
+    }
+}
+
+fn device_changes(old: &ExportDevice, new: &ExportDevice) -> Vec<Change> {
+    let mut changes = Vec::new();
+    if old.address != new.address {
+        changes.push(Change::Address { old: old.address.clone(), new: new.address.clone() });
+    }
+    if let Some((old, new)) = changed(&old.name, &new.name) {
+        changes.push(Change::DeviceName { old, new });
+    }
+    if old.vendor_id != new.vendor_id || old.vendor_name != new.vendor_name {
+        let vendor = |d: &ExportDevice| format!("{} ({})", d.vendor_name, d.vendor_id);
+        changes.push(Change::Vendor { old: vendor(old), new: vendor(new) });
+    }
+    if let Some((old, new)) = changed(&old.firmware_revision, &new.firmware_revision) {
+        changes.push(Change::FirmwareRevision { old, new });
+    }
+    if let Some((old, new)) = changed(&old.application_software_version, &new.application_software_version) {
+        changes.push(Change::ApplicationSoftwareVersion { old, new });
+    }
+    if !old.objects.is_empty() && !new.objects.is_empty() {
+        changes.extend(object_changes(&old.objects, &new.objects));
+    }
+    changes
+}
+
+fn object_changes(old: &[ExportObject], new: &[ExportObject]) -> Vec<Change> {
+    let key = |o: &ExportObject| (o.object_type_id, o.instance);
+    let old: BTreeMap<_, &ExportObject> = old.iter().map(|o| (key(o), o)).collect();
+    let new: BTreeMap<_, &ExportObject> = new.iter().map(|o| (key(o), o)).collect();
+    let keys: BTreeSet<_> = old.keys().chain(new.keys()).collect();
+
+    let mut changes = Vec::new();
+    for k in keys {
+        match (old.get(k), new.get(k)) {
+            (None, Some(o)) => changes.push(Change::ObjectAdded {
+                object_type: o.object_type.clone(),
+                instance: o.instance,
+                name: o.name.clone(),
+            }),
+            (Some(o), None) => changes.push(Change::ObjectRemoved {
+                object_type: o.object_type.clone(),
+                instance: o.instance,
+                name: o.name.clone(),
+            }),
+            (Some(before), Some(after)) => {
+                let known = |text: &String| Some(text.clone()).filter(|t| !t.is_empty());
+                if let Some((old, new)) = changed(&known(&before.name), &known(&after.name)) {
+                    changes.push(Change::ObjectRenamed { object_type: after.object_type.clone(), instance: after.instance, old, new });
+                }
+                if let Some((old, new)) = changed(&known(&before.units), &known(&after.units)) {
+                    changes.push(Change::UnitsChanged { object_type: after.object_type.clone(), instance: after.instance, old, new });
+                }
+            }
+            (None, None) => {}
+        }
+    }
+    changes
+}
+
+/// Both values, if both scans read them and they differ
+fn changed(old: &Option<String>, new: &Option<String>) -> Option<(String, String)> {
+    match (old, new) {
+        (Some(old), Some(new)) if old != new => Some((old.clone(), new.clone())),
+        _ => None,
+    }
+}
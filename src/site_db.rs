@@ -0,0 +1,171 @@
+//! On-disk site database: the devices and points of the last scan of a site,
+//! kept as a versioned JSON file so a new session starts from what was known.
+//!
+//! Devices are stored in the [`crate::export`] schema. [`DB_VERSION`] is bumped
+//! when the file layout changes; files written by a newer version are refused
+//! rather than silently truncated on the next save.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use anyhow::{Result, anyhow};
+use bacnet_rs::object::{ObjectIdentifier, ObjectType};
+use serde::{Deserialize, Serialize};
+use crate::app::BacnetObject;
+use crate::bacnet::{DeviceStatus, DiscoveredDevice};
+use crate::export::{ExportDevice, ExportObject, SCHEMA_VERSION, ScanExport};
+use crate::mstp;
+use crate::property::{self, BacnetValue, PropertyEntry, StatusFlags};
+
+pub const DB_VERSION: u32 = 1;
+
+/// Default location of the site database, in the working directory
+pub const DEFAULT_PATH: &str = "bacnet-site.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SiteDb {
+    pub db_version: u32,
+    pub site: String,
+    /// Unix time the database was first written
+    pub created_at: u64,
+    /// Unix time of the last save
+    pub updated_at: u64,
+    /// Number of saves so far
+    pub scans: u32,
+    pub devices: Vec<ExportDevice>,
+}
+
+/// Everything needed to show a site's devices before they answer again
+#[derive(Debug, Default)]
+pub struct RestoredSite {
+    pub devices: HashMap<u32, DiscoveredDevice>,
+    pub device_properties: HashMap<u32, Vec<PropertyEntry>>,
+    pub device_objects: HashMap<u32, Vec<BacnetObject>>,
+}
+
+impl SiteDb {
+    pub fn new(site: &str) -> Self {
+        let now = now_unix();
+        Self { db_version: DB_VERSION, site: site.to_string(), created_at: now, updated_at: now, scans: 0, devices: Vec::new() }
+    }
+
+    /// Reads the database, or returns None if the file does not exist yet
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let db: SiteDb = serde_json::from_str(&text)?;
+        if db.db_version > DB_VERSION {
+            return Err(anyhow!(
+                "{} was written by a newer version (database version {}, supported {})",
+                path.display(), db.db_version, DB_VERSION
+            ));
+        }
+        Ok(Some(db))
+    }
+
+    /// Writes the database through a temporary file so a crash cannot leave it half written
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut tmp = path.as_os_str().to_os_string();
+        tmp.push(".tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Replaces the stored devices with a new snapshot
+    pub fn update(&mut self, scan: ScanExport) {
+        self.devices = scan.devices;
+        self.updated_at = scan.generated_at;
+        self.scans += 1;
+    }
+
+    /// The stored devices as a scan, e.g. as the baseline of a [`crate::scan_diff::ScanDiff`]
+    pub fn to_scan(&self) -> ScanExport {
+        ScanExport { schema_version: SCHEMA_VERSION, generated_at: self.updated_at, devices: self.devices.clone() }
+    }
+
+    /// Rebuilds the in-memory maps from the stored devices. Devices come back
+    /// offline until they answer again; present values are not restored.
+    pub fn restore(&self) -> RestoredSite {
+        let mut site = RestoredSite::default();
+        for stored in &self.devices {
+            let Some(address) = parse_address(&stored.address) else { continue };
+            site.devices.insert(stored.device_id, DiscoveredDevice {
+                vendor_id: stored.vendor_id,
+                vendor_name: stored.vendor_name.clone(),
+                max_apdu: stored.max_apdu,
+                segmentation: segmentation_value(&stored.segmentation),
+                last_seen: instant_at(stored.last_seen),
+                status: DeviceStatus::Offline,
+                ..DiscoveredDevice::new(stored.device_id, address)
+            });
+            let texts = [
+                (property::OBJECT_NAME, &stored.name),
+                (property::FIRMWARE_REVISION, &stored.firmware_revision),
+                (property::APPLICATION_SOFTWARE_VERSION, &stored.application_software_version),
+            ];
+            let props: Vec<PropertyEntry> = texts.into_iter()
+                .filter_map(|(id, text)| Some(PropertyEntry {
+                    id,
+                    array_index: None,
+                    value: Ok(BacnetValue::CharacterString(text.clone()?)),
+                }))
+                .collect();
+            if !props.is_empty() {
+                site.device_properties.insert(stored.device_id, props);
+            }
+            let objects: Vec<BacnetObject> = stored.objects.iter().filter_map(restore_object).collect();
+            if !objects.is_empty() {
+                site.device_objects.insert(stored.device_id, objects);
+            }
+        }
+        site
+    }
+}
+
+fn restore_object(stored: &ExportObject) -> Option<BacnetObject> {
+    let object_type = ObjectType::try_from(stored.object_type_id).ok()?;
+    let status_flags = match (stored.in_alarm, stored.fault, stored.overridden) {
+        (Some(in_alarm), Some(fault), Some(overridden)) => Some(StatusFlags {
+            in_alarm,
+            fault,
+            overridden,
+            out_of_service: stored.out_of_service.unwrap_or(false),
+        }),
+        _ => None,
+    };
+    // Description and state texts stay unset so the first poll reads the details again
+    Some(BacnetObject {
+        units: stored.units.clone(),
+        units_code: stored.units_code,
+        status_flags,
+        out_of_service: stored.out_of_service,
+        ..BacnetObject::new(ObjectIdentifier::new(object_type, stored.instance), stored.name.clone())
+    })
+}
+
+fn parse_address(text: &str) -> Option<SocketAddr> {
+    match text.strip_prefix("mstp:") {
+        Some(mac) => mac.parse().ok().map(mstp::mac_to_addr),
+        None => text.parse().ok(),
+    }
+}
+
+fn segmentation_value(name: &str) -> u32 {
+    (0..4).find(|v| property::segmentation_name(*v).eq_ignore_ascii_case(name)).unwrap_or(3)
+}
+
+fn now_unix() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// The monotonic instant matching a stored Unix time, as far back as the clock allows
+fn instant_at(unix_secs: u64) -> Instant {
+    let age = Duration::from_secs(now_unix().saturating_sub(unix_secs));
+    let now = Instant::now();
+    now.checked_sub(age).unwrap_or(now)
+}
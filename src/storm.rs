@@ -0,0 +1,239 @@
+//! Broadcast storm and chatty-device detection.
+//!
+//! A [`BroadcastMonitor`] keeps the broadcasts received over a sliding window and
+//! measures their rate in total, per source and per service. Any rate above its
+//! [`StormThresholds`] limit raises a [`StormWarning`], e.g. a controller flooding
+//! I-Ams or a workstation repeating Who-Is.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::net::IpAddr;
+use std::time::Duration;
+use crate::traffic::Datagram;
+
+/// Window the broadcast rates are measured over
+pub const STORM_WINDOW: Duration = Duration::from_secs(10);
+/// Rows shown in the top talker tables
+const TOP_TALKERS: usize = 10;
+
+/// Broadcasts per second above which a warning is raised
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StormThresholds {
+    /// All broadcasts on the network
+    pub total: f64,
+    /// Broadcasts from one source address
+    pub per_source: f64,
+    /// Broadcasts of one service, from all sources
+    pub per_service: f64,
+}
+
+impl Default for StormThresholds {
+    fn default() -> Self {
+        Self { total: 50.0, per_source: 5.0, per_service: 30.0 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StormWarning {
+    Storm { rate: f64, limit: f64 },
+    /// A single source broadcasting too often; `service` is what it sends most
+    ChattySource { source: IpAddr, service: String, rate: f64, limit: f64 },
+    ServiceFlood { service: String, rate: f64, limit: f64 },
+}
+
+impl fmt::Display for StormWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StormWarning::Storm { rate, limit } => {
+                write!(f, "Broadcast storm: {:.1} broadcasts/s (limit {:.1})", rate, limit)
+            }
+            StormWarning::ChattySource { source, service, rate, limit } => {
+                write!(f, "Chatty device {}: {:.1} broadcasts/s, mostly {} (limit {:.1})", source, rate, service, limit)
+            }
+            StormWarning::ServiceFlood { service, rate, limit } => {
+                write!(f, "{} flood: {:.1}/s from all sources (limit {:.1})", service, rate, limit)
+            }
+        }
+    }
+}
+
+impl StormWarning {
+    /// True when both warnings are about the same thing, whatever the rates
+    pub fn same_subject(&self, other: &StormWarning) -> bool {
+        match (self, other) {
+            (StormWarning::Storm { .. }, StormWarning::Storm { .. }) => true,
+            (StormWarning::ChattySource { source: a, .. }, StormWarning::ChattySource { source: b, .. }) => a == b,
+            (StormWarning::ServiceFlood { service: a, .. }, StormWarning::ServiceFlood { service: b, .. }) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Broadcast count and rate for one source or service
+#[derive(Debug, Clone, PartialEq)]
+pub struct Talker {
+    pub name: String,
+    pub packets: usize,
+    pub rate: f64,
+}
+
+/// Broadcasts received over the last [`STORM_WINDOW`]
+#[derive(Debug, Clone)]
+pub struct BroadcastMonitor {
+    pub thresholds: StormThresholds,
+    window: Duration,
+    /// Arrival time (since the Unix epoch), source and service of each broadcast, oldest first
+    events: VecDeque<(Duration, IpAddr, String)>,
+    /// When monitoring started, so rates are not understated before a full window has passed
+    started: Option<Duration>,
+}
+
+impl Default for BroadcastMonitor {
+    fn default() -> Self {
+        Self::new(StormThresholds::default())
+    }
+}
+
+impl BroadcastMonitor {
+    pub fn new(thresholds: StormThresholds) -> Self {
+        Self::with_window(thresholds, STORM_WINDOW)
+    }
+
+    pub fn with_window(thresholds: StormThresholds, window: Duration) -> Self {
+        Self { thresholds, window: window.max(Duration::from_secs(1)), events: VecDeque::new(), started: None }
+    }
+
+    /// Counts the datagram if it is a broadcast; everything else is ignored
+    pub fn record(&mut self, datagram: &Datagram) {
+        self.start(datagram.at);
+        if !datagram.packet.is_broadcast() {
+            return;
+        }
+        // A Forwarded-NPDU comes from the BBMD; the broadcast belongs to the device that sent it
+        let source = datagram.packet.bvll.as_ref().and_then(|b| b.origin).unwrap_or(datagram.source);
+        self.events.push_back((datagram.at, source.ip(), datagram.packet.category()));
+        self.prune(datagram.at);
+    }
+
+    /// Marks when monitoring started, if nothing has been recorded yet. Until a full
+    /// window has passed, rates are measured from here.
+    pub fn start(&mut self, at: Duration) {
+        self.started.get_or_insert(at);
+    }
+
+    /// Forgets everything, including when monitoring started
+    pub fn clear(&mut self) {
+        self.events.clear();
+        self.started = None;
+    }
+
+    fn prune(&mut self, now: Duration) {
+        let cutoff = now.saturating_sub(self.window);
+        while self.events.front().is_some_and(|(at, _, _)| *at < cutoff) {
+            self.events.pop_front();
+        }
+    }
+
+    /// Broadcasts in the window ending at `now`
+    fn recent(&self, now: Duration) -> impl Iterator<Item = &(Duration, IpAddr, String)> {
+        let cutoff = now.saturating_sub(self.window);
+        self.events.iter().filter(move |(at, _, _)| *at >= cutoff && *at <= now)
+    }
+
+    /// Seconds the rates at `now` are measured over: the window, or less right after starting
+    fn span(&self, now: Duration) -> f64 {
+        let elapsed = self.started.map_or(Duration::ZERO, |s| now.saturating_sub(s));
+        elapsed.min(self.window).max(Duration::from_secs(1)).as_secs_f64()
+    }
+
+    /// All broadcasts per second at `now`
+    pub fn total_rate(&self, now: Duration) -> f64 {
+        self.recent(now).count() as f64 / self.span(now)
+    }
+
+    /// Sources by broadcast rate, busiest first
+    pub fn top_sources(&self, now: Duration) -> Vec<Talker> {
+        self.talkers(now, |(_, source, _)| source.to_string())
+    }
+
+    /// Services by broadcast rate, busiest first
+    pub fn top_services(&self, now: Duration) -> Vec<Talker> {
+        self.talkers(now, |(_, _, service)| service.clone())
+    }
+
+    fn talkers(&self, now: Duration, key: impl Fn(&(Duration, IpAddr, String)) -> String) -> Vec<Talker> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for event in self.recent(now) {
+            *counts.entry(key(event)).or_default() += 1;
+        }
+        let span = self.span(now);
+        let mut talkers: Vec<Talker> = counts.into_iter()
+            .map(|(name, packets)| Talker { name, packets, rate: packets as f64 / span })
+            .collect();
+        talkers.sort_by(|a, b| b.packets.cmp(&a.packets).then_with(|| a.name.cmp(&b.name)));
+        talkers
+    }
+
+    /// Every rate above its limit at `now`: the total first, then sources, then services
+    pub fn warnings(&self, now: Duration) -> Vec<StormWarning> {
+        let limits = self.thresholds;
+        let mut warnings = Vec::new();
+        let total = self.total_rate(now);
+        if total > limits.total {
+            warnings.push(StormWarning::Storm { rate: total, limit: limits.total });
+        }
+        let mut sources: HashMap<IpAddr, usize> = HashMap::new();
+        for (_, source, _) in self.recent(now) {
+            *sources.entry(*source).or_default() += 1;
+        }
+        let mut chatty: Vec<(IpAddr, f64)> = sources.into_iter()
+            .map(|(source, packets)| (source, packets as f64 / self.span(now)))
+            .filter(|(_, rate)| *rate > limits.per_source)
+            .collect();
+        chatty.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        for (source, rate) in chatty {
+            let service = self.main_service(now, source);
+            warnings.push(StormWarning::ChattySource { source, service, rate, limit: limits.per_source });
+        }
+        for talker in self.top_services(now).into_iter().filter(|t| t.rate > limits.per_service) {
+            warnings.push(StormWarning::ServiceFlood { service: talker.name, rate: talker.rate, limit: limits.per_service });
+        }
+        warnings
+    }
+
+    /// The service a source broadcast most often in the window
+    fn main_service(&self, now: Duration, source: IpAddr) -> String {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for (_, _, service) in self.recent(now).filter(|(_, s, _)| *s == source) {
+            *counts.entry(service.as_str()).or_default() += 1;
+        }
+        counts.into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(service, _)| service.to_string())
+            .unwrap_or_default()
+    }
+
+    /// Rates and top talker tables as text
+    pub fn lines(&self, now: Duration) -> Vec<String> {
+        let mut lines = vec![format!(
+            "{} broadcasts in the last {:.0} s ({:.1}/s; limits {:.1}/s total, {:.1}/s per source, {:.1}/s per service)",
+            self.recent(now).count(), self.span(now), self.total_rate(now),
+            self.thresholds.total, self.thresholds.per_source, self.thresholds.per_service
+        )];
+        for (header, talkers) in [("SOURCE", self.top_sources(now)), ("SERVICE", self.top_services(now))] {
+            if talkers.is_empty() {
+                continue;
+            }
+            let width = talkers.iter().take(TOP_TALKERS).map(|t| t.name.len()).max().unwrap_or(0).max(header.len());
+            lines.push(String::new());
+            lines.push(format!("{:<width$}  {:>7}  {:>7}", header, "PACKETS", "PKT/S", width = width));
+            lines.extend(talkers.iter().take(TOP_TALKERS).map(|t| {
+                format!("{:<width$}  {:>7}  {:>7.1}", t.name, t.packets, t.rate, width = width)
+            }));
+            if talkers.len() > TOP_TALKERS {
+                lines.push(format!("... {} more", talkers.len() - TOP_TALKERS));
+            }
+        }
+        lines
+    }
+}
@@ -0,0 +1,332 @@
+//! Network topology: IP subnets, routers, BACnet networks and devices.
+//!
+//! A [`Topology`] is built from the traffic the tool receives. I-Am sources place
+//! devices on their B/IP address, or behind a router when the NPDU carries an SNET;
+//! SNETs and I-Am-Router-To-Network replies tell which networks each router
+//! reaches; Forwarded-NPDUs and Read-BDT acks reveal BBMDs and their peers.
+//! [`Topology::tree`] lays it out as IP subnet → router → network → device, and
+//! [`Topology::to_dot`] as a Graphviz graph.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use anyhow::Result;
+use crate::network::DataLink;
+use crate::decode::BdtEntry;
+use crate::traffic::Datagram;
+
+/// Who-Is-Router-To-Network for every network, as a local broadcast
+pub const WHO_IS_ROUTER_TO_NETWORK: [u8; 7] = [0x81, 0x0B, 0x00, 0x07, 0x01, 0x80, 0x00];
+
+/// Asks every router on the local network which networks it reaches
+pub fn send_who_is_router(link: &(impl DataLink + ?Sized), dest: SocketAddr) -> Result<()> {
+    link.send_to(&WHO_IS_ROUTER_TO_NETWORK, dest)?;
+    Ok(())
+}
+
+/// An IPv4 subnet, e.g. `192.168.1.0/24`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Subnet {
+    pub network: Ipv4Addr,
+    pub prefix: u8,
+}
+
+impl Subnet {
+    /// The subnet of `ip` with this netmask
+    pub fn new(ip: Ipv4Addr, netmask: Ipv4Addr) -> Self {
+        let mask = u32::from(netmask);
+        Self { network: Ipv4Addr::from(u32::from(ip) & mask), prefix: mask.leading_ones() as u8 }
+    }
+
+    fn mask(&self) -> u32 {
+        u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0)
+    }
+
+    pub fn contains(&self, ip: Ipv4Addr) -> bool {
+        u32::from(ip) & self.mask() == u32::from(self.network)
+    }
+}
+
+impl fmt::Display for Subnet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix)
+    }
+}
+
+/// Where a device answered from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceLocation {
+    /// B/IP address of the device, or of its router when `network` is set
+    pub address: SocketAddr,
+    /// Remote network the device is on (the SNET of its I-Am)
+    pub network: Option<u16>,
+    /// MAC address on the remote network (the SADR); empty for B/IP devices
+    pub mac: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Subnet,
+    /// A B/IP address: a device, router or BBMD
+    Host,
+    /// A peer in a BBMD's broadcast distribution table
+    Peer,
+    Network,
+    /// A device behind a router
+    Device,
+}
+
+/// One row of the topology tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeNode {
+    pub depth: usize,
+    pub kind: NodeKind,
+    pub label: String,
+}
+
+/// What is known about one B/IP address
+struct Host<'a> {
+    address: SocketAddr,
+    /// Devices answering directly from the address
+    devices: Vec<u32>,
+    /// Networks routed by the address
+    networks: Vec<u16>,
+    /// Broadcast distribution table if the address is a BBMD; empty until read
+    bdt: Option<&'a [BdtEntry]>,
+}
+
+impl Host<'_> {
+    fn label(&self) -> String {
+        let mut roles = Vec::new();
+        if !self.networks.is_empty() {
+            roles.push("Router");
+        }
+        if self.bdt.is_some() {
+            roles.push("BBMD");
+        }
+        let devices = match self.devices.as_slice() {
+            [] => None,
+            [id] => Some(format!("Device {}", id)),
+            ids => Some(format!("Devices {}", ids.iter().map(u32::to_string).collect::<Vec<_>>().join(", "))),
+        };
+        match (roles.is_empty(), devices) {
+            (true, Some(devices)) => format!("{}  {}", devices, self.address),
+            (false, Some(devices)) => format!("{} {} ({})", roles.join(" + "), self.address, devices),
+            (_, None) => format!("{} {}", roles.join(" + "), self.address),
+        }
+    }
+
+    /// Peers in the broadcast distribution table, without the BBMD itself
+    fn peers(&self) -> impl Iterator<Item = SocketAddr> + '_ {
+        self.bdt.unwrap_or_default().iter().map(|e| e.address).filter(|a| *a != self.address)
+    }
+}
+
+/// Subnets, routers, networks and devices seen on the wire
+#[derive(Debug, Clone, Default)]
+pub struct Topology {
+    /// Subnets of the local interfaces; other addresses are assumed to be on a /24
+    local_subnets: Vec<Subnet>,
+    devices: BTreeMap<u32, DeviceLocation>,
+    /// Router address and the networks reachable through it
+    routers: BTreeMap<SocketAddr, BTreeSet<u16>>,
+    /// BBMD address and its broadcast distribution table, empty until read
+    bbmds: BTreeMap<SocketAddr, Vec<BdtEntry>>,
+}
+
+impl Topology {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the subnet of a local interface
+    pub fn add_local_subnet(&mut self, ip: Ipv4Addr, netmask: Ipv4Addr) {
+        let subnet = Subnet::new(ip, netmask);
+        if !self.local_subnets.contains(&subnet) {
+            self.local_subnets.push(subnet);
+        }
+    }
+
+    /// Forgets everything learned from traffic; the local subnets stay
+    pub fn clear(&mut self) {
+        self.devices.clear();
+        self.routers.clear();
+        self.bbmds.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty() && self.routers.is_empty() && self.bbmds.is_empty()
+    }
+
+    pub fn device(&self, device_id: u32) -> Option<&DeviceLocation> {
+        self.devices.get(&device_id)
+    }
+
+    /// Networks reachable through the router at `address`
+    pub fn routed_networks(&self, address: SocketAddr) -> Vec<u16> {
+        self.routers.get(&address).map(|n| n.iter().copied().collect()).unwrap_or_default()
+    }
+
+    /// Learns what it can from a received datagram
+    pub fn record(&mut self, datagram: &Datagram) {
+        let packet = &datagram.packet;
+        let Some(bvll) = &packet.bvll else { return };
+        if bvll.function == 0x03 {
+            self.bbmds.insert(datagram.source, bvll.bdt.clone());
+            return;
+        }
+        // A BBMD relays broadcasts from other subnets as Forwarded-NPDUs
+        let sender = match bvll.origin {
+            Some(origin) => {
+                self.bbmds.entry(datagram.source).or_default();
+                origin
+            }
+            None => datagram.source,
+        };
+        let Some(npdu) = &packet.npdu else { return };
+        if let Some(source) = &npdu.source {
+            self.routers.entry(sender).or_default().insert(source.net);
+        }
+        if let Some(message) = &packet.network_message
+            && message.message_type == 0x01
+        {
+            self.routers.entry(sender).or_default().extend(&message.networks);
+        }
+        if let Some(device_id) = packet.iam_device {
+            let (network, mac) = match &npdu.source {
+                Some(source) => (Some(source.net), source.mac.clone()),
+                None => (None, Vec::new()),
+            };
+            self.devices.insert(device_id, DeviceLocation { address: sender, network, mac });
+        }
+    }
+
+    /// The subnet an address is on: the local subnet containing it, or its /24
+    pub fn subnet_of(&self, ip: Ipv4Addr) -> Subnet {
+        self.local_subnets.iter()
+            .filter(|s| s.contains(ip))
+            .max_by_key(|s| s.prefix)
+            .copied()
+            .unwrap_or_else(|| Subnet::new(ip, Ipv4Addr::new(255, 255, 255, 0)))
+    }
+
+    /// Every B/IP address worth a Read-BDT: routers, BBMDs and devices answering directly
+    pub fn bdt_targets(&self) -> Vec<SocketAddr> {
+        self.hosts().values().flatten().map(|h| h.address).collect()
+    }
+
+    /// Known B/IP addresses by subnet, in address order
+    fn hosts(&self) -> BTreeMap<Subnet, Vec<Host<'_>>> {
+        let mut addresses: BTreeSet<SocketAddr> = self.routers.keys().copied().collect();
+        addresses.extend(self.devices.values().map(|d| d.address));
+        for (bbmd, table) in &self.bbmds {
+            addresses.insert(*bbmd);
+            addresses.extend(table.iter().map(|e| e.address));
+        }
+        let mut hosts: BTreeMap<Subnet, Vec<Host>> = BTreeMap::new();
+        for address in addresses {
+            let IpAddr::V4(ip) = address.ip() else { continue };
+            // Every address in a BDT is a BBMD, even before its own table is read
+            let listed = self.bbmds.values().flatten().any(|e| e.address == address);
+            hosts.entry(self.subnet_of(ip)).or_default().push(Host {
+                address,
+                devices: self.devices.iter().filter(|(_, d)| d.address == address && d.network.is_none()).map(|(id, _)| *id).collect(),
+                networks: self.routed_networks(address),
+                bdt: self.bbmds.get(&address).map(Vec::as_slice).or(listed.then_some(&[][..])),
+            });
+        }
+        hosts
+    }
+
+    /// Devices behind the router at `address` on `network`
+    fn routed_devices(&self, address: SocketAddr, network: u16) -> Vec<(u32, &DeviceLocation)> {
+        self.devices.iter()
+            .filter(|(_, d)| d.address == address && d.network == Some(network))
+            .map(|(id, d)| (*id, d))
+            .collect()
+    }
+
+    /// IP subnet → router, BBMD or device → network → device
+    pub fn tree(&self) -> Vec<TreeNode> {
+        let node = |depth, kind, label: String| TreeNode { depth, kind, label };
+        let mut nodes = Vec::new();
+        for (subnet, hosts) in self.hosts() {
+            let local = if self.local_subnets.contains(&subnet) { " (local)" } else { "" };
+            nodes.push(node(0, NodeKind::Subnet, format!("{}{}", subnet, local)));
+            for host in &hosts {
+                nodes.push(node(1, NodeKind::Host, host.label()));
+                nodes.extend(host.peers().map(|peer| node(2, NodeKind::Peer, format!("BDT peer {}", peer))));
+                for &network in &host.networks {
+                    let devices = self.routed_devices(host.address, network);
+                    let count = match devices.len() {
+                        1 => "1 device".to_string(),
+                        n => format!("{} devices", n),
+                    };
+                    nodes.push(node(2, NodeKind::Network, format!("Network {} ({})", network, count)));
+                    nodes.extend(devices.into_iter().map(|(id, d)| {
+                        node(3, NodeKind::Device, format!("Device {}  MAC {}", id, mac_text(&d.mac)))
+                    }));
+                }
+            }
+        }
+        nodes
+    }
+
+    /// Graphviz DOT: a cluster per subnet, an ellipse per network and dashed edges between BBMD peers
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph bacnet {\n    rankdir=LR;\n    node [shape=box, fontname=\"Helvetica\"];\n");
+        let mut links = Vec::new();
+        let mut peerings = BTreeSet::new();
+        let mut networks = BTreeSet::new();
+        for (i, (subnet, hosts)) in self.hosts().into_iter().enumerate() {
+            dot.push_str(&format!("    subgraph cluster_{} {{\n        label=\"{}\";\n", i, subnet));
+            for host in &hosts {
+                dot.push_str(&format!("        \"{}\" [label=\"{}\"];\n", host.address, host.label()));
+                for peer in host.peers() {
+                    peerings.insert((host.address.min(peer), host.address.max(peer)));
+                }
+                for &network in &host.networks {
+                    networks.insert(network);
+                    links.push(format!("    \"{}\" -- \"net:{}\";\n", host.address, network));
+                    for (id, d) in self.routed_devices(host.address, network) {
+                        links.push(format!("    \"device:{}\" [label=\"Device {}\\nMAC {}\"];\n", id, id, mac_text(&d.mac)));
+                        links.push(format!("    \"net:{}\" -- \"device:{}\";\n", network, id));
+                    }
+                }
+            }
+            dot.push_str("    }\n");
+        }
+        for network in networks {
+            dot.push_str(&format!("    \"net:{}\" [label=\"Network {}\", shape=ellipse];\n", network, network));
+        }
+        links.iter().for_each(|link| dot.push_str(link));
+        for (a, b) in peerings {
+            dot.push_str(&format!("    \"{}\" -- \"{}\" [style=dashed, label=\"BDT\"];\n", a, b));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Draws the tree with box-drawing connectors, one line per node
+pub fn tree_lines(nodes: &[TreeNode]) -> Vec<String> {
+    // A column continues while a later node sits at its depth before the tree climbs above it
+    let continues = |from: usize, depth: usize| {
+        nodes[from..].iter().take_while(|n| n.depth >= depth).any(|n| n.depth == depth)
+    };
+    nodes.iter().enumerate().map(|(i, node)| {
+        let mut line = String::new();
+        for depth in 1..node.depth {
+            line.push_str(if continues(i + 1, depth) { "│  " } else { "   " });
+        }
+        if node.depth > 0 {
+            line.push_str(if continues(i + 1, node.depth) { "├─ " } else { "└─ " });
+        }
+        line.push_str(&node.label);
+        line
+    }).collect()
+}
+
+fn mac_text(mac: &[u8]) -> String {
+    mac.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(":")
+}
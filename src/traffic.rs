@@ -0,0 +1,449 @@
+//! Filtering and statistics over decoded BACnet/IP traffic.
+//!
+//! A [`PacketFilter`] is a whitespace-separated list of `key=value` terms that
+//! must all match. A value may list alternatives separated by commas, and a term
+//! prefixed with `!` must not match:
+//!
+//! | Key | Matches |
+//! |-----|---------|
+//! | `host` | source or destination IP (`10.0.0.5`, or `10.0.0.5:47808` to include the port) |
+//! | `src`, `dst` | source or destination IP |
+//! | `device` | packets from or to the address the device's I-Am came from, and Who-Is ranges covering the instance |
+//! | `service` | service name, case and `-`/`_` ignored (`readproperty`, `who-is`) |
+//! | `type` | `confirmed`, `unconfirmed`, `simple-ack`, `complex-ack`, `segment-ack`, `error`, `reject`, `abort`, `network`, `bvll`, `malformed`, or `broadcast` |
+//! | `net` | DNET or SNET of the NPDU, or a network listed by a network layer message |
+//!
+//! For example `service=readproperty,readpropertymultiple !src=10.0.0.1` or
+//! `type=error,reject,abort`. Device addresses are looked up in a
+//! [`DeviceAddresses`] table fed with the I-Ams seen so far.
+
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use anyhow::{Result, anyhow};
+use crate::decode::{ApduHeader, DecodedPacket, NetworkAddress};
+use crate::network::DataLink;
+
+/// One datagram seen on the wire, with its decode
+#[derive(Debug, Clone)]
+pub struct Datagram {
+    /// Time since the Unix epoch
+    pub at: Duration,
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+    pub length: usize,
+    pub packet: DecodedPacket,
+}
+
+impl Datagram {
+    pub fn new(at: Duration, source: SocketAddr, destination: SocketAddr, data: &[u8]) -> Self {
+        Self { at, source, destination, length: data.len(), packet: crate::decode::decode_packet(data) }
+    }
+}
+
+/// A data link that also records every datagram it sends in a traffic log, with
+/// `local` as the source. Received datagrams are left to the receive loop.
+pub struct LoggedLink {
+    link: Arc<dyn DataLink>,
+    local: SocketAddr,
+    log: Arc<Mutex<TrafficLog>>,
+}
+
+impl LoggedLink {
+    pub fn new(link: Arc<dyn DataLink>, local: SocketAddr, log: Arc<Mutex<TrafficLog>>) -> Self {
+        Self { link, local, log }
+    }
+}
+
+impl DataLink for LoggedLink {
+    fn send_to(&self, buf: &[u8], dest: SocketAddr) -> io::Result<usize> {
+        let sent = self.link.send_to(buf, dest)?;
+        let at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        self.log.lock().unwrap().push(Datagram::new(at, self.local, dest, buf));
+        Ok(sent)
+    }
+
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.link.recv_from(buf)
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.link.set_nonblocking(nonblocking)
+    }
+}
+
+/// Where a datagram received on a BACnet socket was sent. A socket does not report
+/// this, but it only receives datagrams for this host or a broadcast, so broadcast
+/// BVLL functions are taken to be sent to `broadcast` and everything else to `local`.
+pub fn received_destination(data: &[u8], local: SocketAddr, broadcast: SocketAddr) -> SocketAddr {
+    if matches!(data.get(1), Some(0x04 | 0x09 | 0x0B)) { broadcast } else { local }
+}
+
+/// Time of day (UTC) with milliseconds, as packet captures show it
+pub fn time_of_day(since_epoch: Duration) -> String {
+    let secs = since_epoch.as_secs() % 86_400;
+    format!("{:02}:{:02}:{:02}.{:03}", secs / 3600, secs / 60 % 60, secs % 60, since_epoch.subsec_millis())
+}
+
+/// Datagrams kept by the traffic view
+pub const TRAFFIC_LOG_CAPACITY: usize = 2000;
+
+/// Where each device answers from, learned from its I-Ams
+#[derive(Debug, Clone, Default)]
+pub struct DeviceAddresses {
+    /// B/IP address, and the SNET/SADR for a device behind a router
+    devices: HashMap<u32, (SocketAddr, Option<NetworkAddress>)>,
+}
+
+impl DeviceAddresses {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the sender of an I-Am; the origin rather than the BBMD for a Forwarded-NPDU
+    pub fn learn(&mut self, datagram: &Datagram) {
+        let packet = &datagram.packet;
+        let Some(instance) = packet.iam_device else { return };
+        let address = packet.bvll.as_ref().and_then(|b| b.origin).unwrap_or(datagram.source);
+        let remote = packet.npdu.as_ref().and_then(|n| n.source.clone());
+        self.devices.insert(instance, (address, remote));
+    }
+
+    pub fn get(&self, instance: u32) -> Option<SocketAddr> {
+        self.devices.get(&instance).map(|(address, _)| *address)
+    }
+
+    /// True if the datagram was sent by or to the device
+    fn involves(&self, instance: u32, datagram: &Datagram) -> bool {
+        let Some((address, remote)) = self.devices.get(&instance) else { return false };
+        let packet = &datagram.packet;
+        let npdu = packet.npdu.as_ref();
+        let sender = packet.bvll.as_ref().and_then(|b| b.origin).unwrap_or(datagram.source);
+        let from = sender == *address && (remote.is_none() || npdu.and_then(|n| n.source.as_ref()) == remote.as_ref());
+        let to = datagram.destination == *address && (remote.is_none() || npdu.and_then(|n| n.destination.as_ref()) == remote.as_ref());
+        from || to
+    }
+}
+
+/// The most recent datagrams, oldest first; the oldest is dropped when full
+#[derive(Debug, Clone)]
+pub struct TrafficLog {
+    entries: VecDeque<Datagram>,
+    capacity: usize,
+    paused: bool,
+    /// Datagrams not kept because the log was paused
+    pub missed: u64,
+    /// Learned from every I-Am pushed, paused or not, and kept when the log is cleared
+    pub devices: DeviceAddresses,
+}
+
+impl Default for TrafficLog {
+    fn default() -> Self {
+        Self::with_capacity(TRAFFIC_LOG_CAPACITY)
+    }
+}
+
+impl TrafficLog {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { entries: VecDeque::new(), capacity: capacity.max(1), paused: false, missed: 0, devices: DeviceAddresses::new() }
+    }
+
+    /// Keeps a datagram unless the log is paused
+    pub fn push(&mut self, datagram: Datagram) {
+        self.devices.learn(&datagram);
+        if self.paused {
+            self.missed += 1;
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(datagram);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.missed = 0;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Datagram> {
+        self.entries.get(index)
+    }
+
+    /// Datagrams oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &Datagram> {
+        self.entries.iter()
+    }
+
+    /// Positions of the datagrams matching a filter
+    pub fn matching(&self, filter: &PacketFilter) -> Vec<usize> {
+        self.entries.iter().enumerate().filter(|(_, d)| filter.matches(d, &self.devices)).map(|(i, _)| i).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PacketFilter {
+    terms: Vec<Term>,
+    text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Term {
+    negated: bool,
+    condition: Condition,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Host(Vec<AddressPattern>),
+    Source(Vec<AddressPattern>),
+    Destination(Vec<AddressPattern>),
+    Device(Vec<u32>),
+    Service(Vec<String>),
+    Type(Vec<String>),
+    Network(Vec<u16>),
+}
+
+/// An IP address, optionally with a port
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AddressPattern {
+    ip: IpAddr,
+    port: Option<u16>,
+}
+
+const TYPES: &[&str] = &[
+    "confirmed", "unconfirmed", "simple-ack", "complex-ack", "segment-ack", "error", "reject", "abort",
+    "network", "bvll", "malformed", "broadcast",
+];
+
+impl PacketFilter {
+    /// Parses a filter expression; an empty expression matches everything
+    pub fn parse(text: &str) -> Result<Self> {
+        let terms = text.split_whitespace().map(parse_term).collect::<Result<Vec<_>>>()?;
+        Ok(Self { terms, text: text.split_whitespace().collect::<Vec<_>>().join(" ") })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// The expression as parsed, normalised to single spaces
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// `devices` resolves `device=` terms to addresses
+    pub fn matches(&self, datagram: &Datagram, devices: &DeviceAddresses) -> bool {
+        self.terms.iter().all(|term| term.condition.matches(datagram, devices) != term.negated)
+    }
+}
+
+fn parse_term(term: &str) -> Result<Term> {
+    let (negated, term) = match term.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, term),
+    };
+    let (key, value) = term.split_once('=').ok_or_else(|| anyhow!("Filter term \"{}\" is not key=value", term))?;
+    let values: Vec<&str> = value.split(',').filter(|v| !v.is_empty()).collect();
+    if values.is_empty() {
+        return Err(anyhow!("Filter term \"{}\" has no value", term));
+    }
+    let addresses = || values.iter().map(|v| parse_address(v)).collect::<Result<Vec<_>>>();
+    let condition = match key.to_ascii_lowercase().as_str() {
+        "host" => Condition::Host(addresses()?),
+        "src" => Condition::Source(addresses()?),
+        "dst" => Condition::Destination(addresses()?),
+        "device" => Condition::Device(values.iter().map(|v| {
+            v.parse().map_err(|_| anyhow!("Invalid device instance: {}", v))
+        }).collect::<Result<_>>()?),
+        "service" => Condition::Service(values.iter().map(|v| normalise(v)).collect()),
+        "type" => Condition::Type(values.iter().map(|v| {
+            let v = v.to_ascii_lowercase();
+            TYPES.contains(&v.as_str()).then_some(v.clone())
+                .ok_or_else(|| anyhow!("Unknown packet type \"{}\" (expected one of {})", v, TYPES.join(", ")))
+        }).collect::<Result<_>>()?),
+        "net" => Condition::Network(values.iter().map(|v| {
+            v.parse().map_err(|_| anyhow!("Invalid network number: {}", v))
+        }).collect::<Result<_>>()?),
+        _ => return Err(anyhow!("Unknown filter key \"{}\" (expected host, src, dst, device, service, type or net)", key)),
+    };
+    Ok(Term { negated, condition })
+}
+
+fn parse_address(text: &str) -> Result<AddressPattern> {
+    if let Ok(addr) = text.parse::<SocketAddr>() {
+        return Ok(AddressPattern { ip: addr.ip(), port: Some(addr.port()) });
+    }
+    let ip = text.parse().map_err(|_| anyhow!("Invalid address: {}", text))?;
+    Ok(AddressPattern { ip, port: None })
+}
+
+/// Lower case without `-` and `_`, so `Who-Is`, `who_is` and `whois` compare equal
+fn normalise(name: &str) -> String {
+    name.chars().filter(|c| *c != '-' && *c != '_').flat_map(char::to_lowercase).collect()
+}
+
+impl AddressPattern {
+    fn matches(&self, addr: SocketAddr) -> bool {
+        addr.ip() == self.ip && self.port.is_none_or(|p| p == addr.port())
+    }
+}
+
+impl Condition {
+    fn matches(&self, datagram: &Datagram, devices: &DeviceAddresses) -> bool {
+        let packet = &datagram.packet;
+        match self {
+            Condition::Host(patterns) => patterns.iter().any(|p| {
+                p.matches(datagram.source) || p.matches(datagram.destination)
+            }),
+            Condition::Source(patterns) => patterns.iter().any(|p| p.matches(datagram.source)),
+            Condition::Destination(patterns) => patterns.iter().any(|p| p.matches(datagram.destination)),
+            Condition::Device(instances) => instances.iter().any(|i| names_device(datagram, *i, devices)),
+            Condition::Service(names) => packet.apdu.as_ref()
+                .and_then(ApduHeader::service_name)
+                .is_some_and(|service| names.contains(&normalise(&service))),
+            Condition::Type(types) => types.iter().any(|t| {
+                if t == "broadcast" { packet.is_broadcast() } else { t == packet.kind() }
+            }),
+            Condition::Network(networks) => {
+                let npdu = packet.npdu.as_ref();
+                let routed = [npdu.and_then(|n| n.destination.as_ref()), npdu.and_then(|n| n.source.as_ref())]
+                    .into_iter().flatten().any(|a| networks.contains(&a.net));
+                let listed = packet.network_message.as_ref().is_some_and(|m| m.networks.iter().any(|n| networks.contains(n)));
+                routed || listed
+            }
+        }
+    }
+}
+
+fn names_device(datagram: &Datagram, instance: u32, devices: &DeviceAddresses) -> bool {
+    let packet = &datagram.packet;
+    // A Who-Is covering the instance; one without limits asks every device
+    if let Some(ApduHeader::UnconfirmedRequest { service: 8 }) = packet.apdu {
+        return packet.who_is_range.is_none_or(|(low, high)| (low..=high).contains(&instance));
+    }
+    packet.iam_device == Some(instance) || devices.involves(instance, datagram)
+}
+
+/// Packet and byte counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Counter {
+    pub packets: u64,
+    pub bytes: u64,
+}
+
+impl Counter {
+    fn add(&mut self, bytes: usize) {
+        self.packets += 1;
+        self.bytes += bytes as u64;
+    }
+}
+
+/// Traffic counts over an interval
+#[derive(Debug, Clone, Default)]
+pub struct TrafficStats {
+    pub total: Counter,
+    pub broadcasts: u64,
+    pub errors: u64,
+    pub rejects: u64,
+    pub aborts: u64,
+    /// Per source IP
+    pub hosts: HashMap<IpAddr, Counter>,
+    /// Per service, network message or BVLL function
+    pub services: HashMap<String, Counter>,
+    first: Option<Duration>,
+    last: Option<Duration>,
+}
+
+/// Rows shown per table in the summary
+const TABLE_ROWS: usize = 15;
+
+impl TrafficStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, datagram: &Datagram) {
+        let packet = &datagram.packet;
+        self.total.add(datagram.length);
+        self.hosts.entry(datagram.source.ip()).or_default().add(datagram.length);
+        self.services.entry(packet.category()).or_default().add(datagram.length);
+        if packet.is_broadcast() {
+            self.broadcasts += 1;
+        }
+        match packet.kind() {
+            "error" => self.errors += 1,
+            "reject" => self.rejects += 1,
+            "abort" => self.aborts += 1,
+            _ => {}
+        }
+        self.first = Some(self.first.map_or(datagram.at, |f| f.min(datagram.at)));
+        self.last = Some(self.last.map_or(datagram.at, |l| l.max(datagram.at)));
+    }
+
+    /// Time between the first and last packet recorded
+    pub fn span(&self) -> Duration {
+        match (self.first, self.last) {
+            (Some(first), Some(last)) => last.saturating_sub(first),
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// The summary table, with rates over `elapsed`
+    pub fn lines(&self, elapsed: Duration) -> Vec<String> {
+        let secs = elapsed.as_secs_f64().max(0.001);
+        let rate = |count: u64| format!("{:.1}", count as f64 / secs);
+        let mut lines = vec![
+            format!(
+                "{} packets ({}/s), {} bytes over {:.1} s; {} broadcasts ({}/s)",
+                self.total.packets, rate(self.total.packets), self.total.bytes, elapsed.as_secs_f64(), self.broadcasts, rate(self.broadcasts)
+            ),
+            format!("Errors {}, rejects {}, aborts {}", self.errors, self.rejects, self.aborts),
+        ];
+
+        let mut table = |header: &str, counts: Vec<(String, Counter)>| {
+            let mut rows = vec![vec![header.to_string(), "PACKETS".to_string(), "PKT/S".to_string(), "BYTES".to_string()]];
+            let hidden = counts.len().saturating_sub(TABLE_ROWS);
+            rows.extend(counts.into_iter().take(TABLE_ROWS).map(|(name, c)| {
+                vec![name, c.packets.to_string(), rate(c.packets), c.bytes.to_string()]
+            }));
+            let widths: Vec<usize> = (0..4).map(|col| rows.iter().map(|r| r[col].chars().count()).max().unwrap_or(0)).collect();
+            lines.push(String::new());
+            lines.extend(rows.iter().map(|row| {
+                let cells: Vec<String> = row.iter().zip(&widths).map(|(cell, width)| format!("{:<width$}", cell, width = width)).collect();
+                cells.join("  ").trim_end().to_string()
+            }));
+            if hidden > 0 {
+                lines.push(format!("... {} more", hidden));
+            }
+        };
+        table("HOST", sorted(self.hosts.iter().map(|(ip, c)| (ip.to_string(), *c))));
+        table("SERVICE", sorted(self.services.iter().map(|(name, c)| (name.clone(), *c))));
+        lines
+    }
+}
+
+/// Busiest first, then by name
+fn sorted(counts: impl Iterator<Item = (String, Counter)>) -> Vec<(String, Counter)> {
+    let mut counts: Vec<_> = counts.collect();
+    counts.sort_by(|a, b| b.1.packets.cmp(&a.1.packets).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
@@ -1,10 +1,27 @@
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Table, Row},
+    symbols,
+    text::{Line, Span},
+    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, List, ListItem, Paragraph, Table, Row, Wrap},
     Frame,
 };
-use crate::app::{App, ViewState};
+use crate::app::{App, BacnetObject, SiteMarker, ViewState};
+use crate::bacnet::DeviceStatus;
+use crate::format::{format_present_value, format_property};
+use crate::health::{format_rtt, DeviceHealth, HealthGrade};
+use crate::mstp;
+use crate::topology::{tree_lines, NodeKind};
+use crate::traffic::time_of_day;
+use crate::property::{self, BacnetValue};
+use bacnet_rs::object::ObjectIdentifier;
+use std::net::SocketAddr;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Height of the trend pane below the object table
+const TREND_PANE_HEIGHT: u16 = 12;
+/// Narrowest time window the trend chart shows, so a few samples do not fill it
+const TREND_MIN_WINDOW_SECS: f64 = 60.0;
 
 pub fn render(f: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
@@ -22,6 +39,13 @@ pub fn render(f: &mut Frame, app: &mut App) {
         ViewState::InterfaceSelect => "BACnet Discovery Tool - Select Interface".to_string(),
         ViewState::DeviceList => "BACnet Discovery Tool - Devices".to_string(),
         ViewState::ObjectList(id) => format!("BACnet Discovery Tool - Device {} Objects", id),
+        ViewState::ObjectProperties(id, obj) => {
+            format!("BACnet Discovery Tool - Device {} {:?}:{} Properties", id, obj.object_type, obj.instance)
+        }
+        ViewState::ScanDiff => "BACnet Discovery Tool - Changes Since Baseline".to_string(),
+        ViewState::Traffic => "BACnet Discovery Tool - Traffic".to_string(),
+        ViewState::Health => "BACnet Discovery Tool - Device Health".to_string(),
+        ViewState::Topology => "BACnet Discovery Tool - Network Topology".to_string(),
     };
     
     let title = Paragraph::new(title_text)
@@ -29,25 +53,53 @@ pub fn render(f: &mut Frame, app: &mut App) {
         .block(Block::default().borders(Borders::ALL).title("Information"));
     f.render_widget(title, chunks[0]);
 
+    app.list_rows_area = None;
+
     match app.view_state {
         ViewState::InterfaceSelect => render_interface_list(f, chunks[1], app),
         ViewState::DeviceList => render_device_list(f, chunks[1], app),
         ViewState::ObjectList(id) => render_object_list(f, chunks[1], app, id),
+        ViewState::ObjectProperties(id, obj) => render_object_properties(f, chunks[1], app, id, obj),
+        ViewState::ScanDiff => render_scan_diff(f, chunks[1], app),
+        ViewState::Traffic => render_traffic(f, chunks[1], app),
+        ViewState::Health => render_health(f, chunks[1], app),
+        ViewState::Topology => render_topology(f, chunks[1], app),
     }
 
     // Status Bar
-    let status = Paragraph::new(app.status_message.as_str())
+    let status_text = if app.search_editing {
+        format!("Search: {}_  (Enter to keep, Esc to clear)", app.object_filter.query)
+    } else if let Some(input) = &app.traffic_filter_input {
+        format!("Filter: {}_  (e.g. service=readproperty src=10.0.0.5 !type=unconfirmed; Enter to apply, Esc to cancel)", input)
+    } else if let Some(input) = &app.jump_input {
+        format!("Go to: {}_  (device ID, instance or row number; Enter to jump, Esc to cancel)", input)
+    } else {
+        app.status_message.clone()
+    };
+    let status = Paragraph::new(status_text)
         .block(Block::default().borders(Borders::ALL).title("Status"));
     f.render_widget(status, chunks[2]);
 }
 
+/// Records where the rows of a bordered list are drawn, for mouse clicks and paging
+fn track_rows(app: &mut App, area: Rect, header_rows: u16) {
+    let inner = Block::default().borders(Borders::ALL).inner(area);
+    let rows = Rect { y: inner.y + header_rows, height: inner.height.saturating_sub(header_rows), ..inner };
+    app.list_rows_area = Some(rows);
+    app.page_size = rows.height as usize;
+}
+
 fn render_interface_list(f: &mut Frame, area: ratatui::layout::Rect, app: &mut App) {
-    let items: Vec<ListItem> = app.interfaces
+    track_rows(app, area, 0);
+    let mut items: Vec<ListItem> = app.interfaces
         .iter()
         .map(|iface| {
             ListItem::new(format!("{} ({})", iface.name, iface.addr.ip()))
         })
         .collect();
+    if let Some(port) = &app.mstp_port {
+        items.push(ListItem::new(format!("MS/TP ({})", port)));
+    }
 
     let list = List::new(items)
         .block(Block::default().borders(Borders::ALL).title("Network Interfaces"))
@@ -56,26 +108,279 @@ fn render_interface_list(f: &mut Frame, area: ratatui::layout::Rect, app: &mut A
     f.render_stateful_widget(list, area, &mut app.interface_list_state);
 }
 
+fn render_scan_diff(f: &mut Frame, area: Rect, app: &mut App) {
+    track_rows(app, area, 0);
+    let lines = app.scan_diff.as_ref().map(|diff| diff.lines()).unwrap_or_default();
+    let items: Vec<ListItem> = lines.into_iter().map(|line| {
+        let color = match line.trim_start().chars().next() {
+            Some('+') => Color::Green,
+            Some('-') => Color::Red,
+            Some('~') => Color::Yellow,
+            _ => Color::White,
+        };
+        ListItem::new(line).style(Style::default().fg(color))
+    }).collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title("Scan Diff (Esc to go back)"))
+        .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+    f.render_stateful_widget(list, area, &mut app.diff_list_state);
+}
+
+fn render_topology(f: &mut Frame, area: Rect, app: &mut App) {
+    track_rows(app, area, 0);
+    let nodes = app.topology_tree();
+    let items: Vec<ListItem> = nodes.iter().zip(tree_lines(&nodes)).map(|(node, line)| {
+        let style = match node.kind {
+            NodeKind::Subnet => Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            NodeKind::Host => Style::default().fg(Color::Cyan),
+            NodeKind::Peer => Style::default().fg(Color::Magenta),
+            NodeKind::Network => Style::default().fg(Color::Green),
+            NodeKind::Device => Style::default(),
+        };
+        ListItem::new(line).style(style)
+    }).collect();
+
+    let title = if items.is_empty() {
+        "Network Topology - nothing learned yet; press 'd' to ask routers and BBMDs".to_string()
+    } else {
+        let subnets = nodes.iter().filter(|n| n.kind == NodeKind::Subnet).count();
+        let networks = nodes.iter().filter(|n| n.kind == NodeKind::Network).count();
+        format!("Network Topology - {} subnets, {} routed networks ('x' exports DOT)", subnets, networks)
+    };
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+    f.render_stateful_widget(list, area, &mut app.topology_list_state);
+}
+
+/// Height of the decoded packet pane below the traffic list
+const TRAFFIC_DETAIL_HEIGHT: u16 = 14;
+
+fn render_traffic(f: &mut Frame, area: Rect, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(5), Constraint::Length(TRAFFIC_DETAIL_HEIGHT)])
+        .split(area);
+    app.follow_traffic();
+    track_rows(app, chunks[0], 0);
+
+    let log = app.traffic.lock().unwrap();
+    let visible = log.matching(&app.traffic_filter);
+    let items: Vec<ListItem> = visible.iter().filter_map(|i| log.get(*i)).map(|d| {
+        let color = match d.packet.kind() {
+            "error" | "reject" | "abort" | "malformed" => Color::Red,
+            "network" | "bvll" => Color::Yellow,
+            _ if d.packet.is_broadcast() => Color::Cyan,
+            _ => Color::White,
+        };
+        let text = format!("{}  {:<21} -> {:<21} {:>5}  {}", time_of_day(d.at), d.source, d.destination, d.length, d.packet.summary());
+        ListItem::new(text).style(Style::default().fg(color))
+    }).collect();
+
+    let mut title = format!("Traffic ({} of {} packets", visible.len(), log.len());
+    if !app.traffic_filter.is_empty() {
+        title.push_str(&format!(", filter: {}", app.traffic_filter.as_str()));
+    }
+    title.push(')');
+    if log.is_paused() {
+        title.push_str(&format!(" PAUSED, {} missed", log.missed));
+    }
+    let detail: Vec<String> = app.traffic_list_state.selected()
+        .and_then(|row| visible.get(row))
+        .and_then(|i| log.get(*i))
+        .map(|d| d.packet.lines())
+        .unwrap_or_default();
+    drop(log);
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+        .highlight_symbol(">> ");
+    f.render_stateful_widget(list, chunks[0], &mut app.traffic_list_state);
+
+    let detail = Paragraph::new(detail.join("\n"))
+        .block(Block::default().borders(Borders::ALL).title("Decode"))
+        .wrap(Wrap { trim: false });
+    f.render_widget(detail, chunks[1]);
+}
+
+fn grade_color(grade: HealthGrade) -> Color {
+    match grade {
+        HealthGrade::Good => Color::Green,
+        HealthGrade::Fair => Color::Yellow,
+        HealthGrade::Poor => Color::Red,
+    }
+}
+
+/// Height of the broadcast monitor pane below the health table
+const BROADCAST_PANE_HEIGHT: u16 = 14;
+
+fn render_health(f: &mut Frame, area: Rect, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(6), Constraint::Length(BROADCAST_PANE_HEIGHT)])
+        .split(area);
+    let area = chunks[0];
+    track_rows(app, area, 2);
+    let ranking = app.health_ranking();
+    let header = Row::new(vec![
+        "#", "Device", "Address", "Health", "Requests", "Timeouts", "Errors", "Rejects", "Aborts", "Fail %", "Min RTT", "Avg RTT", "Max RTT", "Jitter",
+    ])
+    .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+    .bottom_margin(1);
+
+    let rows: Vec<Row> = ranking.iter().enumerate().map(|(rank, (device_id, addr, h))| {
+        Row::new(vec![
+            (rank + 1).to_string(),
+            device_id.map_or_else(|| "?".to_string(), |id| id.to_string()),
+            format_address(addr),
+            h.grade().label().to_string(),
+            h.requests.to_string(),
+            h.timeouts.to_string(),
+            h.errors.to_string(),
+            h.rejects.to_string(),
+            h.aborts.to_string(),
+            format!("{:.1}", h.failure_rate() * 100.0),
+            format_rtt(h.min_rtt),
+            format_rtt(h.avg_rtt()),
+            format_rtt(h.max_rtt),
+            format_rtt(h.jitter()),
+        ])
+        .style(Style::default().fg(grade_color(h.grade())))
+    }).collect();
+
+    let title = if ranking.is_empty() {
+        "Device Health - no confirmed requests sent yet".to_string()
+    } else {
+        format!("Device Health - {} devices, worst first", ranking.len())
+    };
+    let table = Table::new(rows, [
+        Constraint::Length(4),
+        Constraint::Length(9),
+        Constraint::Min(16),
+        Constraint::Length(7),
+        Constraint::Length(9),
+        Constraint::Length(9),
+        Constraint::Length(7),
+        Constraint::Length(8),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(9),
+        Constraint::Length(9),
+        Constraint::Length(9),
+        Constraint::Length(9),
+    ])
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(title))
+    .row_highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+    .highlight_symbol(">> ");
+    f.render_stateful_widget(table, area, &mut app.health_table_state);
+
+    render_broadcasts(f, chunks[1], app);
+}
+
+/// Broadcast rates and warnings, with the busiest sources and services beside them
+fn render_broadcasts(f: &mut Frame, area: Rect, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(30), Constraint::Percentage(30)])
+        .split(area);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let monitor = app.broadcasts.lock().unwrap();
+    let limits = monitor.thresholds;
+
+    let mut lines = vec![
+        Line::from(format!("{:.1} broadcasts/s", monitor.total_rate(now))),
+        Line::from(format!("Limits: {:.1}/s total, {:.1}/s per source, {:.1}/s per service", limits.total, limits.per_source, limits.per_service)),
+        Line::from(""),
+    ];
+    if app.broadcast_warnings.is_empty() {
+        lines.push(Line::styled("No broadcast thresholds exceeded.", Style::default().fg(Color::Green)));
+    }
+    lines.extend(app.broadcast_warnings.iter().map(|w| {
+        Line::styled(w.to_string(), Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+    }));
+    let summary = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title("Broadcasts"));
+    f.render_widget(summary, chunks[0]);
+
+    for (chunk, title, talkers, limit) in [
+        (chunks[1], "Top Sources", monitor.top_sources(now), limits.per_source),
+        (chunks[2], "Top Services", monitor.top_services(now), limits.per_service),
+    ] {
+        let rows: Vec<Row> = talkers.into_iter().map(|t| {
+            let style = if t.rate > limit { Style::default().fg(Color::Red) } else { Style::default() };
+            Row::new(vec![t.name, t.packets.to_string(), format!("{:.1}", t.rate)]).style(style)
+        }).collect();
+        let header = Row::new(vec!["Name", "Packets", "Per s"])
+            .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+        let table = Table::new(rows, [Constraint::Min(10), Constraint::Length(8), Constraint::Length(7)])
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(table, chunk);
+    }
+}
+
+/// Health column of the device list; devices no request was sent to show "-"
+fn health_cell(health: Option<&DeviceHealth>) -> Span<'static> {
+    match health {
+        Some(h) => Span::styled(format!("{:<14}", h.summary()), Style::default().fg(grade_color(h.grade()))),
+        None => Span::raw(format!("{:<14}", "-")),
+    }
+}
+
 fn render_device_list(f: &mut Frame, area: ratatui::layout::Rect, app: &mut App) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
         .split(area);
+    track_rows(app, chunks[0], 0);
 
     let devices_lock = app.devices.lock().unwrap();
-    let mut device_ids: Vec<_> = devices_lock.keys().cloned().collect();
-    device_ids.sort();
+    let address_book = app.address_book.lock().unwrap();
+    let device_properties = app.device_properties.lock().unwrap();
+    let device_ids = app.device_sort.apply(&devices_lock, &device_properties);
+    let health = app.health.lock().unwrap();
 
     let items: Vec<ListItem> = device_ids
         .iter()
         .map(|id| {
             let d = &devices_lock[id];
-            ListItem::new(format!("Device ID: {} ({})", d.device_id, d.vendor_name))
+            let health_column = health_cell(health.get(&d.address));
+            let label = match device_properties.get(id).and_then(|p| property::find(p, property::OBJECT_NAME)) {
+                Some(BacnetValue::CharacterString(name)) => format!("Device ID: {} ({}) {}", d.device_id, d.vendor_name, name),
+                _ => format!("Device ID: {} ({})", d.device_id, d.vendor_name),
+            };
+            let marker = app.site_marker(d.device_id);
+            let label = match marker {
+                Some(SiteMarker::New) => format!("[NEW] {}", label),
+                Some(SiteMarker::Missing) => format!("[MISSING] {}", label),
+                None => label,
+            };
+            let item = |label: String| ListItem::new(Line::from(vec![health_column.clone(), Span::raw(label)]));
+            if address_book.is_duplicate(d.device_id) {
+                item(format!("[DUPLICATE] {}", label))
+                    .style(Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD))
+            } else if address_book.previous_address(d.device_id).is_some() {
+                item(format!("[ADDRESS CHANGED] {}", label))
+                    .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            } else {
+                match (d.status, marker) {
+                    (_, Some(SiteMarker::Missing)) => item(label).style(Style::default().fg(Color::DarkGray)),
+                    (DeviceStatus::Online, Some(SiteMarker::New)) => item(label).style(Style::default().fg(Color::Green)),
+                    (DeviceStatus::Online, _) => item(label),
+                    (DeviceStatus::Offline, _) => item(label).style(Style::default().fg(Color::Red)),
+                }
+            }
         })
         .collect();
 
     let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title("Discovered Devices"))
+        .block(Block::default().borders(Borders::ALL).title(format!("Discovered Devices - sorted by {}", app.device_sort.describe())))
         .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
         .highlight_symbol(">> ");
     f.render_stateful_widget(list, chunks[0], &mut app.list_state);
@@ -85,67 +390,132 @@ fn render_device_list(f: &mut Frame, area: ratatui::layout::Rect, app: &mut App)
         .and_then(|id| devices_lock.get(id));
 
     let details_text = match selected_device {
-        Some(d) => format!(
-            "Device ID:     {}\n\
-             IP Address:    {}\n\
-             Vendor:        {} (ID: {})\n\
-             Max APDU:      {}\n\
-             Segmentation:  {}\n\
-             Last Seen:     {}s ago\n\n\
-             Press 'Enter' to view objects.",
-            d.device_id,
-            d.address,
-            d.vendor_name,
-            d.vendor_id,
-            d.max_apdu,
-            match d.segmentation {
-                0 => "Both",
-                1 => "Transmit",
-                2 => "Receive",
-                3 => "None",
-                _ => "Unknown",
-            },
-            d.last_seen.elapsed().as_secs()
-        ),
+        Some(d) => {
+            let mut text = format!(
+                "Device ID:     {}\n\
+                 Address:       {}\n\
+                 Vendor:        {} (ID: {})\n\
+                 Max APDU:      {}\n\
+                 Segmentation:  {}\n\
+                 Status:        {}\n\
+                 Last Seen:     {}s ago\n",
+                d.device_id,
+                format_address(&d.address),
+                d.vendor_name,
+                d.vendor_id,
+                d.max_apdu,
+                property::segmentation_name(d.segmentation),
+                match d.status {
+                    DeviceStatus::Online => "Online".to_string(),
+                    DeviceStatus::Offline => format!("OFFLINE ({} timeouts)", d.consecutive_timeouts),
+                },
+                d.last_seen.elapsed().as_secs()
+            );
+            if let Some(h) = health.get(&d.address) {
+                text.push_str(&format!(
+                    "\nHealth:        {} ({} requests, {} timeouts, {} errors, {} rejects, {} aborts)\n\
+                     Round Trip:    min {}, avg {}, max {}, jitter {}\n",
+                    h.grade().label(), h.requests, h.timeouts, h.errors, h.rejects, h.aborts,
+                    format_rtt(h.min_rtt), format_rtt(h.avg_rtt()), format_rtt(h.max_rtt), format_rtt(h.jitter())
+                ));
+            }
+            if let Some(props) = device_properties.get(&d.device_id) {
+                text.push_str("\nDevice Object:\n");
+                for entry in props.iter().filter(|p| p.value.is_ok()) {
+                    text.push_str(&format!("  {:<32} {}\n", format!("{}:", property::property_name(entry.id)), format_property(entry)));
+                }
+            }
+            if let Some(history) = address_book.get(d.device_id).filter(|h| h.records.len() > 1) {
+                text.push_str(if history.duplicate {
+                    "\nDUPLICATE INSTANCE - addresses seen:\n"
+                } else {
+                    "\nAddress history:\n"
+                });
+                for record in &history.records {
+                    text.push_str(&format!(
+                        "  {} ({} I-Am, last {}s ago)\n",
+                        format_address(&record.address),
+                        record.count,
+                        record.last_seen.elapsed().as_secs()
+                    ));
+                }
+            }
+            text.push_str("\nPress 'Enter' to view objects.");
+            text
+        }
         None => "Press 'd' to scan for devices.\nSelect a device to view details.".to_string(),
     };
 
     let details = Paragraph::new(details_text)
+        .wrap(Wrap { trim: false })
         .block(Block::default().borders(Borders::ALL).title("Device Details"));
     f.render_widget(details, chunks[1]);
 }
 
 fn render_object_list(f: &mut Frame, area: ratatui::layout::Rect, app: &mut App, device_id: u32) {
+    let has_objects = app.device_objects.lock().unwrap().contains_key(&device_id);
+    let (table_area, trend_area) = if has_objects {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(6), Constraint::Length(TREND_PANE_HEIGHT)])
+            .split(area);
+        (chunks[0], Some(chunks[1]))
+    } else {
+        (area, None)
+    };
+    track_rows(app, table_area, 2);
     let objects_lock = app.device_objects.lock().unwrap();
     let objects = objects_lock.get(&device_id);
 
     match objects {
         Some(objs) => {
-            let header = Row::new(vec!["ID", "Name", "Value", "Units"])
+            let header = Row::new(vec!["ID", "Name", "Value", "Units", "Flags"])
                 .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
                 .bottom_margin(1);
             
-            let rows: Vec<Row> = objs.iter().map(|obj| {
-                Row::new(vec![
+            let visible = app.visible_objects(objs);
+            let title = if app.object_filter.is_active() {
+                format!("Objects (Points) - {} of {} [{}] - sorted by {}", visible.len(), objs.len(), app.object_filter.describe(), app.object_sort.describe())
+            } else {
+                format!("Objects (Points) - {} - sorted by {}", objs.len(), app.object_sort.describe())
+            };
+
+            let rows: Vec<Row> = visible.iter().map(|obj| {
+                let row = Row::new(vec![
                     format!("{:?}:{}", obj.id.object_type, obj.id.instance),
                     obj.name.clone(),
-                    obj.present_value.clone(),
+                    format_present_value(obj),
                     obj.units.clone(),
-                ])
+                    format_flags(obj),
+                ]);
+                if obj.in_alarm() {
+                    row.style(Style::default().fg(Color::Red))
+                } else if obj.is_faulted() {
+                    row.style(Style::default().fg(Color::Yellow))
+                } else if obj.is_overridden() {
+                    row.style(Style::default().fg(Color::Magenta))
+                } else {
+                    row
+                }
             }).collect();
 
             let table = Table::new(rows, [
-                Constraint::Percentage(25),
-                Constraint::Percentage(35),
                 Constraint::Percentage(20),
+                Constraint::Percentage(30),
+                Constraint::Percentage(15),
+                Constraint::Percentage(15),
                 Constraint::Percentage(20),
             ])
             .header(header)
-            .block(Block::default().borders(Borders::ALL).title("Objects (Points)"))
+            .block(Block::default().borders(Borders::ALL).title(title))
             .row_highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
             .highlight_symbol(">> ");
 
-            f.render_stateful_widget(table, area, &mut app.object_table_state);
+            let selected = app.object_table_state.selected().and_then(|i| visible.get(i).copied());
+            if let Some(trend_area) = trend_area {
+                render_trend(f, trend_area, selected);
+            }
+            f.render_stateful_widget(table, table_area, &mut app.object_table_state);
         }
         None => {
             let msg = "No points discovered yet.\nPress 'd' to discover points for this device.";
@@ -156,3 +526,111 @@ fn render_object_list(f: &mut Frame, area: ratatui::layout::Rect, app: &mut App,
         }
     }
 }
+
+/// Chart of the selected point's recent samples with min/max/avg over the window.
+fn render_trend(f: &mut Frame, area: Rect, point: Option<&BacnetObject>) {
+    let Some(point) = point else {
+        let p = Paragraph::new("Select a point to see its trend.")
+            .block(Block::default().borders(Borders::ALL).title("Trend"))
+            .style(Style::default().fg(Color::Gray));
+        f.render_widget(p, area);
+        return;
+    };
+    let (Some(stats), Some(oldest)) = (point.history.stats(), point.history.iter().next()) else {
+        let p = Paragraph::new("No numeric samples yet; values are recorded on every poll.")
+            .block(Block::default().borders(Borders::ALL).title(format!("Trend: {}", point.name)))
+            .style(Style::default().fg(Color::Gray));
+        f.render_widget(p, area);
+        return;
+    };
+
+    // Seconds before now, so a point that stopped updating drifts to the left
+    let now = Instant::now();
+    let data: Vec<(f64, f64)> = point.history.iter()
+        .map(|s| (-(now.duration_since(s.at).as_secs_f64()), s.value))
+        .collect();
+    let window = now.duration_since(oldest.at).as_secs_f64().max(TREND_MIN_WINDOW_SECS);
+    let padding = ((stats.max - stats.min) * 0.05).max(0.5);
+    let (y_min, y_max) = (stats.min - padding, stats.max + padding);
+
+    let title = format!(
+        "Trend: {} - min {:.2}  max {:.2}  avg {:.2}  ({} samples over {}s)",
+        point.name, stats.min, stats.max, stats.avg, stats.count, point.history.span().as_secs()
+    );
+    let dataset = Dataset::default()
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(Color::Cyan))
+        .data(&data);
+    let chart = Chart::new(vec![dataset])
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .x_axis(Axis::default()
+            .style(Style::default().fg(Color::Gray))
+            .bounds([-window, 0.0])
+            .labels([format!("-{:.0}s", window), "now".to_string()]))
+        .y_axis(Axis::default()
+            .style(Style::default().fg(Color::Gray))
+            .bounds([y_min, y_max])
+            .labels([format!("{:.2}", y_min), format!("{:.2}", y_max)]));
+    f.render_widget(chart, area);
+}
+
+/// Short form of the status flags, plus the reliability when it reports a problem.
+fn format_flags(obj: &BacnetObject) -> String {
+    let mut flags = Vec::new();
+    if obj.in_alarm() { flags.push("ALARM".to_string()); }
+    if obj.status_flags.is_some_and(|f| f.fault) { flags.push("FAULT".to_string()); }
+    if obj.is_overridden() { flags.push("OVRD".to_string()); }
+    if obj.is_out_of_service() { flags.push("OOS".to_string()); }
+    if let Some(r) = obj.reliability.filter(|r| *r != 0) {
+        flags.push(property::reliability_name(r).map_or_else(|| format!("Reliability {}", r), str::to_string));
+    }
+    match (flags.is_empty(), obj.status_flags) {
+        (false, _) => flags.join(" "),
+        (true, Some(_)) => "-".to_string(),
+        (true, None) => String::new(),
+    }
+}
+
+fn render_object_properties(f: &mut Frame, area: ratatui::layout::Rect, app: &mut App, device_id: u32, object_id: ObjectIdentifier) {
+    track_rows(app, area, 2);
+    let properties_lock = app.object_properties.lock().unwrap();
+
+    match properties_lock.get(&(device_id, object_id)) {
+        Some(props) => {
+            let header = Row::new(vec!["Property", "Value"])
+                .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+                .bottom_margin(1);
+
+            let rows: Vec<Row> = props.iter().map(|entry| {
+                let name = match entry.array_index {
+                    Some(index) => format!("{}[{}]", property::property_name(entry.id), index),
+                    None => property::property_name(entry.id),
+                };
+                let row = Row::new(vec![name, format_property(entry)]);
+                if entry.value.is_err() { row.style(Style::default().fg(Color::DarkGray)) } else { row }
+            }).collect();
+
+            let table = Table::new(rows, [Constraint::Percentage(35), Constraint::Percentage(65)])
+                .header(header)
+                .block(Block::default().borders(Borders::ALL).title(format!("Properties ({})", props.len())))
+                .row_highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+                .highlight_symbol(">> ");
+
+            f.render_stateful_widget(table, area, &mut app.property_table_state);
+        }
+        None => {
+            let p = Paragraph::new("Reading properties...")
+                .block(Block::default().borders(Borders::ALL).title("Properties"))
+                .style(Style::default().fg(Color::Gray));
+            f.render_widget(p, area);
+        }
+    }
+}
+
+fn format_address(addr: &SocketAddr) -> String {
+    match mstp::addr_to_mac(addr) {
+        Some(mac) => format!("MS/TP MAC {}", mac),
+        None => addr.to_string(),
+    }
+}
@@ -0,0 +1,51 @@
+use bacnet_discovery::address_book::{AddressBook, AddressEvent, DUPLICATE_WINDOW};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+fn addr(s: &str) -> SocketAddr {
+    s.parse().unwrap()
+}
+
+#[test]
+fn test_same_address_is_not_a_conflict() {
+    let mut book = AddressBook::new();
+    let now = Instant::now();
+    assert_eq!(book.record(100, addr("10.0.0.1:47808"), now), None);
+    assert_eq!(book.record(100, addr("10.0.0.1:47808"), now + Duration::from_secs(60)), None);
+    assert!(book.conflicts().is_empty());
+    assert_eq!(book.get(100).unwrap().records[0].count, 2);
+}
+
+#[test]
+fn test_duplicate_instance_detected() {
+    let mut book = AddressBook::new();
+    let now = Instant::now();
+    book.record(100, addr("10.0.0.1:47808"), now);
+    let event = book.record(100, addr("10.0.0.2:47808"), now + Duration::from_millis(300));
+    assert_eq!(event, Some(AddressEvent::Duplicate {
+        device_id: 100,
+        addresses: vec![addr("10.0.0.1:47808"), addr("10.0.0.2:47808")],
+    }));
+    assert!(book.is_duplicate(100));
+
+    // Once flagged, later flips keep reporting the duplicate
+    let later = now + DUPLICATE_WINDOW * 3;
+    assert!(matches!(book.record(100, addr("10.0.0.1:47808"), later), Some(AddressEvent::Duplicate { .. })));
+    assert_eq!(book.conflicts().len(), 1);
+}
+
+#[test]
+fn test_address_change_detected() {
+    let mut book = AddressBook::new();
+    let now = Instant::now();
+    book.record(200, addr("10.0.0.5:47808"), now);
+    let event = book.record(200, addr("10.0.0.9:47808"), now + DUPLICATE_WINDOW + Duration::from_secs(1));
+    assert_eq!(event, Some(AddressEvent::Changed {
+        device_id: 200,
+        from: addr("10.0.0.5:47808"),
+        to: addr("10.0.0.9:47808"),
+    }));
+    assert!(!book.is_duplicate(200));
+    assert_eq!(book.previous_address(200), Some(addr("10.0.0.5:47808")));
+    assert_eq!(book.get(200).unwrap().records.len(), 2);
+}
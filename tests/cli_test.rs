@@ -0,0 +1,17 @@
+use bacnet_discovery::cli::{parse_duration, MAX_DURATION};
+use std::time::Duration;
+
+#[test]
+fn test_parse_duration() {
+    assert_eq!(parse_duration("5").unwrap(), Duration::from_secs(5));
+    assert_eq!(parse_duration("0.25").unwrap(), Duration::from_millis(250));
+    assert_eq!(parse_duration("86400").unwrap(), MAX_DURATION);
+
+    for invalid in ["", "abc", "-1", "NaN", "inf"] {
+        assert!(parse_duration(invalid).is_err(), "{:?} was accepted", invalid);
+    }
+    // Representable, but would overflow the deadline
+    let err = parse_duration("1e19").unwrap_err();
+    assert!(err.to_string().contains("too long"), "{}", err);
+    assert!(parse_duration("86400.5").is_err());
+}
@@ -0,0 +1,25 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+use bacnet_discovery::app::BacnetObject;
+use bacnet_discovery::bacnet::DiscoveredDevice;
+use bacnet_discovery::property::PresentValue;
+use bacnet_rs::object::{ObjectIdentifier, ObjectType};
+
+/// A Delta Controls device without segmentation support at `address`
+pub fn device(device_id: u32, address: &str) -> DiscoveredDevice {
+    DiscoveredDevice {
+        vendor_id: 8,
+        vendor_name: "Delta Controls".to_string(),
+        segmentation: 0,
+        ..DiscoveredDevice::new(device_id, address.parse().unwrap())
+    }
+}
+
+/// A point holding `value`
+pub fn point(object_type: ObjectType, instance: u32, name: &str, value: PresentValue) -> BacnetObject {
+    BacnetObject {
+        present_value: value,
+        ..BacnetObject::new(ObjectIdentifier::new(object_type, instance), name.to_string())
+    }
+}
@@ -0,0 +1,163 @@
+use bacnet_discovery::decode::{ApduHeader, Field, decode_packet};
+use bacnet_discovery::property::decode_value;
+
+fn field<'a>(fields: &'a [Field], name: &str) -> &'a Field {
+    fields.iter().find(|f| f.name == name).unwrap_or_else(|| panic!("no field {} in {:?}", name, fields))
+}
+
+#[test]
+fn test_who_is_with_range() {
+    let packet = decode_packet(&[0x81, 0x0B, 0x00, 0x0D, 0x01, 0x00, 0x10, 0x08, 0x09, 0x64, 0x1A, 0x03, 0xE8]);
+    assert_eq!(packet.error, None);
+    assert_eq!(packet.bvll.as_ref().unwrap().function, 0x0B);
+    assert_eq!(packet.apdu, Some(ApduHeader::UnconfirmedRequest { service: 8 }));
+    assert_eq!(field(&packet.parameters, "Low limit").value, "100");
+    assert_eq!(field(&packet.parameters, "High limit").value, "1000");
+    assert_eq!(packet.summary(), "Who-Is 100 1000");
+    assert_eq!(packet.who_is_range, Some((100, 1000)));
+    assert!(packet.lines()[0].starts_with("BVLL: Original-Broadcast-NPDU (0x0B)"));
+}
+
+#[test]
+fn test_i_am() {
+    let packet = decode_packet(&[
+        0x81, 0x0A, 0x00, 0x14, 0x01, 0x00, 0x10, 0x00,
+        0xC4, 0x02, 0x00, 0x03, 0xE9, 0x22, 0x05, 0xC4, 0x91, 0x00, 0x21, 0x0F,
+    ]);
+    assert_eq!(packet.error, None);
+    assert_eq!(field(&packet.parameters, "Device").value, "Device:1001");
+    assert_eq!(packet.iam_device, Some(1001));
+    assert_eq!(field(&packet.parameters, "Max APDU").value, "1476");
+    assert_eq!(field(&packet.parameters, "Segmentation").value, "Both (0)");
+    assert!(field(&packet.parameters, "Vendor").value.ends_with("(15)"));
+}
+
+#[test]
+fn test_read_property_request_and_ack() {
+    let request = decode_packet(&[
+        0x81, 0x0A, 0x00, 0x11, 0x01, 0x04, 0x02, 0x05, 0x07, 0x0C,
+        0x0C, 0x00, 0x00, 0x00, 0x01, 0x19, 0x55,
+    ]);
+    assert_eq!(request.error, None);
+    let npdu = request.npdu.as_ref().unwrap();
+    assert!(npdu.expecting_reply());
+    assert!(!npdu.is_network_message());
+    match request.apdu.unwrap() {
+        ApduHeader::ConfirmedRequest { invoke_id, service, max_apdu, segmented, .. } => {
+            assert_eq!((invoke_id, service, max_apdu, segmented), (7, 12, 1476, false));
+        }
+        other => panic!("unexpected APDU {:?}", other),
+    }
+    assert_eq!(field(&request.parameters, "Object").value, "AnalogInput:1");
+    assert_eq!(field(&request.parameters, "Property").value, "Present_Value (85)");
+    assert_eq!(request.summary(), "ReadProperty request AnalogInput:1 Present_Value (85) (invoke 7)");
+
+    let ack = decode_packet(&[
+        0x81, 0x0A, 0x00, 0x17, 0x01, 0x00, 0x30, 0x07, 0x0C,
+        0x0C, 0x00, 0x00, 0x00, 0x01, 0x19, 0x55, 0x3E, 0x44, 0x42, 0x28, 0x00, 0x00, 0x3F,
+    ]);
+    assert_eq!(ack.error, None);
+    assert_eq!(ack.apdu.unwrap().invoke_id(), Some(7));
+    assert!(field(&ack.parameters, "Value").value.contains("42"));
+    assert!(ack.lines().iter().any(|l| l.starts_with("APDU: Complex-ACK, invoke 7, service ReadProperty (12)")));
+}
+
+#[test]
+fn test_read_property_multiple_request() {
+    let packet = decode_packet(&[
+        0x81, 0x0A, 0x00, 0x15, 0x01, 0x04, 0x00, 0x05, 0x08, 0x0E,
+        0x0C, 0x00, 0x00, 0x00, 0x01, 0x1E, 0x09, 0x55, 0x09, 0x4D, 0x1F,
+    ]);
+    assert_eq!(packet.error, None);
+    let properties = field(&packet.parameters, "Properties");
+    let names: Vec<&str> = properties.children.iter().map(|f| f.value.as_str()).collect();
+    assert_eq!(names, ["Present_Value (85)", "Object_Name (77)"]);
+    assert!(packet.lines().contains(&"    Property: Object_Name (77)".to_string()));
+}
+
+#[test]
+fn test_error_and_reject() {
+    let error = decode_packet(&[0x81, 0x0A, 0x00, 0x0D, 0x01, 0x00, 0x50, 0x07, 0x0C, 0x91, 0x02, 0x91, 0x20]);
+    assert_eq!(error.error, None);
+    assert_eq!(field(&error.parameters, "Error class").value, "Property (2)");
+    assert_eq!(field(&error.parameters, "Error code").value, "Unknown-Property (32)");
+    assert_eq!(error.summary(), "ReadProperty error Property (2) Unknown-Property (32) (invoke 7)");
+
+    let reject = decode_packet(&[0x81, 0x0A, 0x00, 0x09, 0x01, 0x00, 0x60, 0x07, 0x09]);
+    assert_eq!(reject.apdu, Some(ApduHeader::Reject { invoke_id: 7, reason: 9 }));
+    assert_eq!(reject.summary(), "Reject: Unrecognized-Service (invoke 7)");
+}
+
+#[test]
+fn test_routed_npdu() {
+    let packet = decode_packet(&[
+        0x81, 0x0A, 0x00, 0x12, 0x01, 0x2C, 0x00, 0x05, 0x01, 0x0A,
+        0x00, 0x07, 0x02, 0x12, 0x34, 0xFF, 0x10, 0x08,
+    ]);
+    assert_eq!(packet.error, None);
+    let npdu = packet.npdu.as_ref().unwrap();
+    assert_eq!(npdu.destination.as_ref().map(|d| (d.net, d.mac.clone())), Some((5, vec![0x0A])));
+    assert_eq!(npdu.source.as_ref().map(|s| (s.net, s.mac.clone())), Some((7, vec![0x12, 0x34])));
+    assert_eq!(npdu.hop_count, Some(255));
+    assert!(packet.lines()[1].ends_with("DNET 5 DADR 0A, SNET 7 SADR 12:34, hop count 255"));
+    assert_eq!(packet.summary(), "Who-Is");
+}
+
+#[test]
+fn test_network_message_and_forwarded_npdu() {
+    let packet = decode_packet(&[0x81, 0x0B, 0x00, 0x0B, 0x01, 0x80, 0x01, 0x00, 0x05, 0x00, 0x06]);
+    assert_eq!(packet.error, None);
+    let message = packet.network_message.as_ref().unwrap();
+    assert_eq!(message.message_type, 1);
+    let networks: Vec<&str> = message.fields.iter().map(|f| f.value.as_str()).collect();
+    assert_eq!(networks, ["5", "6"]);
+    assert_eq!(message.networks, [5, 6]);
+    assert_eq!(packet.summary(), "I-Am-Router-To-Network");
+    assert_eq!(packet.apdu, None);
+
+    let forwarded = decode_packet(&[0x81, 0x04, 0x00, 0x0E, 0xC0, 0xA8, 0x01, 0x05, 0xBA, 0xC0, 0x01, 0x00, 0x10, 0x08]);
+    assert_eq!(forwarded.bvll.unwrap().origin, Some("192.168.1.5:47808".parse().unwrap()));
+    assert_eq!(forwarded.apdu, Some(ApduHeader::UnconfirmedRequest { service: 8 }));
+}
+
+#[test]
+fn test_read_bdt_ack() {
+    let packet = decode_packet(&[
+        0x81, 0x03, 0x00, 0x18,
+        0xC0, 0xA8, 0x01, 0x01, 0xBA, 0xC0, 0xFF, 0xFF, 0xFF, 0xFF,
+        0x0A, 0x00, 0x00, 0x01, 0xBA, 0xC0, 0xFF, 0xFF, 0xFF, 0x00,
+    ]);
+    let bvll = packet.bvll.unwrap();
+    let entries: Vec<String> = bvll.bdt.iter().map(|e| format!("{} {}", e.address, e.mask)).collect();
+    assert_eq!(entries, ["192.168.1.1:47808 255.255.255.255", "10.0.0.1:47808 255.255.255.0"]);
+    assert_eq!(field(&bvll.fields, "BDT entry").value, "192.168.1.1:47808 mask 255.255.255.255");
+}
+
+#[test]
+fn test_malformed_packets() {
+    let packet = decode_packet(b"hello");
+    assert!(packet.bvll.is_none());
+    assert!(packet.error.is_some());
+
+    let short = decode_packet(&[0x81, 0x0A, 0x00, 0x20, 0x01, 0x00]);
+    assert!(short.error.unwrap().contains("BVLL length 32"));
+
+    // Truncated context tag: the decoded part is kept and the rest shown as hex
+    let truncated = decode_packet(&[0x81, 0x0A, 0x00, 0x0C, 0x01, 0x04, 0x02, 0x05, 0x07, 0x0C, 0x0C, 0x00]);
+    assert!(matches!(truncated.apdu, Some(ApduHeader::ConfirmedRequest { invoke_id: 7, .. })));
+    assert_eq!(field(&truncated.parameters, "Undecoded").value, "0C 00");
+    assert!(truncated.error.is_some());
+}
+
+#[test]
+fn test_deeply_nested_tags() {
+    // A run of opening tags is rejected past the nesting limit instead of overflowing the stack
+    let run = vec![0x0E; 64 * 1024];
+    assert_eq!(decode_value(&run), None);
+
+    let mut packet = vec![0x81, 0x0B, 0xFF, 0xFF, 0x01, 0x00, 0x10, 0x08];
+    packet.resize(0xFFFF, 0x0E);
+    let decoded = decode_packet(&packet);
+    assert!(matches!(decoded.apdu, Some(ApduHeader::UnconfirmedRequest { service: 8 })));
+    assert_eq!(decoded.who_is_range, None);
+}
@@ -1,9 +1,8 @@
-use bacnet_discovery::network::create_shared_socket;
-use bacnet_discovery::bacnet::{send_whois_to, process_response};
+use bacnet_discovery::bacnet::{send_whois_to, send_ranged_whois, process_response};
 use bacnet_rs::{
     network::Npdu,
     object::Device,
-    service::{IAmRequest, UnconfirmedServiceChoice},
+    service::{IAmRequest, UnconfirmedServiceChoice, WhoIsRequest},
 };
 use std::net::{SocketAddr, UdpSocket};
 use std::time::{Duration, Instant};
@@ -24,11 +23,11 @@ fn test_local_discovery() {
         
         while start.elapsed() < Duration::from_secs(2) {
             socket.set_read_timeout(Some(Duration::from_millis(100))).ok();
-            if let Ok((len, source)) = socket.recv_from(&mut buf) {
-                if is_whois(&buf[..len]) {
-                    let response = create_iam_response(&device);
-                    socket.send_to(&response, source).ok();
-                }
+            if let Ok((len, source)) = socket.recv_from(&mut buf)
+                && is_whois(&buf[..len])
+            {
+                let response = create_iam_response(&device);
+                socket.send_to(&response, source).ok();
             }
         }
     });
@@ -47,13 +46,12 @@ fn test_local_discovery() {
 
     scanner_socket.set_read_timeout(Some(Duration::from_secs(1))).ok();
     while start.elapsed() < Duration::from_secs(3) {
-        if let Ok((len, addr)) = scanner_socket.recv_from(&mut buf) {
-            if let Some(device) = process_response(&buf[..len], addr) {
-                if device.device_id == 99999 {
-                    found = true;
-                    break;
-                }
-            }
+        if let Ok((len, addr)) = scanner_socket.recv_from(&mut buf)
+            && let Some(device) = process_response(&buf[..len], addr)
+            && device.device_id == 99999
+        {
+            found = true;
+            break;
         }
     }
 
@@ -90,3 +88,20 @@ fn create_iam_response(device: &Device) -> Vec<u8> {
     bvlc[3] = (total_len & 0xFF) as u8;
     bvlc
 }
+
+#[test]
+fn test_ranged_whois() {
+    let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+    receiver.set_read_timeout(Some(Duration::from_secs(1))).ok();
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    send_ranged_whois(&sender, receiver.local_addr().unwrap(), 100, 200).unwrap();
+
+    let mut buf = [0u8; 1500];
+    let (len, _) = receiver.recv_from(&mut buf).unwrap();
+    let (_, npdu_len) = Npdu::decode(&buf[4..len]).unwrap();
+    let apdu = &buf[4 + npdu_len..len];
+    assert_eq!(apdu[1], UnconfirmedServiceChoice::WhoIs as u8);
+    let whois = WhoIsRequest::decode(&apdu[2..]).unwrap();
+    assert_eq!(whois.device_instance_range_low_limit, Some(100));
+    assert_eq!(whois.device_instance_range_high_limit, Some(200));
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use bacnet_discovery::ede::EdeExport;
+use bacnet_discovery::property::{self, BacnetValue, PresentValue, PropertyEntry, StateTexts};
+use bacnet_rs::object::ObjectType;
+use common::{device, point};
+use std::collections::HashMap;
+
+fn on_off() -> Option<StateTexts> {
+    Some(StateTexts { inactive: Some("Off".to_string()), active: Some("On".to_string()), states: Vec::new() })
+}
+
+fn sample() -> EdeExport {
+    let devices = HashMap::from([(1234, device(1234, "10.0.0.1:47808"))]);
+    let text = |id, t: &str| PropertyEntry { id, array_index: None, value: Ok(BacnetValue::CharacterString(t.to_string())) };
+    let properties = HashMap::from([(1234, vec![text(property::OBJECT_NAME, "AHU-1"), text(property::DESCRIPTION, "Roof; north")])]);
+
+    let mut temp = point(ObjectType::AnalogInput, 1, "Supply Temp", PresentValue::Unknown);
+    temp.units_code = Some(62);
+    temp.description = Some("Supply air".to_string());
+    let mut fan = point(ObjectType::BinaryOutput, 1, "Fan", PresentValue::Unknown);
+    fan.state_texts = on_off();
+    let mut pump = point(ObjectType::BinaryOutput, 2, "Pump", PresentValue::Unknown);
+    pump.state_texts = on_off();
+    let mut mode = point(ObjectType::MultiStateValue, 1, "Mode", PresentValue::Unknown);
+    mode.state_texts = Some(StateTexts { states: vec!["Off".into(), "Low".into(), "High".into()], ..Default::default() });
+    let objects = HashMap::from([(1234, vec![mode, pump, fan, temp])]);
+
+    EdeExport::new("Site A", &devices, &properties, &objects)
+}
+
+#[test]
+fn test_ede_main_file() {
+    let ede = sample();
+    let lines: Vec<&str> = ede.main.split("\r\n").filter(|l| !l.is_empty()).collect();
+    assert_eq!(lines[0], "#Engineering-Data-Exchange - B.I.G.-EU");
+    assert_eq!(lines[1], "PROJECT_NAME;Site A");
+    assert!(lines[3].starts_with("TIMESTAMP_OF_LAST_CHANGE;20"));
+    assert_eq!(lines[5], "VERSION_OF_LAYOUT;2.2");
+    assert!(lines[7].starts_with("# keyname;device obj.-instance;object-name;object-type;object-instance;description"));
+
+    // Device object first, separators in text replaced
+    assert_eq!(lines[8], "1234_DEV1234;1234;AHU-1;8;1234;Roof, north;;;;;;;;;;");
+    assert_eq!(lines[9], "1234_AI1;1234;Supply Temp;0;1;Supply air;;;;;;;;;62;");
+    // Both binary outputs share state text reference 1
+    assert_eq!(lines[10], "1234_BO1;1234;Fan;4;1;;;;;;;;;1;;");
+    assert_eq!(lines[11], "1234_BO2;1234;Pump;4;2;;;;;;;;;1;;");
+    assert_eq!(lines[12], "1234_MSV1;1234;Mode;19;1;;;;;;;;;2;;");
+    assert_eq!(lines.len(), 13);
+}
+
+#[test]
+fn test_ede_text_files() {
+    let ede = sample();
+    assert_eq!(
+        ede.state_texts,
+        "#EDE-Statetexts - B.I.G.-EU\r\n#Reference Number;Text 1;Text 2;Text 3\r\n1;Off;On\r\n2;Off;Low;High\r\n"
+    );
+    let units: Vec<&str> = ede.unit_texts.split("\r\n").filter(|l| !l.is_empty()).collect();
+    assert_eq!(units[1], "#Unit Code;Unit Text");
+    assert!(units[2].starts_with("62;"));
+    assert_eq!(units.len(), 3);
+}
+
+#[test]
+fn test_ede_write_files() {
+    let dir = std::env::temp_dir().join(format!("bacnet-ede-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let paths = sample().write_files(&dir.join("site")).unwrap();
+    assert_eq!(paths, [dir.join("site_EDE.csv"), dir.join("site_StateTexts.csv"), dir.join("site_UnitTexts.csv")]);
+    assert!(paths.iter().all(|p| p.exists()));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,147 @@
+mod common;
+
+use bacnet_discovery::address_book::AddressEvent;
+use bacnet_discovery::export::{csv_line, ScanExport, CSV_HEADER, SCHEMA_VERSION};
+use bacnet_discovery::property::{self, BacnetValue, PresentValue, PropertyEntry, PropertyError, StateTexts, StatusFlags};
+use bacnet_rs::object::{ObjectIdentifier, ObjectType};
+use common::{device, point};
+use std::collections::HashMap;
+
+fn sample_export() -> ScanExport {
+    let devices = HashMap::from([(200, device(200, "10.0.0.2:47808")), (100, device(100, "10.0.0.1:47808"))]);
+    let properties = HashMap::from([(100, vec![PropertyEntry {
+        id: property::OBJECT_NAME,
+        array_index: None,
+        value: Ok(BacnetValue::CharacterString("AHU-1".to_string())),
+    }])]);
+
+    let mut temp = point(ObjectType::AnalogInput, 1, "Supply Air, Temp", PresentValue::Analog(21.5));
+    temp.units = "degrees-celsius".to_string();
+    temp.status_flags = Some(StatusFlags { in_alarm: true, overridden: true, ..Default::default() });
+    temp.reliability = Some(0);
+    let mut fan = point(ObjectType::BinaryOutput, 3, "Fan \"S1\"", PresentValue::Binary(true));
+    fan.state_texts = Some(StateTexts { inactive: Some("Off".to_string()), active: Some("On".to_string()), states: Vec::new() });
+    let objects = HashMap::from([(100, vec![fan, temp, point(ObjectType::AnalogValue, 9, "Unread", PresentValue::Unknown)])]);
+
+    ScanExport::new(&devices, &properties, &objects)
+}
+
+#[test]
+fn test_json_schema() {
+    let export = sample_export();
+    let json: serde_json::Value = serde_json::from_str(&export.to_json().unwrap()).unwrap();
+    assert_eq!(json["schema_version"], SCHEMA_VERSION);
+
+    let device = &json["devices"][0];
+    assert_eq!(device["device_id"], 100);
+    assert_eq!(device["name"], "AHU-1");
+    assert_eq!(device["address"], "10.0.0.1:47808");
+    assert_eq!(device["segmentation"], "both");
+    assert_eq!(device["online"], true);
+    assert_eq!(json["devices"][1]["name"], serde_json::Value::Null);
+
+    // Points are ordered by type, then instance
+    let objects = device["objects"].as_array().unwrap();
+    assert_eq!(objects.len(), 3);
+    assert_eq!(objects[0]["object_type"], "AnalogInput");
+    assert_eq!(objects[0]["object_type_id"], 0);
+    assert_eq!(objects[0]["present_value"], 21.5);
+    assert_eq!(objects[0]["in_alarm"], true);
+    assert_eq!(objects[0]["fault"], false);
+    assert_eq!(objects[0]["reliability"], "No-Fault-Detected");
+    assert_eq!(objects[1]["present_value"], serde_json::Value::Null);
+    assert_eq!(objects[1]["in_alarm"], serde_json::Value::Null);
+    assert_eq!(objects[2]["present_value"], true);
+    assert_eq!(objects[2]["present_value_text"], "On");
+
+    assert_eq!(ScanExport::from_json(&export.to_json().unwrap()).unwrap(), export);
+}
+
+#[test]
+fn test_csv_rows() {
+    let csv = sample_export().to_csv();
+    let lines: Vec<&str> = csv.split("\r\n").filter(|l| !l.is_empty()).collect();
+    assert_eq!(lines[0], CSV_HEADER.join(","));
+    assert_eq!(lines.len(), 5);
+    assert_eq!(
+        lines[1],
+        "100,AHU-1,10.0.0.1:47808,8,Delta Controls,1476,both,true,AnalogInput,0,1,\"Supply Air, Temp\",,degrees-celsius,21.50,ALARM OVRD,No-Fault-Detected,"
+    );
+    assert!(lines[3].ends_with("BinaryOutput,4,3,\"Fan \"\"S1\"\"\",,,On,,,"));
+    // A device without points still gets a row
+    assert_eq!(lines[4], "200,,10.0.0.2:47808,8,Delta Controls,1476,both,true,,,,,,,,,,");
+}
+
+#[test]
+fn test_csv_quoting() {
+    assert_eq!(csv_line(["a".to_string(), "b,c".to_string(), "line\nbreak".to_string()]), "a,\"b,c\",\"line\nbreak\"\r\n");
+}
+
+#[test]
+fn test_write_files() {
+    let dir = std::env::temp_dir().join(format!("bacnet-export-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (csv, json) = sample_export().write_files(&dir.join("scan")).unwrap();
+    assert_eq!(csv, dir.join("scan.csv"));
+    assert!(std::fs::read_to_string(&csv).unwrap().starts_with("device_id,"));
+    assert_eq!(ScanExport::from_json(&std::fs::read_to_string(&json).unwrap()).unwrap().devices.len(), 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_table() {
+    let table = sample_export().to_table();
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("DEVICE  NAME   ADDRESS"));
+    assert_eq!(lines[1], "100     AHU-1  10.0.0.1:47808  Delta Controls (8)  1476      both          online  3");
+    // Columns line up even where the name is empty
+    assert_eq!(lines[2].find("10.0.0.2"), lines[1].find("10.0.0.1"));
+}
+
+#[test]
+fn test_requested_properties() {
+    let mut scan = sample_export();
+    let temp = ObjectIdentifier::new(ObjectType::AnalogInput, 1);
+    scan.set_properties(100, temp, &[
+        PropertyEntry { id: property::HIGH_LIMIT, array_index: None, value: Ok(BacnetValue::Real(30.0)) },
+        PropertyEntry { id: property::RELIABILITY, array_index: None, value: Ok(BacnetValue::Enumerated(0)) },
+        PropertyEntry { id: property::DEADBAND, array_index: None, value: Err(PropertyError { class: 2, code: 32 }) },
+    ]);
+
+    let json: serde_json::Value = serde_json::from_str(&scan.to_json().unwrap()).unwrap();
+    let objects = &json["devices"][0]["objects"];
+    let temp = objects.as_array().unwrap().iter().find(|o| o["instance"] == 1).unwrap();
+    assert_eq!(temp["properties"]["High_Limit"], "30.00");
+    assert_eq!(temp["properties"]["Reliability"], "No-Fault-Detected");
+    assert!(temp["properties"].get("Deadband").is_none());
+    // Points without requested properties leave the field out
+    assert!(objects[1].get("properties").is_none());
+    assert_eq!(ScanExport::from_json(&scan.to_json().unwrap()).unwrap(), scan);
+}
+
+#[test]
+fn test_address_conflicts() {
+    let mut scan = sample_export();
+    scan.set_conflicts(&[
+        AddressEvent::Duplicate { device_id: 100, addresses: vec!["10.0.0.1:47808".parse().unwrap(), "10.0.0.7:47808".parse().unwrap()] },
+        AddressEvent::Changed { device_id: 200, from: "10.0.0.9:47808".parse().unwrap(), to: "10.0.0.2:47808".parse().unwrap() },
+        AddressEvent::Changed { device_id: 999, from: "10.0.0.9:47808".parse().unwrap(), to: "10.0.0.2:47808".parse().unwrap() },
+    ]);
+
+    let json: serde_json::Value = serde_json::from_str(&scan.to_json().unwrap()).unwrap();
+    assert_eq!(json["devices"][0]["duplicate_addresses"], serde_json::json!(["10.0.0.1:47808", "10.0.0.7:47808"]));
+    assert!(json["devices"][0].get("previous_address").is_none());
+    assert_eq!(json["devices"][1]["previous_address"], "10.0.0.9:47808");
+    assert_eq!(ScanExport::from_json(&scan.to_json().unwrap()).unwrap(), scan);
+
+    let csv = scan.to_csv();
+    let lines: Vec<&str> = csv.split("\r\n").filter(|l| !l.is_empty()).collect();
+    assert!(lines[1].ends_with(",\"duplicate instance at 10.0.0.1:47808, 10.0.0.7:47808\""));
+    assert!(lines[4].ends_with(",moved from 10.0.0.9:47808"));
+
+    let table = scan.to_table();
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines[3], "CONFLICT: device 100 duplicate instance at 10.0.0.1:47808, 10.0.0.7:47808");
+    assert_eq!(lines[4], "CONFLICT: device 200 moved from 10.0.0.9:47808");
+}
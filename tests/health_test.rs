@@ -0,0 +1,80 @@
+use bacnet_discovery::health::{format_rtt, DeviceHealth, HealthGrade, HealthStats, RequestOutcome};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+fn ms(millis: u64) -> Option<Duration> {
+    Some(Duration::from_millis(millis))
+}
+
+#[test]
+fn test_round_trip_statistics() {
+    let mut health = DeviceHealth::default();
+    assert_eq!((health.avg_rtt(), health.jitter()), (None, None));
+    for rtt in [20, 40, 30] {
+        health.record(RequestOutcome::Ack, ms(rtt));
+    }
+    assert_eq!((health.min_rtt, health.max_rtt, health.avg_rtt()), (ms(20), ms(40), ms(30)));
+    // |40 - 20| and |30 - 40|
+    assert_eq!(health.jitter(), ms(15));
+    assert_eq!(health.grade(), HealthGrade::Good);
+    assert_eq!(health.summary(), "Good 30 ms");
+}
+
+#[test]
+fn test_grades() {
+    let mut health = DeviceHealth::default();
+    for _ in 0..9 {
+        health.record(RequestOutcome::Ack, ms(10));
+    }
+    health.record(RequestOutcome::Reject, ms(10));
+    assert_eq!((health.requests, health.rejects, health.failures()), (10, 1, 1));
+    assert_eq!(health.grade(), HealthGrade::Fair);
+    assert_eq!(health.summary(), "Fair 10% fail");
+
+    // A timeout on the last request is poor until the device answers again
+    health.record(RequestOutcome::Timeout, None);
+    assert_eq!(health.grade(), HealthGrade::Poor);
+    assert_eq!(health.avg_rtt(), ms(10), "timeouts have no round trip");
+    health.record(RequestOutcome::Ack, ms(10));
+    assert_eq!(health.grade(), HealthGrade::Fair);
+
+    let mut slow = DeviceHealth::default();
+    slow.record(RequestOutcome::Ack, ms(800));
+    assert_eq!(slow.grade(), HealthGrade::Fair);
+
+    let mut failing = DeviceHealth::default();
+    failing.record(RequestOutcome::Ack, ms(5));
+    failing.record(RequestOutcome::Abort, ms(5));
+    failing.record(RequestOutcome::Error, ms(5));
+    assert_eq!(failing.grade(), HealthGrade::Poor);
+}
+
+#[test]
+fn test_ranking_worst_first() {
+    let addr = |host: u8| -> SocketAddr { format!("10.0.0.{}:47808", host).parse().unwrap() };
+    let mut stats = HealthStats::new();
+    stats.record(addr(1), RequestOutcome::Ack, ms(5));
+    stats.record(addr(2), RequestOutcome::Ack, ms(50));
+    stats.record(addr(3), RequestOutcome::Ack, ms(5));
+    stats.record(addr(3), RequestOutcome::Timeout, None);
+    stats.record(addr(4), RequestOutcome::Ack, ms(5));
+    stats.record(addr(4), RequestOutcome::Ack, ms(5));
+    stats.record(addr(4), RequestOutcome::Ack, ms(5));
+    stats.record(addr(4), RequestOutcome::Ack, ms(5));
+    stats.record(addr(4), RequestOutcome::Ack, ms(5));
+    stats.record(addr(4), RequestOutcome::Error, ms(5));
+
+    let order: Vec<SocketAddr> = stats.ranked().into_iter().map(|(a, _)| a).collect();
+    assert_eq!(order, [addr(3), addr(4), addr(2), addr(1)]);
+    assert_eq!(stats.get(&addr(3)).unwrap().timeouts, 1);
+
+    stats.clear();
+    assert!(stats.is_empty());
+}
+
+#[test]
+fn test_format_rtt() {
+    assert_eq!(format_rtt(None), "-");
+    assert_eq!(format_rtt(Some(Duration::from_micros(2500))), "2.5 ms");
+    assert_eq!(format_rtt(ms(120)), "120 ms");
+}
@@ -0,0 +1,43 @@
+use bacnet_discovery::app::BacnetObject;
+use bacnet_discovery::bacnet::PointReading;
+use bacnet_discovery::history::{HistoryStats, PointHistory, HISTORY_CAPACITY};
+use bacnet_discovery::property::PresentValue;
+use bacnet_rs::object::{ObjectIdentifier, ObjectType};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_history_drops_oldest_when_full() {
+    let mut history = PointHistory::with_capacity(3);
+    let start = Instant::now();
+    for i in 0..5 {
+        history.push(start + Duration::from_secs(i * 5), i as f64);
+    }
+    assert_eq!(history.len(), 3);
+    assert_eq!(history.iter().map(|s| s.value).collect::<Vec<_>>(), [2.0, 3.0, 4.0]);
+    assert_eq!(history.span(), Duration::from_secs(10));
+    assert_eq!(history.stats(), Some(HistoryStats { min: 2.0, max: 4.0, avg: 3.0, count: 3 }));
+}
+
+#[test]
+fn test_empty_history_has_no_stats() {
+    let history = PointHistory::default();
+    assert!(history.is_empty());
+    assert_eq!(history.stats(), None);
+    assert_eq!(history.span(), Duration::ZERO);
+}
+
+#[test]
+fn test_point_updates_feed_history() {
+    let mut point = BacnetObject::new(ObjectIdentifier::new(ObjectType::BinaryValue, 1), "BV 1".to_string());
+    point.apply_reading(PointReading { present_value: PresentValue::Binary(true), ..Default::default() });
+    point.apply_reading(PointReading { present_value: PresentValue::Binary(false), ..Default::default() });
+    // Values without a numeric form are not recorded
+    point.apply_reading(PointReading { present_value: PresentValue::Unknown, ..Default::default() });
+    assert_eq!(point.history.iter().map(|s| s.value).collect::<Vec<_>>(), [1.0, 0.0]);
+
+    for _ in 0..HISTORY_CAPACITY {
+        point.apply_reading(PointReading { present_value: PresentValue::Analog(20.0), ..Default::default() });
+    }
+    assert_eq!(point.history.len(), HISTORY_CAPACITY);
+    assert_eq!(point.history.stats().unwrap().min, 20.0);
+}
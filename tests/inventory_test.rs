@@ -0,0 +1,107 @@
+use bacnet_discovery::bacnet::{self, parse_confirmed_response, ConfirmedResponse, DiscoveredDevice, RequestRegistrar};
+use bacnet_discovery::inventory::{inventory_device, InvokeIds};
+use bacnet_discovery::property::{self, BacnetValue, PresentValue};
+use bacnet_rs::{
+    app::Apdu,
+    network::Npdu,
+    object::{ObjectIdentifier, ObjectType},
+    service::ConfirmedServiceChoice,
+};
+use std::collections::HashMap;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+/// Answers RPM with services / service-request-denied and ReadProperty from a
+/// device 1234 holding Analog Value 1 and Binary Value 2. Counts the RPMs.
+fn spawn_device_without_rpm(rpm_requests: Arc<AtomicUsize>) -> SocketAddr {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = socket.local_addr().unwrap();
+    thread::spawn(move || {
+        let mut buf = [0u8; 1500];
+        while let Ok((len, source)) = socket.recv_from(&mut buf) {
+            let (_npdu, npdu_len) = Npdu::decode(&buf[4..len]).unwrap();
+            let Ok(Apdu::ConfirmedRequest { invoke_id, service_choice, service_data, .. }) = Apdu::decode(&buf[4 + npdu_len..len]) else {
+                continue;
+            };
+            let apdu = if service_choice == ConfirmedServiceChoice::ReadPropertyMultiple as u8 {
+                rpm_requests.fetch_add(1, Ordering::Relaxed);
+                vec![0x50, invoke_id, service_choice, 0x91, 0x05, 0x91, 0x1D]
+            } else {
+                read_property_ack(invoke_id, service_choice, &service_data)
+            };
+            let mut msg = Npdu::new().encode();
+            msg.extend_from_slice(&apdu);
+            let mut bvlc = vec![0x81, 0x0A, 0, (msg.len() + 4) as u8];
+            bvlc.extend_from_slice(&msg);
+            socket.send_to(&bvlc, source).unwrap();
+        }
+    });
+    addr
+}
+
+fn read_property_ack(invoke_id: u8, service: u8, data: &[u8]) -> Vec<u8> {
+    let object_type = u32::from_be_bytes([data[1], data[2], data[3], data[4]]) >> 22;
+    let value: &[u8] = match (object_type, &data[5..7]) {
+        // Object_List of the device
+        (8, [0x19, 76]) => &[0xC4, 0x02, 0x00, 0x00, 0x04, 0xD2, 0xC4, 0x00, 0x80, 0x00, 0x01, 0xC4, 0x01, 0x40, 0x00, 0x02],
+        (_, [0x19, 77]) => &[0x74, 0x00, b'F', b'a', b'n'],
+        (2, [0x19, 85]) => &[0x44, 0x42, 0x48, 0x00, 0x00],
+        _ => return vec![0x50, invoke_id, service, 0x91, 0x02, 0x91, 0x20],
+    };
+    let mut ack = vec![0x30, invoke_id, service];
+    ack.extend_from_slice(&data[..7]);
+    ack.push(0x3E);
+    ack.extend_from_slice(value);
+    ack.push(0x3F);
+    ack
+}
+
+/// Dispatches responses arriving on `scanner` to the registered requests.
+fn spawn_dispatcher(scanner: &UdpSocket) -> RequestRegistrar {
+    let (tx_register, rx_register) = mpsc::channel::<(SocketAddr, u8, oneshot::Sender<ConfirmedResponse>)>(10);
+    let s_recv = scanner.try_clone().unwrap();
+    thread::spawn(move || {
+        let mut rx_register = rx_register;
+        let mut pending = HashMap::new();
+        let mut buf = [0u8; 1500];
+        while let Ok((len, _)) = s_recv.recv_from(&mut buf) {
+            let Some((id, response)) = parse_confirmed_response(&buf[..len]) else { continue };
+            // The request is registered before it is sent
+            while !pending.contains_key(&id) {
+                let Some((_, registered, tx)) = rx_register.blocking_recv() else { return };
+                pending.insert(registered, tx);
+            }
+            let _ = pending.remove(&id).unwrap().send(response);
+        }
+    });
+    RequestRegistrar::new(tx_register)
+}
+
+#[tokio::test]
+async fn test_deep_scan_of_device_denying_rpm() {
+    let rpm_requests = Arc::new(AtomicUsize::new(0));
+    let device = DiscoveredDevice::new(1234, spawn_device_without_rpm(Arc::clone(&rpm_requests)));
+
+    let scanner = UdpSocket::bind("127.0.0.1:0").unwrap();
+    scanner.set_read_timeout(Some(Duration::from_secs(3))).unwrap();
+    let tx_register = spawn_dispatcher(&scanner);
+
+    let inventory = inventory_device(&scanner, &device, None, &InvokeIds::default(), &tx_register).await;
+
+    // Only the first RPM goes out; the device is read with ReadProperty from then on
+    assert_eq!(rpm_requests.load(Ordering::Relaxed), 1);
+    assert!(tx_register.rpm_unsupported(device.address));
+
+    assert_eq!(property::find(&inventory.properties, property::OBJECT_NAME), Some(&BacnetValue::CharacterString("Fan".to_string())));
+    let ids: Vec<ObjectIdentifier> = inventory.objects.iter().map(|(point, _)| point.id).collect();
+    assert_eq!(ids, vec![ObjectIdentifier::new(ObjectType::AnalogValue, 1), ObjectIdentifier::new(ObjectType::BinaryValue, 2)]);
+
+    let (analog, props) = &inventory.objects[0];
+    assert_eq!(analog.present_value, PresentValue::Analog(50.0));
+    assert_eq!(props.len(), bacnet::detail_properties(ObjectType::AnalogValue).len());
+    assert!(!analog.needs_details());
+}
@@ -0,0 +1,284 @@
+use bacnet_discovery::bacnet::{send_whois_to, process_response, read_device_objects, parse_confirmed_response, ConfirmedResponse, RequestRegistrar};
+use bacnet_discovery::mstp::{self, Frame, FrameReceiver, FrameType, MasterNode, MasterState, MstpConfig, MstpPort, ReceiveError};
+use bacnet_discovery::network::DataLink;
+use bacnet_rs::{
+    app::Apdu,
+    network::Npdu,
+    object::{ObjectIdentifier, ObjectType},
+    service::{ConfirmedServiceChoice, IAmRequest, UnconfirmedServiceChoice},
+};
+use serialport::{SerialPort, TTYPort};
+use std::io::{Read, Write};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+fn decode_all(bytes: &[u8]) -> Vec<Result<Frame, ReceiveError>> {
+    let mut receiver = FrameReceiver::new();
+    bytes.iter().filter_map(|&b| receiver.push(b)).collect()
+}
+
+#[test]
+fn test_frame_round_trip() {
+    let token = Frame::token(0x10, 0x05);
+    let encoded = token.encode();
+    assert_eq!(&encoded[..2], &[0x55, 0xFF]);
+    assert_eq!(encoded.len(), 8);
+
+    let data = Frame::new(FrameType::DataNotExpectingReply, 0xFF, 3, vec![0x01, 0x20, 0xFF, 0xFF, 0x00, 0xFF]);
+    let mut stream = vec![0x00, 0x55]; // line noise before the preamble
+    stream.extend(token.encode());
+    stream.extend(data.encode());
+
+    let frames: Vec<Frame> = decode_all(&stream).into_iter().map(|r| r.unwrap()).collect();
+    assert_eq!(frames, vec![token, data]);
+}
+
+#[test]
+fn test_crc_residues() {
+    // Worked examples from Annex G
+    let header = [0x00, 0x10, 0x05, 0x00, 0x00];
+    let crc = mstp::header_crc(&header);
+    assert_eq!(crc, 0x8C);
+    assert_eq!(!mstp::data_crc(&[0x01, 0x22, 0x30]), 0x42EF);
+
+    // Running the CRC over the data plus its transmitted CRC yields the Clause 9 residue
+    let mut with_crc = header.to_vec();
+    with_crc.push(crc);
+    assert_eq!(!mstp::header_crc(&with_crc), 0x55);
+
+    let data = [0x01, 0x22, 0x30];
+    let mut with_crc = data.to_vec();
+    with_crc.extend_from_slice(&mstp::data_crc(&data).to_le_bytes());
+    assert_eq!(!mstp::data_crc(&with_crc), 0xF0B8);
+}
+
+#[test]
+fn test_receiver_rejects_corruption() {
+    let mut bad_header = Frame::token(1, 2).encode();
+    bad_header[7] ^= 0x01;
+    assert_eq!(decode_all(&bad_header), vec![Err(ReceiveError::HeaderCrc)]);
+
+    let mut bad_data = Frame::new(FrameType::DataNotExpectingReply, 1, 2, vec![1, 2, 3]).encode();
+    bad_data[9] ^= 0x80;
+    assert_eq!(decode_all(&bad_data), vec![Err(ReceiveError::DataCrc)]);
+}
+
+#[test]
+fn test_master_node_polls_then_passes_token() {
+    let config = MstpConfig { mac: 1, max_master: 4, ..Default::default() };
+    let start = Instant::now();
+    let mut node = MasterNode::new(config, start);
+
+    // Silence: the token is lost and station 1 starts polling for a successor
+    assert!(node.poll(start + Duration::from_millis(100)).is_empty());
+    let out = node.poll(start + Duration::from_millis(600));
+    assert_eq!(out, vec![Frame::poll_for_master(2, 1)]);
+    assert_eq!(node.state(), MasterState::PollForMaster);
+
+    // Station 3 answers the second poll and receives the token
+    let t = start + Duration::from_millis(700);
+    assert_eq!(node.poll(t), vec![Frame::poll_for_master(3, 1)]);
+    let reply = Frame::new(FrameType::ReplyToPollForMaster, 1, 3, Vec::new());
+    assert_eq!(node.handle_frame(reply, t), vec![Frame::token(3, 1)]);
+    assert_eq!(node.next_station(), 3);
+    assert_eq!(node.state(), MasterState::PassToken);
+
+    // Queued data goes out once the token comes back
+    node.queue_data(3, vec![0x01, 0x04, 0x00], true);
+    let out = node.handle_frame(Frame::token(1, 3), t + Duration::from_millis(10));
+    assert_eq!(out, vec![Frame::new(FrameType::DataExpectingReply, 3, 1, vec![0x01, 0x04, 0x00])]);
+    assert_eq!(node.state(), MasterState::WaitForReply);
+
+    let answer = Frame::new(FrameType::DataNotExpectingReply, 1, 3, vec![0x01, 0x00, 0x30]);
+    let out = node.handle_frame(answer.clone(), t + Duration::from_millis(20));
+    assert_eq!(out, vec![Frame::token(3, 1)]);
+    assert_eq!(node.take_received(), Some(answer));
+
+    // A Test_Response ends the wait but is not handed up as data
+    node.queue_data(3, vec![0x01, 0x04, 0x00], true);
+    node.handle_frame(Frame::token(1, 3), t + Duration::from_millis(30));
+    assert_eq!(node.state(), MasterState::WaitForReply);
+    let out = node.handle_frame(Frame::new(FrameType::TestResponse, 1, 3, vec![9, 9]), t + Duration::from_millis(40));
+    assert_eq!(out, vec![Frame::token(3, 1)]);
+    assert_eq!(node.take_received(), None);
+}
+
+#[test]
+fn test_master_node_declares_sole_master() {
+    let config = MstpConfig { mac: 0, max_master: 2, ..Default::default() };
+    let start = Instant::now();
+    let mut node = MasterNode::new(config, start);
+
+    let mut t = start + Duration::from_millis(500);
+    assert_eq!(node.poll(t), vec![Frame::poll_for_master(1, 0)]);
+    t += Duration::from_millis(60);
+    assert_eq!(node.poll(t), vec![Frame::poll_for_master(2, 0)]);
+    t += Duration::from_millis(60);
+    node.queue_data(mstp::BROADCAST_MAC, vec![0x01, 0x00], false);
+    let out = node.poll(t);
+    assert!(node.is_sole_master());
+    assert_eq!(out[0], Frame::new(FrameType::DataNotExpectingReply, 255, 0, vec![0x01, 0x00]));
+}
+
+#[test]
+fn test_master_node_answers_poll_and_test_request() {
+    let config = MstpConfig { mac: 7, ..Default::default() };
+    let now = Instant::now();
+    let mut node = MasterNode::new(config, now);
+
+    let out = node.handle_frame(Frame::poll_for_master(7, 2), now);
+    assert_eq!(out, vec![Frame::new(FrameType::ReplyToPollForMaster, 2, 7, Vec::new())]);
+
+    let out = node.handle_frame(Frame::new(FrameType::TestRequest, 7, 2, vec![9, 9]), now);
+    assert_eq!(out, vec![Frame::new(FrameType::TestResponse, 2, 7, vec![9, 9])]);
+
+    // Frames for other stations are ignored
+    assert!(node.handle_frame(Frame::poll_for_master(8, 2), now).is_empty());
+}
+
+#[test]
+fn test_mac_address_mapping() {
+    let addr = mstp::mac_to_addr(42);
+    assert_eq!(mstp::addr_to_mac(&addr), Some(42));
+    assert_eq!(mstp::addr_to_mac(&"192.168.1.42:47808".parse().unwrap()), None);
+}
+
+#[tokio::test]
+async fn test_discovery_over_pty() {
+    let (peer_port, node_port) = TTYPort::pair().expect("Failed to open pty pair");
+    let running = Arc::new(AtomicBool::new(true));
+    let peer_running = Arc::clone(&running);
+    let peer = thread::spawn(move || run_simulated_peer(peer_port, 5, 4321, peer_running));
+
+    let config = MstpConfig { mac: 1, max_master: 7, ..Default::default() };
+    let link = Arc::new(MstpPort::from_port(Box::new(node_port), config).unwrap());
+    link.set_nonblocking(true).unwrap();
+
+    let (tx_register, mut rx_register) = mpsc::channel::<(SocketAddr, u8, tokio::sync::oneshot::Sender<ConfirmedResponse>)>(10);
+    let tx_register = RequestRegistrar::new(tx_register);
+    let (tx_found, mut rx_found) = mpsc::channel(10);
+    let link_recv = Arc::clone(&link);
+    let receiver = tokio::spawn(async move {
+        let mut pending = std::collections::HashMap::new();
+        let mut buf = [0u8; 1500];
+        loop {
+            while let Ok((_, id, tx)) = rx_register.try_recv() { pending.insert(id, tx); }
+            if let Ok((len, addr)) = link_recv.recv_from(&mut buf) {
+                let data = &buf[..len];
+                if let Some(device) = process_response(data, addr) {
+                    let _ = tx_found.send(device).await;
+                } else if let Some((id, sdata)) = parse_confirmed_response(data)
+                    && let Some(tx) = pending.remove(&id)
+                {
+                    let _ = tx.send(sdata);
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+    });
+
+    send_whois_to(link.as_ref(), mstp::mac_to_addr(mstp::BROADCAST_MAC)).unwrap();
+    let device = tokio::time::timeout(Duration::from_secs(5), rx_found.recv()).await.unwrap().unwrap();
+    assert_eq!(device.device_id, 4321);
+    assert_eq!(mstp::addr_to_mac(&device.address), Some(5));
+
+    let points = read_device_objects(link.as_ref(), device.address, device.device_id, 1, &tx_register).await.unwrap();
+    assert_eq!(points.len(), 1);
+    assert_eq!(points[0].id, ObjectIdentifier::new(ObjectType::AnalogInput, 1));
+
+    receiver.abort();
+    running.store(false, Ordering::SeqCst);
+    peer.join().unwrap();
+}
+
+/// A minimal master node: answers polls, hands the token straight back, and
+/// replies to Who-Is and ReadPropertyMultiple.
+fn run_simulated_peer(mut port: TTYPort, mac: u8, device_id: u32, running: Arc<AtomicBool>) {
+    port.set_timeout(Duration::from_millis(10)).unwrap();
+    let mut receiver = FrameReceiver::new();
+    let mut buf = [0u8; 256];
+    let mut pending_iam = false;
+
+    while running.load(Ordering::SeqCst) {
+        let Ok(len) = port.read(&mut buf) else { continue };
+        for &byte in &buf[..len] {
+            let Some(Ok(frame)) = receiver.push(byte) else { continue };
+            if frame.destination != mac && frame.destination != mstp::BROADCAST_MAC {
+                continue;
+            }
+            let reply = match frame.frame_type {
+                FrameType::PollForMaster => vec![Frame::new(FrameType::ReplyToPollForMaster, frame.source, mac, Vec::new())],
+                FrameType::Token => {
+                    let mut out = Vec::new();
+                    if pending_iam {
+                        pending_iam = false;
+                        out.push(Frame::new(FrameType::DataNotExpectingReply, mstp::BROADCAST_MAC, mac, iam_npdu(device_id)));
+                    }
+                    out.push(Frame::token(frame.source, mac));
+                    out
+                }
+                FrameType::DataNotExpectingReply if is_whois(&frame.data) => {
+                    pending_iam = true;
+                    Vec::new()
+                }
+                FrameType::DataExpectingReply => match rpm_invoke_id(&frame.data) {
+                    Some(invoke_id) => vec![Frame::new(FrameType::DataNotExpectingReply, frame.source, mac, rpm_ack_npdu(invoke_id, device_id))],
+                    None => Vec::new(),
+                },
+                _ => Vec::new(),
+            };
+            for f in reply {
+                port.write_all(&f.encode()).unwrap();
+            }
+        }
+    }
+}
+
+fn is_whois(npdu: &[u8]) -> bool {
+    let Ok((_npdu, npdu_len)) = Npdu::decode(npdu) else { return false };
+    let apdu = &npdu[npdu_len..];
+    apdu.len() >= 2 && apdu[0] == 0x10 && apdu[1] == UnconfirmedServiceChoice::WhoIs as u8
+}
+
+fn rpm_invoke_id(npdu: &[u8]) -> Option<u8> {
+    let (_npdu, npdu_len) = Npdu::decode(npdu).ok()?;
+    match Apdu::decode(&npdu[npdu_len..]).ok()? {
+        Apdu::ConfirmedRequest { invoke_id, service_choice: 14, .. } => Some(invoke_id),
+        _ => None,
+    }
+}
+
+fn iam_npdu(device_id: u32) -> Vec<u8> {
+    let iam = IAmRequest::new(ObjectIdentifier::new(ObjectType::Device, device_id), 480, 3, 260);
+    let mut buf = Vec::new();
+    iam.encode(&mut buf).unwrap();
+    let mut msg = Npdu::new().encode();
+    msg.extend_from_slice(&[0x10, UnconfirmedServiceChoice::IAm as u8]);
+    msg.extend_from_slice(&buf);
+    msg
+}
+
+fn rpm_ack_npdu(invoke_id: u8, device_id: u32) -> Vec<u8> {
+    let mut service_data = vec![0x0C];
+    service_data.extend_from_slice(&(((ObjectType::Device as u32) << 22) | device_id).to_be_bytes());
+    service_data.extend_from_slice(&[0x1E, 0x29, 76, 0x4E, 0xC4]);
+    service_data.extend_from_slice(&(((ObjectType::AnalogInput as u32) << 22) | 1).to_be_bytes());
+    service_data.extend_from_slice(&[0x4F, 0x1F]);
+
+    let apdu = Apdu::ComplexAck {
+        segmented: false,
+        more_follows: false,
+        invoke_id,
+        sequence_number: None,
+        proposed_window_size: None,
+        service_choice: ConfirmedServiceChoice::ReadPropertyMultiple as u8,
+        service_data,
+    };
+    let mut msg = Npdu::new().encode();
+    msg.extend_from_slice(&apdu.encode());
+    msg
+}
@@ -0,0 +1,223 @@
+use bacnet_discovery::app::{App, BacnetObject, ViewState};
+use bacnet_discovery::bacnet::DiscoveredDevice;
+use bacnet_rs::object::{ObjectIdentifier, ObjectType};
+use ratatui::layout::Rect;
+
+fn app_with_points(count: u32) -> App {
+    let mut app = App::new();
+    let points = (1..=count)
+        .map(|i| BacnetObject::new(ObjectIdentifier::new(ObjectType::AnalogValue, i * 10), format!("AV {}", i * 10)))
+        .collect();
+    app.device_objects.lock().unwrap().insert(1, points);
+    app.view_state = ViewState::ObjectList(1);
+    app.object_table_state.select(Some(0));
+    app
+}
+
+#[test]
+fn test_paging_and_ends() {
+    let mut app = app_with_points(25);
+    app.page_size = 10;
+
+    app.page_down();
+    assert_eq!(app.object_table_state.selected(), Some(10));
+    app.page_down();
+    app.page_down();
+    // Paging stops at the last row instead of wrapping
+    assert_eq!(app.object_table_state.selected(), Some(24));
+    app.page_up();
+    assert_eq!(app.object_table_state.selected(), Some(14));
+
+    app.select_first();
+    assert_eq!(app.object_table_state.selected(), Some(0));
+    app.select_last();
+    assert_eq!(app.object_table_state.selected(), Some(24));
+    app.move_selection(-3);
+    assert_eq!(app.object_table_state.selected(), Some(21));
+}
+
+#[test]
+fn test_jump_to_instance_or_row() {
+    let mut app = app_with_points(25);
+
+    // 120 is the instance of the 12th point
+    for c in "120".chars() {
+        app.push_jump_digit(c);
+    }
+    app.finish_jump(true);
+    assert_eq!(app.jump_input, None);
+    assert_eq!(app.object_table_state.selected(), Some(11));
+
+    // 7 is no instance, so it is a row number
+    app.jump_to_number(7);
+    assert_eq!(app.object_table_state.selected(), Some(6));
+
+    app.jump_to_number(999);
+    assert_eq!(app.object_table_state.selected(), Some(6));
+
+    app.push_jump_digit('3');
+    app.finish_jump(false);
+    assert_eq!(app.object_table_state.selected(), Some(6));
+}
+
+#[test]
+fn test_jump_to_device_id() {
+    let mut app = App::new();
+    for device_id in [5000, 12, 300] {
+        app.record_device(DiscoveredDevice::new(device_id, "10.0.0.1:47808".parse().unwrap()));
+    }
+    app.view_state = ViewState::DeviceList;
+    app.jump_to_number(300);
+    assert_eq!(app.list_state.selected(), Some(1));
+}
+
+#[test]
+fn test_click_selects_row_under_pointer() {
+    let mut app = app_with_points(25);
+    app.list_rows_area = Some(Rect::new(2, 5, 60, 10));
+
+    assert!(app.click(10, 8));
+    assert_eq!(app.object_table_state.selected(), Some(3));
+
+    // Clicks outside the rows, e.g. on the header, are ignored
+    assert!(!app.click(10, 4));
+    assert_eq!(app.object_table_state.selected(), Some(3));
+
+    // Rows below the last point do not exist
+    let mut short = app_with_points(2);
+    short.list_rows_area = Some(Rect::new(2, 5, 60, 10));
+    assert!(!short.click(10, 9));
+}
+
+#[test]
+fn test_traffic_view() {
+    use bacnet_discovery::traffic::Datagram;
+    use std::time::Duration;
+
+    let who_is = [0x81, 0x0B, 0x00, 0x08, 0x01, 0x00, 0x10, 0x08];
+    let reject = [0x81, 0x0A, 0x00, 0x09, 0x01, 0x00, 0x60, 0x07, 0x09];
+    let datagram = |data: &[u8]| {
+        Datagram::new(Duration::ZERO, "10.0.0.5:47808".parse().unwrap(), "10.0.0.9:47808".parse().unwrap(), data)
+    };
+    let mut app = App::new();
+    app.show_traffic();
+    assert!(matches!(app.view_state, ViewState::InterfaceSelect), "no traffic before an interface is selected");
+
+    app.view_state = ViewState::DeviceList;
+    for data in [&who_is[..], &reject, &who_is] {
+        app.traffic.lock().unwrap().push(datagram(data));
+    }
+    app.show_traffic();
+    app.follow_traffic();
+    assert_eq!(app.traffic_list_state.selected(), Some(2));
+
+    // Moving off the newest packet holds the selection as traffic arrives
+    app.previous();
+    app.traffic.lock().unwrap().push(datagram(&who_is));
+    app.follow_traffic();
+    assert_eq!(app.traffic_list_state.selected(), Some(1));
+    app.select_last();
+    app.traffic.lock().unwrap().push(datagram(&reject));
+    app.follow_traffic();
+    assert_eq!(app.traffic_list_state.selected(), Some(4));
+
+    app.start_search();
+    assert_eq!(app.traffic_filter_input.as_deref(), Some(""));
+    app.traffic_filter_input = Some("type=bogus".to_string());
+    app.finish_traffic_filter(true);
+    assert!(app.traffic_filter_input.is_some(), "an invalid filter keeps the prompt open");
+    app.traffic_filter_input = Some("type=reject".to_string());
+    app.finish_traffic_filter(true);
+    assert_eq!(app.traffic_filter.as_str(), "type=reject");
+    app.follow_traffic();
+    assert_eq!(app.traffic_list_state.selected(), Some(1));
+
+    app.toggle_traffic_pause();
+    app.traffic.lock().unwrap().push(datagram(&reject));
+    assert_eq!(app.traffic.lock().unwrap().len(), 5);
+    app.clear_traffic();
+    assert!(app.traffic.lock().unwrap().is_empty());
+
+    app.exit_view();
+    assert!(matches!(app.view_state, ViewState::DeviceList));
+}
+
+#[test]
+fn test_health_view() {
+    use bacnet_discovery::health::RequestOutcome;
+    use std::time::Duration;
+
+    let mut app = App::new();
+    for (device_id, host) in [(1, 1), (2, 2)] {
+        app.record_device(DiscoveredDevice::new(device_id, format!("10.0.0.{}:47808", host).parse().unwrap()));
+    }
+    {
+        let mut health = app.health.lock().unwrap();
+        health.record("10.0.0.1:47808".parse().unwrap(), RequestOutcome::Ack, Some(Duration::from_millis(5)));
+        health.record("10.0.0.2:47808".parse().unwrap(), RequestOutcome::Timeout, None);
+        health.record("10.0.0.3:47808".parse().unwrap(), RequestOutcome::Ack, Some(Duration::from_millis(9)));
+    }
+    app.view_state = ViewState::DeviceList;
+    app.show_health();
+    assert!(matches!(app.view_state, ViewState::Health));
+    assert_eq!(app.health_table_state.selected(), Some(0));
+
+    // The device that timed out first; an address no device answers from has no ID
+    let ranking: Vec<Option<u32>> = app.health_ranking().into_iter().map(|(id, _, _)| id).collect();
+    assert_eq!(ranking, [Some(2), None, Some(1)]);
+    app.select_last();
+    assert_eq!(app.health_table_state.selected(), Some(2));
+
+    app.clear_health();
+    assert!(app.health_ranking().is_empty());
+    app.exit_view();
+    assert!(matches!(app.view_state, ViewState::DeviceList));
+}
+
+#[test]
+fn test_topology_view() {
+    use bacnet_discovery::topology::NodeKind;
+    use bacnet_discovery::traffic::Datagram;
+    use std::time::Duration;
+
+    let mut app = App::new();
+    app.show_topology();
+    assert!(matches!(app.view_state, ViewState::InterfaceSelect), "nothing is received before an interface is selected");
+
+    // I-Am from device 7 on network 9 behind a router
+    let iam = [
+        0x81, 0x0B, 0x00, 0x18, 0x01, 0x08, 0x00, 0x09, 0x01, 0x02,
+        0x10, 0x00, 0xC4, 0x02, 0x00, 0x00, 0x07, 0x22, 0x05, 0xC4, 0x91, 0x03, 0x21, 0x0F,
+    ];
+    let datagram = Datagram::new(Duration::ZERO, "10.0.0.1:47808".parse().unwrap(), "10.0.0.255:47808".parse().unwrap(), &iam);
+    app.topology.lock().unwrap().record(&datagram);
+
+    app.view_state = ViewState::DeviceList;
+    app.show_topology();
+    assert!(matches!(app.view_state, ViewState::Topology));
+    assert_eq!(app.topology_list_state.selected(), Some(0));
+    let kinds: Vec<NodeKind> = app.topology_tree().iter().map(|n| n.kind).collect();
+    assert_eq!(kinds, [NodeKind::Subnet, NodeKind::Host, NodeKind::Network, NodeKind::Device]);
+    app.select_last();
+    assert_eq!(app.topology_list_state.selected(), Some(3));
+
+    app.clear_topology();
+    assert!(app.topology_tree().is_empty());
+    app.exit_view();
+    assert!(matches!(app.view_state, ViewState::DeviceList));
+}
+
+#[test]
+fn test_overlay_views_return_to_previous_view() {
+    let mut app = app_with_points(3);
+    app.show_traffic();
+    app.show_health();
+    assert!(matches!(app.view_state, ViewState::Health));
+    app.exit_view();
+    assert!(matches!(app.view_state, ViewState::ObjectList(1)));
+
+    app.view_state = ViewState::ObjectProperties(1, ObjectIdentifier::new(ObjectType::AnalogValue, 10));
+    app.show_topology();
+    app.exit_view();
+    assert!(matches!(app.view_state, ViewState::ObjectProperties(1, _)));
+}
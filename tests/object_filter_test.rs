@@ -0,0 +1,120 @@
+mod common;
+
+use bacnet_discovery::app::{fuzzy_match, App, BacnetObject, ObjectFilter, TypeFilter, ViewState};
+use bacnet_discovery::bacnet::PointReading;
+use bacnet_discovery::property::{PresentValue, StatusFlags};
+use bacnet_rs::object::{ObjectIdentifier, ObjectType};
+use common::point;
+
+fn sample_points() -> Vec<BacnetObject> {
+    let mut alarmed = point(ObjectType::AnalogInput, 2, "AHU-1 Return Air Temp", PresentValue::Unknown);
+    alarmed.status_flags = Some(StatusFlags { in_alarm: true, ..Default::default() });
+    let mut overridden = point(ObjectType::BinaryOutput, 1, "AHU-1 Supply Fan", PresentValue::Unknown);
+    overridden.status_flags = Some(StatusFlags { overridden: true, ..Default::default() });
+    let mut described = point(ObjectType::MultiStateValue, 7, "MSV 7", PresentValue::Unknown);
+    described.description = Some("Occupancy mode".to_string());
+    vec![
+        point(ObjectType::AnalogInput, 1, "AHU-1 Supply Air Temp", PresentValue::Unknown),
+        alarmed,
+        overridden,
+        described,
+        point(ObjectType::Schedule, 1, "Office hours", PresentValue::Unknown),
+    ]
+}
+
+#[test]
+fn test_fuzzy_match() {
+    assert!(fuzzy_match("ahusat", "AHU-1 Supply Air Temp"));
+    assert!(fuzzy_match("supply temp", "AHU-1 Supply Air Temp"));
+    assert!(fuzzy_match("", "anything"));
+    assert!(!fuzzy_match("tempsupply", "AHU-1 Supply Air Temp"));
+    assert!(!fuzzy_match("xyz", "AHU-1 Supply Air Temp"));
+}
+
+#[test]
+fn test_filter_by_query_type_and_problems() {
+    let points = sample_points();
+    let names = |filter: &ObjectFilter| filter.apply(&points).iter().map(|p| p.name.clone()).collect::<Vec<_>>();
+
+    let mut filter = ObjectFilter { query: "supply".to_string(), ..Default::default() };
+    assert_eq!(names(&filter), ["AHU-1 Supply Air Temp", "AHU-1 Supply Fan"]);
+
+    // Description and both forms of the object ID are searched
+    filter.query = "occupancy".to_string();
+    assert_eq!(names(&filter), ["MSV 7"]);
+    filter.query = "ai2".to_string();
+    assert_eq!(names(&filter), ["AHU-1 Return Air Temp"]);
+    filter.query = "binaryoutput:1".to_string();
+    assert_eq!(names(&filter), ["AHU-1 Supply Fan"]);
+
+    filter.query.clear();
+    filter.type_filter = TypeFilter::AnalogInput;
+    assert_eq!(names(&filter).len(), 2);
+    filter.type_filter = TypeFilter::MultiState;
+    assert_eq!(names(&filter), ["MSV 7"]);
+    filter.type_filter = TypeFilter::Other;
+    assert_eq!(names(&filter), ["Office hours"]);
+
+    filter.type_filter = TypeFilter::All;
+    filter.problems_only = true;
+    assert_eq!(names(&filter), ["AHU-1 Return Air Temp", "AHU-1 Supply Fan"]);
+    assert_eq!(filter.describe(), "alarmed/overridden");
+}
+
+#[test]
+fn test_type_filter_cycles_back_to_all() {
+    let mut filter = TypeFilter::All;
+    let mut labels = Vec::new();
+    for _ in 0..9 {
+        filter = filter.next();
+        labels.push(filter.label());
+    }
+    assert_eq!(labels, ["AI", "AO", "AV", "BI", "BO", "BV", "MSx", "Other", "All"]);
+}
+
+#[test]
+fn test_selection_follows_filtered_view() {
+    let mut app = App::new();
+    app.device_objects.lock().unwrap().insert(1, sample_points());
+    app.view_state = ViewState::ObjectList(1);
+
+    app.start_search();
+    for c in "ahu".chars() {
+        app.push_search_char(c);
+    }
+    app.finish_search(true);
+    assert_eq!(app.object_table_state.selected(), Some(0));
+
+    // Three matches: wrapping happens at the end of the filtered list
+    app.next();
+    app.next();
+    app.next();
+    assert_eq!(app.object_table_state.selected(), Some(0));
+    app.previous();
+    assert_eq!(app.object_table_state.selected(), Some(2));
+
+    app.enter_object();
+    assert!(matches!(app.view_state, ViewState::ObjectProperties(1, id) if id == ObjectIdentifier::new(ObjectType::BinaryOutput, 1)));
+
+    app.exit_view();
+    app.start_search();
+    app.push_search_char('z');
+    assert_eq!(app.object_table_state.selected(), None);
+    app.finish_search(false);
+    assert_eq!(app.object_table_state.selected(), Some(0));
+    assert!(!app.object_filter.is_active());
+}
+
+#[test]
+fn test_first_poll_fills_name_and_description() {
+    let mut obj = point(ObjectType::AnalogValue, 3, "AnalogValue:3", PresentValue::Unknown);
+    assert!(obj.needs_details());
+    obj.apply_reading(PointReading {
+        present_value: PresentValue::Analog(1.0),
+        name: Some("Setpoint".to_string()),
+        description: Some(String::new()),
+        ..Default::default()
+    });
+    assert_eq!(obj.name, "Setpoint");
+    assert!(!obj.needs_details());
+}
@@ -0,0 +1,76 @@
+use bacnet_discovery::decode::{ApduHeader, decode_packet};
+use bacnet_discovery::pcap::{PcapWriter, read_capture, read_capture_file};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::Duration;
+
+fn fixture(name: &str) -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+#[test]
+fn test_write_and_read_back() {
+    let who_is = [0x81, 0x0B, 0x00, 0x08, 0x01, 0x00, 0x10, 0x08];
+    let source: SocketAddr = "10.1.2.3:47808".parse().unwrap();
+    let broadcast: SocketAddr = "255.255.255.255:47808".parse().unwrap();
+
+    let mut writer = PcapWriter::new(Vec::new()).unwrap();
+    writer.write_packet(Duration::new(1_760_000_000, 123_456_000), source, broadcast, &who_is).unwrap();
+    writer.write_packet(Duration::new(1_760_000_001, 0), "10.1.2.4:47808".parse().unwrap(), source, &who_is).unwrap();
+    let bytes = writer.into_inner();
+    // File header, then per record: 16-byte header, Ethernet, IPv4, UDP, payload
+    assert_eq!(bytes.len(), 24 + 2 * (16 + 14 + 20 + 8 + who_is.len()));
+    assert_eq!(&bytes[..4], &[0xD4, 0xC3, 0xB2, 0xA1]);
+
+    let packets = read_capture(bytes.as_slice()).unwrap();
+    assert_eq!(packets.len(), 2);
+    assert_eq!(packets[0].timestamp, Duration::new(1_760_000_000, 123_456_000));
+    assert_eq!(packets[0].source, source);
+    assert_eq!(packets[0].destination, broadcast);
+    assert_eq!(packets[0].payload, who_is);
+    assert_eq!(packets[1].destination, source);
+}
+
+#[test]
+fn test_ipv6_is_refused() {
+    let mut writer = PcapWriter::new(Vec::new()).unwrap();
+    let v6: SocketAddr = "[::1]:47808".parse().unwrap();
+    assert!(writer.write_packet(Duration::ZERO, v6, v6, &[0x81]).is_err());
+}
+
+#[test]
+fn test_read_pcapng_fixture() {
+    let packets = read_capture_file(&fixture("read_property.pcapng")).unwrap();
+    // The ARP frame in the capture is skipped
+    assert_eq!(packets.len(), 4);
+    assert_eq!(packets[0].timestamp, Duration::new(1_760_000_000, 0));
+    assert_eq!(packets[1].timestamp, Duration::new(1_760_000_000, 12_345_678));
+    assert_eq!(packets[2].source, "192.168.1.10:50123".parse().unwrap());
+
+    let summaries: Vec<String> = packets.iter().map(|p| decode_packet(&p.payload).summary()).collect();
+    assert_eq!(summaries, [
+        "Who-Is 100 1000",
+        "I-Am Device:1001 1476",
+        "ReadProperty request AnalogInput:1 Present_Value (85) (invoke 7)",
+        "ReadProperty ack AnalogInput:1 Present_Value (85) (invoke 7)",
+    ]);
+}
+
+#[test]
+fn test_read_big_endian_vlan_fixture() {
+    let packets = read_capture_file(&fixture("router_vlan.pcap")).unwrap();
+    assert_eq!(packets.len(), 2);
+    assert_eq!(packets[1].timestamp, Duration::new(1_760_000_101, 250_000_000));
+    assert_eq!(packets[0].destination, "10.0.0.255:47808".parse().unwrap());
+
+    let network = decode_packet(&packets[0].payload);
+    assert_eq!(network.network_message.map(|m| m.message_type), Some(1));
+    let forwarded = decode_packet(&packets[1].payload);
+    assert_eq!(forwarded.apdu, Some(ApduHeader::UnconfirmedRequest { service: 8 }));
+}
+
+#[test]
+fn test_not_a_capture() {
+    assert!(read_capture(&b"{\"devices\": []}"[..]).is_err());
+    assert!(read_capture(&[0xD4, 0xC3, 0xB2, 0xA1, 0x02][..]).is_err());
+}
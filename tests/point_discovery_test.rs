@@ -1,4 +1,4 @@
-use bacnet_discovery::bacnet::{send_whois_to, process_response, read_device_objects};
+use bacnet_discovery::bacnet::{send_whois_to, process_response, read_device_objects, ConfirmedResponse, RequestRegistrar};
 use bacnet_rs::{
     app::Apdu,
     network::Npdu,
@@ -27,11 +27,11 @@ async fn test_point_discovery() {
                         let iam = create_iam_response(device_id);
                         socket.send_to(&iam, source).ok();
                     }
-                } else if let Some((invoke_id, service, _)) = process_confirmed_request(data) {
-                    if service == 14 { // RPM
-                        let res = create_rpm_response(invoke_id, device_id);
-                        socket.send_to(&res, source).ok();
-                    }
+                } else if let Some((invoke_id, service, _)) = process_confirmed_request(data)
+                    && service == 14 // RPM
+                {
+                    let res = create_rpm_response(invoke_id, device_id);
+                    socket.send_to(&res, source).ok();
                 }
             }
         }
@@ -42,17 +42,18 @@ async fn test_point_discovery() {
     let scanner_socket = UdpSocket::bind("127.0.0.1:0").expect("Scanner failed to bind");
     let dest: SocketAddr = format!("127.0.0.1:{}", responder_port).parse().unwrap();
     
-    let (tx_register, mut rx_register) = mpsc::channel::<(u8, tokio::sync::oneshot::Sender<Vec<u8>>)>(10);
+    let (tx_register, mut rx_register) = mpsc::channel::<(SocketAddr, u8, tokio::sync::oneshot::Sender<ConfirmedResponse>)>(10);
+    let tx_register = RequestRegistrar::new(tx_register);
     let (tx_found, mut rx_found) = mpsc::channel(10);
     
     let s_clone = scanner_socket.try_clone().unwrap();
     tokio::spawn(async move {
-        let mut pending: std::collections::HashMap<u8, tokio::sync::oneshot::Sender<Vec<u8>>> = std::collections::HashMap::new();
+        let mut pending: std::collections::HashMap<u8, tokio::sync::oneshot::Sender<ConfirmedResponse>> = std::collections::HashMap::new();
         let mut buf = [0u8; 1500];
         loop {
             tokio::select! {
                 reg = rx_register.recv() => {
-                    if let Some((id, tx)) = reg { pending.insert(id, tx); }
+                    if let Some((_, id, tx)) = reg { pending.insert(id, tx); }
                 }
                 _ = tokio::task::yield_now() => {
                     s_clone.set_nonblocking(true).ok();
@@ -61,6 +62,8 @@ async fn test_point_discovery() {
                         if let Some(device) = process_response(data, addr) {
                             let _ = tx_found.send(device).await;
                         } else if let Some((id, sdata)) = bacnet_discovery::bacnet::parse_confirmed_response(data) {
+                            // The request is registered before it is sent, so pick up any registration still queued
+                            while let Ok((_, id, tx)) = rx_register.try_recv() { pending.insert(id, tx); }
                             if let Some(tx) = pending.remove(&id) { let _ = tx.send(sdata); }
                         }
                     }
@@ -0,0 +1,157 @@
+use bacnet_discovery::bacnet::DiscoveredDevice;
+use bacnet_discovery::probe::{
+    self, BdtEntry, BdtReply, CheckResult, LocalInterface, ProbeReport, Responder, Status, parse_bdt_reply, read_bdts,
+};
+use std::collections::BTreeMap;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::thread;
+use std::time::Duration;
+
+fn eth0() -> LocalInterface {
+    LocalInterface {
+        name: "eth0".to_string(),
+        ip: Ipv4Addr::new(192, 168, 1, 10),
+        netmask: Ipv4Addr::new(255, 255, 255, 0),
+        broadcast: Some(Ipv4Addr::new(192, 168, 1, 255)),
+    }
+}
+
+fn responder(device_id: u32, address: &str, via: Option<&str>) -> Responder {
+    Responder {
+        device: DiscoveredDevice::new(device_id, address.parse().unwrap()),
+        via: via.map(|v| v.parse().unwrap()),
+    }
+}
+
+#[test]
+fn test_subnets_and_interfaces() {
+    let iface = eth0();
+    assert!(iface.contains("192.168.1.200".parse().unwrap()));
+    assert!(!iface.contains("192.168.2.1".parse().unwrap()));
+    assert!(!iface.contains("::1".parse().unwrap()));
+    assert_eq!(iface.cidr(), "192.168.1.10/24");
+
+    let responders = [
+        responder(1, "192.168.1.20:47808", None),
+        // Misconfigured: answers directly from another subnet
+        responder(2, "10.1.1.5:47808", None),
+        // Relayed by a BBMD from a remote subnet
+        responder(3, "172.16.0.9:47808", Some("192.168.1.1:47808")),
+    ];
+    let interfaces = [iface];
+    let checks = probe::check_interfaces(&interfaces, &responders);
+    assert_eq!(checks[0].status, Status::Pass);
+    assert_eq!(checks[0].details, ["Device 1 at 192.168.1.20:47808"]);
+
+    let subnets = probe::check_subnets(&interfaces, &responders);
+    assert_eq!(subnets.status, Status::Warn);
+    assert_eq!(subnets.details[0], "Device 2 at 10.1.1.5:47808 is outside 192.168.1.10/24");
+    assert_eq!(subnets.details.len(), 2);
+
+    assert_eq!(probe::check_interfaces(&interfaces, &[])[0].status, Status::Warn);
+    assert_eq!(probe::check_interfaces(&[], &responders)[0].status, Status::Fail);
+    assert_eq!(
+        probe::probe_targets(&responders),
+        ["192.168.1.20:47808", "10.1.1.5:47808", "172.16.0.9:47808", "192.168.1.1:47808"].map(|a| a.parse::<SocketAddr>().unwrap())
+    );
+}
+
+#[test]
+fn test_duplicates() {
+    let unique = [responder(1, "192.168.1.20:47808", None), responder(2, "192.168.1.21:47808", None)];
+    assert_eq!(probe::check_duplicates(&unique).status, Status::Pass);
+
+    let duplicated = [responder(7, "192.168.1.20:47808", None), responder(7, "192.168.1.21:47808", None)];
+    let check = probe::check_duplicates(&duplicated);
+    assert_eq!(check.status, Status::Fail);
+    assert!(check.details[0].starts_with("Duplicate device instance 7 at"));
+}
+
+#[test]
+fn test_parse_bdt_reply() {
+    let ack = [
+        0x81, 0x03, 0x00, 0x18,
+        192, 168, 1, 1, 0xBA, 0xC0, 255, 255, 255, 255,
+        10, 0, 0, 1, 0xBA, 0xC0, 255, 255, 255, 0,
+    ];
+    let Some(BdtReply::Table(entries)) = parse_bdt_reply(&ack) else { panic!() };
+    assert_eq!(entries, [
+        BdtEntry { address: "192.168.1.1:47808".parse().unwrap(), mask: Ipv4Addr::BROADCAST },
+        BdtEntry { address: "10.0.0.1:47808".parse().unwrap(), mask: Ipv4Addr::new(255, 255, 255, 0) },
+    ]);
+    assert_eq!(parse_bdt_reply(&[0x81, 0x00, 0x00, 0x06, 0x00, 0x20]), Some(BdtReply::Nak(0x20)));
+    assert_eq!(parse_bdt_reply(&probe::READ_BDT), None);
+    assert_eq!(parse_bdt_reply(&[0x81, 0x0A, 0x00, 0x08, 0x01, 0x00, 0x10, 0x08]), None);
+}
+
+#[test]
+fn test_read_bdts() {
+    // A BBMD that lists only its peer, and a device that is no BBMD
+    let bbmd = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let plain = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let (bbmd_addr, plain_addr, silent_addr) = (bbmd.local_addr().unwrap(), plain.local_addr().unwrap(), silent.local_addr().unwrap());
+    for (socket, reply) in [
+        (bbmd, vec![0x81, 0x03, 0x00, 0x0E, 10, 0, 0, 1, 0xBA, 0xC0, 255, 255, 255, 255]),
+        (plain, vec![0x81, 0x00, 0x00, 0x06, 0x00, 0x20]),
+    ] {
+        thread::spawn(move || {
+            let mut buf = [0u8; 64];
+            let (len, source) = socket.recv_from(&mut buf).unwrap();
+            assert_eq!(&buf[..len], &probe::READ_BDT);
+            socket.send_to(&reply, source).unwrap();
+        });
+    }
+
+    let scanner = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let replies = read_bdts(&scanner, &[bbmd_addr, plain_addr, silent_addr], Duration::from_millis(500)).unwrap();
+    assert!(matches!(&replies[&bbmd_addr], Some(BdtReply::Table(entries)) if entries.len() == 1));
+    assert_eq!(replies[&plain_addr], Some(BdtReply::Nak(0x20)));
+    assert_eq!(replies[&silent_addr], None);
+
+    let check = probe::check_bbmds(&replies);
+    assert_eq!(check.status, Status::Warn, "the table does not list the BBMD itself");
+    assert_eq!(check.summary, "1 BBMD(s) among 3 probed; 1 refused, 1 did not answer");
+    assert_eq!(probe::check_bbmds(&BTreeMap::new()).status, Status::Pass);
+}
+
+#[test]
+fn test_read_bdts_send_failure() {
+    // An IPv6 target cannot be reached from an IPv4 socket
+    let unreachable: SocketAddr = "[::1]:47808".parse().unwrap();
+    let scanner = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let replies = read_bdts(&scanner, &[unreachable], Duration::from_millis(200)).unwrap();
+    assert!(matches!(replies[&unreachable], Some(BdtReply::SendFailed(_))));
+
+    let check = probe::check_bbmds(&replies);
+    assert_eq!(check.status, Status::Warn);
+    assert_eq!(check.summary, "0 BBMD(s) among 1 probed; 0 refused, 0 did not answer, 1 could not be reached");
+    assert!(check.details[0].starts_with("Could not send a Read-BDT to [::1]:47808"));
+}
+
+#[test]
+fn test_port_held_exclusively() {
+    let holder = UdpSocket::bind("0.0.0.0:0").unwrap();
+    let checks = probe::check_port(holder.local_addr().unwrap().port());
+    assert_eq!(checks[0].status, Status::Warn);
+    assert_eq!(checks[1].status, Status::Fail);
+    drop(holder);
+}
+
+#[test]
+fn test_report() {
+    let mut report = ProbeReport::new();
+    assert_eq!(report.status(), Status::Pass);
+    report.push(CheckResult::new("Port 47808", Status::Pass, "Free"));
+    report.push(CheckResult::new("BBMDs", Status::Warn, "Odd table").with_details(["BBMD 10.0.0.1:47808".to_string()]));
+    assert_eq!(report.status(), Status::Warn);
+    report.push(CheckResult::new("Duplicate device IDs", Status::Fail, "1 duplicate"));
+    assert_eq!(report.status(), Status::Fail);
+
+    let text = report.to_text();
+    assert!(text.starts_with("[PASS] Port 47808: Free\n[WARN] BBMDs: Odd table\n       BBMD 10.0.0.1:47808\n"));
+    assert!(text.ends_with("1 passed, 1 warnings, 1 failed\n"));
+    let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+    assert_eq!(json["checks"][2]["status"], "fail");
+    assert_eq!(json["checks"][1]["details"][0], "BBMD 10.0.0.1:47808");
+}
@@ -0,0 +1,488 @@
+use bacnet_discovery::app::BacnetObject;
+use bacnet_discovery::bacnet::{detail_properties, parse_confirmed_response, read_all_properties, read_point, read_properties, read_property_set, ConfirmedResponse, PointReading, RequestRegistrar, UNKNOWN_ERROR};
+use bacnet_discovery::health::HealthStats;
+use bacnet_discovery::property::{self, decode_error, decode_value, parse_rpm_ack, BacnetValue, PresentValue, PropertyError, StateTexts, StatusFlags};
+use bacnet_rs::{
+    app::Apdu,
+    network::Npdu,
+    object::{ObjectIdentifier, ObjectType},
+    service::ConfirmedServiceChoice,
+};
+use std::collections::HashMap;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+fn device_ack(device_id: u32) -> Vec<u8> {
+    let mut data = vec![0x0C];
+    data.extend_from_slice(&(((ObjectType::Device as u32) << 22) | device_id).to_be_bytes());
+    data.push(0x1E);
+    // Object_Name: "AHU-1"
+    data.extend_from_slice(&[0x29, 77, 0x4E, 0x75, 0x06, 0x00, b'A', b'H', b'U', b'-', b'1', 0x4F]);
+    // UTC_Offset: -60
+    data.extend_from_slice(&[0x29, 119, 0x4E, 0x31, 0xC4, 0x4F]);
+    // Local_Date 2026-10-18 and Local_Time 12:30:05.00
+    data.extend_from_slice(&[0x29, 56, 0x4E, 0xA4, 126, 10, 18, 7, 0x4F]);
+    data.extend_from_slice(&[0x29, 57, 0x4E, 0xB4, 12, 30, 5, 0, 0x4F]);
+    // Protocol_Services_Supported with ReadProperty (12) and TimeSynchronization (32) set
+    data.extend_from_slice(&[0x29, 97, 0x4E, 0x85, 0x06, 0x07, 0x00, 0x08, 0x00, 0x00, 0x80, 0x4F]);
+    // Max_Master: unknown-property
+    data.extend_from_slice(&[0x29, 64, 0x5E, 0x91, 0x02, 0x91, 0x20, 0x5F]);
+    data.push(0x1F);
+    data
+}
+
+#[test]
+fn test_parse_rpm_ack() {
+    let results = parse_rpm_ack(&device_ack(1234)).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].object, ObjectIdentifier::new(ObjectType::Device, 1234));
+
+    let props = &results[0].properties;
+    assert_eq!(props.len(), 6);
+    assert_eq!(property::find(props, property::OBJECT_NAME), Some(&BacnetValue::CharacterString("AHU-1".to_string())));
+    assert_eq!(property::find(props, property::UTC_OFFSET), Some(&BacnetValue::Signed(-60)));
+    assert_eq!(
+        property::find(props, property::LOCAL_DATE),
+        Some(&BacnetValue::Date { year: 2026, month: 10, day: 18, weekday: 7 })
+    );
+    assert_eq!(
+        property::find(props, property::LOCAL_TIME),
+        Some(&BacnetValue::Time { hour: 12, minute: 30, second: 5, hundredths: 0 })
+    );
+
+    let Some(BacnetValue::BitString(bits)) = property::find(props, property::PROTOCOL_SERVICES_SUPPORTED) else {
+        panic!("Services_Supported not decoded as a bitstring");
+    };
+    assert_eq!(bits.len(), 33);
+    let set: Vec<_> = bits.iter().enumerate().filter(|(_, b)| **b).map(|(i, _)| i).collect();
+    assert_eq!(set, vec![12, 32]);
+
+    let max_master = props.iter().find(|p| p.id == property::MAX_MASTER).unwrap();
+    assert_eq!(max_master.value, Err(PropertyError { class: 2, code: 32 }));
+    assert_eq!(property::find(props, property::MAX_MASTER), None);
+}
+
+#[test]
+fn test_decode_values() {
+    // Extended length character string in ISO 8859-1
+    let mut data = vec![0x75, 0x05, 0x05, b'B', 0xFC, b'r', b'o'];
+    assert_eq!(decode_value(&data), Some((BacnetValue::CharacterString("Büro".to_string()), 7)));
+
+    // Application boolean carries its value in the tag
+    assert_eq!(decode_value(&[0x11]), Some((BacnetValue::Boolean(true), 1)));
+    assert_eq!(decode_value(&[0x10]), Some((BacnetValue::Boolean(false), 1)));
+
+    assert_eq!(decode_value(&[0x32, 0xFF, 0x38]), Some((BacnetValue::Signed(-200), 3)));
+    assert_eq!(decode_value(&[0x23, 0x01, 0x00, 0x00]), Some((BacnetValue::Unsigned(65536), 4)));
+    assert_eq!(decode_value(&[0x44, 0x41, 0xBC, 0x00, 0x00]), Some((BacnetValue::Real(23.5), 5)));
+
+    // Constructed value with a nested context tag
+    data = vec![0x2E, 0x09, 0x03, 0x2F];
+    assert_eq!(
+        decode_value(&data),
+        Some((BacnetValue::Constructed { tag: 2, values: vec![BacnetValue::Context { tag: 0, data: vec![3] }] }, 4))
+    );
+
+    // Truncated values are rejected instead of read past the end
+    assert_eq!(decode_value(&[0x44, 0x41, 0xBC]), None);
+    assert_eq!(decode_value(&[0x2E, 0x09, 0x03]), None);
+}
+
+#[tokio::test]
+async fn test_read_properties() {
+    let responder = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let responder_addr = responder.local_addr().unwrap();
+    let device_id = 1234;
+
+    thread::spawn(move || {
+        let mut buf = [0u8; 1500];
+        let (len, source) = responder.recv_from(&mut buf).unwrap();
+        let (_npdu, npdu_len) = Npdu::decode(&buf[4..len]).unwrap();
+        let Ok(Apdu::ConfirmedRequest { invoke_id, service_data, .. }) = Apdu::decode(&buf[4 + npdu_len..len]) else {
+            panic!("Expected a confirmed request");
+        };
+        // Property identifiers above 255 need two octets
+        assert!(service_data.windows(3).any(|w| w == [0x0A, 0x01, 0x49]));
+
+        let apdu = Apdu::ComplexAck {
+            segmented: false,
+            more_follows: false,
+            invoke_id,
+            sequence_number: None,
+            proposed_window_size: None,
+            service_choice: ConfirmedServiceChoice::ReadPropertyMultiple as u8,
+            service_data: device_ack(device_id),
+        };
+        let mut msg = Npdu::new().encode();
+        msg.extend_from_slice(&apdu.encode());
+        let mut bvlc = vec![0x81, 0x0A, 0, (msg.len() + 4) as u8];
+        bvlc.extend_from_slice(&msg);
+        responder.send_to(&bvlc, source).unwrap();
+    });
+
+    let scanner = UdpSocket::bind("127.0.0.1:0").unwrap();
+    scanner.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    let (tx_register, mut rx_register) = mpsc::channel::<(SocketAddr, u8, oneshot::Sender<ConfirmedResponse>)>(10);
+    let tx_register = RequestRegistrar::new(tx_register);
+
+    let s_recv = scanner.try_clone().unwrap();
+    tokio::task::spawn_blocking(move || {
+        let mut buf = [0u8; 1500];
+        let (len, _) = s_recv.recv_from(&mut buf).unwrap();
+        let (id, data) = parse_confirmed_response(&buf[..len]).unwrap();
+        let (_, registered_id, tx) = rx_register.blocking_recv().unwrap();
+        assert_eq!(id, registered_id);
+        let _ = tx.send(data);
+    });
+
+    let obj = ObjectIdentifier::new(ObjectType::Device, device_id);
+    let props = read_properties(&scanner, responder_addr, obj, &[property::OBJECT_NAME, 329], 7, &tx_register).await.unwrap();
+    assert_eq!(property::find(&props, property::OBJECT_NAME), Some(&BacnetValue::CharacterString("AHU-1".to_string())));
+}
+
+#[test]
+fn test_decode_error() {
+    // ReadProperty error: property / unknown-property
+    assert_eq!(decode_error(&[0x91, 0x02, 0x91, 0x20]), Some(PropertyError { class: 2, code: 32 }));
+    // ReadPropertyMultiple wraps the error in context tag 0
+    assert_eq!(decode_error(&[0x0E, 0x91, 0x01, 0x91, 0x1F, 0x0F, 0x1E]), Some(PropertyError { class: 1, code: 31 }));
+
+    let packet = [0x81, 0x0A, 0x00, 0x0D, 0x01, 0x00, 0x50, 0x07, 0x0C, 0x91, 0x02, 0x91, 0x20];
+    assert_eq!(parse_confirmed_response(&packet), Some((7, ConfirmedResponse::Error { class: 2, code: 32 })));
+
+    // A truncated error body still answers the request
+    let packet = [0x81, 0x0A, 0x00, 0x0B, 0x01, 0x00, 0x50, 0x07, 0x0C, 0x91, 0x02];
+    assert_eq!(parse_confirmed_response(&packet), Some((7, ConfirmedResponse::Error { class: UNKNOWN_ERROR, code: UNKNOWN_ERROR })));
+}
+
+/// Answers confirmed requests on a local socket with whatever APDU `handler` builds.
+fn spawn_responder(handler: impl Fn(u8, u8, &[u8]) -> Vec<u8> + Send + 'static) -> SocketAddr {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = socket.local_addr().unwrap();
+    thread::spawn(move || {
+        let mut buf = [0u8; 1500];
+        while let Ok((len, source)) = socket.recv_from(&mut buf) {
+            let (_npdu, npdu_len) = Npdu::decode(&buf[4..len]).unwrap();
+            let Ok(Apdu::ConfirmedRequest { invoke_id, service_choice, service_data, .. }) = Apdu::decode(&buf[4 + npdu_len..len]) else {
+                continue;
+            };
+            let mut msg = Npdu::new().encode();
+            msg.extend_from_slice(&handler(invoke_id, service_choice, &service_data));
+            let mut bvlc = vec![0x81, 0x0A, 0, (msg.len() + 4) as u8];
+            bvlc.extend_from_slice(&msg);
+            socket.send_to(&bvlc, source).unwrap();
+        }
+    });
+    addr
+}
+
+/// Dispatches responses arriving on `scanner` to the registered requests.
+fn spawn_dispatcher(scanner: &UdpSocket) -> RequestRegistrar {
+    let (tx_register, rx_register) = mpsc::channel::<(SocketAddr, u8, oneshot::Sender<ConfirmedResponse>)>(10);
+    let s_recv = scanner.try_clone().unwrap();
+    thread::spawn(move || {
+        let mut rx_register = rx_register;
+        let mut pending = HashMap::new();
+        let mut buf = [0u8; 1500];
+        while let Ok((len, _)) = s_recv.recv_from(&mut buf) {
+            let Some((id, response)) = parse_confirmed_response(&buf[..len]) else { continue };
+            // The request is registered before it is sent
+            while !pending.contains_key(&id) {
+                let Some((_, registered, tx)) = rx_register.blocking_recv() else { return };
+                pending.insert(registered, tx);
+            }
+            let _ = pending.remove(&id).unwrap().send(response);
+        }
+    });
+    RequestRegistrar::new(tx_register)
+}
+
+#[tokio::test]
+async fn test_read_all_properties_falls_back_to_read_property() {
+    // A device without RPM support: rejects RPM, answers ReadProperty
+    let responder = spawn_responder(|invoke_id, service, data| {
+        if service == ConfirmedServiceChoice::ReadPropertyMultiple as u8 {
+            return vec![0x60, invoke_id, 0x09]; // Reject: unrecognized-service
+        }
+        let mut ack = vec![0x30, invoke_id, service];
+        ack.extend_from_slice(&data[..5]);
+        let prop = match &data[5..] {
+            [0x19, p, ..] => *p as u32,
+            [0x1A, hi, lo, ..] => u16::from_be_bytes([*hi, *lo]) as u32,
+            _ => panic!("Unexpected ReadProperty request"),
+        };
+        let value: &[u8] = match prop {
+            371 => &[0x91, 85, 0x91, 111, 0x91, 22],
+            75 => &[0xC4, 0x00, 0x00, 0x00, 0x03],
+            77 => &[0x75, 0x04, 0x00, b'O', b'A', b'T'],
+            79 => &[0x91, 0x00],
+            85 => &[0x44, 0x41, 0xBC, 0x00, 0x00],
+            111 => &[0x82, 0x04, 0x20], // Overridden
+            _ => return vec![0x50, invoke_id, service, 0x91, 0x02, 0x91, 0x20],
+        };
+        ack.extend_from_slice(&data[5..if prop > 255 { 8 } else { 7 }]);
+        ack.push(0x3E);
+        ack.extend_from_slice(value);
+        ack.push(0x3F);
+        ack
+    });
+
+    let scanner = UdpSocket::bind("127.0.0.1:0").unwrap();
+    scanner.set_read_timeout(Some(Duration::from_secs(3))).unwrap();
+    let tx_register = spawn_dispatcher(&scanner);
+
+    let mut invoke_id = 0u8;
+    let obj = ObjectIdentifier::new(ObjectType::AnalogInput, 3);
+    let props = read_all_properties(&scanner, responder, obj, || { invoke_id += 1; invoke_id }, &tx_register).await.unwrap();
+
+    let ids: Vec<u32> = props.iter().map(|p| p.id).collect();
+    assert_eq!(ids, vec![75, 77, 79, 85, 111, 22]);
+    assert_eq!(property::find(&props, property::OBJECT_NAME), Some(&BacnetValue::CharacterString("OAT".to_string())));
+    assert_eq!(property::find(&props, property::STATUS_FLAGS), Some(&BacnetValue::BitString(vec![false, false, true, false])));
+    assert_eq!(props[5].value, Err(PropertyError { class: 2, code: 32 }));
+}
+
+#[tokio::test]
+async fn test_read_all_properties_uses_rpm_all() {
+    let responder = spawn_responder(|invoke_id, service, data| {
+        assert_eq!(service, ConfirmedServiceChoice::ReadPropertyMultiple as u8);
+        // Only the ALL property is requested
+        assert_eq!(&data[5..], &[0x1E, 0x09, 0x08, 0x1F]);
+        let mut ack = vec![0x30, invoke_id, service];
+        ack.extend_from_slice(&device_ack(1234));
+        ack
+    });
+
+    let scanner = UdpSocket::bind("127.0.0.1:0").unwrap();
+    scanner.set_read_timeout(Some(Duration::from_secs(3))).unwrap();
+    let tx_register = spawn_dispatcher(&scanner);
+
+    let obj = ObjectIdentifier::new(ObjectType::Device, 1234);
+    let props = read_all_properties(&scanner, responder, obj, || 1, &tx_register).await.unwrap();
+    assert_eq!(props.len(), 6);
+}
+
+#[tokio::test]
+async fn test_read_property_set_without_rpm() {
+    // Rejects RPM; has a Present_Value but no Description
+    let responder = spawn_responder(|invoke_id, service, data| {
+        if service == ConfirmedServiceChoice::ReadPropertyMultiple as u8 {
+            return vec![0x60, invoke_id, 0x09];
+        }
+        if data[5..7] != [0x19, 85] {
+            return vec![0x50, invoke_id, service, 0x91, 0x02, 0x91, 0x20];
+        }
+        let mut ack = vec![0x30, invoke_id, service];
+        ack.extend_from_slice(&data[..7]);
+        ack.extend_from_slice(&[0x3E, 0x44, 0x42, 0x48, 0x00, 0x00, 0x3F]);
+        ack
+    });
+
+    let scanner = UdpSocket::bind("127.0.0.1:0").unwrap();
+    scanner.set_read_timeout(Some(Duration::from_secs(3))).unwrap();
+    let tx_register = spawn_dispatcher(&scanner);
+
+    let obj = ObjectIdentifier::new(ObjectType::AnalogValue, 1);
+    let props = read_property_set(&scanner, responder, obj, &[property::PRESENT_VALUE, property::DESCRIPTION], 1, &tx_register).await.unwrap();
+    assert_eq!(props.len(), 2);
+    assert_eq!(props[0].value, Ok(BacnetValue::Real(50.0)));
+    assert_eq!(props[1].value, Err(PropertyError { class: 2, code: 32 }));
+}
+
+#[tokio::test]
+async fn test_read_device_properties_after_rpm_services_error() {
+    // services / service-request-denied for RPM, as some controllers answer instead of a Reject
+    let responder = spawn_responder(|invoke_id, service, data| rpm_or_present_value(invoke_id, service, data, vec![0x50, invoke_id, service, 0x91, 0x05, 0x91, 0x1D]));
+
+    let scanner = UdpSocket::bind("127.0.0.1:0").unwrap();
+    scanner.set_read_timeout(Some(Duration::from_secs(3))).unwrap();
+    let health = Arc::new(Mutex::new(HealthStats::new()));
+    let tx_register = spawn_dispatcher(&scanner).with_health(Arc::clone(&health));
+
+    let obj = ObjectIdentifier::new(ObjectType::Device, 1234);
+    let props = read_property_set(&scanner, responder, obj, property::DEVICE_PROPERTIES, 1, &tx_register).await.unwrap();
+    assert_eq!(props.len(), property::DEVICE_PROPERTIES.len());
+    assert_eq!(property::find(&props, property::OBJECT_NAME), Some(&BacnetValue::CharacterString("Fan".to_string())));
+    assert!(tx_register.rpm_unsupported(responder));
+
+    // Entering the device again skips the RPM
+    read_property_set(&scanner, responder, obj, property::DEVICE_PROPERTIES, 2, &tx_register).await.unwrap();
+    let requests = health.lock().unwrap().get(&responder).map(|d| d.requests);
+    assert_eq!(requests, Some(1 + 2 * property::DEVICE_PROPERTIES.len() as u64));
+}
+
+#[test]
+fn test_property_ids_and_detail_sets() {
+    assert_eq!(property::property_id("Present_Value"), Some(85));
+    assert_eq!(property::property_id("present-value"), Some(85));
+    assert_eq!(property::property_id("OBJECT_NAME"), Some(77));
+    assert_eq!(property::property_id("1001"), Some(1001));
+    assert_eq!(property::property_id("no-such-property"), None);
+
+    let analog = detail_properties(ObjectType::AnalogInput);
+    assert!(analog.contains(&property::UNITS) && analog.contains(&property::OBJECT_NAME));
+    let binary = detail_properties(ObjectType::BinaryValue);
+    assert!(!binary.contains(&property::UNITS) && binary.contains(&property::ACTIVE_TEXT));
+}
+
+#[test]
+fn test_point_reading_flags() {
+    let mut ack = vec![0x0C, 0x00, 0x00, 0x00, 0x01, 0x1E];
+    ack.extend_from_slice(&[0x29, 85, 0x4E, 0x44, 0x41, 0xBC, 0x00, 0x00, 0x4F]);
+    ack.extend_from_slice(&[0x29, 111, 0x4E, 0x82, 0x04, 0x60, 0x4F]); // Fault, Overridden
+    ack.extend_from_slice(&[0x29, 103, 0x4E, 0x91, 0x04, 0x4F]); // Open-Loop
+    ack.extend_from_slice(&[0x29, 81, 0x4E, 0x10, 0x4F]);
+    ack.push(0x1F);
+
+    let props = &parse_rpm_ack(&ack).unwrap()[0].properties;
+    let reading = PointReading::from_properties(ObjectType::AnalogInput, props);
+    assert_eq!(reading.present_value, PresentValue::Analog(23.5));
+    assert_eq!(reading.status_flags, Some(StatusFlags { in_alarm: false, fault: true, overridden: true, out_of_service: false }));
+    assert_eq!(reading.reliability, Some(4));
+    assert_eq!(reading.out_of_service, Some(false));
+
+    let mut point = BacnetObject::new(ObjectIdentifier::new(ObjectType::AnalogInput, 1), "AI 1".to_string());
+    point.apply_reading(reading);
+    assert!(!point.in_alarm());
+    assert!(point.is_faulted());
+    assert!(point.is_overridden());
+    assert!(!point.is_out_of_service());
+
+    // A reading without status keeps the last known flags
+    point.apply_reading(PointReading { present_value: PresentValue::Analog(1.0), ..Default::default() });
+    assert_eq!(point.present_value, PresentValue::Analog(1.0));
+    assert!(point.is_overridden());
+}
+
+#[tokio::test]
+async fn test_read_point_without_rpm() {
+    let responder = spawn_responder(|invoke_id, service, data| rpm_or_present_value(invoke_id, service, data, vec![0x60, invoke_id, 0x09]));
+
+    let scanner = UdpSocket::bind("127.0.0.1:0").unwrap();
+    scanner.set_read_timeout(Some(Duration::from_secs(3))).unwrap();
+    let health = Arc::new(Mutex::new(HealthStats::new()));
+    let tx_register = spawn_dispatcher(&scanner).with_health(Arc::clone(&health));
+
+    let obj = ObjectIdentifier::new(ObjectType::AnalogValue, 1);
+    let reading = read_point(&scanner, responder, obj, false, 1, &tx_register).await.unwrap();
+    assert_eq!(reading.present_value, PresentValue::Analog(50.0));
+    assert!(reading.status_flags.is_some_and(|f| f.in_alarm));
+    assert_eq!((reading.reliability, reading.out_of_service), (None, None));
+
+    // The rejected RPM and a ReadProperty for each polled property
+    {
+        let health = health.lock().unwrap();
+        let device = health.get(&responder).unwrap();
+        assert_eq!((device.requests, device.acks, device.errors, device.rejects), (5, 2, 2, 1));
+        assert!(device.avg_rtt().is_some() && device.jitter().is_some());
+    }
+
+    // Later polls skip RPM altogether
+    assert!(tx_register.rpm_unsupported(responder));
+    let reading = read_point(&scanner, responder, obj, false, 2, &tx_register).await.unwrap();
+    assert_eq!(reading.present_value, PresentValue::Analog(50.0));
+    assert!(reading.status_flags.is_some_and(|f| f.in_alarm));
+    let counts = health.lock().unwrap().get(&responder).map(|d| (d.requests, d.acks, d.rejects));
+    assert_eq!(counts, Some((9, 4, 1)));
+
+    // Details are read one property at a time; those the object lacks read as empty
+    let mut point = BacnetObject::new(ObjectIdentifier::new(ObjectType::BinaryValue, 1), "BV 1".to_string());
+    assert!(point.needs_details());
+    let reading = read_point(&scanner, responder, point.id, true, 3, &tx_register).await.unwrap();
+    assert_eq!(reading.name.as_deref(), Some("Fan"));
+    assert_eq!(reading.description, Some(String::new()));
+    assert_eq!(reading.state_texts, Some(StateTexts::default()));
+    point.apply_reading(reading);
+    assert!(!point.needs_details());
+}
+
+#[tokio::test]
+async fn test_read_point_rpm_error_and_abort() {
+    // services / service-request-denied for RPM from one device, an Abort from the other
+    let denying = spawn_responder(|invoke_id, service, data| rpm_or_present_value(invoke_id, service, data, vec![0x50, invoke_id, service, 0x91, 0x05, 0x91, 0x1D]));
+    let aborting = spawn_responder(|invoke_id, service, data| rpm_or_present_value(invoke_id, service, data, vec![0x71, invoke_id, 0x04]));
+
+    let scanner = UdpSocket::bind("127.0.0.1:0").unwrap();
+    scanner.set_read_timeout(Some(Duration::from_secs(3))).unwrap();
+    let tx_register = spawn_dispatcher(&scanner);
+
+    let obj = ObjectIdentifier::new(ObjectType::AnalogValue, 1);
+    let reading = read_point(&scanner, denying, obj, false, 1, &tx_register).await.unwrap();
+    assert_eq!(reading.present_value, PresentValue::Analog(50.0));
+    assert!(tx_register.rpm_unsupported(denying));
+
+    // An Abort falls back for this poll only, and still reads the details
+    let reading = read_point(&scanner, aborting, obj, true, 2, &tx_register).await.unwrap();
+    assert_eq!(reading.present_value, PresentValue::Analog(50.0));
+    assert_eq!(reading.name.as_deref(), Some("Fan"));
+    assert!(!tx_register.rpm_unsupported(aborting));
+}
+
+/// Answers RPM with `rpm_reply`, ReadProperty of Present_Value with 50.0, of
+/// Status_Flags with in-alarm and of Object_Name with "Fan", and any other
+/// ReadProperty with unknown-property.
+fn rpm_or_present_value(invoke_id: u8, service: u8, data: &[u8], rpm_reply: Vec<u8>) -> Vec<u8> {
+    if service == ConfirmedServiceChoice::ReadPropertyMultiple as u8 {
+        return rpm_reply;
+    }
+    let value: &[u8] = match data[5..7] {
+        [0x19, 85] => &[0x44, 0x42, 0x48, 0x00, 0x00],
+        [0x19, 111] => &[0x82, 0x04, 0x80],
+        [0x19, 77] => &[0x74, 0x00, b'F', b'a', b'n'],
+        _ => return vec![0x50, invoke_id, service, 0x91, 0x02, 0x91, 0x20],
+    };
+    let mut ack = vec![0x30, invoke_id, service];
+    ack.extend_from_slice(&data[..7]);
+    ack.push(0x3E);
+    ack.extend_from_slice(value);
+    ack.push(0x3F);
+    ack
+}
+
+#[test]
+fn test_typed_present_values() {
+    assert_eq!(PresentValue::from_value(ObjectType::BinaryInput, &BacnetValue::Enumerated(1)), PresentValue::Binary(true));
+    assert_eq!(PresentValue::from_value(ObjectType::MultiStateValue, &BacnetValue::Unsigned(3)), PresentValue::Multistate(3));
+    assert_eq!(PresentValue::from_value(ObjectType::AnalogValue, &BacnetValue::Unsigned(3)), PresentValue::Unsigned(3));
+    assert_eq!(PresentValue::from_value(ObjectType::AnalogInput, &BacnetValue::Real(21.5)), PresentValue::Analog(21.5));
+    assert_eq!(PresentValue::Binary(true).as_f64(), Some(1.0));
+    assert_eq!(PresentValue::Text("x".to_string()).as_f64(), None);
+}
+
+#[test]
+fn test_point_reading_state_texts() {
+    // Multistate Value 2: Present_Value 2, State_Text {"Off", "Low", "High"}
+    let mut ack = vec![0x0C, 0x03, 0x80, 0x00, 0x02, 0x1E];
+    ack.extend_from_slice(&[0x29, 85, 0x4E, 0x21, 0x02, 0x4F]);
+    ack.extend_from_slice(&[0x29, 110, 0x4E]);
+    for text in ["Off", "Low", "High"] {
+        // Lengths above 4 use the extended length octet
+        match text.len() + 1 {
+            len @ 0..=4 => ack.push(0x70 | len as u8),
+            len => ack.extend_from_slice(&[0x75, len as u8]),
+        }
+        ack.push(0x00);
+        ack.extend_from_slice(text.as_bytes());
+    }
+    ack.extend_from_slice(&[0x4F, 0x1F]);
+
+    let props = &parse_rpm_ack(&ack).unwrap()[0].properties;
+    let reading = PointReading::from_properties(ObjectType::MultiStateValue, props);
+    assert_eq!(reading.present_value, PresentValue::Multistate(2));
+    assert_eq!(reading.state_texts, Some(StateTexts {
+        inactive: None,
+        active: None,
+        states: vec!["Off".to_string(), "Low".to_string(), "High".to_string()],
+    }));
+
+    // Polls without the texts keep the ones already read
+    let mut point = BacnetObject::new(ObjectIdentifier::new(ObjectType::MultiStateValue, 2), "MSV 2".to_string());
+    assert!(point.needs_details());
+    point.apply_reading(reading);
+    assert!(point.state_texts.is_some());
+    point.apply_reading(PointReading { present_value: PresentValue::Multistate(3), ..Default::default() });
+    assert_eq!(point.state_texts.as_ref().unwrap().states.len(), 3);
+}
@@ -0,0 +1,151 @@
+mod common;
+
+use bacnet_discovery::app::{App, BacnetObject, ViewState};
+use bacnet_discovery::export::ScanExport;
+use bacnet_discovery::property::PresentValue;
+use bacnet_discovery::scan_diff::{self, Change, ScanDiff};
+use bacnet_discovery::site_db::SiteDb;
+use bacnet_rs::object::ObjectType;
+use common::{device, point};
+use std::collections::HashMap;
+
+fn sensor(instance: u32, name: &str, units: &str) -> BacnetObject {
+    BacnetObject {
+        units: units.to_string(),
+        ..point(ObjectType::AnalogInput, instance, name, PresentValue::Analog(1.0))
+    }
+}
+
+/// Devices 100 and 200, with three points on device 100
+fn before() -> ScanExport {
+    let devices = HashMap::from([(100, device(100, "10.0.0.1:47808")), (200, device(200, "10.0.0.2:47808"))]);
+    let objects = HashMap::from([(100, vec![
+        sensor(1, "Supply Temp", "degrees-celsius"),
+        sensor(2, "Return Temp", "degrees-celsius"),
+        sensor(3, "Old Sensor", ""),
+    ])]);
+    let mut scan = ScanExport::new(&devices, &HashMap::new(), &objects);
+    scan.devices[0].firmware_revision = Some("1.0".to_string());
+    scan
+}
+
+#[test]
+fn test_identical_scans() {
+    let diff = ScanDiff::new(&before(), &before());
+    assert!(diff.is_empty());
+    assert_eq!(diff.lines(), vec!["No differences."]);
+}
+
+#[test]
+fn test_device_and_object_changes() {
+    // Device 200 replaced by 300; device 100 moved, updated and re-engineered
+    let devices = HashMap::from([(100, device(100, "10.0.0.9:47808")), (300, device(300, "10.0.0.3:47808"))]);
+    let objects = HashMap::from([(100, vec![
+        sensor(1, "Supply Air Temp", "degrees-celsius"),
+        sensor(2, "Return Temp", "degrees-fahrenheit"),
+        sensor(4, "New Sensor", ""),
+    ])]);
+    let mut after = ScanExport::new(&devices, &HashMap::new(), &objects);
+    after.devices[0].firmware_revision = Some("1.2".to_string());
+    after.devices[0].vendor_name = "Siemens".to_string();
+    after.devices[0].vendor_id = 7;
+
+    let diff = ScanDiff::new(&before(), &after);
+    assert_eq!(diff.added_devices.len(), 1);
+    assert_eq!(diff.added_devices[0].device_id, 300);
+    assert_eq!(diff.removed_devices[0].device_id, 200);
+    assert_eq!(diff.changed_devices.len(), 1);
+
+    let changes = &diff.changed_devices[0].changes;
+    assert_eq!(changes[0], Change::Address { old: "10.0.0.1:47808".to_string(), new: "10.0.0.9:47808".to_string() });
+    assert_eq!(changes[1], Change::Vendor { old: "Delta Controls (8)".to_string(), new: "Siemens (7)".to_string() });
+    assert_eq!(changes[2], Change::FirmwareRevision { old: "1.0".to_string(), new: "1.2".to_string() });
+    assert!(changes.contains(&Change::ObjectRenamed {
+        object_type: "AnalogInput".to_string(),
+        instance: 1,
+        old: "Supply Temp".to_string(),
+        new: "Supply Air Temp".to_string(),
+    }));
+    assert!(changes.contains(&Change::UnitsChanged {
+        object_type: "AnalogInput".to_string(),
+        instance: 2,
+        old: "degrees-celsius".to_string(),
+        new: "degrees-fahrenheit".to_string(),
+    }));
+    assert!(changes.iter().any(|c| matches!(c, Change::ObjectRemoved { instance: 3, .. })));
+    assert!(changes.iter().any(|c| matches!(c, Change::ObjectAdded { instance: 4, .. })));
+    assert_eq!(changes.len(), 7);
+
+    let text = diff.to_text();
+    assert!(text.contains("+ Device 300 at 10.0.0.3:47808 (Delta Controls, 0 objects)\n"));
+    assert!(text.contains("- Device 200 at 10.0.0.2:47808"));
+    assert!(text.contains("~ Device 100\n    address 10.0.0.1:47808 -> 10.0.0.9:47808\n"));
+    assert!(text.contains("    AnalogInput 2 units degrees-celsius -> degrees-fahrenheit\n"));
+    assert!(text.ends_with("1 device(s) added, 1 removed, 1 changed.\n"));
+
+    let json: serde_json::Value = serde_json::from_str(&diff.to_json().unwrap()).unwrap();
+    assert_eq!(json["changed_devices"][0]["changes"][2]["change"], "firmware_revision");
+    assert_eq!(json["removed_devices"][0]["device_id"], 200);
+}
+
+#[test]
+fn test_unread_values_are_not_changes() {
+    // Points not discovered, firmware not read and units not polled in the newer scan
+    let devices = HashMap::from([(100, device(100, "10.0.0.1:47808")), (200, device(200, "10.0.0.2:47808"))]);
+    let mut after = ScanExport::new(&devices, &HashMap::new(), &HashMap::new());
+    assert!(ScanDiff::new(&before(), &after).is_empty());
+
+    let objects = HashMap::from([(100, vec![sensor(1, "Supply Temp", ""), sensor(2, "Return Temp", ""), sensor(3, "", "")])]);
+    after = ScanExport::new(&devices, &HashMap::new(), &objects);
+    assert!(ScanDiff::new(&before(), &after).is_empty());
+}
+
+#[test]
+fn test_load_export_and_site_database() {
+    let dir = std::env::temp_dir();
+    let export_path = dir.join(format!("scan-diff-export-{}.json", std::process::id()));
+    let db_path = dir.join(format!("scan-diff-site-{}.json", std::process::id()));
+
+    std::fs::write(&export_path, before().to_json().unwrap()).unwrap();
+    let mut db = SiteDb::new("plant");
+    db.update(before());
+    db.save(&db_path).unwrap();
+
+    let from_export = scan_diff::load_scan(&export_path).unwrap();
+    let from_db = scan_diff::load_scan(&db_path).unwrap();
+    assert_eq!(from_export.devices, from_db.devices);
+    assert!(scan_diff::load_scan(&dir.join("no-such-scan.json")).is_err());
+
+    std::fs::remove_file(&export_path).unwrap();
+    std::fs::remove_file(&db_path).unwrap();
+}
+
+#[test]
+fn test_compare_with_site_database() {
+    let mut db = SiteDb::new("plant");
+    db.update(before());
+    let mut app = App::new();
+    app.load_site(db);
+
+    // Only device 100 answers; 200 is listed from the database but counts as removed
+    app.record_device(device(100, "10.0.0.1:47808"));
+    app.view_state = ViewState::DeviceList;
+    app.compare_with_baseline();
+    let diff = app.scan_diff.as_ref().unwrap();
+    assert_eq!(diff.removed_devices.len(), 1);
+    assert_eq!(diff.removed_devices[0].device_id, 200);
+    assert!(diff.changed_devices.is_empty());
+    assert!(matches!(app.view_state, ViewState::ScanDiff));
+
+    app.exit_view();
+    assert!(matches!(app.view_state, ViewState::DeviceList));
+}
+
+#[test]
+fn test_compare_without_baseline() {
+    let mut app = App::new();
+    app.view_state = ViewState::DeviceList;
+    app.compare_with_baseline();
+    assert!(app.scan_diff.is_none());
+    assert!(matches!(app.view_state, ViewState::DeviceList));
+}
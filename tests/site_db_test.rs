@@ -0,0 +1,103 @@
+mod common;
+
+use bacnet_discovery::app::{App, BacnetObject, SiteMarker};
+use bacnet_discovery::bacnet::DeviceStatus;
+use bacnet_discovery::export::ScanExport;
+use bacnet_discovery::mstp;
+use bacnet_discovery::property::PresentValue;
+use bacnet_discovery::site_db::{SiteDb, DB_VERSION};
+use bacnet_rs::object::ObjectType;
+use common::{device, point};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+fn room_temp(instance: u32) -> BacnetObject {
+    BacnetObject {
+        units: "degrees-celsius".to_string(),
+        units_code: Some(62),
+        description: Some("Room".to_string()),
+        ..point(ObjectType::AnalogInput, instance, &format!("Temp {}", instance), PresentValue::Analog(20.0))
+    }
+}
+
+/// A site database that knows devices 100 (IP, with one point) and 200 (MS/TP MAC 5)
+fn site() -> SiteDb {
+    let devices = HashMap::from([
+        (100, device(100, "10.0.0.1:47808")),
+        (200, device(200, &mstp::mac_to_addr(5).to_string())),
+    ]);
+    let objects = HashMap::from([(100, vec![room_temp(1)])]);
+    let mut db = SiteDb::new("plant");
+    db.update(ScanExport::new(&devices, &HashMap::new(), &objects));
+    db
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("bacnet-site-{}-{}.json", name, std::process::id()))
+}
+
+#[test]
+fn test_save_and_load_round_trip() {
+    let path = temp_path("roundtrip");
+    assert_eq!(SiteDb::load(&path).unwrap(), None);
+
+    let db = site();
+    db.save(&path).unwrap();
+    let loaded = SiteDb::load(&path).unwrap().unwrap();
+    assert_eq!(loaded, db);
+    assert_eq!(loaded.db_version, DB_VERSION);
+    assert_eq!(loaded.scans, 1);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_newer_database_is_refused() {
+    let path = temp_path("newer");
+    let mut db = site();
+    db.db_version = DB_VERSION + 1;
+    db.save(&path).unwrap();
+    assert!(SiteDb::load(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_restore_devices_offline() {
+    let restored = site().restore();
+    let ip = &restored.devices[&100];
+    assert_eq!(ip.status, DeviceStatus::Offline);
+    assert_eq!(ip.address, "10.0.0.1:47808".parse::<SocketAddr>().unwrap());
+    assert_eq!(ip.segmentation, 0);
+    assert_eq!(restored.devices[&200].address, mstp::mac_to_addr(5));
+
+    let points = &restored.device_objects[&100];
+    assert_eq!(points[0].name, "Temp 1");
+    assert_eq!(points[0].units_code, Some(62));
+    assert_eq!(points[0].present_value, PresentValue::Unknown);
+    // Details are read again by the first poll
+    assert!(points[0].needs_details());
+}
+
+#[test]
+fn test_new_and_missing_markers() {
+    let mut app = App::new();
+    assert_eq!(app.site_marker(100), None);
+
+    app.load_site(site());
+    assert_eq!(app.devices.lock().unwrap().len(), 2);
+    assert_eq!(app.device_objects.lock().unwrap()[&100].len(), 1);
+    assert_eq!(app.site_marker(100), Some(SiteMarker::Missing));
+
+    // An I-Am from a known device clears its marker; an unknown one is new
+    app.record_device(device(100, "10.0.0.1:47808"));
+    app.record_device(device(300, "10.0.0.3:47808"));
+    assert_eq!(app.site_marker(100), None);
+    assert_eq!(app.site_marker(200), Some(SiteMarker::Missing));
+    assert_eq!(app.site_marker(300), Some(SiteMarker::New));
+
+    // A rescan starts again from the database
+    app.clear();
+    assert_eq!(app.devices.lock().unwrap().len(), 2);
+    assert_eq!(app.site_marker(100), Some(SiteMarker::Missing));
+    assert_eq!(app.site_marker(300), None);
+}
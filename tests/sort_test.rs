@@ -0,0 +1,121 @@
+mod common;
+
+use bacnet_discovery::app::{App, BacnetObject, DeviceSortKey, ObjectSort, ObjectSortKey, ViewState};
+use bacnet_discovery::bacnet::DiscoveredDevice;
+use bacnet_discovery::property::{self, BacnetValue, PresentValue, PropertyEntry};
+use bacnet_rs::object::ObjectType;
+use common::{device, point};
+use std::time::{Duration, Instant};
+
+fn seen_device(device_id: u32, vendor: &str, address: &str, seen_secs_ago: u64) -> DiscoveredDevice {
+    DiscoveredDevice {
+        vendor_name: vendor.to_string(),
+        last_seen: Instant::now() - Duration::from_secs(seen_secs_ago),
+        ..device(device_id, address)
+    }
+}
+
+fn app_with_devices() -> App {
+    let mut app = App::new();
+    for d in [
+        seen_device(300, "Acme", "10.0.0.5:47808", 10),
+        seen_device(100, "Zeta", "10.0.0.9:47808", 30),
+        seen_device(200, "Beta", "10.0.0.1:47808", 20),
+    ] {
+        app.record_device(d);
+    }
+    let name = |n: &str| vec![PropertyEntry { id: property::OBJECT_NAME, array_index: None, value: Ok(BacnetValue::CharacterString(n.to_string())) }];
+    app.device_properties.lock().unwrap().insert(100, name("Chiller Plant"));
+    app.device_properties.lock().unwrap().insert(300, name("AHU Controller"));
+    app.view_state = ViewState::DeviceList;
+    app
+}
+
+#[test]
+fn test_device_sort_keys() {
+    let mut app = app_with_devices();
+    assert_eq!(app.visible_device_ids(), [100, 200, 300]);
+
+    let mut orders = Vec::new();
+    for _ in 0..4 {
+        app.cycle_sort_key();
+        orders.push((app.device_sort.key, app.visible_device_ids()));
+    }
+    assert_eq!(orders, [
+        (DeviceSortKey::Vendor, vec![300, 200, 100]),
+        (DeviceSortKey::Address, vec![200, 300, 100]),
+        (DeviceSortKey::LastSeen, vec![100, 200, 300]),
+        // Devices without a name go last
+        (DeviceSortKey::Name, vec![300, 100, 200]),
+    ]);
+
+    app.toggle_sort_direction();
+    assert_eq!(app.visible_device_ids(), [100, 300, 200]);
+}
+
+#[test]
+fn test_unnamed_devices_sort_last() {
+    let mut app = app_with_devices();
+    app.record_device(seen_device(400, "Acme", "10.0.0.7:47808", 5));
+    let empty = vec![PropertyEntry { id: property::OBJECT_NAME, array_index: None, value: Ok(BacnetValue::CharacterString(String::new())) }];
+    app.device_properties.lock().unwrap().insert(400, empty);
+    app.device_sort.key = DeviceSortKey::Name;
+
+    // Device 200 has no name read yet and 400 an empty one; both stay last
+    assert_eq!(app.visible_device_ids(), [300, 100, 200, 400]);
+    app.toggle_sort_direction();
+    assert_eq!(app.visible_device_ids(), [100, 300, 400, 200]);
+}
+
+#[test]
+fn test_enter_device_follows_sorted_list() {
+    let mut app = app_with_devices();
+    app.list_state.select(Some(0));
+    app.cycle_sort_key(); // Vendor: Acme (300) first
+
+    // The selection stays on the same device when the order changes
+    assert_eq!(app.list_state.selected(), Some(2));
+
+    app.list_state.select(Some(0));
+    app.enter_device();
+    assert!(matches!(app.view_state, ViewState::ObjectList(300)));
+}
+
+#[test]
+fn test_object_sort_keys() {
+    let objects = [
+        point(ObjectType::BinaryInput, 1, "Fan status", PresentValue::Binary(true)),
+        point(ObjectType::AnalogInput, 7, "supply temp", PresentValue::Analog(21.5)),
+        point(ObjectType::AnalogInput, 2, "Return temp", PresentValue::Unknown),
+        point(ObjectType::AnalogValue, 3, "Label", PresentValue::Text("abc".to_string())),
+    ];
+    let order = |key, descending| {
+        let mut rows: Vec<&BacnetObject> = objects.iter().collect();
+        ObjectSort { key, descending }.apply(&mut rows);
+        rows.iter().map(|o| o.name.as_str()).collect::<Vec<_>>()
+    };
+
+    assert_eq!(order(ObjectSortKey::Type, false), ["Return temp", "supply temp", "Label", "Fan status"]);
+    assert_eq!(order(ObjectSortKey::Instance, false), ["Fan status", "Return temp", "Label", "supply temp"]);
+    assert_eq!(order(ObjectSortKey::Name, false), ["Fan status", "Label", "Return temp", "supply temp"]);
+    // Numbers first, then text, then values not read yet
+    assert_eq!(order(ObjectSortKey::Value, false), ["Fan status", "supply temp", "Label", "Return temp"]);
+    assert_eq!(order(ObjectSortKey::Value, true), ["Return temp", "Label", "supply temp", "Fan status"]);
+}
+
+#[test]
+fn test_object_selection_kept_across_sort() {
+    let mut app = App::new();
+    app.device_objects.lock().unwrap().insert(1, vec![
+        point(ObjectType::AnalogInput, 1, "B", PresentValue::Analog(1.0)),
+        point(ObjectType::AnalogInput, 2, "A", PresentValue::Analog(2.0)),
+    ]);
+    app.view_state = ViewState::ObjectList(1);
+    app.object_table_state.select(Some(0));
+
+    app.cycle_sort_key(); // Instance
+    app.cycle_sort_key(); // Name
+    assert_eq!(app.object_table_state.selected(), Some(1));
+    app.enter_object();
+    assert!(matches!(app.view_state, ViewState::ObjectProperties(1, id) if id.instance == 1));
+}
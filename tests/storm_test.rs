@@ -0,0 +1,110 @@
+use bacnet_discovery::app::App;
+use bacnet_discovery::storm::{BroadcastMonitor, StormThresholds, StormWarning};
+use bacnet_discovery::traffic::Datagram;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+const WHO_IS: [u8; 8] = [0x81, 0x0B, 0x00, 0x08, 0x01, 0x00, 0x10, 0x08];
+const I_AM: [u8; 20] = [
+    0x81, 0x0B, 0x00, 0x14, 0x01, 0x00, 0x10, 0x00, 0xC4, 0x02, 0x00, 0x03, 0xE9, 0x22, 0x05, 0xC4, 0x91, 0x03, 0x21, 0x0F,
+];
+/// A unicast ReadProperty request
+const READ_PROPERTY: [u8; 17] = [
+    0x81, 0x0A, 0x00, 0x11, 0x01, 0x04, 0x00, 0x05, 0x07, 0x0C, 0x0C, 0x00, 0x00, 0x00, 0x01, 0x19, 0x55,
+];
+
+fn datagram(millis: u64, host: u8, data: &[u8]) -> Datagram {
+    let source: SocketAddr = format!("10.0.0.{}:47808", host).parse().unwrap();
+    let at = Duration::from_secs(1_760_000_000) + Duration::from_millis(millis);
+    Datagram::new(at, source, "10.0.0.255:47808".parse().unwrap(), data)
+}
+
+fn at(millis: u64) -> Duration {
+    Duration::from_secs(1_760_000_000) + Duration::from_millis(millis)
+}
+
+#[test]
+fn test_chatty_device() {
+    let mut monitor = BroadcastMonitor::new(StormThresholds { total: 50.0, per_source: 5.0, per_service: 30.0 });
+    // Device .7 sends an I-Am every 100 ms for 10 s; .2 sends one Who-Is a second
+    for i in 0..100 {
+        monitor.record(&datagram(i * 100, 7, &I_AM));
+    }
+    for i in 0..10 {
+        monitor.record(&datagram(i * 1000, 2, &WHO_IS));
+        monitor.record(&datagram(i * 1000 + 1, 3, &READ_PROPERTY));
+    }
+
+    let now = at(10_000);
+    assert!((monitor.total_rate(now) - 11.0).abs() < 0.01, "unicasts are not counted");
+    let sources = monitor.top_sources(now);
+    assert_eq!(sources[0].name, "10.0.0.7");
+    assert_eq!((sources[0].packets, sources.len()), (100, 2));
+    assert_eq!(monitor.top_services(now)[0].name, "I-Am");
+
+    let warnings = monitor.warnings(now);
+    assert_eq!(warnings.len(), 1);
+    let StormWarning::ChattySource { source, service, rate, .. } = &warnings[0] else { panic!("{:?}", warnings) };
+    assert_eq!(*source, "10.0.0.7".parse::<IpAddr>().unwrap());
+    assert_eq!(service, "I-Am");
+    assert!((rate - 10.0).abs() < 0.01);
+    assert_eq!(warnings[0].to_string(), "Chatty device 10.0.0.7: 10.0 broadcasts/s, mostly I-Am (limit 5.0)");
+
+    // Once the flood stops it ages out of the window
+    assert!(monitor.warnings(at(25_000)).is_empty());
+}
+
+#[test]
+fn test_forwarded_broadcasts_count_for_their_origin() {
+    let mut monitor = BroadcastMonitor::new(StormThresholds { total: 50.0, per_source: 5.0, per_service: 30.0 });
+    // The BBMD at .1 forwards I-Ams that device 192.168.5.20 broadcast on another subnet
+    let mut forwarded = vec![0x81, 0x04, 0x00, 0x1A, 192, 168, 5, 20, 0xBA, 0xC0];
+    forwarded.extend_from_slice(&I_AM[4..]);
+    for i in 0..60 {
+        monitor.record(&datagram(i * 100, 1, &forwarded));
+    }
+
+    let sources = monitor.top_sources(at(6_000));
+    assert_eq!(sources.len(), 1);
+    assert_eq!((sources[0].name.as_str(), sources[0].packets), ("192.168.5.20", 60));
+    let warnings = monitor.warnings(at(6_000));
+    assert!(matches!(&warnings[..], [StormWarning::ChattySource { source, .. }] if *source == "192.168.5.20".parse::<IpAddr>().unwrap()));
+}
+
+#[test]
+fn test_storm_and_service_flood() {
+    let mut monitor = BroadcastMonitor::new(StormThresholds { total: 20.0, per_source: 5.0, per_service: 15.0 });
+    // 50 devices answering a Who-Is at once is a burst, spread over a second it is a storm
+    for host in 10..60 {
+        monitor.record(&datagram(u64::from(host - 10) * 20, host, &I_AM));
+    }
+    let warnings = monitor.warnings(at(1000));
+    assert!(matches!(warnings[0], StormWarning::Storm { .. }));
+    assert!(matches!(&warnings[1], StormWarning::ServiceFlood { service, .. } if service == "I-Am"));
+    assert_eq!(warnings.len(), 2, "no single device is chatty");
+
+    let lines = monitor.lines(at(1000));
+    assert!(lines[0].starts_with("50 broadcasts in the last 1 s (50.0/s"));
+    assert!(lines.iter().any(|l| l.starts_with("... 40 more")));
+    monitor.clear();
+    assert_eq!(monitor.total_rate(at(1000)), 0.0);
+}
+
+#[test]
+fn test_app_raises_new_warnings_once() {
+    let mut app = App::new();
+    for i in 0..100 {
+        app.broadcasts.lock().unwrap().record(&datagram(i * 100, 7, &WHO_IS));
+    }
+    let raised = app.check_broadcasts(at(10_000));
+    assert_eq!(raised.len(), 1);
+    assert!(app.status_message.starts_with("WARNING: Chatty device 10.0.0.7"));
+
+    // Still active with another rate: not raised again
+    app.broadcasts.lock().unwrap().record(&datagram(10_000, 7, &WHO_IS));
+    assert!(app.check_broadcasts(at(10_000)).is_empty());
+    assert_eq!(app.broadcast_warnings.len(), 1);
+
+    app.clear_health();
+    assert!(app.broadcast_warnings.is_empty());
+}
@@ -0,0 +1,101 @@
+use bacnet_discovery::topology::{NodeKind, Subnet, Topology, tree_lines};
+use bacnet_discovery::traffic::Datagram;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+/// I-Am APDU for a device with max APDU 1476, no segmentation, vendor 15
+fn iam(device_id: u32) -> Vec<u8> {
+    let id = (8u32 << 22 | device_id).to_be_bytes();
+    vec![0x10, 0x00, 0xC4, id[0], id[1], id[2], id[3], 0x22, 0x05, 0xC4, 0x91, 0x03, 0x21, 0x0F]
+}
+
+/// Prefixes a BVLL header with the right length
+fn bvll(function: u8, body: &[u8]) -> Vec<u8> {
+    let len = (body.len() + 4) as u16;
+    let mut data = vec![0x81, function, (len >> 8) as u8, len as u8];
+    data.extend_from_slice(body);
+    data
+}
+
+fn received(topology: &mut Topology, source: &str, data: &[u8]) {
+    let source: SocketAddr = source.parse().unwrap();
+    topology.record(&Datagram::new(Duration::ZERO, source, "192.168.1.10:47808".parse().unwrap(), data));
+}
+
+fn site() -> Topology {
+    let mut topology = Topology::new();
+    topology.add_local_subnet(Ipv4Addr::new(192, 168, 1, 10), Ipv4Addr::new(255, 255, 255, 0));
+    // A B/IP device
+    received(&mut topology, "192.168.1.20:47808", &bvll(0x0B, &[[0x01, 0x00].as_slice(), &iam(1001)].concat()));
+    // An MS/TP device on network 5 behind a router, which also announces network 6
+    received(&mut topology, "192.168.1.5:47808", &bvll(0x0B, &[[0x01, 0x08, 0x00, 0x05, 0x01, 0x03].as_slice(), &iam(5001)].concat()));
+    received(&mut topology, "192.168.1.5:47808", &bvll(0x0A, &[0x01, 0x80, 0x01, 0x00, 0x05, 0x00, 0x06]));
+    // A device on a remote subnet, relayed by the local BBMD
+    let forwarded = [[10, 1, 0, 30, 0xBA, 0xC0, 0x01, 0x00].as_slice(), &iam(3000)].concat();
+    received(&mut topology, "192.168.1.1:47808", &bvll(0x04, &forwarded));
+    // The BBMD's table: itself and its peer on the remote subnet
+    received(&mut topology, "192.168.1.1:47808", &bvll(0x03, &[
+        192, 168, 1, 1, 0xBA, 0xC0, 255, 255, 255, 255,
+        10, 1, 0, 1, 0xBA, 0xC0, 255, 255, 255, 255,
+    ]));
+    topology
+}
+
+#[test]
+fn test_subnet() {
+    let subnet = Subnet::new(Ipv4Addr::new(10, 1, 2, 3), Ipv4Addr::new(255, 255, 0, 0));
+    assert_eq!(subnet.to_string(), "10.1.0.0/16");
+    assert!(subnet.contains(Ipv4Addr::new(10, 1, 200, 1)));
+    assert!(!subnet.contains(Ipv4Addr::new(10, 2, 0, 1)));
+    assert!(Subnet::new(Ipv4Addr::new(10, 1, 2, 3), Ipv4Addr::UNSPECIFIED).contains(Ipv4Addr::BROADCAST));
+}
+
+#[test]
+fn test_topology_from_traffic() {
+    let topology = site();
+    let routed = topology.device(5001).unwrap();
+    assert_eq!(routed.address, "192.168.1.5:47808".parse().unwrap());
+    assert_eq!((routed.network, routed.mac.as_slice()), (Some(5), [0x03].as_slice()));
+    assert_eq!(topology.device(3000).unwrap().address, "10.1.0.30:47808".parse().unwrap());
+    assert_eq!(topology.routed_networks("192.168.1.5:47808".parse().unwrap()), [5, 6]);
+    assert_eq!(topology.subnet_of(Ipv4Addr::new(172, 16, 4, 9)).to_string(), "172.16.4.0/24");
+
+    let nodes = topology.tree();
+    assert_eq!(tree_lines(&nodes), [
+        "10.1.0.0/24",
+        "├─ BBMD 10.1.0.1:47808",
+        "└─ Device 3000  10.1.0.30:47808",
+        "192.168.1.0/24 (local)",
+        "├─ BBMD 192.168.1.1:47808",
+        "│  └─ BDT peer 10.1.0.1:47808",
+        "├─ Router 192.168.1.5:47808",
+        "│  ├─ Network 5 (1 device)",
+        "│  │  └─ Device 5001  MAC 03",
+        "│  └─ Network 6 (0 devices)",
+        "└─ Device 1001  192.168.1.20:47808",
+    ]);
+    assert_eq!(nodes[0].kind, NodeKind::Subnet);
+    assert_eq!(nodes[8].kind, NodeKind::Device);
+
+    let targets = topology.bdt_targets();
+    assert_eq!(targets.len(), 5);
+    assert!(targets.contains(&"192.168.1.5:47808".parse().unwrap()));
+}
+
+#[test]
+fn test_dot_export() {
+    let dot = site().to_dot();
+    assert!(dot.starts_with("graph bacnet {"));
+    assert!(dot.contains("label=\"192.168.1.0/24\";"));
+    assert!(dot.contains("\"192.168.1.5:47808\" [label=\"Router 192.168.1.5:47808\"];"));
+    assert!(dot.contains("\"net:6\" [label=\"Network 6\", shape=ellipse];"));
+    assert!(dot.contains("\"192.168.1.5:47808\" -- \"net:5\";"));
+    assert!(dot.contains("\"net:5\" -- \"device:5001\";"));
+    assert!(dot.contains("\"10.1.0.1:47808\" -- \"192.168.1.1:47808\" [style=dashed, label=\"BDT\"];"));
+    assert!(dot.trim_end().ends_with('}'));
+
+    let mut empty = site();
+    empty.clear();
+    assert!(empty.is_empty());
+    assert!(empty.tree().is_empty());
+}
@@ -0,0 +1,158 @@
+use bacnet_discovery::pcap::read_capture_file;
+use bacnet_discovery::network::DataLink;
+use bacnet_discovery::traffic::{Datagram, DeviceAddresses, LoggedLink, PacketFilter, TrafficLog, TrafficStats, received_destination, time_of_day};
+use std::net::{SocketAddr, UdpSocket};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn capture(name: &str) -> Vec<Datagram> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+    read_capture_file(&path).unwrap().into_iter()
+        .map(|p| Datagram::new(p.timestamp, p.source, p.destination, &p.payload))
+        .collect()
+}
+
+fn matching(datagrams: &[Datagram], filter: &str) -> Vec<String> {
+    let filter = PacketFilter::parse(filter).unwrap();
+    let mut devices = DeviceAddresses::new();
+    datagrams.iter().for_each(|d| devices.learn(d));
+    datagrams.iter().filter(|d| filter.matches(d, &devices)).map(|d| d.packet.category()).collect()
+}
+
+#[test]
+fn test_filters() {
+    let datagrams = capture("read_property.pcapng");
+    assert_eq!(matching(&datagrams, "").len(), 4);
+    assert_eq!(matching(&datagrams, "service=readproperty"), ["ReadProperty", "ReadProperty"]);
+    assert_eq!(matching(&datagrams, "service=WHO_IS,i-am"), ["Who-Is", "I-Am"]);
+    assert_eq!(matching(&datagrams, "type=confirmed"), ["ReadProperty"]);
+    assert_eq!(matching(&datagrams, "type=broadcast"), ["Who-Is"]);
+    assert_eq!(matching(&datagrams, "src=192.168.1.20"), ["I-Am", "ReadProperty"]);
+    assert_eq!(matching(&datagrams, "dst=192.168.1.10:50123"), ["ReadProperty"]);
+    assert_eq!(matching(&datagrams, "host=192.168.1.20 !type=unconfirmed"), ["ReadProperty", "ReadProperty"]);
+    // The Who-Is range 100-1000 does not cover 1001; its I-Am places it at 192.168.1.20
+    assert_eq!(matching(&datagrams, "device=1001"), ["I-Am", "ReadProperty", "ReadProperty"]);
+    assert_eq!(matching(&datagrams, "device=500"), ["Who-Is"]);
+}
+
+#[test]
+fn test_network_filter() {
+    let datagrams = capture("router_vlan.pcap");
+    assert_eq!(matching(&datagrams, "net=6"), ["I-Am-Router-To-Network"]);
+    assert_eq!(matching(&datagrams, "type=network"), ["I-Am-Router-To-Network"]);
+    assert!(matching(&datagrams, "net=7").is_empty());
+}
+
+#[test]
+fn test_invalid_filters() {
+    for text in ["service", "color=red", "type=bogus", "src=10.0.0", "device=x", "net=70000", "host="] {
+        assert!(PacketFilter::parse(text).is_err(), "{} should not parse", text);
+    }
+}
+
+#[test]
+fn test_statistics() {
+    let datagrams = capture("read_property.pcapng");
+    let mut stats = TrafficStats::new();
+    datagrams.iter().for_each(|d| stats.record(d));
+    // A reject from a third host
+    let reject = [0x81, 0x0A, 0x00, 0x09, 0x01, 0x00, 0x60, 0x07, 0x09];
+    let source: SocketAddr = "192.168.1.30:47808".parse().unwrap();
+    stats.record(&Datagram::new(Duration::new(1_760_000_001, 0), source, "192.168.1.10:47808".parse().unwrap(), &reject));
+
+    assert_eq!(stats.total.packets, 5);
+    assert_eq!(stats.total.bytes, 13 + 20 + 17 + 23 + 9);
+    assert_eq!((stats.broadcasts, stats.errors, stats.rejects, stats.aborts), (1, 0, 1, 0));
+    assert_eq!(stats.services["ReadProperty"].packets, 2);
+    assert_eq!(stats.hosts[&source.ip()].packets, 1);
+    assert_eq!(stats.span(), Duration::from_secs(1));
+
+    let lines = stats.lines(Duration::from_secs(10));
+    assert_eq!(lines[0], "5 packets (0.5/s), 82 bytes over 10.0 s; 1 broadcasts (0.1/s)");
+    assert_eq!(lines[1], "Errors 0, rejects 1, aborts 0");
+    let hosts = lines.iter().position(|l| l.starts_with("HOST")).unwrap();
+    assert!(lines[hosts + 1].starts_with("192.168.1.10  2"));
+    assert!(lines.iter().any(|l| l.starts_with("Reject ")));
+}
+
+#[test]
+fn test_received_destination() {
+    let local: SocketAddr = "10.0.0.9:47808".parse().unwrap();
+    let broadcast: SocketAddr = "10.0.0.255:47808".parse().unwrap();
+    let who_is = [0x81, 0x0B, 0x00, 0x08, 0x01, 0x00, 0x10, 0x08];
+    let unicast = [0x81, 0x0A, 0x00, 0x08, 0x01, 0x00, 0x10, 0x08];
+    assert_eq!(received_destination(&who_is, local, broadcast), broadcast);
+    assert_eq!(received_destination(&unicast, local, broadcast), local);
+}
+
+#[test]
+fn test_traffic_log() {
+    let datagrams = capture("read_property.pcapng");
+    let mut log = TrafficLog::with_capacity(3);
+    datagrams.iter().cloned().for_each(|d| log.push(d));
+    // The oldest (the Who-Is) was dropped
+    assert_eq!(log.len(), 3);
+    assert_eq!(log.get(0).unwrap().packet.category(), "I-Am");
+    assert_eq!(log.matching(&PacketFilter::parse("type=confirmed").unwrap()), [1]);
+    assert_eq!(log.matching(&PacketFilter::parse("device=1001").unwrap()), [0, 1, 2]);
+
+    log.set_paused(true);
+    log.push(datagrams[0].clone());
+    assert_eq!((log.len(), log.missed), (3, 1));
+    log.clear();
+    assert!(log.is_empty());
+    assert_eq!(log.missed, 0);
+    assert_eq!(log.devices.get(1001).map(|a| a.ip()), Some("192.168.1.20".parse().unwrap()));
+    assert_eq!(time_of_day(Duration::new(1_760_000_000, 12_345_678)), "08:53:20.012");
+}
+
+#[test]
+fn test_device_filter_behind_router() {
+    let router: SocketAddr = "10.0.0.1:47808".parse().unwrap();
+    let scanner: SocketAddr = "10.0.0.5:47808".parse().unwrap();
+    // I-Am from device 7 at SNET 9, SADR 02, and ReadProperty requests to DADR 02 and 03
+    let iam = [
+        0x81, 0x0B, 0x00, 0x18, 0x01, 0x08, 0x00, 0x09, 0x01, 0x02,
+        0x10, 0x00, 0xC4, 0x02, 0x00, 0x00, 0x07, 0x22, 0x05, 0xC4, 0x91, 0x03, 0x21, 0x0F,
+    ];
+    let read = |mac: u8| [
+        0x81, 0x0A, 0x00, 0x16, 0x01, 0x24, 0x00, 0x09, 0x01, mac, 0xFF,
+        0x00, 0x05, 0x07, 0x0C, 0x0C, 0x00, 0x00, 0x00, 0x01, 0x19, 0x55,
+    ];
+    let datagrams = [
+        Datagram::new(Duration::ZERO, router, "10.0.0.255:47808".parse().unwrap(), &iam),
+        Datagram::new(Duration::ZERO, scanner, router, &read(0x02)),
+        Datagram::new(Duration::ZERO, scanner, router, &read(0x03)),
+    ];
+    let filter = PacketFilter::parse("device=7").unwrap();
+    let mut devices = DeviceAddresses::new();
+    datagrams.iter().for_each(|d| devices.learn(d));
+    let matched: Vec<bool> = datagrams.iter().map(|d| filter.matches(d, &devices)).collect();
+    assert_eq!(matched, [true, true, false]);
+}
+
+#[test]
+fn test_logged_link_records_sent_datagrams() {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let peer = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let local = socket.local_addr().unwrap();
+    let dest = peer.local_addr().unwrap();
+    let log = Arc::new(Mutex::new(TrafficLog::default()));
+    let link = LoggedLink::new(Arc::new(socket), local, Arc::clone(&log));
+
+    let who_is = [0x81, 0x0B, 0x00, 0x08, 0x01, 0x00, 0x10, 0x08];
+    assert_eq!(link.send_to(&who_is, dest).unwrap(), who_is.len());
+    let log = log.lock().unwrap();
+    assert_eq!(log.len(), 1);
+    let sent = log.get(0).unwrap();
+    assert_eq!((sent.source, sent.destination), (local, dest));
+    assert_eq!(sent.packet.category(), "Who-Is");
+
+    // Received datagrams are left to the receive loop
+    let mut buf = [0u8; 16];
+    assert_eq!(peer.recv_from(&mut buf).unwrap().0, who_is.len());
+    peer.send_to(&who_is, local).unwrap();
+    link.recv_from(&mut buf).unwrap();
+    assert_eq!(log.len(), 1);
+}
@@ -1,4 +1,4 @@
-use bacnet_discovery::bacnet::{process_response, DiscoveredDevice};
+use bacnet_discovery::bacnet::{process_response, DeviceStatus, OFFLINE_AFTER_TIMEOUTS};
 use bacnet_rs::service::{IAmRequest, UnconfirmedServiceChoice};
 use bacnet_rs::network::Npdu;
 use bacnet_rs::object::{ObjectIdentifier, ObjectType};
@@ -50,3 +50,49 @@ fn test_process_invalid_packet() {
     assert!(process_response(&[0, 1, 2], source).is_none());
     assert!(process_response(&[0x81, 0x0A, 0, 10, 0, 0], source).is_none());
 }
+
+#[test]
+fn test_device_liveness() {
+    let iam = IAmRequest::new(ObjectIdentifier::new(ObjectType::Device, 77), 1476, 0, 260);
+    let mut iam_buf = Vec::new();
+    iam.encode(&mut iam_buf).unwrap();
+    let mut packet = vec![0x81, 0x0A, 0x00, 0x00];
+    packet.extend_from_slice(&Npdu::new().encode());
+    packet.extend_from_slice(&[0x10, UnconfirmedServiceChoice::IAm as u8]);
+    packet.extend_from_slice(&iam_buf);
+    packet[3] = packet.len() as u8;
+
+    let mut device = process_response(&packet, "10.0.0.7:47808".parse().unwrap()).unwrap();
+    assert_eq!(device.status, DeviceStatus::Online);
+
+    for _ in 1..OFFLINE_AFTER_TIMEOUTS {
+        assert!(!device.record_timeout());
+    }
+    assert!(device.record_timeout());
+    assert_eq!(device.status, DeviceStatus::Offline);
+    // Further timeouts do not report the transition again
+    assert!(!device.record_timeout());
+
+    let before = device.last_seen;
+    device.mark_seen();
+    assert!(device.is_online());
+    assert_eq!(device.consecutive_timeouts, 0);
+    assert!(device.last_seen >= before);
+}
+
+#[test]
+fn test_forwarded_iam_uses_originating_address() {
+    let iam = IAmRequest::new(ObjectIdentifier::new(ObjectType::Device, 55), 1476, 0, 260);
+    let mut iam_buf = Vec::new();
+    iam.encode(&mut iam_buf).unwrap();
+    // Forwarded-NPDU from a BBMD, originally sent by 192.168.2.20:47808
+    let mut packet = vec![0x81, 0x04, 0x00, 0x00, 192, 168, 2, 20, 0xBA, 0xC0];
+    packet.extend_from_slice(&Npdu::new().encode());
+    packet.extend_from_slice(&[0x10, UnconfirmedServiceChoice::IAm as u8]);
+    packet.extend_from_slice(&iam_buf);
+    packet[3] = packet.len() as u8;
+
+    let bbmd: SocketAddr = "192.168.1.1:47808".parse().unwrap();
+    let device = process_response(&packet, bbmd).unwrap();
+    assert_eq!(device.address, "192.168.2.20:47808".parse::<SocketAddr>().unwrap());
+}